@@ -0,0 +1,59 @@
+//! Cache contention benchmark: many concurrent resolvers loading cached keys
+//! from one DataLoader, with a single cache shard versus a sharded cache.
+//!
+//! Run with `cargo bench --bench dataloader_cache`.
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pleme_graphql_helpers::dataloaders::{BatchLoader, DataLoader};
+use std::collections::HashMap;
+
+const KEYS: u64 = 1_000;
+const TASKS: usize = 256;
+const LOADS_PER_TASK: u64 = 100;
+
+struct IdentityLoader;
+
+#[async_trait]
+impl BatchLoader<u64, u64> for IdentityLoader {
+    async fn load_batch(&self, keys: &[u64]) -> HashMap<u64, u64> {
+        keys.iter().map(|k| (*k, *k)).collect()
+    }
+}
+
+fn concurrent_cached_loads(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("dataloader_cached_loads");
+    for shards in [1, 16] {
+        let loader = DataLoader::builder(IdentityLoader)
+            .cache_shards(shards)
+            .build();
+        runtime.block_on(loader.load_many((0..KEYS).collect()));
+
+        group.bench_with_input(BenchmarkId::from_parameter(shards), &loader, |b, loader| {
+            b.to_async(&runtime).iter(|| async {
+                let tasks: Vec<_> = (0..TASKS)
+                    .map(|task| {
+                        let loader = loader.clone();
+                        tokio::spawn(async move {
+                            for i in 0..LOADS_PER_TASK {
+                                loader.load((task as u64 * 31 + i) % KEYS).await;
+                            }
+                        })
+                    })
+                    .collect();
+                for task in tasks {
+                    task.await.unwrap();
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, concurrent_cached_loads);
+criterion_main!(benches);
@@ -0,0 +1,188 @@
+//! Derive macros for pleme-graphql-helpers
+//!
+//! Use through the `derive` feature of `pleme-graphql-helpers`, which
+//! re-exports these macros.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Ident, LitStr, Type};
+
+/// Derive `BatchLoader` for a sqlx-backed loader
+///
+/// The struct must have a `PgPool` field (named `pool` unless overridden).
+/// The query receives the batch of keys as `$1`; each returned row is keyed
+/// by `key_field`.
+///
+/// ```rust,ignore
+/// #[derive(BatchLoader)]
+/// #[batch_loader(
+///     key = "Uuid",
+///     value = "User",
+///     query = "SELECT * FROM users WHERE id = ANY($1)",
+///     key_field = "id",
+/// )]
+/// struct UserLoader {
+///     pool: PgPool,
+/// }
+/// ```
+#[proc_macro_derive(BatchLoader, attributes(batch_loader))]
+pub fn derive_batch_loader(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_batch_loader(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Parsed `#[batch_loader(...)]` attribute
+struct BatchLoaderArgs {
+    key: Type,
+    value: Type,
+    query: LitStr,
+    key_field: Ident,
+    pool: Ident,
+}
+
+impl BatchLoaderArgs {
+    fn from_input(input: &DeriveInput) -> syn::Result<Self> {
+        let mut key = None;
+        let mut value = None;
+        let mut query = None;
+        let mut key_field = None;
+        let mut pool = None;
+
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("batch_loader"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let lit: LitStr = meta.value()?.parse()?;
+                if meta.path.is_ident("key") {
+                    key = Some(lit.parse()?);
+                } else if meta.path.is_ident("value") {
+                    value = Some(lit.parse()?);
+                } else if meta.path.is_ident("query") {
+                    query = Some(lit);
+                } else if meta.path.is_ident("key_field") {
+                    key_field = Some(lit.parse()?);
+                } else if meta.path.is_ident("pool") {
+                    pool = Some(lit.parse()?);
+                } else {
+                    return Err(meta.error("unknown batch_loader attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let missing = |name: &str| {
+            syn::Error::new_spanned(
+                &input.ident,
+                format!("missing `#[batch_loader({} = \"...\")]`", name),
+            )
+        };
+
+        Ok(Self {
+            key: key.ok_or_else(|| missing("key"))?,
+            value: value.ok_or_else(|| missing("value"))?,
+            query: query.ok_or_else(|| missing("query"))?,
+            key_field: key_field.ok_or_else(|| missing("key_field"))?,
+            pool: pool.unwrap_or_else(|| Ident::new("pool", proc_macro2::Span::call_site())),
+        })
+    }
+}
+
+fn expand_batch_loader(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let BatchLoaderArgs {
+        key,
+        value,
+        query,
+        key_field,
+        pool,
+    } = BatchLoaderArgs::from_input(&input)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #[::pleme_graphql_helpers::__private::async_trait]
+        impl #impl_generics ::pleme_graphql_helpers::dataloaders::BatchLoader<#key, #value>
+            for #ident #ty_generics #where_clause
+        {
+            async fn load_batch(
+                &self,
+                keys: &[#key],
+            ) -> ::std::collections::HashMap<#key, #value> {
+                ::pleme_graphql_helpers::dataloaders::successes(self.try_load_batch(keys).await)
+            }
+
+            async fn try_load_batch(
+                &self,
+                keys: &[#key],
+            ) -> ::std::result::Result<
+                ::std::collections::HashMap<
+                    #key,
+                    ::std::result::Result<#value, ::pleme_graphql_helpers::dataloaders::LoadError>,
+                >,
+                ::pleme_graphql_helpers::dataloaders::LoadError,
+            > {
+                let rows = ::pleme_graphql_helpers::__private::sqlx::query_as::<_, #value>(#query)
+                    .bind(keys)
+                    .fetch_all(&self.#pool)
+                    .await
+                    .map_err(|e| {
+                        ::pleme_graphql_helpers::dataloaders::LoadError::Batch(e.to_string())
+                    })?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|row| (::std::clone::Clone::clone(&row.#key_field), Ok(row)))
+                    .collect())
+            }
+
+            fn name(&self) -> &str {
+                stringify!(#ident)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_batch_loader() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[batch_loader(
+                key = "Uuid",
+                value = "User",
+                query = "SELECT * FROM users WHERE id = ANY($1)",
+                key_field = "id",
+                pool = "db",
+            )]
+            struct UserLoader {
+                db: PgPool,
+            }
+        };
+
+        let output = expand_batch_loader(input).unwrap().to_string();
+        assert!(output.contains("BatchLoader < Uuid , User >"));
+        assert!(output.contains("\"SELECT * FROM users WHERE id = ANY($1)\""));
+        assert!(output.contains("self . db"));
+        assert!(output.contains("row . id"));
+    }
+
+    #[test]
+    fn test_missing_attribute() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[batch_loader(key = "Uuid", value = "User")]
+            struct UserLoader {
+                pool: PgPool,
+            }
+        };
+
+        let err = expand_batch_loader(input).err().unwrap();
+        assert!(err.to_string().contains("missing `#[batch_loader(query"));
+    }
+}
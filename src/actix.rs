@@ -0,0 +1,196 @@
+//! actix-web GraphQL handlers
+//!
+//! Equivalents of the axum handlers for services on actix-web. Auth
+//! settings, policies, and loader factories are registered with `app_data`
+//! instead of axum `Extension`s; authentication and execution go through
+//! the same code as [`graphql_handler`](crate::auth::graphql_handler).
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use actix_web::{web, App};
+//! use pleme_graphql_helpers::actix::{graphiql_route, graphql_handler};
+//! use pleme_graphql_helpers::auth::{AuthHeaderConfig, TenantEnforcement};
+//!
+//! let app = App::new()
+//!     .app_data(web::Data::new(schema))
+//!     .app_data(AuthHeaderConfig::from_env())
+//!     .app_data(TenantEnforcement::new().allow("login"))
+//!     .route("/graphql", web::post().to(graphql_handler::<Query, Mutation, EmptySubscription>))
+//!     .route("/graphiql", graphiql_route("/graphql"));
+//! ```
+
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError, Route};
+use async_graphql::{BatchRequest, ObjectType, Schema, SubscriptionType};
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::auth::{
+    authenticate, execute_batch, request_id, AnonymousAccess, AuthContext, AuthHeaderConfig,
+    AuthRejection, AuthSettings, BatchLimits, ClientInfo, ExecutionOptions, ExpiredTokens,
+    GatewayVerifier, SharedApiKeyResolver, SharedAuditSink, TenantEnforcement, TrustedProxies,
+};
+use crate::dataloaders::SharedLoaderFactory;
+use crate::extensions::cache::insert_cache_header;
+use crate::extensions::SharedOperationRegistry;
+use crate::http::{graphiql_html, IdeConfig};
+
+impl ResponseError for AuthRejection {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status.as_u16()).unwrap_or(StatusCode::UNAUTHORIZED)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.to_json())
+    }
+}
+
+/// Authenticate an actix request
+///
+/// Applies the same header, API key, gateway, JWT, expiry, and
+/// impersonation checks as the axum [`Authenticated`](crate::auth::Authenticated)
+/// extractor.
+pub async fn authenticate_request(req: &HttpRequest) -> Result<AuthContext, AuthRejection> {
+    authenticate(
+        req.method().as_str(),
+        req.path(),
+        &to_headers(req),
+        &auth_settings(req),
+    )
+    .await
+}
+
+/// GraphQL handler with authentication context injection
+///
+/// Accepts single and batched JSON requests. If a [`SharedLoaderFactory`]
+/// is registered, every operation gets a fresh loader registry.
+pub async fn graphql_handler<Query, Mutation, Subscription>(
+    schema: web::Data<Schema<Query, Mutation, Subscription>>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, AuthRejection>
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    let auth = authenticate_request(&req).await?;
+    let batch: BatchRequest = match serde_json::from_slice(&body) {
+        Ok(batch) => batch,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e.to_string())),
+    };
+
+    let proxies = req
+        .app_data::<TrustedProxies>()
+        .copied()
+        .unwrap_or_default();
+    let client = ClientInfo::from_headers_with_peer(&to_headers(&req), req.peer_addr(), proxies);
+    let factory = req.app_data::<SharedLoaderFactory>().cloned();
+    let mut headers = request_id::response_headers(&auth);
+    let response = execute_batch(
+        schema.get_ref(),
+        batch,
+        auth,
+        &execution_options(&req),
+        |request| {
+            let request = request.data(client.clone());
+            match &factory {
+                Some(factory) => request.data(factory.build()),
+                None => request,
+            }
+        },
+    )
+    .await?;
+    insert_cache_header(&mut headers, response.is_ok(), response.cache_control());
+
+    let mut builder = HttpResponse::Ok();
+    for (name, value) in &headers {
+        if let Ok(value) = value.to_str() {
+            builder.insert_header((name.as_str(), value));
+        }
+    }
+    Ok(builder.json(response))
+}
+
+/// GET route serving GraphiQL for `endpoint` (debug builds only)
+pub fn graphiql_route(endpoint: &str) -> Route {
+    graphiql_route_with(IdeConfig::new(endpoint))
+}
+
+/// GET route serving GraphiQL with explicit configuration
+pub fn graphiql_route_with(config: IdeConfig) -> Route {
+    let html = config.enabled.then(|| graphiql_html(&config));
+    web::get().to(move || {
+        let html = html.clone();
+        async move {
+            match html {
+                Some(html) => HttpResponse::Ok()
+                    .content_type("text/html; charset=utf-8")
+                    .body(html),
+                None => HttpResponse::NotFound().finish(),
+            }
+        }
+    })
+}
+
+/// Copy actix request headers into an `http` 1.x header map
+fn to_headers(req: &HttpRequest) -> HeaderMap {
+    req.headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                HeaderName::from_bytes(name.as_str().as_bytes()).ok()?,
+                HeaderValue::from_bytes(value.as_bytes()).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Auth settings registered with `app_data`
+fn auth_settings(req: &HttpRequest) -> AuthSettings {
+    AuthSettings {
+        headers: req
+            .app_data::<AuthHeaderConfig>()
+            .cloned()
+            .unwrap_or_default(),
+        api_keys: req.app_data::<SharedApiKeyResolver>().cloned(),
+        gateway: req.app_data::<GatewayVerifier>().cloned(),
+        expired_tokens: req.app_data::<ExpiredTokens>().copied().unwrap_or_default(),
+        #[cfg(feature = "jwks")]
+        jwt: req.app_data::<crate::auth::SharedJwtVerifier>().cloned(),
+    }
+}
+
+/// Execution policies registered with `app_data`
+fn execution_options(req: &HttpRequest) -> ExecutionOptions {
+    ExecutionOptions {
+        audit: req.app_data::<SharedAuditSink>().cloned(),
+        anonymous: req.app_data::<AnonymousAccess>().cloned(),
+        tenant: req.app_data::<TenantEnforcement>().cloned(),
+        operations: req.app_data::<SharedOperationRegistry>().cloned(),
+        batch: req.app_data::<BatchLimits>().copied().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_authenticate_request_uses_app_data() {
+        let user_id = Uuid::new_v4();
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("x-gw-user", user_id.to_string()))
+            .app_data(AuthHeaderConfig::new().with_user_id_header("x-gw-user"))
+            .to_http_request();
+
+        let auth = authenticate_request(&req).await.unwrap();
+        assert_eq!(auth.user_id, Some(user_id));
+    }
+
+    #[test]
+    fn test_rejection_response() {
+        let response = AuthRejection::token_expired().error_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -5,94 +5,339 @@
 //! - Creating GraphQL request context with auth info
 //! - Standard Axum handler for GraphQL endpoints with auth
 
-use async_graphql::{Context, Request, Response, Schema};
+use async_graphql::{BatchRequest, BatchResponse, Context, Data, Request, Response, Schema};
 use axum::{
-    extract::Extension,
-    http::HeaderMap,
+    extract::{Extension, RawQuery},
+    http::{HeaderMap, StatusCode},
     Json,
 };
+use futures::StreamExt;
 use pleme_rbac::AuthzContext;
+use std::time::Instant;
 use uuid::Uuid;
 
+use crate::dataloaders::SharedLoaderFactory;
+use crate::extensions::cache::insert_cache_header;
+
+pub mod api_key;
+pub mod audit;
+pub mod client;
+pub mod config;
+pub mod context;
+pub mod directives;
+pub mod extract;
+pub mod gateway;
+pub mod guards;
+pub mod impersonation;
+#[cfg(feature = "jwks")]
+pub mod jwt;
+pub mod layer;
+mod operation;
+pub mod policy;
+pub mod rejection;
+pub mod request;
+pub mod request_id;
+pub mod ws;
+
+pub use api_key::{ApiKeyResolver, SharedApiKeyResolver};
+pub use audit::{AuditEvent, AuditSink, SharedAuditSink};
+pub use client::{client_ip, get_client_info, ClientInfo, ForwardingHeader, TrustedProxies};
+pub use config::AuthHeaderConfig;
+pub use context::{AuthContext, CompanyId, UserId};
+pub use directives::AuthDirectives;
+pub(crate) use extract::{authenticate, AuthSettings};
+pub use extract::{Authenticated, ExpiredTokens};
+pub use gateway::GatewayVerifier;
+pub use guards::{AuthRequired, CompanyRequired, PermissionRequired, RoleRequired};
+pub use impersonation::{get_impersonation, Impersonation};
+#[cfg(feature = "jwks")]
+pub use jwt::{JwtError, JwtVerifier, SharedJwtVerifier};
+pub use layer::{AuthLayer, AuthService};
+pub(crate) use operation::{select_operation, OperationInfo};
+pub use policy::{AnonymousAccess, BatchLimits, ExecutionOptions, TenantEnforcement};
+pub use rejection::AuthRejection;
+pub use request::{GraphQLBatchRequest, GraphQLRequest, UploadConfig};
+pub use request_id::{get_request_id, RequestId};
+pub use ws::graphql_ws_handler;
+
 /// Extract user_id from x-user-id header
 pub fn extract_user_id(headers: &HeaderMap) -> Option<Uuid> {
-    headers
-        .get("x-user-id")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| Uuid::parse_str(s).ok())
+    AuthHeaderConfig::default().user_id(headers)
 }
 
 /// Extract company_id from x-company-id header
 pub fn extract_company_id(headers: &HeaderMap) -> Option<Uuid> {
-    headers
-        .get("x-company-id")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| Uuid::parse_str(s).ok())
+    AuthHeaderConfig::default().company_id(headers)
 }
 
 /// Extract and parse JWT from Authorization header
 pub fn extract_authz(headers: &HeaderMap) -> AuthzContext {
-    headers
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|auth| {
-            if let Some(token) = auth.strip_prefix("Bearer ") {
-                AuthzContext::from_jwt(token).ok()
-            } else {
-                None
-            }
-        })
-        .unwrap_or_else(AuthzContext::empty)
+    AuthHeaderConfig::default().authz(headers)
 }
 
 /// Standard GraphQL handler with authentication context injection
 ///
-/// Extracts user_id, company_id, and AuthzContext from headers and injects into request
+/// Extracts user_id, company_id, and AuthzContext from headers and injects into request.
+/// Accepts JSON bodies, multipart file uploads, and batched (array) bodies; see
+/// [`GraphQLBatchRequest`]. Batched operations share the same auth context
+/// and run concurrently within the installed [`BatchLimits`]; larger batches
+/// are rejected with `400 Bad Request`. Behind an [`AuthLayer`], the context
+/// the layer stored is used as is. Successful responses carry a
+/// `Cache-Control` header built from the operation's cache hints.
 ///
 /// # Example
 ///
 /// ```rust,no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription, Schema};
 /// use axum::{Router, routing::post};
 /// use pleme_graphql_helpers::auth::graphql_handler;
-/// use async_graphql::Schema;
+/// # struct Query;
+/// # #[async_graphql::Object]
+/// # impl Query {
+/// #     async fn ping(&self) -> bool {
+/// #         true
+/// #     }
+/// # }
 ///
-/// # async fn example(schema: Schema<(), (), ()>) {
-/// let app = Router::new()
-///     .route("/graphql", post(graphql_handler::<(), (), ()>));
+/// # async fn example(schema: Schema<Query, EmptyMutation, EmptySubscription>) {
+/// let app: Router = Router::new().route(
+///     "/graphql",
+///     post(graphql_handler::<Query, EmptyMutation, EmptySubscription>),
+/// );
 /// # }
 /// ```
 pub async fn graphql_handler<Query, Mutation, Subscription>(
     Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
-    headers: HeaderMap,
-    req: Json<Request>,
-) -> Json<Response>
+    options: ExecutionOptions,
+    Authenticated(auth): Authenticated,
+    client: ClientInfo,
+    req: GraphQLBatchRequest,
+) -> Result<(HeaderMap, Json<BatchResponse>), AuthRejection>
+where
+    Query: async_graphql::ObjectType + 'static,
+    Mutation: async_graphql::ObjectType + 'static,
+    Subscription: async_graphql::SubscriptionType + 'static,
+{
+    let mut headers = request_id::response_headers(&auth);
+    let response = execute_batch(&schema, req.into_inner(), auth, &options, |request| {
+        request.data(client.clone())
+    })
+    .await?;
+    insert_cache_header(&mut headers, response.is_ok(), response.cache_control());
+
+    Ok((headers, Json(response)))
+}
+
+/// GraphQL handler with auth context and per-request loaders
+///
+/// Same as [`graphql_handler`], but also builds a fresh
+/// [`LoaderRegistry`](crate::dataloaders::LoaderRegistry)
+/// from the [`SharedLoaderFactory`] extension for every request. Resolvers
+/// read loaders with [`get_loader`](crate::dataloaders::get_loader).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription};
+/// use axum::{Extension, Router, routing::post};
+/// use pleme_graphql_helpers::auth::graphql_handler_with_loaders;
+/// use pleme_graphql_helpers::dataloaders::{LoaderRegistry, SharedLoaderFactory};
+/// use std::sync::Arc;
+/// # struct Query;
+/// # #[async_graphql::Object]
+/// # impl Query {
+/// #     async fn ping(&self) -> bool {
+/// #         true
+/// #     }
+/// # }
+///
+/// let factory: SharedLoaderFactory = Arc::new(|registry: &mut LoaderRegistry| {
+///     // registry.insert(DataLoader::new(UserLoader::new(pool.clone())));
+/// });
+///
+/// let handler = graphql_handler_with_loaders::<Query, EmptyMutation, EmptySubscription>;
+/// let app: Router = Router::new()
+///     .route("/graphql", post(handler))
+///     .layer(Extension(factory));
+/// ```
+pub async fn graphql_handler_with_loaders<Query, Mutation, Subscription>(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    Extension(factory): Extension<SharedLoaderFactory>,
+    options: ExecutionOptions,
+    Authenticated(auth): Authenticated,
+    client: ClientInfo,
+    req: GraphQLBatchRequest,
+) -> Result<(HeaderMap, Json<BatchResponse>), AuthRejection>
 where
     Query: async_graphql::ObjectType + 'static,
     Mutation: async_graphql::ObjectType + 'static,
     Subscription: async_graphql::SubscriptionType + 'static,
 {
-    // Extract auth context from headers
-    let user_id = extract_user_id(&headers);
-    let company_id = extract_company_id(&headers);
-    let authz = extract_authz(&headers);
+    let mut headers = request_id::response_headers(&auth);
+    let response = execute_batch(&schema, req.into_inner(), auth, &options, |request| {
+        request.data(factory.build()).data(client.clone())
+    })
+    .await?;
+    insert_cache_header(&mut headers, response.is_ok(), response.cache_control());
 
-    // Build request with context
-    let mut request = req.0;
+    Ok((headers, Json(response)))
+}
+
+/// GraphQL handler for GET requests with authentication context injection
+///
+/// Reads `query`, `variables`, `operationName`, and `extensions` from the
+/// query string, as sent by Apollo clients and CDN-cached persisted queries.
+/// Mutations are rejected with `405 Method Not Allowed`, since GET requests
+/// must not have side effects.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription};
+/// use axum::{Router, routing::get};
+/// use pleme_graphql_helpers::auth::{graphql_get_handler, graphql_handler};
+/// # struct Query;
+/// # #[async_graphql::Object]
+/// # impl Query {
+/// #     async fn ping(&self) -> bool {
+/// #         true
+/// #     }
+/// # }
+///
+/// let app: Router = Router::new().route(
+///     "/graphql",
+///     get(graphql_get_handler::<Query, EmptyMutation, EmptySubscription>)
+///         .post(graphql_handler::<Query, EmptyMutation, EmptySubscription>),
+/// );
+/// ```
+pub async fn graphql_get_handler<Query, Mutation, Subscription>(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    options: ExecutionOptions,
+    Authenticated(auth): Authenticated,
+    client: ClientInfo,
+    RawQuery(query): RawQuery,
+) -> Result<(HeaderMap, Json<Response>), (StatusCode, String)>
+where
+    Query: async_graphql::ObjectType + 'static,
+    Mutation: async_graphql::ObjectType + 'static,
+    Subscription: async_graphql::SubscriptionType + 'static,
+{
+    let mut request = async_graphql::http::parse_query_string(query.as_deref().unwrap_or_default())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
 
-    if let Some(uid) = user_id {
-        request = request.data(uid);
+    options.resolve(&mut request);
+    let operation = OperationInfo::parse(&request);
+    if operation.as_ref().is_some_and(OperationInfo::is_mutation) {
+        return Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Mutations are not allowed over GET".to_string(),
+        ));
     }
 
-    if let Some(cid) = company_id {
-        request = request.data(cid);
+    let mut response = match options.check(&auth, &request, operation.as_ref()) {
+        Ok(()) => {
+            insert_auth_data(&mut request.data, auth.clone());
+            request.data.insert(client);
+            schema.execute(request).await
+        }
+        Err(rejection) => Response::from_errors(vec![rejection]),
+    };
+    finish_response(&mut response, &auth);
+
+    let mut headers = request_id::response_headers(&auth);
+    insert_cache_header(&mut headers, response.is_ok(), response.cache_control);
+    Ok((headers, Json(response)))
+}
+
+/// Execute a single or batched request with auth data injected
+///
+/// `prepare` adds per-operation data (e.g. a fresh loader registry).
+/// Batches over the size limit in `options` are rejected before anything
+/// runs; batched operations run concurrently up to its concurrency limit.
+/// Persisted query hashes are resolved first, so policies see the operation
+/// that will execute. Operations rejected by a policy in `options` aren't
+/// executed, and mutations are recorded to the audit sink.
+pub(crate) async fn execute_batch<Query, Mutation, Subscription>(
+    schema: &Schema<Query, Mutation, Subscription>,
+    batch: BatchRequest,
+    auth: AuthContext,
+    options: &ExecutionOptions,
+    prepare: impl Fn(Request) -> Request,
+) -> Result<BatchResponse, AuthRejection>
+where
+    Query: async_graphql::ObjectType + 'static,
+    Mutation: async_graphql::ObjectType + 'static,
+    Subscription: async_graphql::SubscriptionType + 'static,
+{
+    let auth = &auth;
+    let execute = |mut request: Request| {
+        options.resolve(&mut request);
+        let operation = OperationInfo::parse(&request);
+        let checked = options.check(auth, &request, operation.as_ref());
+        let event = options
+            .audit
+            .as_ref()
+            .filter(|_| operation.as_ref().is_some_and(OperationInfo::is_mutation))
+            .map(|sink| (sink, AuditEvent::start(auth, &request)));
+        insert_auth_data(&mut request.data, auth.clone());
+        let request = prepare(request);
+        async move {
+            let started = Instant::now();
+            let mut response = match checked {
+                Ok(()) => schema.execute(request).await,
+                Err(rejection) => Response::from_errors(vec![rejection]),
+            };
+            if let Some((sink, event)) = event {
+                sink.record(event.finish(&response, started.elapsed()))
+                    .await;
+            }
+            finish_response(&mut response, auth);
+            response
+        }
+    };
+
+    match batch {
+        BatchRequest::Single(request) => Ok(BatchResponse::Single(execute(request).await)),
+        BatchRequest::Batch(requests) => {
+            options.batch.check(requests.len())?;
+            let responses = futures::stream::iter(requests.into_iter().map(execute))
+                .buffered(options.batch.concurrency())
+                .collect()
+                .await;
+            Ok(BatchResponse::Batch(responses))
+        }
+    }
+}
+
+/// Tag a response with the request ID and impersonation details
+fn finish_response(response: &mut Response, auth: &AuthContext) {
+    if let Some(id) = &auth.request_id {
+        request_id::tag_errors(response, id);
+    }
+    impersonation::tag_response(response, auth);
+}
+
+/// Insert an [`AuthContext`] into request or connection data
+///
+/// The user_id, company_id, and AuthzContext are also inserted individually
+/// for resolvers that read them directly, as [`UserId`] and [`CompanyId`].
+/// The user_id is also inserted as a bare `Uuid` for older resolvers.
+pub(crate) fn insert_auth_data(data: &mut Data, auth: AuthContext) {
+    if let Some(uid) = auth.user_id {
+        data.insert(uid);
+        data.insert(UserId(uid));
     }
 
-    request = request.data(authz);
+    if let Some(cid) = auth.company_id {
+        data.insert(CompanyId(cid));
+    }
 
-    // Execute query
-    let response = schema.execute(request).await;
+    if let Some(id) = &auth.request_id {
+        data.insert(RequestId(id.clone()));
+    }
 
-    Json(response)
+    data.insert(auth.authz.clone());
+    data.insert(auth);
 }
 
 /// Get user_id from GraphQL context
@@ -108,7 +353,13 @@ where
 /// }
 /// ```
 pub fn get_user_id(ctx: &Context<'_>) -> Option<Uuid> {
-    ctx.data_opt::<Uuid>().copied()
+    match ctx.data_opt::<AuthContext>() {
+        Some(auth) => auth.user_id,
+        None => ctx
+            .data_opt::<UserId>()
+            .map(|id| id.0)
+            .or_else(|| ctx.data_opt::<Uuid>().copied()),
+    }
 }
 
 /// Get company_id from GraphQL context
@@ -124,9 +375,26 @@ pub fn get_user_id(ctx: &Context<'_>) -> Option<Uuid> {
 /// }
 /// ```
 pub fn get_company_id(ctx: &Context<'_>) -> Option<Uuid> {
-    // Company ID might be stored as second Uuid in context
-    // This is a simplified version - in practice you'd need a wrapper type
-    ctx.data_opt::<Uuid>().copied()
+    match ctx.data_opt::<AuthContext>() {
+        Some(auth) => auth.company_id,
+        None => ctx.data_opt::<CompanyId>().map(|id| id.0),
+    }
+}
+
+/// Get the unified [`AuthContext`] from GraphQL context
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::Context;
+/// use pleme_graphql_helpers::auth::get_auth_context;
+///
+/// fn resolver(ctx: &Context<'_>) -> async_graphql::Result<uuid::Uuid> {
+///     get_auth_context(ctx).require_user()
+/// }
+/// ```
+pub fn get_auth_context(ctx: &Context<'_>) -> AuthContext {
+    AuthContext::from_ctx(ctx)
 }
 
 /// Get AuthzContext from GraphQL context
@@ -146,3 +414,138 @@ pub fn get_authz_context(ctx: &Context<'_>) -> AuthzContext {
         .cloned()
         .unwrap_or_else(AuthzContext::empty)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Query;
+
+    #[async_graphql::Object]
+    impl Query {
+        async fn user_id(&self, ctx: &Context<'_>) -> Option<String> {
+            get_user_id(ctx).map(|id| id.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_shares_auth() {
+        let schema = Schema::new(
+            Query,
+            async_graphql::EmptyMutation,
+            async_graphql::EmptySubscription,
+        );
+        let user_id = Uuid::new_v4();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-user-id", user_id.to_string().parse().unwrap());
+
+        let batch =
+            BatchRequest::Batch(vec![Request::new("{ userId }"), Request::new("{ userId }")]);
+        let BatchResponse::Batch(responses) = execute_batch(
+            &schema,
+            batch,
+            AuthContext::from_headers(&headers),
+            &ExecutionOptions::default(),
+            |request| request,
+        )
+        .await
+        .unwrap() else {
+            panic!("expected batch response");
+        };
+
+        assert_eq!(responses.len(), 2);
+        for response in responses {
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                serde_json::json!({ "userId": user_id.to_string() })
+            );
+        }
+    }
+
+    struct Mutation;
+
+    #[async_graphql::Object]
+    impl Mutation {
+        async fn rename(&self, name: String) -> String {
+            name
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSink(std::sync::Mutex<Vec<AuditEvent>>);
+
+    #[async_trait::async_trait]
+    impl AuditSink for RecordingSink {
+        async fn record(&self, event: AuditEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_audits_mutations() {
+        let schema = Schema::new(Query, Mutation, async_graphql::EmptySubscription);
+        let sink = std::sync::Arc::new(RecordingSink::default());
+        let shared: SharedAuditSink = sink.clone();
+
+        let batch = BatchRequest::Batch(vec![
+            Request::new("{ userId }"),
+            Request::new(r#"mutation Rename { rename(name: "x") }"#).operation_name("Rename"),
+        ]);
+        execute_batch(
+            &schema,
+            batch,
+            AuthContext::anonymous(),
+            &ExecutionOptions {
+                audit: Some(shared),
+                ..Default::default()
+            },
+            |request| request,
+        )
+        .await
+        .unwrap();
+
+        let events = sink.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].operation_name.as_deref(), Some("Rename"));
+        assert!(events[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_limits_size() {
+        let schema = Schema::new(
+            Query,
+            async_graphql::EmptyMutation,
+            async_graphql::EmptySubscription,
+        );
+        let options = ExecutionOptions {
+            batch: BatchLimits::new().with_max_size(2).with_concurrency(1),
+            ..Default::default()
+        };
+        let batch =
+            |size| BatchRequest::Batch((0..size).map(|_| Request::new("{ userId }")).collect());
+
+        let BatchResponse::Batch(responses) = execute_batch(
+            &schema,
+            batch(2),
+            AuthContext::anonymous(),
+            &options,
+            |request| request,
+        )
+        .await
+        .unwrap() else {
+            panic!("expected batch response");
+        };
+        assert_eq!(responses.len(), 2);
+
+        let rejection = execute_batch(
+            &schema,
+            batch(3),
+            AuthContext::anonymous(),
+            &options,
+            |request| request,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(rejection.status, StatusCode::BAD_REQUEST);
+    }
+}
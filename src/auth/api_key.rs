@@ -0,0 +1,92 @@
+//! API key authentication for machine-to-machine clients
+//!
+//! Clients send `x-api-key` instead of a bearer token. The handlers look the
+//! key up with the [`SharedApiKeyResolver`] extension, when one is
+//! installed, and use the resulting service principal as the request's
+//! [`AuthContext`].
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use std::sync::Arc;
+
+use super::AuthContext;
+
+/// Header carrying the API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Looks up API keys
+///
+/// # Example
+///
+/// ```rust,ignore
+/// struct DbKeys(PgPool);
+///
+/// #[async_trait]
+/// impl ApiKeyResolver for DbKeys {
+///     async fn resolve(&self, key: &str) -> Option<AuthContext> {
+///         let row = find_key(&self.0, key).await.ok()??;
+///         Some(AuthContext::service(row.service_name).with_company(row.company_id))
+///     }
+/// }
+///
+/// let app = Router::new()
+///     .route("/graphql", post(graphql_handler::<Query, Mutation, EmptySubscription>))
+///     .layer(Extension(Arc::new(DbKeys(pool)) as SharedApiKeyResolver));
+/// ```
+#[async_trait]
+pub trait ApiKeyResolver: Send + Sync {
+    /// The auth context for `key`, or `None` if the key is unknown or revoked
+    async fn resolve(&self, key: &str) -> Option<AuthContext>;
+}
+
+/// Shared API key resolver, installed as an axum `Extension`
+pub type SharedApiKeyResolver = Arc<dyn ApiKeyResolver>;
+
+/// Extract the API key from the `x-api-key` header
+pub fn extract_api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{authenticate, AuthSettings};
+    use axum::http::{HeaderValue, StatusCode};
+
+    struct StaticKeys;
+
+    #[async_trait]
+    impl ApiKeyResolver for StaticKeys {
+        async fn resolve(&self, key: &str) -> Option<AuthContext> {
+            (key == "valid").then(|| AuthContext::service("billing-worker"))
+        }
+    }
+
+    fn headers(key: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static(key));
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_with_api_key() {
+        let settings = AuthSettings {
+            api_keys: Some(Arc::new(StaticKeys)),
+            ..Default::default()
+        };
+
+        let auth = authenticate("POST", "/graphql", &headers("valid"), &settings)
+            .await
+            .unwrap();
+        assert_eq!(auth.service.as_deref(), Some("billing-worker"));
+        assert!(auth.is_authenticated());
+
+        let rejection = authenticate("POST", "/graphql", &headers("revoked"), &settings)
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,320 @@
+//! Caller IP, user agent, and locale
+//!
+//! The IP is the socket peer when the app is served with
+//! `into_make_service_with_connect_info`. Behind [`TrustedProxies`] it is
+//! read from the one [`ForwardingHeader`] the proxies set, taking the entry
+//! the outermost trusted proxy appended rather than the client-controlled
+//! leftmost one. The locale is negotiated
+//! from `Accept-Language`, and a federation gateway asks for inline traces
+//! with `apollo-federation-include-trace: ftv1`.
+
+use async_graphql::Context;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{
+        header::{ACCEPT_LANGUAGE, USER_AGENT},
+        request::Parts,
+        HeaderMap,
+    },
+};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::error::Locale;
+
+/// Header a federation gateway sets to `ftv1` to request inline traces
+pub const INCLUDE_TRACE: &str = "apollo-federation-include-trace";
+
+/// Header the trusted proxies record the client address in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ForwardingHeader {
+    /// `Forwarded` (RFC 7239), using each element's `for=` address
+    Forwarded,
+    /// `X-Forwarded-For`
+    #[default]
+    XForwardedFor,
+    /// `X-Real-IP`, a single address
+    XRealIp,
+}
+
+impl ForwardingHeader {
+    /// Header name
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Forwarded => "forwarded",
+            Self::XForwardedFor => "x-forwarded-for",
+            Self::XRealIp => "x-real-ip",
+        }
+    }
+}
+
+/// Reverse proxies in front of the service
+///
+/// Forwarding headers are only read when at least one proxy is trusted,
+/// and only the configured [`ForwardingHeader`] (`X-Forwarded-For` by
+/// default): proxies pass other forwarding headers through untouched, so a
+/// client could set them to any address. With `hops` proxies, the client
+/// is the `hops`-th address from the right of the header: each proxy
+/// appends the peer it saw, so entries further left were sent by the
+/// client. Read from the request extensions; add it with
+/// `.layer(Extension(TrustedProxies::hops(1)))`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    hops: usize,
+    header: ForwardingHeader,
+}
+
+impl TrustedProxies {
+    /// No proxies: forwarding headers are ignored
+    pub const NONE: Self = Self {
+        hops: 0,
+        header: ForwardingHeader::XForwardedFor,
+    };
+
+    /// Trust `hops` proxies setting `X-Forwarded-For`
+    pub const fn hops(hops: usize) -> Self {
+        Self {
+            hops,
+            header: ForwardingHeader::XForwardedFor,
+        }
+    }
+
+    /// Read the client address from `header` instead
+    pub const fn with_header(mut self, header: ForwardingHeader) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Number of trusted proxies
+    pub const fn hop_count(self) -> usize {
+        self.hops
+    }
+
+    /// Header the client address is read from
+    pub const fn header(self) -> ForwardingHeader {
+        self.header
+    }
+
+    /// Of the addresses in a forwarding header, the one the outermost
+    /// trusted proxy appended
+    fn pick<T>(self, mut entries: Vec<T>) -> Option<T> {
+        if self.hops == 0 || entries.is_empty() {
+            return None;
+        }
+        let index = entries.len().saturating_sub(self.hops);
+        Some(entries.swap_remove(index))
+    }
+}
+
+/// Client details stored in the GraphQL context
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    /// Preferred locale for user-facing messages
+    pub locale: Locale,
+    /// Whether the gateway asked for an inline `ftv1` trace
+    pub include_trace: bool,
+}
+
+impl ClientInfo {
+    /// Read client details from request headers
+    ///
+    /// Leaves `ip` unset; see [`from_headers_with_peer`](Self::from_headers_with_peer).
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            ip: None,
+            user_agent: headers
+                .get(USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            locale: headers
+                .get(ACCEPT_LANGUAGE)
+                .and_then(|v| v.to_str().ok())
+                .map(Locale::from_accept_language)
+                .unwrap_or_default(),
+            include_trace: headers
+                .get(INCLUDE_TRACE)
+                .is_some_and(|v| v.as_bytes() == b"ftv1"),
+        }
+    }
+
+    /// Read client details, taking the IP from forwarding headers set by
+    /// `proxies` or else the socket peer address
+    pub fn from_headers_with_peer(
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+        proxies: TrustedProxies,
+    ) -> Self {
+        let mut info = Self::from_headers(headers);
+        info.ip = client_ip(headers, proxies).or(peer.map(|addr| addr.ip()));
+        info
+    }
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        let proxies = parts
+            .extensions
+            .get::<TrustedProxies>()
+            .copied()
+            .unwrap_or_default();
+        Ok(Self::from_headers_with_peer(&parts.headers, peer, proxies))
+    }
+}
+
+/// Get the caller's [`ClientInfo`]
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::Context;
+/// use pleme_graphql_helpers::auth::get_client_info;
+///
+/// fn caller_ip(ctx: &Context<'_>) -> Option<std::net::IpAddr> {
+///     get_client_info(ctx).ip
+/// }
+/// ```
+pub fn get_client_info(ctx: &Context<'_>) -> ClientInfo {
+    ctx.data_opt::<ClientInfo>().cloned().unwrap_or_default()
+}
+
+/// The original client IP from the forwarding header set by `proxies`
+///
+/// `None` when no proxies are trusted. Other forwarding headers are
+/// ignored.
+pub fn client_ip(headers: &HeaderMap, proxies: TrustedProxies) -> Option<IpAddr> {
+    let value = headers
+        .get(proxies.header.name())
+        .and_then(|v| v.to_str().ok())?;
+
+    let entries = match proxies.header {
+        ForwardingHeader::Forwarded => value.split(',').map(forwarded_for).collect(),
+        ForwardingHeader::XForwardedFor => value.split(',').map(parse_ip).collect(),
+        ForwardingHeader::XRealIp => vec![parse_ip(value)],
+    };
+    proxies.pick(entries).flatten()
+}
+
+/// The `for=` address of a `Forwarded` element
+///
+/// `None` for obfuscated or `unknown` nodes.
+fn forwarded_for(element: &str) -> Option<IpAddr> {
+    element
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+        .and_then(|(_, node)| parse_ip(node))
+}
+
+/// Parse an address with optional quotes, brackets, and port
+fn parse_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    value
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            value
+                .strip_prefix('[')?
+                .strip_suffix(']')?
+                .parse::<IpAddr>()
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_client_ip_headers() {
+        let one = TrustedProxies::hops(1);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", HeaderValue::from_static("10.0.0.3"));
+        assert_eq!(
+            client_ip(&headers, one.with_header(ForwardingHeader::XRealIp)),
+            "10.0.0.3".parse().ok()
+        );
+
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.2"),
+        );
+        assert_eq!(client_ip(&headers, one), "10.0.0.2".parse().ok());
+        assert_eq!(
+            client_ip(&headers, TrustedProxies::hops(2)),
+            "203.0.113.7".parse().ok()
+        );
+        assert_eq!(
+            client_ip(&headers, TrustedProxies::hops(5)),
+            "203.0.113.7".parse().ok()
+        );
+
+        headers.insert(
+            "forwarded",
+            HeaderValue::from_static(r#"for=10.0.0.1, for="[2001:db8::1]:4711";proto=https"#),
+        );
+        assert_eq!(
+            client_ip(&headers, one.with_header(ForwardingHeader::Forwarded)),
+            "2001:db8::1".parse().ok()
+        );
+    }
+
+    #[test]
+    fn test_client_ip_ignores_unconfigured_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("forwarded", HeaderValue::from_static("for=198.51.100.66"));
+        headers.insert("x-real-ip", HeaderValue::from_static("198.51.100.67"));
+        assert_eq!(client_ip(&headers, TrustedProxies::hops(1)), None);
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.7"));
+        assert_eq!(
+            client_ip(&headers, TrustedProxies::hops(1)),
+            "203.0.113.7".parse().ok()
+        );
+    }
+
+    #[test]
+    fn test_client_ip_ignores_spoofed_entries() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("198.51.100.66, 203.0.113.7"),
+        );
+        assert_eq!(client_ip(&headers, TrustedProxies::NONE), None);
+        assert_eq!(
+            client_ip(&headers, TrustedProxies::hops(1)),
+            "203.0.113.7".parse().ok()
+        );
+
+        let peer = "192.0.2.9:5000".parse().ok();
+        let info = ClientInfo::from_headers_with_peer(&headers, peer, TrustedProxies::NONE);
+        assert_eq!(info.ip, "192.0.2.9".parse().ok());
+    }
+
+    #[test]
+    fn test_client_info_peer_fallback() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("curl/8.0"));
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("pt-BR,en;q=0.5"));
+        headers.insert(INCLUDE_TRACE, HeaderValue::from_static("ftv1"));
+        let peer = "192.0.2.9:5000".parse().ok();
+
+        let info = ClientInfo::from_headers_with_peer(&headers, peer, TrustedProxies::NONE);
+        assert_eq!(info.ip, "192.0.2.9".parse().ok());
+        assert_eq!(info.user_agent.as_deref(), Some("curl/8.0"));
+        assert_eq!(info.locale, Locale::PtBr);
+        assert!(info.include_trace);
+    }
+}
@@ -0,0 +1,210 @@
+//! Identity header configuration
+//!
+//! Header names default to `x-user-id`, `x-company-id`, and
+//! `Authorization: Bearer <token>`. Services behind a gateway using other
+//! names install an [`AuthHeaderConfig`] as an axum `Extension`.
+//!
+//! The token may also be read from a session cookie. An `Authorization`
+//! header takes precedence over the cookie when both are present.
+
+use axum::http::{header::COOKIE, HeaderMap};
+use pleme_rbac::AuthzContext;
+use std::borrow::Cow;
+use uuid::Uuid;
+
+/// Environment variable overriding the user ID header
+pub const USER_ID_HEADER_ENV: &str = "PLEME_USER_ID_HEADER";
+/// Environment variable overriding the company ID header
+pub const COMPANY_ID_HEADER_ENV: &str = "PLEME_COMPANY_ID_HEADER";
+/// Environment variable overriding the authorization header
+pub const AUTHORIZATION_HEADER_ENV: &str = "PLEME_AUTHORIZATION_HEADER";
+/// Environment variable overriding the bearer prefix
+pub const BEARER_PREFIX_ENV: &str = "PLEME_BEARER_PREFIX";
+/// Environment variable naming the session cookie
+pub const SESSION_COOKIE_ENV: &str = "PLEME_SESSION_COOKIE";
+
+/// Names of the headers identity is read from
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::auth::AuthHeaderConfig;
+///
+/// let config = AuthHeaderConfig::new()
+///     .with_user_id_header("x-staging-user")
+///     .with_bearer_prefix("Token ");
+/// assert_eq!(config.user_id_header, "x-staging-user");
+/// ```
+#[derive(Debug, Clone)]
+pub struct AuthHeaderConfig {
+    pub user_id_header: Cow<'static, str>,
+    pub company_id_header: Cow<'static, str>,
+    pub authorization_header: Cow<'static, str>,
+    /// Prefix stripped from the authorization header (including the space)
+    pub bearer_prefix: Cow<'static, str>,
+    /// Cookie holding the token when there is no authorization header
+    pub session_cookie: Option<Cow<'static, str>>,
+}
+
+impl Default for AuthHeaderConfig {
+    fn default() -> Self {
+        Self {
+            user_id_header: Cow::Borrowed("x-user-id"),
+            company_id_header: Cow::Borrowed("x-company-id"),
+            authorization_header: Cow::Borrowed("Authorization"),
+            bearer_prefix: Cow::Borrowed("Bearer "),
+            session_cookie: None,
+        }
+    }
+}
+
+impl AuthHeaderConfig {
+    /// Default header names
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults overridden by `PLEME_USER_ID_HEADER`,
+    /// `PLEME_COMPANY_ID_HEADER`, `PLEME_AUTHORIZATION_HEADER`,
+    /// `PLEME_BEARER_PREFIX`, and `PLEME_SESSION_COOKIE` when set
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().map(Cow::Owned);
+        let defaults = Self::default();
+        Self {
+            user_id_header: var(USER_ID_HEADER_ENV).unwrap_or(defaults.user_id_header),
+            company_id_header: var(COMPANY_ID_HEADER_ENV).unwrap_or(defaults.company_id_header),
+            authorization_header: var(AUTHORIZATION_HEADER_ENV)
+                .unwrap_or(defaults.authorization_header),
+            bearer_prefix: var(BEARER_PREFIX_ENV).unwrap_or(defaults.bearer_prefix),
+            session_cookie: var(SESSION_COOKIE_ENV),
+        }
+    }
+
+    /// Set the user ID header name
+    pub fn with_user_id_header(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.user_id_header = name.into();
+        self
+    }
+
+    /// Set the company ID header name
+    pub fn with_company_id_header(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.company_id_header = name.into();
+        self
+    }
+
+    /// Set the authorization header name
+    pub fn with_authorization_header(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.authorization_header = name.into();
+        self
+    }
+
+    /// Set the token prefix, e.g. `"Bearer "`
+    pub fn with_bearer_prefix(mut self, prefix: impl Into<Cow<'static, str>>) -> Self {
+        self.bearer_prefix = prefix.into();
+        self
+    }
+
+    /// Extract the user ID
+    pub fn user_id(&self, headers: &HeaderMap) -> Option<Uuid> {
+        uuid_header(headers, &self.user_id_header)
+    }
+
+    /// Extract the company ID
+    pub fn company_id(&self, headers: &HeaderMap) -> Option<Uuid> {
+        uuid_header(headers, &self.company_id_header)
+    }
+
+    /// Read the token from a session cookie when no header is present
+    pub fn with_session_cookie(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.session_cookie = Some(name.into());
+        self
+    }
+
+    /// Extract the bearer token
+    ///
+    /// Uses the authorization header if present (even if malformed), and
+    /// the session cookie otherwise.
+    pub fn bearer_token<'a>(&self, headers: &'a HeaderMap) -> Option<&'a str> {
+        match headers.get(self.authorization_header.as_ref()) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|v| v.strip_prefix(self.bearer_prefix.as_ref())),
+            None => self
+                .session_cookie
+                .as_deref()
+                .and_then(|name| cookie(headers, name)),
+        }
+    }
+
+    /// Parse the bearer token into an [`AuthzContext`]
+    pub fn authz(&self, headers: &HeaderMap) -> AuthzContext {
+        self.bearer_token(headers)
+            .and_then(|token| AuthzContext::from_jwt(token).ok())
+            .unwrap_or_else(AuthzContext::empty)
+    }
+}
+
+/// Value of the first cookie named `name`
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.trim_matches('"'))
+        .filter(|value| !value.is_empty())
+}
+
+fn uuid_header(headers: &HeaderMap, name: &str) -> Option<Uuid> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| Uuid::parse_str(s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_custom_header_names() {
+        let user_id = Uuid::new_v4();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-gw-user",
+            HeaderValue::from_str(&user_id.to_string()).unwrap(),
+        );
+        headers.insert("x-gw-auth", HeaderValue::from_static("Token abc.def.ghi"));
+
+        let config = AuthHeaderConfig::new()
+            .with_user_id_header("x-gw-user")
+            .with_authorization_header("x-gw-auth")
+            .with_bearer_prefix("Token ");
+
+        assert_eq!(config.user_id(&headers), Some(user_id));
+        assert_eq!(config.company_id(&headers), None);
+        assert_eq!(config.bearer_token(&headers), Some("abc.def.ghi"));
+        assert_eq!(AuthHeaderConfig::default().bearer_token(&headers), None);
+    }
+
+    #[test]
+    fn test_session_cookie_precedence() {
+        let config = AuthHeaderConfig::new().with_session_cookie("session");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; session=cookie.token.sig"),
+        );
+        assert_eq!(config.bearer_token(&headers), Some("cookie.token.sig"));
+
+        headers.insert(
+            "Authorization",
+            HeaderValue::from_static("Bearer header.token.sig"),
+        );
+        assert_eq!(config.bearer_token(&headers), Some("header.token.sig"));
+    }
+}
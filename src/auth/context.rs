@@ -0,0 +1,246 @@
+//! Unified per-request auth context
+//!
+//! Built once from request headers by the GraphQL handlers and stored in the
+//! request data, replacing separate lookups for user, company, and authz.
+
+use async_graphql::Context;
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use pleme_rbac::AuthzContext;
+use serde_json::Value;
+use std::fmt;
+use uuid::Uuid;
+
+use super::impersonation::IMPERSONATOR_HEADER;
+use super::AuthHeaderConfig;
+use crate::error::ErrorCode;
+
+/// Header carrying the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Authenticated user ID in request data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UserId(pub Uuid);
+
+/// Caller's company ID in request data
+///
+/// A separate type from [`UserId`] so the two don't overwrite each other in
+/// the type-keyed request data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompanyId(pub Uuid);
+
+/// Authentication details for one request
+#[derive(Clone)]
+pub struct AuthContext {
+    pub user_id: Option<Uuid>,
+    pub company_id: Option<Uuid>,
+    pub authz: AuthzContext,
+    /// Decoded JWT claims from the Authorization header
+    ///
+    /// Unverified unless a `JwtVerifier` built the context; never used for
+    /// authorization decisions.
+    pub token_claims: Option<Value>,
+    pub request_id: Option<String>,
+    /// Service principal for API key callers
+    pub service: Option<String>,
+    /// Real user when support staff act as `user_id`
+    pub impersonator_id: Option<Uuid>,
+}
+
+impl AuthContext {
+    /// Context for an unauthenticated request
+    pub fn anonymous() -> Self {
+        Self {
+            user_id: None,
+            company_id: None,
+            authz: AuthzContext::empty(),
+            token_claims: None,
+            request_id: None,
+            service: None,
+            impersonator_id: None,
+        }
+    }
+
+    /// Context for a service principal (e.g. an API key client)
+    pub fn service(name: impl Into<String>) -> Self {
+        Self {
+            service: Some(name.into()),
+            ..Self::anonymous()
+        }
+    }
+
+    /// Set the company the caller acts within
+    pub fn with_company(mut self, company_id: Uuid) -> Self {
+        self.company_id = Some(company_id);
+        self
+    }
+
+    /// Build the context from request headers
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self::from_headers_with(headers, &AuthHeaderConfig::default())
+    }
+
+    /// Build the context from request headers with custom header names
+    ///
+    /// A request ID is generated when `x-request-id` is absent.
+    pub fn from_headers_with(headers: &HeaderMap, config: &AuthHeaderConfig) -> Self {
+        Self {
+            user_id: config.user_id(headers),
+            company_id: config.company_id(headers),
+            authz: config.authz(headers),
+            token_claims: config.bearer_token(headers).and_then(decode_token_claims),
+            request_id: Some(request_id_from(headers)),
+            service: None,
+            impersonator_id: headers
+                .get(IMPERSONATOR_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| Uuid::parse_str(s).ok()),
+        }
+    }
+
+    /// Anonymous context keeping the request's ID
+    #[cfg(feature = "jwks")]
+    pub(crate) fn anonymous_request(headers: &HeaderMap) -> Self {
+        Self {
+            request_id: Some(request_id_from(headers)),
+            ..Self::anonymous()
+        }
+    }
+
+    /// Get the context from GraphQL request data
+    ///
+    /// Returns an anonymous context if none was injected.
+    pub fn from_ctx(ctx: &Context<'_>) -> Self {
+        ctx.data_opt::<AuthContext>()
+            .cloned()
+            .unwrap_or_else(Self::anonymous)
+    }
+
+    /// Whether a user or service is authenticated
+    pub fn is_authenticated(&self) -> bool {
+        self.user_id.is_some() || self.service.is_some()
+    }
+
+    /// The authenticated user, or an `UNAUTHENTICATED` error
+    pub fn require_user(&self) -> async_graphql::Result<Uuid> {
+        self.user_id
+            .ok_or_else(|| unauthenticated("Authentication required"))
+    }
+
+    /// The caller's company, or an `UNAUTHENTICATED` error
+    pub fn require_company(&self) -> async_graphql::Result<Uuid> {
+        self.company_id
+            .ok_or_else(|| unauthenticated("Company context required"))
+    }
+
+    /// Whether the bearer token's `exp` claim is in the past
+    ///
+    /// The claims aren't verified here; this only tells clients holding a
+    /// stale token to refresh it.
+    pub fn token_expired(&self) -> bool {
+        self.token_claims
+            .as_ref()
+            .and_then(|c| c.get("exp"))
+            .and_then(Value::as_i64)
+            .is_some_and(|exp| exp <= chrono::Utc::now().timestamp())
+    }
+
+    /// Whether the authz context grants `role`
+    ///
+    /// Reads [`authz`](Self::authz), never the unverified `token_claims`.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.authz.has_role(role)
+    }
+
+    /// Whether the authz context grants `permission`
+    ///
+    /// Reads [`authz`](Self::authz), never the unverified `token_claims`.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.authz.has_permission(permission)
+    }
+}
+
+impl Default for AuthContext {
+    fn default() -> Self {
+        Self::anonymous()
+    }
+}
+
+impl fmt::Debug for AuthContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthContext")
+            .field("user_id", &self.user_id)
+            .field("company_id", &self.company_id)
+            .field("request_id", &self.request_id)
+            .field("service", &self.service)
+            .field("impersonator_id", &self.impersonator_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `UNAUTHENTICATED` GraphQL error
+pub(crate) fn unauthenticated(message: &str) -> async_graphql::Error {
+    ErrorCode::Unauthenticated.error(message)
+}
+
+/// `FORBIDDEN` GraphQL error
+pub(crate) fn forbidden(message: &str) -> async_graphql::Error {
+    ErrorCode::Forbidden.error(message)
+}
+
+/// Decode the claims of a bearer token without verifying it
+fn decode_token_claims(token: &str) -> Option<Value> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// The `x-request-id` header, or a generated ID when absent
+pub(crate) fn request_id_from(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_from_headers() {
+        let user_id = Uuid::new_v4();
+        let company_id = Uuid::new_v4();
+        let claims = URL_SAFE_NO_PAD.encode(r#"{"sub":"user-1"}"#);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-user-id",
+            HeaderValue::from_str(&user_id.to_string()).unwrap(),
+        );
+        headers.insert(
+            "x-company-id",
+            HeaderValue::from_str(&company_id.to_string()).unwrap(),
+        );
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("req-1"));
+        headers.insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer header.{}.sig", claims)).unwrap(),
+        );
+
+        let auth = AuthContext::from_headers(&headers);
+        assert_eq!(auth.require_user().unwrap(), user_id);
+        assert_eq!(auth.require_company().unwrap(), company_id);
+        assert_eq!(auth.request_id.as_deref(), Some("req-1"));
+        assert_eq!(auth.token_claims.unwrap()["sub"], "user-1");
+    }
+
+    #[test]
+    fn test_require_user_unauthenticated() {
+        let err = AuthContext::anonymous().require_user().unwrap_err();
+        let code = err.extensions.unwrap().get("code").cloned();
+        assert_eq!(code, Some(async_graphql::Value::from("UNAUTHENTICATED")));
+    }
+}
@@ -0,0 +1,182 @@
+//! `@auth` and `@hasRole` schema directives
+//!
+//! Fields are annotated declaratively and the [`AuthDirectives`] extension
+//! enforces the annotations before the resolver runs, using the same
+//! `UNAUTHENTICATED` / `FORBIDDEN` codes as the [guards](super::guards).
+//! The directives also appear in the exported SDL.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use pleme_graphql_helpers::auth::directives::{auth, has_role, AuthDirectives};
+//!
+//! #[Object]
+//! impl Query {
+//!     #[graphql(directive = auth::apply(None))]
+//!     async fn me(&self, ctx: &Context<'_>) -> Result<User> {
+//!         todo!()
+//!     }
+//!
+//!     #[graphql(directive = auth::apply(Some("ADMIN".to_string())))]
+//!     async fn audit_log(&self) -> Vec<Entry> {
+//!         todo!()
+//!     }
+//!
+//!     #[graphql(directive = has_role::apply("support".to_string()))]
+//!     async fn tickets(&self) -> Vec<Ticket> {
+//!         todo!()
+//!     }
+//! }
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .extension(AuthDirectives)
+//!     .finish();
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo,
+};
+use async_graphql::registry::MetaDirectiveInvocation;
+use async_graphql::{Pos, ServerResult, TypeDirective, Value};
+use std::sync::Arc;
+
+use super::context::unauthenticated;
+use super::guards::require_role;
+use super::AuthContext;
+
+/// Require authentication, and optionally a role
+#[TypeDirective(name = "auth", location = "FieldDefinition")]
+pub fn auth(requires: Option<String>) {}
+
+/// Require a role
+#[TypeDirective(name = "hasRole", location = "FieldDefinition")]
+pub fn has_role(role: String) {}
+
+/// Extension enforcing `@auth` and `@hasRole` on resolved fields
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuthDirectives;
+
+impl ExtensionFactory for AuthDirectives {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(AuthDirectivesExtension)
+    }
+}
+
+struct AuthDirectivesExtension;
+
+#[async_trait::async_trait]
+impl Extension for AuthDirectivesExtension {
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if !info.is_for_introspection {
+            let field = ctx
+                .schema_env
+                .registry
+                .types
+                .get(info.parent_type)
+                .and_then(|ty| ty.field_by_name(info.name));
+            if let Some(field) = field {
+                let auth_ctx = ctx.data_opt::<AuthContext>().cloned().unwrap_or_default();
+                for directive in &field.directive_invocations {
+                    check(&auth_ctx, directive).map_err(|e| e.into_server_error(Pos::default()))?;
+                }
+            }
+        }
+
+        next.run(ctx, info).await
+    }
+}
+
+/// Check one directive invocation; unrelated directives pass
+fn check(auth_ctx: &AuthContext, directive: &MetaDirectiveInvocation) -> async_graphql::Result<()> {
+    match directive.name.as_str() {
+        "auth" => match directive.args.get("requires").and_then(name_arg) {
+            Some(role) => require_role(auth_ctx, role),
+            None if auth_ctx.is_authenticated() => Ok(()),
+            None => Err(unauthenticated("Authentication required")),
+        },
+        "hasRole" => match directive.args.get("role").and_then(name_arg) {
+            Some(role) => require_role(auth_ctx, role),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// A string or enum argument value
+fn name_arg(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Enum(name) => Some(name.as_str()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+    use uuid::Uuid;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(directive = auth::apply(None))]
+        async fn me(&self) -> bool {
+            true
+        }
+
+        #[graphql(directive = auth::apply(Some("ADMIN".to_string())))]
+        async fn admin(&self) -> bool {
+            true
+        }
+
+        async fn public(&self) -> bool {
+            true
+        }
+    }
+
+    async fn error_code(auth_ctx: AuthContext, query: &str) -> Option<Value> {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(AuthDirectives)
+            .finish();
+        let response = schema.execute(Request::new(query).data(auth_ctx)).await;
+        response
+            .errors
+            .first()
+            .and_then(|e| e.extensions.as_ref())
+            .and_then(|ext| ext.get("code").cloned())
+    }
+
+    #[tokio::test]
+    async fn test_auth_directives() {
+        let mut auth_ctx = AuthContext::anonymous();
+        assert_eq!(error_code(auth_ctx.clone(), "{ public }").await, None);
+        assert_eq!(
+            error_code(auth_ctx.clone(), "{ me }").await,
+            Some(Value::from("UNAUTHENTICATED"))
+        );
+
+        auth_ctx.user_id = Some(Uuid::new_v4());
+        assert_eq!(error_code(auth_ctx.clone(), "{ me }").await, None);
+        assert_eq!(
+            error_code(auth_ctx.clone(), "{ admin }").await,
+            Some(Value::from("FORBIDDEN"))
+        );
+
+        auth_ctx.authz = pleme_rbac::AuthzContext::from_claims(
+            auth_ctx.user_id.unwrap(),
+            String::new(),
+            String::new(),
+            vec!["ADMIN".to_string()],
+            Vec::new(),
+            Default::default(),
+        );
+        assert_eq!(error_code(auth_ctx, "{ admin }").await, None);
+    }
+}
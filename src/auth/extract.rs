@@ -0,0 +1,240 @@
+//! Request authentication
+//!
+//! Builds the [`AuthContext`] for a request from its headers and the auth
+//! settings installed as axum `Extension`s ([`AuthHeaderConfig`],
+//! [`SharedApiKeyResolver`], [`GatewayVerifier`], [`ExpiredTokens`], and
+//! with the `jwks` feature a `SharedJwtVerifier`).
+//!
+//! Without a gateway or JWT verifier, identity headers and the bearer token
+//! are trusted as set by the upstream proxy.
+
+use axum::{
+    extract::{FromRequestParts, OriginalUri},
+    http::{request::Parts, Extensions, HeaderMap},
+};
+
+use super::api_key::extract_api_key;
+use super::context::request_id_from;
+use super::gateway::GatewayVerifier;
+use super::impersonation;
+#[cfg(feature = "jwks")]
+use super::jwt::{JwtError, SharedJwtVerifier};
+use super::{AuthContext, AuthHeaderConfig, AuthRejection, SharedApiKeyResolver};
+
+/// Handling of requests whose bearer token has expired
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpiredTokens {
+    /// Reject with `401` and code `TOKEN_EXPIRED` so clients refresh
+    #[default]
+    Reject,
+    /// Continue as an anonymous request
+    Anonymous,
+}
+
+/// Auth settings read from request extensions
+#[derive(Clone, Default)]
+pub(crate) struct AuthSettings {
+    pub(crate) headers: AuthHeaderConfig,
+    pub(crate) api_keys: Option<SharedApiKeyResolver>,
+    pub(crate) gateway: Option<GatewayVerifier>,
+    pub(crate) expired_tokens: ExpiredTokens,
+    #[cfg(feature = "jwks")]
+    pub(crate) jwt: Option<SharedJwtVerifier>,
+}
+
+impl AuthSettings {
+    /// Settings stored by an [`AuthLayer`](super::AuthLayer), or built from
+    /// individually installed extensions
+    pub(crate) fn from_extensions(extensions: &Extensions) -> Self {
+        if let Some(settings) = extensions.get::<AuthSettings>() {
+            return settings.clone();
+        }
+
+        Self {
+            headers: extensions
+                .get::<AuthHeaderConfig>()
+                .cloned()
+                .unwrap_or_default(),
+            api_keys: extensions.get::<SharedApiKeyResolver>().cloned(),
+            gateway: extensions.get::<GatewayVerifier>().cloned(),
+            expired_tokens: extensions
+                .get::<ExpiredTokens>()
+                .copied()
+                .unwrap_or_default(),
+            #[cfg(feature = "jwks")]
+            jwt: extensions.get::<SharedJwtVerifier>().cloned(),
+        }
+    }
+}
+
+/// Build the request's [`AuthContext`]
+///
+/// With a [`GatewayVerifier`], every request must carry a valid gateway
+/// signature over its `method`, `path`, and identity headers or it is
+/// rejected with `401 Unauthorized`. Requests carrying `x-api-key` are
+/// then resolved with the API key resolver and rejected with
+/// `401 Unauthorized` if the key is unknown or no resolver is installed.
+/// With a JWT verifier, other requests are identified by their verified
+/// bearer token alone; an invalid token is rejected with `401` and a
+/// request without one is anonymous. Otherwise they use the identity
+/// headers and bearer token. An expired bearer token is handled per
+/// [`ExpiredTokens`]. Impersonation without permission is rejected with
+/// `403 Forbidden`.
+pub(crate) async fn authenticate(
+    method: &str,
+    path: &str,
+    headers: &HeaderMap,
+    settings: &AuthSettings,
+) -> Result<AuthContext, AuthRejection> {
+    if let Some(gateway) = &settings.gateway {
+        gateway
+            .verify(method, path, headers, &settings.headers)
+            .map_err(|e| AuthRejection::unauthenticated(e.to_string()))?;
+    }
+
+    let Some(key) = extract_api_key(headers) else {
+        #[cfg(feature = "jwks")]
+        if let Some(verifier) = &settings.jwt {
+            return match verifier.auth_context(headers, &settings.headers).await {
+                Ok(auth) => Ok(auth),
+                Err(JwtError::MissingToken) => Ok(AuthContext::anonymous_request(headers)),
+                Err(JwtError::Expired) if settings.expired_tokens == ExpiredTokens::Anonymous => {
+                    Ok(AuthContext::anonymous_request(headers))
+                }
+                Err(e) => Err(e.into()),
+            };
+        }
+
+        let from_headers = AuthContext::from_headers_with(headers, &settings.headers);
+        if from_headers.token_expired() {
+            return match settings.expired_tokens {
+                ExpiredTokens::Reject => Err(AuthRejection::token_expired()),
+                ExpiredTokens::Anonymous => Ok(AuthContext {
+                    request_id: from_headers.request_id,
+                    ..AuthContext::anonymous()
+                }),
+            };
+        }
+        impersonation::check(&from_headers)?;
+        return Ok(from_headers);
+    };
+
+    let mut auth = match &settings.api_keys {
+        Some(resolver) => resolver.resolve(key).await,
+        None => None,
+    }
+    .ok_or_else(|| AuthRejection::unauthenticated("Invalid API key"))?;
+
+    if auth.request_id.is_none() {
+        auth.request_id = Some(request_id_from(headers));
+    }
+    Ok(auth)
+}
+
+/// Path of the request as received, before any router nesting stripped it
+pub(crate) fn request_path(parts: &Parts) -> &str {
+    parts
+        .extensions
+        .get::<OriginalUri>()
+        .map_or(parts.uri.path(), |uri| uri.path())
+}
+
+/// Extractor for the request's [`AuthContext`]
+///
+/// Usable in REST handlers as well as the GraphQL handlers. Behind an
+/// [`AuthLayer`](super::AuthLayer) this reads the context the layer stored;
+/// otherwise it authenticates the request itself.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pleme_graphql_helpers::auth::Authenticated;
+///
+/// async fn me(Authenticated(auth): Authenticated) -> String {
+///     format!("{:?}", auth.user_id)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Authenticated(pub AuthContext);
+
+impl<S> FromRequestParts<S> for Authenticated
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(auth) = parts.extensions.get::<AuthContext>() {
+            return Ok(Authenticated(auth.clone()));
+        }
+
+        let settings = AuthSettings::from_extensions(&parts.extensions);
+        authenticate(
+            parts.method.as_str(),
+            request_path(parts),
+            &parts.headers,
+            &settings,
+        )
+        .await
+        .map(Authenticated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::rejection::TOKEN_EXPIRED;
+    use axum::http::{HeaderValue, Request};
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_extractor_uses_header_config() {
+        let user_id = Uuid::new_v4();
+        let (mut parts, _) = Request::builder()
+            .header("x-gw-user", user_id.to_string())
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts
+            .extensions
+            .insert(AuthHeaderConfig::new().with_user_id_header("x-gw-user"));
+        parts
+            .headers
+            .insert("x-user-id", HeaderValue::from_static("ignored"));
+
+        let Authenticated(auth) = Authenticated::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(auth.user_id, Some(user_id));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token() {
+        let claims = URL_SAFE_NO_PAD.encode(r#"{"sub":"user-1","exp":1000}"#);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_str(&format!("Bearer header.{}.sig", claims)).unwrap(),
+        );
+        headers.insert(
+            "x-user-id",
+            HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap(),
+        );
+
+        let rejection = authenticate("POST", "/graphql", &headers, &AuthSettings::default())
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.code, TOKEN_EXPIRED);
+
+        let settings = AuthSettings {
+            expired_tokens: ExpiredTokens::Anonymous,
+            ..Default::default()
+        };
+        let auth = authenticate("POST", "/graphql", &headers, &settings)
+            .await
+            .unwrap();
+        assert!(!auth.is_authenticated());
+        assert!(auth.token_claims.is_none());
+    }
+}
@@ -0,0 +1,144 @@
+//! Reusable resolver guards
+//!
+//! Read the injected [`AuthContext`] and fail with standard `code`
+//! extensions: `UNAUTHENTICATED` when no user is present and `FORBIDDEN`
+//! when the user lacks a role or permission.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! #[Object]
+//! impl Mutation {
+//!     #[graphql(guard = "CompanyRequired.and(PermissionRequired(\"orders:write\"))")]
+//!     async fn create_order(&self, ctx: &Context<'_>, input: OrderInput) -> Result<Order> {
+//!         todo!()
+//!     }
+//! }
+//! ```
+
+use async_graphql::{Context, Guard, Result};
+
+use super::context::{forbidden, unauthenticated};
+use super::AuthContext;
+
+/// Requires an authenticated user or service
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuthRequired;
+
+impl Guard for AuthRequired {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        if AuthContext::from_ctx(ctx).is_authenticated() {
+            Ok(())
+        } else {
+            Err(unauthenticated("Authentication required"))
+        }
+    }
+}
+
+/// Requires an authenticated user with a company context
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompanyRequired;
+
+impl Guard for CompanyRequired {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        let auth = AuthContext::from_ctx(ctx);
+        auth.require_user()?;
+        auth.require_company().map(|_| ())
+    }
+}
+
+/// Requires an authenticated user with a role
+#[derive(Debug, Clone, Copy)]
+pub struct RoleRequired(pub &'static str);
+
+impl Guard for RoleRequired {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        require_role(&AuthContext::from_ctx(ctx), self.0)
+    }
+}
+
+/// Requires an authenticated user with a permission
+#[derive(Debug, Clone, Copy)]
+pub struct PermissionRequired(pub &'static str);
+
+impl Guard for PermissionRequired {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        let auth = AuthContext::from_ctx(ctx);
+        auth.require_user()?;
+        if auth.has_permission(self.0) {
+            Ok(())
+        } else {
+            Err(forbidden(&format!("Permission '{}' required", self.0)))
+        }
+    }
+}
+
+/// An authenticated user with `role`, shared with the `@auth` directives
+pub(crate) fn require_role(auth: &AuthContext, role: &str) -> Result<()> {
+    auth.require_user()?;
+    if auth.has_role(role) {
+        Ok(())
+    } else {
+        Err(forbidden(&format!("Role '{}' required", role)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema, Value};
+    use pleme_rbac::AuthzContext;
+    use uuid::Uuid;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(guard = "RoleRequired(\"admin\")")]
+        async fn admin(&self) -> bool {
+            true
+        }
+
+        #[graphql(guard = "PermissionRequired(\"orders:read\")")]
+        async fn orders(&self) -> bool {
+            true
+        }
+    }
+
+    async fn error_code(auth: AuthContext, query: &str) -> Option<Value> {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let response = schema.execute(Request::new(query).data(auth)).await;
+        response
+            .errors
+            .first()
+            .and_then(|e| e.extensions.as_ref())
+            .and_then(|ext| ext.get("code").cloned())
+    }
+
+    #[tokio::test]
+    async fn test_guard_error_codes() {
+        let mut auth = AuthContext::anonymous();
+        assert_eq!(
+            error_code(auth.clone(), "{ admin }").await,
+            Some(Value::from("UNAUTHENTICATED"))
+        );
+
+        let user_id = Uuid::new_v4();
+        auth.user_id = Some(user_id);
+        // Unverified claims grant nothing
+        auth.token_claims = Some(serde_json::json!({ "roles": ["admin"] }));
+        auth.authz = AuthzContext::from_claims(
+            user_id,
+            String::new(),
+            String::new(),
+            vec!["member".to_string()],
+            vec!["orders:read".to_string()],
+            Default::default(),
+        );
+        assert_eq!(
+            error_code(auth.clone(), "{ admin }").await,
+            Some(Value::from("FORBIDDEN"))
+        );
+        assert_eq!(error_code(auth, "{ orders }").await, None);
+    }
+}
@@ -0,0 +1,143 @@
+//! Support staff acting on behalf of customers
+//!
+//! The gateway sends the customer as `x-user-id` and the staff member as
+//! `x-impersonator-id`. The request's [`AuthzContext`](pleme_rbac::AuthzContext)
+//! must be the staff member's and grant [`IMPERSONATE_PERMISSION`];
+//! otherwise the request is rejected with `403 Forbidden`. With a
+//! [`GatewayVerifier`](super::gateway::GatewayVerifier) the impersonator
+//! header is covered by the gateway signature. Impersonated responses carry
+//! an `impersonation` extension for audit.
+
+use async_graphql::{Context, Response, Value};
+use uuid::Uuid;
+
+use super::{AuthContext, AuthRejection};
+
+/// Header carrying the real (impersonating) user's ID
+pub const IMPERSONATOR_HEADER: &str = "x-impersonator-id";
+
+/// Permission required to impersonate
+pub const IMPERSONATE_PERMISSION: &str = "users:impersonate";
+
+/// Real and effective identity of an impersonated request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Impersonation {
+    /// The staff member making the request
+    pub real_user_id: Uuid,
+    /// The user being acted as
+    pub effective_user_id: Option<Uuid>,
+}
+
+impl Impersonation {
+    /// The impersonation details of `auth`, if any
+    pub fn from_auth(auth: &AuthContext) -> Option<Self> {
+        auth.impersonator_id.map(|real_user_id| Self {
+            real_user_id,
+            effective_user_id: auth.user_id,
+        })
+    }
+
+    fn to_value(self) -> Value {
+        // Plain JSON objects always convert
+        Value::from_json(serde_json::json!({
+            "realUserId": self.real_user_id,
+            "effectiveUserId": self.effective_user_id,
+        }))
+        .unwrap_or_default()
+    }
+}
+
+/// Get the impersonation details of the current request
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::Context;
+/// use pleme_graphql_helpers::auth::get_impersonation;
+///
+/// fn acting_staff(ctx: &Context<'_>) -> Option<uuid::Uuid> {
+///     get_impersonation(ctx).map(|i| i.real_user_id)
+/// }
+/// ```
+pub fn get_impersonation(ctx: &Context<'_>) -> Option<Impersonation> {
+    ctx.data_opt::<AuthContext>()
+        .and_then(Impersonation::from_auth)
+}
+
+/// Reject impersonation unless the authz context is the impersonator's and
+/// grants [`IMPERSONATE_PERMISSION`]
+pub(crate) fn check(auth: &AuthContext) -> Result<(), AuthRejection> {
+    let Some(impersonator_id) = auth.impersonator_id else {
+        return Ok(());
+    };
+    if auth.authz.user_id == impersonator_id && auth.authz.has_permission(IMPERSONATE_PERMISSION) {
+        Ok(())
+    } else {
+        Err(AuthRejection::forbidden(format!(
+            "Permission '{}' required",
+            IMPERSONATE_PERMISSION
+        )))
+    }
+}
+
+/// Add the `impersonation` extension to an impersonated response
+pub(crate) fn tag_response(response: &mut Response, auth: &AuthContext) {
+    if let Some(impersonation) = Impersonation::from_auth(auth) {
+        response
+            .extensions
+            .insert("impersonation".to_string(), impersonation.to_value());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    fn impersonating(permissions: &[&str]) -> AuthContext {
+        let mut auth = AuthContext::anonymous();
+        let impersonator_id = Uuid::new_v4();
+        auth.user_id = Some(Uuid::new_v4());
+        auth.impersonator_id = Some(impersonator_id);
+        auth.authz = pleme_rbac::AuthzContext::from_claims(
+            impersonator_id,
+            String::new(),
+            String::new(),
+            Vec::new(),
+            permissions.iter().map(|p| p.to_string()).collect(),
+            Default::default(),
+        );
+        auth
+    }
+
+    #[test]
+    fn test_check_requires_permission() {
+        assert!(check(&AuthContext::anonymous()).is_ok());
+        assert!(check(&impersonating(&[IMPERSONATE_PERMISSION])).is_ok());
+        assert_eq!(
+            check(&impersonating(&["orders:read"])).unwrap_err().status,
+            StatusCode::FORBIDDEN
+        );
+
+        // Unverified claims and another staff member's authz grant nothing
+        let mut forged = impersonating(&[]);
+        forged.token_claims = Some(serde_json::json!({ "permissions": [IMPERSONATE_PERMISSION] }));
+        assert!(check(&forged).is_err());
+        let mut other = impersonating(&[IMPERSONATE_PERMISSION]);
+        other.impersonator_id = Some(Uuid::new_v4());
+        assert!(check(&other).is_err());
+    }
+
+    #[test]
+    fn test_tag_response() {
+        let auth = impersonating(&[IMPERSONATE_PERMISSION]);
+        let mut response = Response::new(Value::Null);
+        tag_response(&mut response, &auth);
+
+        let tag = response.extensions["impersonation"]
+            .clone()
+            .into_json()
+            .unwrap();
+        assert_eq!(tag["realUserId"], auth.impersonator_id.unwrap().to_string());
+    }
+}
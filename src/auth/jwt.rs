@@ -0,0 +1,487 @@
+//! Local JWT verification against a JWKS endpoint
+//!
+//! For services at the edge that can't rely on an upstream gateway having
+//! verified the token. Keys are cached and refetched when they go stale or
+//! when a token is signed with an unknown `kid` (key rotation).
+
+use axum::http::HeaderMap;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use pleme_rbac::AuthzContext;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use super::{AuthContext, AuthHeaderConfig, AuthRejection};
+
+/// Default interval after which cached keys are refetched
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Default minimum time between fetches triggered by unknown key IDs
+pub const DEFAULT_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default timeout for JWKS requests
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Claim carrying the caller's company ID
+pub const COMPANY_ID_CLAIM: &str = "company_id";
+
+/// A [`JwtVerifier`] shared across requests
+pub type SharedJwtVerifier = Arc<JwtVerifier>;
+
+/// JWT verification errors
+#[derive(Error, Debug)]
+pub enum JwtError {
+    #[error("Missing bearer token")]
+    MissingToken,
+
+    #[error("Token header has no key ID")]
+    MissingKeyId,
+
+    #[error("Unknown signing key: {0}")]
+    UnknownKey(String),
+
+    #[error("Failed to fetch JWKS: {0}")]
+    Fetch(String),
+
+    #[error("Token expired")]
+    Expired,
+
+    #[error("Invalid token: {0}")]
+    Invalid(#[from] jsonwebtoken::errors::Error),
+
+    #[error("Token claims rejected by authz")]
+    Authz,
+}
+
+impl From<JwtError> for AuthRejection {
+    fn from(error: JwtError) -> Self {
+        match error {
+            JwtError::Expired => AuthRejection::token_expired(),
+            other => AuthRejection::unauthenticated(other.to_string()),
+        }
+    }
+}
+
+/// Cached decoding keys by key ID
+#[derive(Default)]
+struct KeyCache {
+    keys: HashMap<String, DecodingKey>,
+    fetched_at: Option<Instant>,
+    attempted_at: Option<Instant>,
+}
+
+/// Verifies JWT signature, `exp`, `aud`, and `iss` with keys from a JWKS
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let verifier = JwtVerifier::new("https://auth.pleme.io/.well-known/jwks.json")
+///     .with_audience("orders-api")
+///     .with_issuer("https://auth.pleme.io/");
+///
+/// let authz = verifier.authz(token).await?;
+///
+/// // Or authenticate every request with it
+/// let layer = AuthLayer::new().with_jwt_verifier(verifier);
+/// ```
+pub struct JwtVerifier {
+    jwks_url: Option<String>,
+    audience: Vec<String>,
+    issuer: Vec<String>,
+    leeway: u64,
+    refresh_interval: Duration,
+    min_refresh_interval: Duration,
+    fetch_timeout: Duration,
+    client: reqwest::Client,
+    cache: RwLock<KeyCache>,
+    /// Held while fetching, so only one request refreshes at a time
+    refresh: Mutex<()>,
+}
+
+impl JwtVerifier {
+    /// Verifier fetching keys from `jwks_url`
+    pub fn new(jwks_url: impl Into<String>) -> Self {
+        Self::build(Some(jwks_url.into()), HashMap::new())
+    }
+
+    /// Verifier with a fixed key set and no remote fetching
+    pub fn from_jwks(jwks: &JwkSet) -> Result<Self, JwtError> {
+        Ok(Self::build(None, decoding_keys(jwks)?))
+    }
+
+    fn build(jwks_url: Option<String>, keys: HashMap<String, DecodingKey>) -> Self {
+        Self {
+            jwks_url,
+            audience: Vec::new(),
+            issuer: Vec::new(),
+            leeway: 60,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            min_refresh_interval: DEFAULT_MIN_REFRESH_INTERVAL,
+            fetch_timeout: DEFAULT_FETCH_TIMEOUT,
+            client: reqwest::Client::new(),
+            cache: RwLock::new(KeyCache {
+                keys,
+                ..Default::default()
+            }),
+            refresh: Mutex::new(()),
+        }
+    }
+
+    /// Accept tokens for `audience` (may be called repeatedly)
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.push(audience.into());
+        self
+    }
+
+    /// Accept tokens from `issuer` (may be called repeatedly)
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer.push(issuer.into());
+        self
+    }
+
+    /// Clock skew tolerance in seconds for `exp`/`nbf` (default 60)
+    pub fn with_leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// Refetch keys after this interval (default 1 hour)
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Give up on JWKS requests after this long (default 5 seconds)
+    pub fn with_fetch_timeout(mut self, timeout: Duration) -> Self {
+        self.fetch_timeout = timeout;
+        self
+    }
+
+    /// Use a custom HTTP client for JWKS requests
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Verify a token and return its claims
+    pub async fn verify(&self, token: &str) -> Result<Value, JwtError> {
+        let header = decode_header(token)?;
+        let kid = header.kid.ok_or(JwtError::MissingKeyId)?;
+        let key = self.key(&kid).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = self.leeway;
+        validation.validate_nbf = true;
+        if self.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&self.audience);
+        }
+        if !self.issuer.is_empty() {
+            validation.set_issuer(&self.issuer);
+        }
+
+        decode::<Value>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => JwtError::Expired,
+                _ => JwtError::Invalid(e),
+            })
+    }
+
+    /// Verify a token and build its [`AuthzContext`]
+    pub async fn authz(&self, token: &str) -> Result<AuthzContext, JwtError> {
+        self.verify(token).await?;
+        AuthzContext::from_jwt(token).map_err(|_| JwtError::Authz)
+    }
+
+    /// Build an [`AuthContext`] from a verified bearer token
+    ///
+    /// The token is read per `config`. User and company IDs come from the
+    /// verified `sub` and `company_id` claims, never from the identity
+    /// headers; impersonation is not available to verified-token callers.
+    pub async fn auth_context(
+        &self,
+        headers: &HeaderMap,
+        config: &AuthHeaderConfig,
+    ) -> Result<AuthContext, JwtError> {
+        let token = config.bearer_token(headers).ok_or(JwtError::MissingToken)?;
+        let claims = self.verify(token).await?;
+        let authz = AuthzContext::from_jwt(token).map_err(|_| JwtError::Authz)?;
+
+        Ok(AuthContext {
+            user_id: Some(authz.user_id),
+            company_id: claims
+                .get(COMPANY_ID_CLAIM)
+                .and_then(Value::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok()),
+            authz,
+            token_claims: Some(claims),
+            ..AuthContext::anonymous_request(headers)
+        })
+    }
+
+    /// Decoding key for `kid`, refreshing the key set when needed
+    ///
+    /// The fetch runs outside the cache lock, so a slow JWKS endpoint only
+    /// holds up requests waiting for an unknown key; stale known keys keep
+    /// being served while another request refreshes them.
+    async fn key(&self, kid: &str) -> Result<DecodingKey, JwtError> {
+        {
+            let cache = self.cache.read().await;
+            if !self.needs_refresh(&cache, kid)
+                || (cache.keys.contains_key(kid) && self.refresh.try_lock().is_err())
+            {
+                return cache.get(kid);
+            }
+        }
+
+        let _refresh = self.refresh.lock().await;
+        {
+            let mut cache = self.cache.write().await;
+            // Another request may have refreshed while we waited for the lock
+            if !self.needs_refresh(&cache, kid) {
+                return cache.get(kid);
+            }
+            cache.attempted_at = Some(Instant::now());
+        }
+
+        let fetched = self.fetch().await;
+        let mut cache = self.cache.write().await;
+        match fetched {
+            Ok(keys) => {
+                cache.keys = keys;
+                cache.fetched_at = cache.attempted_at;
+            }
+            Err(e) if cache.keys.is_empty() => return Err(e),
+            // Keep serving cached keys while the endpoint is down
+            Err(_) => {}
+        }
+
+        cache.get(kid)
+    }
+
+    /// Whether to refetch before looking up `kid`
+    ///
+    /// Known keys are refreshed after `refresh_interval`; unknown key IDs
+    /// (rotation) trigger a fetch at most every `min_refresh_interval`.
+    fn needs_refresh(&self, cache: &KeyCache, kid: &str) -> bool {
+        if self.jwks_url.is_none() {
+            return false;
+        }
+        let max_age = if cache.keys.contains_key(kid) {
+            self.refresh_interval
+        } else {
+            self.min_refresh_interval
+        };
+        older_than(cache.fetched_at, max_age)
+            && older_than(cache.attempted_at, self.min_refresh_interval)
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, DecodingKey>, JwtError> {
+        let Some(url) = &self.jwks_url else {
+            return Ok(HashMap::new());
+        };
+        let jwks: JwkSet = self
+            .client
+            .get(url)
+            .timeout(self.fetch_timeout)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| JwtError::Fetch(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| JwtError::Fetch(e.to_string()))?;
+        decoding_keys(&jwks)
+    }
+}
+
+impl KeyCache {
+    fn get(&self, kid: &str) -> Result<DecodingKey, JwtError> {
+        self.keys
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| JwtError::UnknownKey(kid.to_string()))
+    }
+}
+
+/// Keys with a `kid`; keys without one can't be selected and are skipped
+fn decoding_keys(jwks: &JwkSet) -> Result<HashMap<String, DecodingKey>, JwtError> {
+    let mut keys = HashMap::new();
+    for jwk in &jwks.keys {
+        if let Some(kid) = &jwk.common.key_id {
+            keys.insert(kid.clone(), DecodingKey::from_jwk(jwk)?);
+        }
+    }
+    Ok(keys)
+}
+
+/// Whether `at` is unset or more than `age` ago
+fn older_than(at: Option<Instant>, age: Duration) -> bool {
+    !at.is_some_and(|at| at.elapsed() < age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+    const SECRET: &[u8] = b"test-signing-secret";
+
+    fn verifier() -> JwtVerifier {
+        let jwks: JwkSet = serde_json::from_value(serde_json::json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "key-1",
+                "alg": "HS256",
+                "k": URL_SAFE_NO_PAD.encode(SECRET),
+            }]
+        }))
+        .unwrap();
+        JwtVerifier::from_jwks(&jwks)
+            .unwrap()
+            .with_audience("orders-api")
+    }
+
+    fn token(kid: &str, aud: &str) -> String {
+        token_expiring(kid, aud, chrono::Utc::now().timestamp() + 300)
+    }
+
+    fn token_expiring(kid: &str, aud: &str, exp: i64) -> String {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(kid.to_string());
+        let claims = serde_json::json!({
+            "sub": "user-1",
+            "aud": aud,
+            "exp": exp,
+        });
+        encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify_valid_token() {
+        let claims = verifier()
+            .verify(&token("key-1", "orders-api"))
+            .await
+            .unwrap();
+        assert_eq!(claims["sub"], "user-1");
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_bad_tokens() {
+        let verifier = verifier();
+        assert!(matches!(
+            verifier.verify(&token("key-1", "billing-api")).await,
+            Err(JwtError::Invalid(_))
+        ));
+        assert!(matches!(
+            verifier.verify(&token("key-2", "orders-api")).await,
+            Err(JwtError::UnknownKey(_))
+        ));
+
+        let expired = token_expiring("key-1", "orders-api", 1000);
+        assert!(matches!(
+            verifier.verify(&expired).await,
+            Err(JwtError::Expired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hung_jwks_fetch_does_not_block_known_keys() {
+        // Accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/jwks.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                open.push(socket);
+            }
+        });
+
+        let mut fixed = verifier();
+        let keys = std::mem::take(&mut fixed.cache.get_mut().keys);
+        let mut verifier = JwtVerifier::build(Some(url), keys)
+            .with_audience("orders-api")
+            .with_fetch_timeout(Duration::from_millis(500));
+        verifier.cache.get_mut().fetched_at = Some(Instant::now());
+        let verifier = Arc::new(verifier);
+
+        let unknown = tokio::spawn({
+            let verifier = verifier.clone();
+            async move { verifier.verify(&token("key-2", "orders-api")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let known = tokio::time::timeout(
+            Duration::from_millis(100),
+            verifier.verify(&token("key-1", "orders-api")),
+        )
+        .await;
+        assert!(matches!(known, Ok(Ok(_))));
+
+        assert!(matches!(
+            unknown.await.unwrap(),
+            Err(JwtError::UnknownKey(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_uses_verified_claims() {
+        use crate::auth::{authenticate, AuthSettings};
+        use axum::http::HeaderValue;
+
+        let (user_id, company_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("key-1".to_string());
+        let claims = serde_json::json!({
+            "sub": user_id.to_string(),
+            "company_id": company_id.to_string(),
+            "aud": "orders-api",
+            "exp": chrono::Utc::now().timestamp() + 300,
+        });
+        let signed = encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap();
+
+        let settings = AuthSettings {
+            headers: AuthHeaderConfig::new().with_authorization_header("x-token"),
+            jwt: Some(Arc::new(verifier())),
+            ..Default::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-token",
+            HeaderValue::from_str(&format!("Bearer {}", signed)).unwrap(),
+        );
+        headers.insert(
+            "x-user-id",
+            HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap(),
+        );
+
+        let auth = authenticate("POST", "/graphql", &headers, &settings)
+            .await
+            .unwrap();
+        assert_eq!(auth.user_id, Some(user_id));
+        assert_eq!(auth.company_id, Some(company_id));
+
+        headers.insert(
+            "x-token",
+            HeaderValue::from_str(&format!("Bearer {}", token("key-1", "billing-api"))).unwrap(),
+        );
+        assert!(authenticate("POST", "/graphql", &headers, &settings)
+            .await
+            .is_err());
+
+        headers.remove("x-token");
+        let auth = authenticate("POST", "/graphql", &headers, &settings)
+            .await
+            .unwrap();
+        assert!(!auth.is_authenticated());
+    }
+}
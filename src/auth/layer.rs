@@ -0,0 +1,187 @@
+//! Tower middleware for request authentication
+//!
+//! [`AuthLayer`] authenticates once at the router level and stores the
+//! [`AuthContext`] in the request extensions, so REST routes and the GraphQL
+//! handlers share one extraction. [`Authenticated`](super::Authenticated)
+//! reads the stored context instead of authenticating again. Responses echo
+//! the request ID in `x-request-id`.
+
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use super::extract::request_path;
+use super::gateway::GatewayVerifier;
+#[cfg(feature = "jwks")]
+use super::jwt::JwtVerifier;
+use super::request_id;
+use super::{authenticate, AuthHeaderConfig, AuthSettings, ExpiredTokens, SharedApiKeyResolver};
+
+/// Layer adding an [`AuthContext`](super::AuthContext) to every request
+///
+/// Requests with an invalid API key or gateway signature are rejected with
+/// `401 Unauthorized`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription};
+/// use axum::{Router, routing::{get, post}};
+/// use pleme_graphql_helpers::auth::{graphql_handler, AuthHeaderConfig, AuthLayer, Authenticated};
+/// # struct Query;
+/// # #[async_graphql::Object]
+/// # impl Query {
+/// #     async fn ping(&self) -> bool {
+/// #         true
+/// #     }
+/// # }
+///
+/// async fn me(Authenticated(auth): Authenticated) -> String {
+///     format!("{:?}", auth.user_id)
+/// }
+///
+/// let app: Router = Router::new()
+///     .route("/me", get(me))
+///     .route(
+///         "/graphql",
+///         post(graphql_handler::<Query, EmptyMutation, EmptySubscription>),
+///     )
+///     .layer(AuthLayer::new().with_header_config(AuthHeaderConfig::from_env()));
+/// ```
+#[derive(Clone, Default)]
+pub struct AuthLayer {
+    settings: AuthSettings,
+}
+
+impl AuthLayer {
+    /// Layer using the default header names
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use custom identity header names
+    pub fn with_header_config(mut self, config: AuthHeaderConfig) -> Self {
+        self.settings.headers = config;
+        self
+    }
+
+    /// Resolve `x-api-key` requests with `resolver`
+    pub fn with_api_keys(mut self, resolver: SharedApiKeyResolver) -> Self {
+        self.settings.api_keys = Some(resolver);
+        self
+    }
+
+    /// Require gateway-signed identity headers
+    pub fn with_gateway(mut self, verifier: GatewayVerifier) -> Self {
+        self.settings.gateway = Some(verifier);
+        self
+    }
+
+    /// Handle expired bearer tokens per `policy`
+    pub fn with_expired_tokens(mut self, policy: ExpiredTokens) -> Self {
+        self.settings.expired_tokens = policy;
+        self
+    }
+
+    /// Identify callers by a bearer token verified with `verifier`
+    #[cfg(feature = "jwks")]
+    pub fn with_jwt_verifier(mut self, verifier: JwtVerifier) -> Self {
+        self.settings.jwt = Some(std::sync::Arc::new(verifier));
+        self
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            settings: self.settings.clone(),
+        }
+    }
+}
+
+/// Service produced by [`AuthLayer`]
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    settings: AuthSettings,
+}
+
+impl<S> Service<Request> for AuthService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // Use the service that was polled ready, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let settings = self.settings.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+            let path = request_path(&parts).to_string();
+            match authenticate(parts.method.as_str(), &path, &parts.headers, &settings).await {
+                Ok(auth) => {
+                    let request_id = request_id::response_headers(&auth);
+                    parts.extensions.insert(auth);
+                    // For handlers that authenticate later (WebSocket init)
+                    parts.extensions.insert(settings);
+                    let mut response = inner.call(Request::from_parts(parts, body)).await?;
+                    response.headers_mut().extend(request_id);
+                    Ok(response)
+                }
+                Err(rejection) => Ok(rejection.into_response()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Authenticated;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use uuid::Uuid;
+
+    async fn user(Authenticated(auth): Authenticated) -> String {
+        auth.user_id.map(|id| id.to_string()).unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_layer_stores_auth_context() {
+        let user_id = Uuid::new_v4();
+        let mut app = Router::new().route("/", get(user)).layer(
+            AuthLayer::new()
+                .with_header_config(AuthHeaderConfig::new().with_user_id_header("x-gw-user")),
+        );
+
+        let request = Request::builder()
+            .uri("/")
+            .header("x-gw-user", user_id.to_string())
+            .body(Body::empty())
+            .unwrap();
+        let response = app.call(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, user_id.to_string());
+    }
+}
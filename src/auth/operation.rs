@@ -0,0 +1,132 @@
+//! Pre-execution operation inspection
+//!
+//! Handler policies (audit, tenant enforcement) need the operation type and
+//! root fields before execution. The document is parsed once per request.
+
+use async_graphql::parser::types::{
+    DocumentOperations, ExecutableDocument, OperationDefinition, OperationType, Selection,
+    SelectionSet,
+};
+use async_graphql::Request;
+
+/// The operation a request will execute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OperationInfo {
+    pub(crate) ty: OperationType,
+    /// Names of the selected root fields (aliases resolved)
+    pub(crate) root_fields: Vec<String>,
+}
+
+impl OperationInfo {
+    /// Parse the operation selected by the request
+    ///
+    /// Returns `None` for unparseable queries or unknown operation names;
+    /// execution reports those errors.
+    pub(crate) fn parse(request: &Request) -> Option<Self> {
+        let document = async_graphql::parser::parse_query(&request.query).ok()?;
+        let operation = select_operation(&document, request.operation_name.as_deref())?;
+        Some(Self::from_operation(&document, operation))
+    }
+
+    /// Inspect an operation of an already parsed document
+    pub(crate) fn from_operation(
+        document: &ExecutableDocument,
+        operation: &OperationDefinition,
+    ) -> Self {
+        let mut root_fields = Vec::new();
+        collect_fields(
+            document,
+            &operation.selection_set.node,
+            &mut Vec::new(),
+            &mut root_fields,
+        );
+
+        Self {
+            ty: operation.ty,
+            root_fields,
+        }
+    }
+
+    pub(crate) fn is_mutation(&self) -> bool {
+        self.ty == OperationType::Mutation
+    }
+
+    /// Whether every root field is an introspection field
+    pub(crate) fn is_introspection(&self) -> bool {
+        !self.root_fields.is_empty()
+            && self
+                .root_fields
+                .iter()
+                .all(|field| matches!(field.as_str(), "__schema" | "__type" | "__typename"))
+    }
+}
+
+/// The operation `operation_name` selects in `document`
+///
+/// Without a name, the first operation is used.
+pub(crate) fn select_operation<'a>(
+    document: &'a ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<&'a OperationDefinition> {
+    let operation = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(op), _) => op,
+        (DocumentOperations::Multiple(ops), Some(name)) => ops
+            .iter()
+            .find(|(op_name, _)| op_name.as_str() == name)
+            .map(|(_, op)| op)?,
+        (DocumentOperations::Multiple(ops), None) => ops.values().next()?,
+    };
+    Some(&operation.node)
+}
+
+/// Field names in a selection set, expanding fragments
+///
+/// The document isn't validated yet, so fragment cycles are skipped.
+fn collect_fields<'a>(
+    document: &'a ExecutableDocument,
+    set: &'a SelectionSet,
+    visited: &mut Vec<&'a str>,
+    fields: &mut Vec<String>,
+) {
+    for selection in &set.items {
+        match &selection.node {
+            Selection::Field(field) => fields.push(field.node.name.node.to_string()),
+            Selection::InlineFragment(fragment) => {
+                collect_fields(document, &fragment.node.selection_set.node, visited, fields)
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                if visited.contains(&name.as_str()) {
+                    continue;
+                }
+                if let Some(fragment) = document.fragments.get(name) {
+                    visited.push(name.as_str());
+                    collect_fields(document, &fragment.node.selection_set.node, visited, fields);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_operation() {
+        let doc = "query A { me { id } } mutation B { logout } \
+                   query C { ...Root } fragment Root on Query { __typename }";
+
+        let a = OperationInfo::parse(&Request::new(doc).operation_name("A")).unwrap();
+        assert!(!a.is_mutation());
+        assert_eq!(a.root_fields, vec!["me"]);
+
+        let b = OperationInfo::parse(&Request::new(doc).operation_name("B")).unwrap();
+        assert!(b.is_mutation());
+
+        let c = OperationInfo::parse(&Request::new(doc).operation_name("C")).unwrap();
+        assert!(c.is_introspection());
+
+        assert!(OperationInfo::parse(&Request::new("{ broken")).is_none());
+    }
+}
@@ -0,0 +1,415 @@
+//! Pre-execution request policies
+//!
+//! Policies are installed as axum `Extension`s and checked by the handlers
+//! for every operation before it executes. A rejected operation gets an
+//! error response with a standard `code` extension; other operations in the
+//! same batch still run.
+
+use async_graphql::{Pos, Request, ServerError};
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, Extensions},
+};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::fmt;
+use std::sync::Arc;
+
+use super::context::{forbidden, unauthenticated};
+use super::operation::OperationInfo;
+use super::{AuthContext, AuthRejection, SharedAuditSink};
+use crate::error::ErrorCode;
+use crate::extensions::persisted::{requested_hash, SharedOperationRegistry};
+
+/// Predicate allowing an anonymous request
+pub type AnonymousPredicate = Arc<dyn Fn(&Request) -> bool + Send + Sync>;
+
+/// Reject unauthenticated operations
+///
+/// Public operations (health checks, `login`, a public catalog) are
+/// allowlisted by root field, or by a predicate over the request (e.g. to
+/// admit known persisted operations). Operation names aren't used since
+/// clients choose them freely. Introspection is always allowed.
+///
+/// Predicates see the request after the handlers resolve its persisted
+/// query hash, so match on the query text that will execute. The
+/// `persistedQuery` extension itself is client-supplied and proves nothing.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{Extension, Router};
+/// use pleme_graphql_helpers::auth::AnonymousAccess;
+/// use pleme_graphql_helpers::extensions::{OperationRegistry, SharedOperationRegistry};
+/// use std::sync::Arc;
+///
+/// let public: SharedOperationRegistry =
+///     Arc::new(OperationRegistry::new().with_operation("query Catalog { products { id } }"));
+/// let app: Router = Router::new().layer(Extension(
+///     AnonymousAccess::new()
+///         .allow("login")
+///         .allow_if(move |request| public.contains_query(&request.query)),
+/// ));
+/// ```
+#[derive(Clone, Default)]
+pub struct AnonymousAccess {
+    allowed_fields: HashSet<String>,
+    predicates: Vec<AnonymousPredicate>,
+}
+
+impl AnonymousAccess {
+    /// Require auth for everything but introspection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `field` as a root field without authentication
+    pub fn allow(mut self, field: impl Into<String>) -> Self {
+        self.allowed_fields.insert(field.into());
+        self
+    }
+
+    /// Allow anonymous requests matching `predicate`
+    pub fn allow_if(
+        mut self,
+        predicate: impl Fn(&Request) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Check an operation; unparseable operations are rejected too
+    pub(crate) fn check(
+        &self,
+        auth: &AuthContext,
+        request: &Request,
+        operation: Option<&OperationInfo>,
+    ) -> Result<(), ServerError> {
+        let allowed = auth.is_authenticated()
+            || operation.is_some_and(|op| {
+                op.is_introspection()
+                    || op
+                        .root_fields
+                        .iter()
+                        .all(|field| field.starts_with("__") || self.allowed_fields.contains(field))
+            })
+            || self.predicates.iter().any(|allow| allow(request));
+        if allowed {
+            Ok(())
+        } else {
+            Err(unauthenticated("Authentication required").into_server_error(Pos::default()))
+        }
+    }
+}
+
+impl fmt::Debug for AnonymousAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnonymousAccess")
+            .field("allowed_fields", &self.allowed_fields)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+/// Reject operations without a company context
+///
+/// Tenant isolation then doesn't depend on every resolver remembering to
+/// check `company_id`. Introspection is always allowed; other root fields
+/// (e.g. `login`) can be allowlisted. Allowlisting matches root field names
+/// rather than client-chosen operation names, which anyone can set.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{Extension, Router};
+/// use pleme_graphql_helpers::auth::TenantEnforcement;
+///
+/// let app: Router = Router::new()
+///     .layer(Extension(TenantEnforcement::new().allow("login").allow("health")));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TenantEnforcement {
+    allowed_fields: HashSet<String>,
+}
+
+impl TenantEnforcement {
+    /// Enforcement with only introspection allowed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `field` as a root field without a company context
+    pub fn allow(mut self, field: impl Into<String>) -> Self {
+        self.allowed_fields.insert(field.into());
+        self
+    }
+
+    /// Check an operation; unparseable operations are rejected too
+    pub(crate) fn check(
+        &self,
+        auth: &AuthContext,
+        operation: Option<&OperationInfo>,
+    ) -> Result<(), ServerError> {
+        if auth.company_id.is_some()
+            || operation.is_some_and(|op| {
+                op.is_introspection()
+                    || op
+                        .root_fields
+                        .iter()
+                        .all(|field| field.starts_with("__") || self.allowed_fields.contains(field))
+            })
+        {
+            return Ok(());
+        }
+        Err(forbidden("Company context required").into_server_error(Pos::default()))
+    }
+}
+
+/// Default maximum number of operations in a batched request
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 10;
+
+/// Default number of batched operations executed at once
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+/// Limits on batched (array) request bodies
+///
+/// Larger batches are rejected with `400 Bad Request` before any operation
+/// runs; the operations of accepted batches run at most `concurrency` at a
+/// time.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{Extension, Router};
+/// use pleme_graphql_helpers::auth::BatchLimits;
+///
+/// let app: Router = Router::new().layer(Extension(BatchLimits::new().with_max_size(5)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchLimits {
+    max_size: usize,
+    concurrency: usize,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_BATCH_SIZE,
+            concurrency: DEFAULT_BATCH_CONCURRENCY,
+        }
+    }
+}
+
+impl BatchLimits {
+    /// Default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of operations per batch
+    pub fn with_max_size(mut self, operations: usize) -> Self {
+        self.max_size = operations;
+        self
+    }
+
+    /// Set how many operations of a batch run at once (at least 1)
+    pub fn with_concurrency(mut self, operations: usize) -> Self {
+        self.concurrency = operations.max(1);
+        self
+    }
+
+    pub(crate) fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Reject batches over the size limit
+    pub(crate) fn check(&self, size: usize) -> Result<(), AuthRejection> {
+        if size > self.max_size {
+            return Err(AuthRejection::bad_request(format!(
+                "Batch of {} operations exceeds the limit of {}",
+                size, self.max_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Execution settings read from request extensions by the GraphQL handlers
+///
+/// Collects the installed [`SharedAuditSink`], [`AnonymousAccess`],
+/// [`TenantEnforcement`], [`SharedOperationRegistry`], and
+/// [`BatchLimits`].
+#[derive(Clone, Default)]
+pub struct ExecutionOptions {
+    pub(crate) audit: Option<SharedAuditSink>,
+    pub(crate) anonymous: Option<AnonymousAccess>,
+    pub(crate) tenant: Option<TenantEnforcement>,
+    pub(crate) operations: Option<SharedOperationRegistry>,
+    pub(crate) batch: BatchLimits,
+}
+
+impl ExecutionOptions {
+    pub(crate) fn from_extensions(extensions: &Extensions) -> Self {
+        Self {
+            audit: extensions.get::<SharedAuditSink>().cloned(),
+            anonymous: extensions.get::<AnonymousAccess>().cloned(),
+            tenant: extensions.get::<TenantEnforcement>().cloned(),
+            operations: extensions.get::<SharedOperationRegistry>().cloned(),
+            batch: extensions.get::<BatchLimits>().copied().unwrap_or_default(),
+        }
+    }
+
+    /// Replace a registered persisted query hash with its query text
+    ///
+    /// Returns whether the query changed. Run before parsing the operation,
+    /// so policies see what the persisted query extension will execute.
+    pub(crate) fn resolve(&self, request: &mut Request) -> bool {
+        let query = requested_hash(request)
+            .and_then(|hash| self.operations.as_ref()?.get(&hash).map(str::to_string));
+        match query {
+            Some(query) if query != request.query => {
+                request.query = query;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Check every policy, returning the error of the first that fails
+    ///
+    /// Hash-only persisted queries that [`resolve`](Self::resolve) couldn't
+    /// resolve are rejected, since no policy can inspect them.
+    pub(crate) fn check(
+        &self,
+        auth: &AuthContext,
+        request: &Request,
+        operation: Option<&OperationInfo>,
+    ) -> Result<(), ServerError> {
+        if operation.is_none() && request.query.is_empty() && requested_hash(request).is_some() {
+            return Err(ErrorCode::PersistedQueryNotFound
+                .error("PersistedQueryNotFound")
+                .into_server_error(Pos::default()));
+        }
+        if let Some(anonymous) = &self.anonymous {
+            anonymous.check(auth, request, operation)?;
+        }
+        if let Some(tenant) = &self.tenant {
+            tenant.check(auth, operation)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S> FromRequestParts<S> for ExecutionOptions
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_extensions(&parts.extensions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn operation(query: &str) -> OperationInfo {
+        OperationInfo::parse(&Request::new(query)).unwrap()
+    }
+
+    #[test]
+    fn test_tenant_enforcement() {
+        let tenant = TenantEnforcement::new().allow("login");
+        let anonymous = AuthContext::anonymous();
+
+        assert!(tenant
+            .check(&anonymous, Some(&operation("{ orders }")))
+            .is_err());
+        assert!(tenant
+            .check(&anonymous, Some(&operation("mutation { login }")))
+            .is_ok());
+        assert!(tenant
+            .check(
+                &anonymous,
+                Some(&operation("{ __schema { types { name } } }"))
+            )
+            .is_ok());
+        assert!(tenant
+            .check(&anonymous, Some(&operation("mutation { login deleteAll }")))
+            .is_err());
+
+        assert!(tenant.check(&anonymous, None).is_err());
+
+        let tenant_user = AuthContext::anonymous().with_company(Uuid::new_v4());
+        assert!(tenant
+            .check(&tenant_user, Some(&operation("{ orders }")))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_execution_options_resolve_persisted_queries() {
+        use crate::extensions::persisted::hash;
+        use crate::extensions::OperationRegistry;
+
+        let options = ExecutionOptions {
+            operations: Some(Arc::new(
+                OperationRegistry::new().with_operation("mutation { deleteAll }"),
+            )),
+            ..Default::default()
+        };
+        let auth = AuthContext::anonymous().with_company(Uuid::new_v4());
+        let persisted = |hash: String| {
+            let mut request = Request::new("");
+            request.extensions.insert(
+                "persistedQuery".to_string(),
+                async_graphql::Value::from_json(
+                    serde_json::json!({ "version": 1, "sha256Hash": hash }),
+                )
+                .unwrap(),
+            );
+            request
+        };
+
+        let mut request = persisted(hash("mutation { deleteAll }"));
+        assert!(options.resolve(&mut request));
+        let operation = OperationInfo::parse(&request);
+        assert!(operation.as_ref().is_some_and(OperationInfo::is_mutation));
+        assert!(options.check(&auth, &request, operation.as_ref()).is_ok());
+
+        let mut request = persisted("unknown".to_string());
+        assert!(!options.resolve(&mut request));
+        assert!(options.check(&auth, &request, None).is_err());
+    }
+
+    #[test]
+    fn test_anonymous_access() {
+        let access = AnonymousAccess::new()
+            .allow("login")
+            .allow_if(|request| request.operation_name.as_deref() == Some("Public"));
+        let anonymous = AuthContext::anonymous();
+        let check = |query: &str| {
+            let request = Request::new(query);
+            let operation = OperationInfo::parse(&request);
+            access.check(&anonymous, &request, operation.as_ref())
+        };
+
+        assert!(check("mutation { login }").is_ok());
+        assert!(check("{ __typename }").is_ok());
+        let public = Request::new("query Public { catalog }").operation_name("Public");
+        let operation = OperationInfo::parse(&public);
+        assert!(access.check(&anonymous, &public, operation.as_ref()).is_ok());
+        assert!(check("{ orders }").is_err());
+        assert!(check("{ broken").is_err());
+
+        let user = AuthContext {
+            user_id: Some(Uuid::new_v4()),
+            ..AuthContext::anonymous()
+        };
+        let request = Request::new("{ orders }");
+        let operation = OperationInfo::parse(&request);
+        assert!(access.check(&user, &request, operation.as_ref()).is_ok());
+    }
+}
@@ -0,0 +1,136 @@
+//! HTTP rejections for failed authentication and unreadable bodies
+//!
+//! Rejections carry the same `code` values as GraphQL errors and are
+//! rendered as a GraphQL error body, so clients handle a rejected request
+//! and a rejected field the same way (e.g. refreshing on `TOKEN_EXPIRED`).
+//! They are never `retryable`: the same request fails the same way.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::fmt;
+
+use crate::error::ErrorCode;
+
+/// Code for a missing or invalid identity
+pub const UNAUTHENTICATED: &str = ErrorCode::Unauthenticated.as_str();
+
+/// Code for an identity lacking a permission
+pub const FORBIDDEN: &str = ErrorCode::Forbidden.as_str();
+
+/// Code for an expired bearer token
+pub const TOKEN_EXPIRED: &str = ErrorCode::TokenExpired.as_str();
+
+/// Code for a malformed request body
+pub const BAD_REQUEST: &str = ErrorCode::BadRequest.as_str();
+
+/// Code for a request body over the size limit
+pub const REQUEST_TOO_LARGE: &str = ErrorCode::RequestTooLarge.as_str();
+
+/// Request rejected before execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthRejection {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl AuthRejection {
+    /// `401 Unauthorized` with code `UNAUTHENTICATED`
+    pub fn unauthenticated(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            code: UNAUTHENTICATED,
+            message: message.into(),
+        }
+    }
+
+    /// `403 Forbidden` with code `FORBIDDEN`
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            code: FORBIDDEN,
+            message: message.into(),
+        }
+    }
+
+    /// `401 Unauthorized` with code `TOKEN_EXPIRED`
+    pub fn token_expired() -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            code: TOKEN_EXPIRED,
+            message: "Token expired".to_string(),
+        }
+    }
+
+    /// `400 Bad Request` with code `BAD_REQUEST`
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            code: BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    /// `413 Payload Too Large` with code `REQUEST_TOO_LARGE`
+    pub fn request_too_large() -> Self {
+        Self {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            code: REQUEST_TOO_LARGE,
+            message: "Request body too large".to_string(),
+        }
+    }
+
+    /// GraphQL error body for the rejection
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        json!({
+            "errors": [{
+                "message": self.message,
+                "extensions": { "code": self.code, "retryable": false },
+            }],
+        })
+    }
+
+    /// The rejection as a GraphQL error, for WebSocket connection init
+    pub fn to_graphql_error(&self) -> async_graphql::Error {
+        use async_graphql::ErrorExtensions;
+
+        let code = self.code;
+        async_graphql::Error::new(&self.message).extend_with(|_, e| {
+            e.set("code", code);
+            e.set("retryable", false);
+        })
+    }
+}
+
+impl fmt::Display for AuthRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.to_json())).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rejection_body() {
+        let response = AuthRejection::token_expired().into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["errors"][0]["extensions"]["code"], TOKEN_EXPIRED);
+    }
+}
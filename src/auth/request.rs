@@ -0,0 +1,283 @@
+//! GraphQL POST body extraction
+//!
+//! Accepts `application/json` bodies and multipart uploads per the
+//! [GraphQL multipart request spec](https://github.com/jaydenseric/graphql-multipart-request-spec)
+//! (`operations` + `map` + files). Uploaded files populate
+//! `async_graphql::Upload` variables. JSON bodies may also be an array of
+//! operations, as sent by Apollo's batch HTTP link.
+
+use async_graphql::http::{receive_batch_body, MultipartOptions};
+use async_graphql::{BatchRequest, Request};
+use axum::{
+    extract::{FromRequest, Request as HttpRequest},
+    http::{header::CONTENT_LENGTH, header::CONTENT_TYPE},
+};
+use futures::{StreamExt, TryStreamExt};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::AuthRejection;
+
+/// Default per-file upload limit (10 MiB)
+pub const DEFAULT_MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
+
+/// Default total request body limit (50 MiB)
+pub const DEFAULT_MAX_BODY_SIZE: usize = 50 * 1024 * 1024;
+
+/// Upload size limits
+///
+/// Read from the request extensions; add it with `.layer(Extension(config))`.
+/// Without one, [`UploadConfig::default`] applies.
+#[derive(Debug, Clone, Copy)]
+pub struct UploadConfig {
+    /// Maximum size of a single uploaded file
+    pub max_file_size: Option<usize>,
+    /// Maximum number of files per request
+    pub max_files: Option<usize>,
+    /// Maximum size of the whole request body
+    pub max_body_size: Option<usize>,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+            max_files: None,
+            max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+        }
+    }
+}
+
+impl UploadConfig {
+    /// Default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the per-file size limit
+    pub fn with_max_file_size(mut self, bytes: usize) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Set the maximum number of files
+    pub fn with_max_files(mut self, files: usize) -> Self {
+        self.max_files = Some(files);
+        self
+    }
+
+    /// Set the total request body limit
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    fn multipart_options(&self) -> MultipartOptions {
+        let mut opts = MultipartOptions::default();
+        if let Some(size) = self.max_file_size {
+            opts = opts.max_file_size(size);
+        }
+        if let Some(files) = self.max_files {
+            opts = opts.max_num_files(files);
+        }
+        opts
+    }
+}
+
+/// A GraphQL request parsed from a JSON or multipart POST body
+///
+/// Rejects malformed bodies with `400 Bad Request` (`BAD_REQUEST`) and
+/// bodies over [`UploadConfig::max_body_size`] with `413 Payload Too Large`
+/// (`REQUEST_TOO_LARGE`), as a GraphQL error body.
+pub struct GraphQLRequest(pub Request);
+
+impl GraphQLRequest {
+    /// Unwrap the inner request
+    pub fn into_inner(self) -> Request {
+        self.0
+    }
+}
+
+impl<S> FromRequest<S> for GraphQLRequest
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request(req: HttpRequest, _state: &S) -> Result<Self, Self::Rejection> {
+        parse_body(req)
+            .await?
+            .into_single()
+            .map(GraphQLRequest)
+            .map_err(|e| AuthRejection::bad_request(e.to_string()))
+    }
+}
+
+/// A single or batched GraphQL request parsed from a POST body
+///
+/// Same body formats and rejections as [`GraphQLRequest`].
+pub struct GraphQLBatchRequest(pub BatchRequest);
+
+impl GraphQLBatchRequest {
+    /// Unwrap the inner batch request
+    pub fn into_inner(self) -> BatchRequest {
+        self.0
+    }
+}
+
+impl<S> FromRequest<S> for GraphQLBatchRequest
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request(req: HttpRequest, _state: &S) -> Result<Self, Self::Rejection> {
+        parse_body(req).await.map(GraphQLBatchRequest)
+    }
+}
+
+/// Parse a JSON or multipart body within the configured limits
+async fn parse_body(req: HttpRequest) -> Result<BatchRequest, AuthRejection> {
+    let config = req
+        .extensions()
+        .get::<UploadConfig>()
+        .copied()
+        .unwrap_or_default();
+
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let content_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+
+    if let (Some(len), Some(max)) = (content_length, config.max_body_size) {
+        if len > max {
+            return Err(AuthRejection::request_too_large());
+        }
+    }
+
+    // Multipart parsing wraps stream errors, so the overflow is flagged too
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let overflowed = exceeded.clone();
+    let mut read = 0usize;
+    let body = req
+        .into_body()
+        .into_data_stream()
+        .map(move |chunk| {
+            let chunk = chunk.map_err(io::Error::other)?;
+            read += chunk.len();
+            match config.max_body_size {
+                Some(max) if read > max => {
+                    overflowed.store(true, Ordering::Relaxed);
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "request body too large",
+                    ))
+                }
+                _ => Ok(chunk),
+            }
+        })
+        .into_async_read();
+
+    receive_batch_body(content_type, body, config.multipart_options())
+        .await
+        .map_err(|e| match e {
+            async_graphql::ParseRequestError::PayloadTooLarge => AuthRejection::request_too_large(),
+            _ if exceeded.load(Ordering::Relaxed) => AuthRejection::request_too_large(),
+            e => AuthRejection::bad_request(e.to_string()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::rejection::REQUEST_TOO_LARGE;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+
+    const BOUNDARY: &str = "graphql-boundary";
+
+    fn multipart_request(file: &str) -> HttpRequest {
+        let body = format!(
+            "--{b}\r\n\
+             Content-Disposition: form-data; name=\"operations\"\r\n\r\n\
+             {{\"query\":\"mutation($file: Upload!) {{ upload(file: $file) }}\",\"variables\":{{\"file\":null}}}}\r\n\
+             --{b}\r\n\
+             Content-Disposition: form-data; name=\"map\"\r\n\r\n\
+             {{\"0\":[\"variables.file\"]}}\r\n\
+             --{b}\r\n\
+             Content-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\n\
+             {file}\r\n\
+             --{b}--\r\n",
+            b = BOUNDARY,
+        );
+        HttpRequest::builder()
+            .method("POST")
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_multipart_populates_uploads() {
+        let request = GraphQLRequest::from_request(multipart_request("hello"), &())
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(request.uploads.len(), 1);
+        assert_eq!(request.uploads[0].filename, "a.txt");
+    }
+
+    #[tokio::test]
+    async fn test_batch_body() {
+        let req = HttpRequest::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"[{"query":"{ a }"},{"query":"{ b }"}]"#))
+            .unwrap();
+        let batch = GraphQLBatchRequest::from_request(req, &())
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(matches!(batch, BatchRequest::Batch(ref ops) if ops.len() == 2));
+
+        let req = HttpRequest::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"[{"query":"{ a }"}]"#))
+            .unwrap();
+        assert!(GraphQLRequest::from_request(req, &()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_limits() {
+        let mut req = multipart_request("hello world");
+        req.extensions_mut()
+            .insert(UploadConfig::new().with_max_file_size(4));
+        let Err(rejection) = GraphQLRequest::from_request(req, &()).await else {
+            panic!("oversized file accepted");
+        };
+        assert_eq!(rejection.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(rejection.code, REQUEST_TOO_LARGE);
+
+        let mut req = multipart_request("hello");
+        req.extensions_mut()
+            .insert(UploadConfig::new().with_max_body_size(16));
+        let Err(rejection) = GraphQLRequest::from_request(req, &()).await else {
+            panic!("oversized body accepted");
+        };
+        assert_eq!(rejection.code, REQUEST_TOO_LARGE);
+    }
+}
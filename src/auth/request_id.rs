@@ -0,0 +1,89 @@
+//! Request ID propagation
+//!
+//! Every request gets an ID: the incoming `x-request-id`, or a generated
+//! UUID. It's stored in the GraphQL context, added to every error's
+//! extensions as `requestId`, and echoed in the `x-request-id` response
+//! header, so logs, traces, and client error reports can be correlated.
+
+use async_graphql::{Context, Response};
+use axum::http::{HeaderMap, HeaderValue};
+
+use super::context::REQUEST_ID_HEADER;
+use super::AuthContext;
+
+/// Request ID stored in the GraphQL context
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(pub String);
+
+/// Get the current request's ID
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::Context;
+/// use pleme_graphql_helpers::auth::get_request_id;
+///
+/// fn log_prefix(ctx: &Context<'_>) -> String {
+///     format!("[{}]", get_request_id(ctx).unwrap_or_default())
+/// }
+/// ```
+pub fn get_request_id(ctx: &Context<'_>) -> Option<String> {
+    ctx.data_opt::<RequestId>()
+        .map(|id| id.0.clone())
+        .or_else(|| ctx.data_opt::<AuthContext>()?.request_id.clone())
+}
+
+/// Add `requestId` to the extensions of every error in `response`
+pub(crate) fn tag_errors(response: &mut Response, request_id: &str) {
+    for error in &mut response.errors {
+        error
+            .extensions
+            .get_or_insert_with(Default::default)
+            .set("requestId", request_id);
+    }
+}
+
+/// Response headers echoing the request ID
+pub(crate) fn response_headers(auth: &AuthContext) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = auth
+        .request_id
+        .as_deref()
+        .and_then(|id| HeaderValue::from_str(id).ok())
+    {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{ServerError, Value};
+
+    #[test]
+    fn test_tag_errors() {
+        let mut response = Response::from_errors(vec![
+            ServerError::new("boom", None),
+            ServerError::new("bang", None),
+        ]);
+        tag_errors(&mut response, "req-1");
+
+        for error in response.errors {
+            assert_eq!(
+                error.extensions.unwrap().get("requestId"),
+                Some(&Value::from("req-1"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generated_request_id() {
+        let auth = AuthContext::from_headers(&HeaderMap::new());
+        let headers = response_headers(&auth);
+        assert_eq!(
+            headers[REQUEST_ID_HEADER].to_str().unwrap(),
+            auth.request_id.unwrap()
+        );
+    }
+}
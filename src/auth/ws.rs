@@ -0,0 +1,420 @@
+//! GraphQL subscriptions over WebSocket with auth
+//!
+//! Speaks `graphql-transport-ws` (and the legacy `graphql-ws` protocol when
+//! a client asks for it). Auth is read from the upgrade request headers;
+//! browsers can't set headers on the upgrade, so the bearer token may
+//! instead come from the `connection_init` payload, e.g.
+//! `{"Authorization": "Bearer ..."}`. Only the configured authorization
+//! header is taken from the payload: identity headers such as `x-user-id`
+//! are set by the gateway and can't be overridden by the client.
+//!
+//! Each operation a client starts is checked against the installed
+//! [`ExecutionOptions`] like an HTTP request: persisted hashes are resolved,
+//! rejected operations are answered with the rejection and completed, and
+//! mutations are audited.
+
+use async_graphql::http::{
+    WebSocket as GraphQLWebSocket, WebSocketProtocols, WsMessage, ALL_WEBSOCKET_PROTOCOLS,
+};
+use async_graphql::{Data, Request, Response, Schema};
+use axum::body::Bytes;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Extension, OriginalUri};
+use axum::http::header::SEC_WEBSOCKET_PROTOCOL;
+use axum::http::{Extensions, HeaderMap, HeaderName, HeaderValue, Method};
+use futures::channel::mpsc;
+use futures::{future, stream, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use super::{
+    authenticate, insert_auth_data, AuditEvent, AuthContext, AuthHeaderConfig, AuthSettings,
+    ClientInfo, ExecutionOptions, OperationInfo, SharedAuditSink,
+};
+
+/// WebSocket handler for GraphQL subscriptions with auth context injection
+///
+/// Injects the same user_id, company_id, AuthzContext, [`AuthContext`], and
+/// [`ClientInfo`] data as [`graphql_handler`](super::graphql_handler), and
+/// applies the same [`ExecutionOptions`] to every operation.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription};
+/// use axum::{Router, routing::get};
+/// use pleme_graphql_helpers::auth::graphql_ws_handler;
+/// # struct Query;
+/// # #[async_graphql::Object]
+/// # impl Query {
+/// #     async fn ping(&self) -> bool {
+/// #         true
+/// #     }
+/// # }
+///
+/// let app: Router = Router::new().route(
+///     "/ws",
+///     get(graphql_ws_handler::<Query, EmptyMutation, EmptySubscription>),
+/// );
+/// ```
+pub async fn graphql_ws_handler<Query, Mutation, Subscription>(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    extensions: Extensions,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    client: ClientInfo,
+    ws: WebSocketUpgrade,
+) -> axum::response::Response
+where
+    Query: async_graphql::ObjectType + 'static,
+    Mutation: async_graphql::ObjectType + 'static,
+    Subscription: async_graphql::SubscriptionType + 'static,
+{
+    let protocol = negotiate_protocol(&headers);
+    let settings = AuthSettings::from_extensions(&extensions);
+    let options = ExecutionOptions::from_extensions(&extensions);
+    let upgrade = Upgrade {
+        method,
+        path: uri.path().to_string(),
+        headers,
+        client,
+        settings,
+    };
+
+    ws.protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| serve(socket, schema, upgrade, options, protocol))
+}
+
+/// The upgrade request, authenticated again on `connection_init`
+struct Upgrade {
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    client: ClientInfo,
+    settings: AuthSettings,
+}
+
+/// Pick the first supported protocol the client offered
+fn negotiate_protocol(headers: &HeaderMap) -> WebSocketProtocols {
+    headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|protocols| {
+            protocols
+                .split(',')
+                .find_map(|p| WebSocketProtocols::from_str(p.trim()).ok())
+        })
+        .unwrap_or(WebSocketProtocols::GraphQLWS)
+}
+
+async fn serve<Query, Mutation, Subscription>(
+    socket: WebSocket,
+    schema: Schema<Query, Mutation, Subscription>,
+    upgrade: Upgrade,
+    options: ExecutionOptions,
+    protocol: WebSocketProtocols,
+) where
+    Query: async_graphql::ObjectType + 'static,
+    Mutation: async_graphql::ObjectType + 'static,
+    Subscription: async_graphql::SubscriptionType + 'static,
+{
+    let (mut sink, input) = socket.split();
+    let (rejections, rejected) = mpsc::unbounded();
+    let policies = Arc::new(Policies {
+        options,
+        protocol,
+        auth: OnceLock::new(),
+        rejections,
+        audits: Mutex::default(),
+    });
+
+    let input = input
+        .take_while(|msg| future::ready(msg.is_ok()))
+        .filter_map({
+            let policies = policies.clone();
+            move |msg| {
+                future::ready(match msg {
+                    Ok(msg @ (Message::Text(_) | Message::Binary(_))) => {
+                        policies.admit(msg.into_data())
+                    }
+                    _ => None,
+                })
+            }
+        });
+
+    let connection = policies.clone();
+    let output = GraphQLWebSocket::new(schema, input, protocol)
+        .on_connection_init(move |payload| async move {
+            let Upgrade {
+                method,
+                path,
+                headers,
+                client,
+                settings,
+            } = upgrade;
+            let headers = connection_headers(headers, &payload, &settings.headers);
+            let auth = authenticate(method.as_str(), &path, &headers, &settings)
+                .await
+                .map_err(|rejection| rejection.to_graphql_error())?;
+            let _ = connection.auth.set(auth.clone());
+            let mut data = Data::default();
+            insert_auth_data(&mut data, auth);
+            data.insert(client);
+            Ok(data)
+        })
+        .map(|msg| match msg {
+            WsMessage::Text(text) => Message::Text(text.into()),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            })),
+        });
+
+    // Rejections are interleaved with executor output until the latter ends
+    let mut output = stream::select(
+        output.map(Some).chain(stream::once(future::ready(None))),
+        rejected.map(Some),
+    )
+    .take_while(|msg| future::ready(msg.is_some()))
+    .filter_map(future::ready);
+
+    while let Some(msg) = output.next().await {
+        policies.audit(&msg).await;
+        if sink.send(msg).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Request policies for the operations of one connection
+struct Policies {
+    options: ExecutionOptions,
+    protocol: WebSocketProtocols,
+    /// Set once `connection_init` authenticated the connection
+    auth: OnceLock<AuthContext>,
+    rejections: mpsc::UnboundedSender<Message>,
+    /// Mutations awaiting their result, by operation ID
+    audits: Mutex<HashMap<String, (SharedAuditSink, AuditEvent, Instant)>>,
+}
+
+impl Policies {
+    /// Check a client message before the executor sees it
+    ///
+    /// Returns the message, with a persisted query resolved, if it may
+    /// pass; rejected operations are answered through `rejections`.
+    fn admit(&self, message: Bytes) -> Option<Bytes> {
+        // Before connection_init the executor closes the connection itself
+        let Some(auth) = self.auth.get() else {
+            return Some(message);
+        };
+        let Ok(mut value) = serde_json::from_slice::<Value>(&message) else {
+            return Some(message);
+        };
+        if !matches!(value["type"].as_str(), Some("subscribe" | "start")) {
+            return Some(message);
+        }
+        let (Some(id), Ok(mut request)) = (
+            value["id"].as_str().map(str::to_string),
+            serde_json::from_value::<Request>(value["payload"].clone()),
+        ) else {
+            return Some(message);
+        };
+
+        let resolved = self.options.resolve(&mut request);
+        let operation = OperationInfo::parse(&request);
+        if let Some(sink) = self
+            .options
+            .audit
+            .as_ref()
+            .filter(|_| operation.as_ref().is_some_and(OperationInfo::is_mutation))
+        {
+            let event = AuditEvent::start(auth, &request);
+            self.audits
+                .lock()
+                .unwrap()
+                .insert(id.clone(), (sink.clone(), event, Instant::now()));
+        }
+
+        if let Err(rejection) = self.options.check(auth, &request, operation.as_ref()) {
+            self.reject(&id, Response::from_errors(vec![rejection]));
+            return None;
+        }
+        if resolved {
+            value["payload"]["query"] = Value::String(request.query);
+            return serde_json::to_vec(&value).ok().map(Bytes::from);
+        }
+        Some(message)
+    }
+
+    /// Answer a rejected operation with its response and complete it
+    fn reject(&self, id: &str, response: Response) {
+        let next = match self.protocol {
+            WebSocketProtocols::SubscriptionsTransportWS => "data",
+            WebSocketProtocols::GraphQLWS => "next",
+        };
+        let messages = [
+            json!({ "type": next, "id": id, "payload": response }),
+            json!({ "type": "complete", "id": id }),
+        ];
+        for message in messages {
+            let _ = self
+                .rejections
+                .unbounded_send(Message::Text(message.to_string().into()));
+        }
+    }
+
+    /// Record a mutation's audit event when its result is sent
+    async fn audit(&self, message: &Message) {
+        let Message::Text(text) = message else {
+            return;
+        };
+        if self.audits.lock().unwrap().is_empty() {
+            return;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(text.as_str()) else {
+            return;
+        };
+        if !matches!(value["type"].as_str(), Some("next" | "data")) {
+            return;
+        }
+        let Some(audit) = value["id"]
+            .as_str()
+            .and_then(|id| self.audits.lock().unwrap().remove(id))
+        else {
+            return;
+        };
+
+        let (sink, event, started) = audit;
+        let response: Response =
+            serde_json::from_value(value["payload"].clone()).unwrap_or_default();
+        sink.record(event.finish(&response, started.elapsed()))
+            .await;
+    }
+}
+
+/// The upgrade headers with the init payload's authorization entry
+///
+/// The entry matching the configured authorization header (compared
+/// case-insensitively) overrides the header; all other entries are ignored.
+fn connection_headers(
+    mut headers: HeaderMap,
+    payload: &Value,
+    config: &AuthHeaderConfig,
+) -> HeaderMap {
+    let Ok(name) = HeaderName::from_str(&config.authorization_header) else {
+        return headers;
+    };
+    let token = payload.as_object().and_then(|entries| {
+        entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.as_str()))
+            .and_then(|(_, value)| value.as_str())
+    });
+    if let Some(value) = token.and_then(|token| HeaderValue::from_str(token).ok()) {
+        headers.insert(name, value);
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthContext;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_connection_headers_from_payload() {
+        let header_user = Uuid::new_v4();
+        let company = Uuid::new_v4();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-user-id",
+            HeaderValue::from_str(&header_user.to_string()).unwrap(),
+        );
+        headers.insert(
+            "x-company-id",
+            HeaderValue::from_str(&company.to_string()).unwrap(),
+        );
+
+        let payload = serde_json::json!({
+            "authorization": "Bearer payload.token.sig",
+            "X-User-Id": Uuid::new_v4().to_string(),
+            "x-impersonator-id": Uuid::new_v4().to_string(),
+            "ignored": 42,
+        });
+
+        let config = AuthHeaderConfig::default();
+        let headers = connection_headers(headers, &payload, &config);
+        assert_eq!(config.bearer_token(&headers), Some("payload.token.sig"));
+
+        let auth = AuthContext::from_headers(&headers);
+        assert_eq!(auth.user_id, Some(header_user));
+        assert_eq!(auth.company_id, Some(company));
+        assert_eq!(auth.impersonator_id, None);
+    }
+
+    #[test]
+    fn test_policies_check_operations() {
+        use crate::auth::TenantEnforcement;
+        use crate::extensions::OperationRegistry;
+
+        let registry = OperationRegistry::new().with_operation("subscription { orders }");
+        let hash = crate::extensions::persisted::hash("subscription { orders }");
+        let (rejections, mut rejected) = mpsc::unbounded();
+        let policies = Policies {
+            options: ExecutionOptions {
+                tenant: Some(TenantEnforcement::new().allow("login")),
+                operations: Some(Arc::new(registry)),
+                ..Default::default()
+            },
+            protocol: WebSocketProtocols::GraphQLWS,
+            auth: OnceLock::new(),
+            rejections,
+            audits: Mutex::default(),
+        };
+        policies.auth.set(AuthContext::anonymous()).unwrap();
+        let subscribe = |payload: Value| {
+            Bytes::from(json!({ "type": "subscribe", "id": "1", "payload": payload }).to_string())
+        };
+
+        let persisted = json!({
+            "query": "",
+            "extensions": { "persistedQuery": { "version": 1, "sha256Hash": hash } },
+        });
+        assert!(policies.admit(subscribe(persisted)).is_none());
+        let Ok(Message::Text(next)) = rejected.try_recv() else {
+            panic!("expected a rejection");
+        };
+        assert!(next.as_str().contains("Company context required"));
+
+        let unknown = json!({
+            "query": "",
+            "extensions": { "persistedQuery": { "version": 1, "sha256Hash": "unknown" } },
+        });
+        assert!(policies.admit(subscribe(unknown)).is_none());
+
+        let login = subscribe(json!({ "query": "subscription { login }" }));
+        assert_eq!(policies.admit(login.clone()), Some(login));
+    }
+
+    #[test]
+    fn test_negotiate_protocol() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(negotiate_protocol(&headers), WebSocketProtocols::GraphQLWS);
+
+        headers.insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static("unknown, graphql-ws"),
+        );
+        assert_eq!(
+            negotiate_protocol(&headers),
+            WebSocketProtocols::SubscriptionsTransportWS
+        );
+    }
+}
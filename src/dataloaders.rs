@@ -4,10 +4,40 @@
 /// See: https://github.com/graphql/dataloader
 
 use async_trait::async_trait;
-use std::collections::HashMap;
+use futures::future::{join_all, BoxFuture, FutureExt, Shared};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+use crate::pagination::Connection;
+use cache::ShardedCache;
+
+mod cache;
+pub mod compat;
+pub mod composite;
+pub mod contextual;
+pub mod grouped;
+pub mod registry;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+pub mod stats;
+
+pub use compat::{FromAsyncGraphqlLoader, ToAsyncGraphqlLoader};
+pub use composite::CompositeKey;
+pub use contextual::{ContextualBatchLoader, ScopedLoader};
+pub use grouped::{GroupedBatchLoader, GroupedDataLoader};
+pub use registry::{get_loader, LoaderFactory, LoaderRegistry, SharedLoaderFactory};
+pub use stats::{DataLoaderStats, LoaderMetrics};
+
+/// Derive [`BatchLoader`] for a sqlx query (requires the `derive` feature)
+#[cfg(feature = "derive")]
+pub use pleme_graphql_helpers_derive::BatchLoader;
 
 /// Batch loader trait for loading multiple items at once
 #[async_trait]
@@ -20,7 +50,60 @@ where
     ///
     /// This method should fetch all items for the given keys in a single
     /// database query or API call to avoid N+1 problems.
+    ///
+    /// Loaders whose queries can fail per key implement `try_load_batch`
+    /// and return [`successes`] of it here.
     async fn load_batch(&self, keys: &[K]) -> HashMap<K, V>;
+
+    /// Fallibly load batch of items by keys
+    ///
+    /// Distinguishes a missing key (absent from the map) from a failed key
+    /// (`Err` entry) and a failed batch (outer `Err`). Defaults to
+    /// `load_batch` with every key succeeding.
+    async fn try_load_batch(&self, keys: &[K]) -> Result<HashMap<K, Result<V, LoadError>>, LoadError> {
+        Ok(self
+            .load_batch(keys)
+            .await
+            .into_iter()
+            .map(|(k, v)| (k, Ok(v)))
+            .collect())
+    }
+
+    /// Loader name used in tracing spans
+    ///
+    /// Defaults to the implementing type's name.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Batch loading errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    #[error("Batch load failed: {0}")]
+    Batch(String),
+
+    #[error("Failed to load key: {0}")]
+    Key(String),
+}
+
+/// The loaded values of a fallible batch, dropping failed keys
+///
+/// A failed batch yields an empty map.
+pub fn successes<K, V>(
+    results: Result<HashMap<K, Result<V, LoadError>>, LoadError>,
+) -> HashMap<K, V>
+where
+    K: Eq + Hash,
+{
+    results
+        .map(|results| {
+            results
+                .into_iter()
+                .filter_map(|(k, v)| v.ok().map(|v| (k, v)))
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 /// DataLoader with caching and batching
@@ -34,7 +117,141 @@ where
     L: BatchLoader<K, V> + 'static,
 {
     loader: Arc<L>,
-    cache: Arc<Mutex<HashMap<K, V>>>,
+    cache: Arc<ShardedCache<K, V>>,
+    in_flight: Arc<Mutex<InFlight<K, V>>>,
+    options: Arc<DataLoaderOptions>,
+    metrics: Arc<LoaderMetrics>,
+    limiter: Option<Arc<Semaphore>>,
+}
+
+/// Outcome of a `try_load_batch` call
+type BatchResults<K, V> = Result<HashMap<K, Result<V, LoadError>>, LoadError>;
+
+/// Shared handle to a dispatched `try_load_batch` call
+type BatchFuture<K, V> = Shared<BoxFuture<'static, Arc<BatchResults<K, V>>>>;
+
+/// Results of the keys a fetch requested, with the first failed batch's
+/// error
+type Fetched<K, V> = (HashMap<K, Result<V, LoadError>>, Option<LoadError>);
+
+/// Batches in flight by key, tagged with the batch id
+///
+/// A batch only caches the keys still registered to its id, so keys
+/// invalidated while it ran aren't overwritten with what it read.
+type InFlight<K, V> = HashMap<K, (u64, BatchFuture<K, V>)>;
+
+/// Source of batch ids
+static NEXT_BATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// DataLoader tuning options
+#[derive(Debug, Clone, Default)]
+struct DataLoaderOptions {
+    max_batch_size: Option<usize>,
+    ttl: Option<Duration>,
+    refresh_after: Option<Duration>,
+    max_concurrency: Option<usize>,
+    cache_shards: Option<usize>,
+}
+
+/// Builder for [`DataLoader`] options
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let loader = DataLoader::builder(UserLoader::new(pool))
+///     .max_batch_size(1000)
+///     .build();
+/// ```
+pub struct DataLoaderBuilder<K, V, L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: BatchLoader<K, V> + 'static,
+{
+    loader: L,
+    options: DataLoaderOptions,
+    metrics: Option<Arc<LoaderMetrics>>,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V, L> DataLoaderBuilder<K, V, L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: BatchLoader<K, V> + 'static,
+{
+    /// Split `load_many` into `load_batch` calls of at most `size` keys
+    ///
+    /// Keeps large requests under database parameter limits (e.g. Postgres
+    /// `IN (...)` lists).
+    pub fn max_batch_size(mut self, size: usize) -> Self {
+        self.options.max_batch_size = Some(size.max(1));
+        self
+    }
+
+    /// Expire cached entries `ttl` after they were loaded
+    ///
+    /// Useful for loaders that outlive a single request (e.g. for the
+    /// duration of a WebSocket subscription).
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.options.ttl = Some(ttl);
+        self
+    }
+
+    /// Record stats into shared counters
+    ///
+    /// Per-request loaders built with the same `metrics` aggregate into one
+    /// set of counters. Without this, each loader keeps its own.
+    pub fn metrics(mut self, metrics: Arc<LoaderMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Serve entries older than `refresh_after` from the cache while
+    /// refetching them in the background (stale-while-revalidate)
+    ///
+    /// Combine with [`ttl`](Self::ttl) to bound how stale a value can get.
+    pub fn refresh_after(mut self, refresh_after: Duration) -> Self {
+        self.options.refresh_after = Some(refresh_after);
+        self
+    }
+
+    /// Run at most `limit` `load_batch` calls at once
+    ///
+    /// Chunks split off by `max_batch_size` run in parallel; this bounds
+    /// how many hit the database concurrently. Unbounded by default.
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        self.options.max_concurrency = Some(limit.max(1));
+        self
+    }
+
+    /// Split the cache into `shards` independently locked shards
+    ///
+    /// Reduces lock contention when many resolvers hit the same loader
+    /// concurrently. Defaults to a single shard.
+    pub fn cache_shards(mut self, shards: usize) -> Self {
+        self.options.cache_shards = Some(shards.max(1));
+        self
+    }
+
+    /// Build the DataLoader
+    pub fn build(self) -> DataLoader<K, V, L> {
+        DataLoader {
+            loader: Arc::new(self.loader),
+            cache: Arc::new(ShardedCache::new(
+                self.options.cache_shards.unwrap_or(1),
+                self.options.ttl,
+                self.options.refresh_after,
+            )),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            limiter: self
+                .options
+                .max_concurrency
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            options: Arc::new(self.options),
+            metrics: self.metrics.unwrap_or_default(),
+        }
+    }
 }
 
 impl<K, V, L> DataLoader<K, V, L>
@@ -45,37 +262,37 @@ where
 {
     /// Create new DataLoader with a batch loader
     pub fn new(loader: L) -> Self {
-        Self {
-            loader: Arc::new(loader),
-            cache: Arc::new(Mutex::new(HashMap::new())),
+        Self::builder(loader).build()
+    }
+
+    /// Create DataLoader builder for custom options
+    pub fn builder(loader: L) -> DataLoaderBuilder<K, V, L> {
+        DataLoaderBuilder {
+            loader,
+            options: DataLoaderOptions::default(),
+            metrics: None,
+            _marker: PhantomData,
         }
     }
 
     /// Load a single item by key
     ///
     /// Checks cache first, then falls back to batch loading if needed.
+    /// Concurrent loads of the same key share one `load_batch` call.
     pub async fn load(&self, key: K) -> Option<V> {
         // Check cache first
-        {
-            let cache = self.cache.lock().await;
-            if let Some(value) = cache.get(&key) {
-                return Some(value.clone());
-            }
+        let mut stale = Vec::new();
+        let cached = self.cache.get(&key, &mut stale);
+        self.revalidate(stale).await;
+        if let Some(value) = cached {
+            self.metrics.record_hits(1);
+            return value;
         }
+        self.metrics.record_misses(1);
 
         // Cache miss - load from batch loader
-        let keys = vec![key.clone()];
-        let results = self.loader.load_batch(&keys).await;
-
-        // Update cache
-        {
-            let mut cache = self.cache.lock().await;
-            for (k, v) in results.iter() {
-                cache.insert(k.clone(), v.clone());
-            }
-        }
-
-        results.get(&key).cloned()
+        let (mut fetched, _) = self.fetch(vec![key.clone()]).await;
+        fetched.remove(&key).and_then(Result::ok)
     }
 
     /// Load multiple items by keys
@@ -85,50 +302,323 @@ where
         let mut result = HashMap::new();
         let mut uncached_keys = Vec::new();
 
+        let lookups = keys.len();
+
         // Check cache for each key
+        let mut stale = Vec::new();
+        for key in keys {
+            match self.cache.get(&key, &mut stale) {
+                Some(Some(value)) => {
+                    result.insert(key, value);
+                }
+                Some(None) => {}
+                None => uncached_keys.push(key),
+            }
+        }
+        self.revalidate(stale).await;
+        self.metrics.record_hits(lookups - uncached_keys.len());
+        self.metrics.record_misses(uncached_keys.len());
+
+        if !uncached_keys.is_empty() {
+            let (fetched, _) = self.fetch(uncached_keys).await;
+            result.extend(successes(Ok(fetched)));
+        }
+
+        result
+    }
+
+    /// Load multiple items, preserving the order of `keys`
+    ///
+    /// Returns one entry per input key (duplicates included), with `None`
+    /// for keys the loader did not return, so results zip with the input.
+    pub async fn load_many_ordered(&self, keys: Vec<K>) -> Vec<Option<V>> {
+        let values = self.load_many(keys.clone()).await;
+        keys.iter().map(|key| values.get(key).cloned()).collect()
+    }
+
+    /// Fetch uncached keys, joining batches already in flight
+    ///
+    /// New keys are dispatched in batches of at most `max_batch_size`; each
+    /// batch updates the cache once, whichever caller ends up polling it.
+    /// Keys of failed batches are left out of the results.
+    async fn fetch(&self, keys: Vec<K>) -> Fetched<K, V> {
+        let mut requested = HashSet::new();
+        let mut batches: Vec<BatchFuture<K, V>> = Vec::new();
+
         {
-            let cache = self.cache.lock().await;
+            let mut in_flight = self.in_flight.lock().await;
+            let mut new_keys = Vec::new();
             for key in keys {
-                if let Some(value) = cache.get(&key) {
-                    result.insert(key, value.clone());
-                } else {
-                    uncached_keys.push(key);
+                if !requested.insert(key.clone()) {
+                    continue;
+                }
+                match in_flight.get(&key) {
+                    Some((_, batch)) => batches.push(batch.clone()),
+                    None => new_keys.push(key),
                 }
             }
+
+            batches.extend(self.dispatch_chunks(&mut in_flight, new_keys));
         }
 
-        // Load uncached keys in batch
-        if !uncached_keys.is_empty() {
-            let batch_results = self.loader.load_batch(&uncached_keys).await;
+        let mut result = HashMap::new();
+        let mut failure = None;
+        for batch_results in join_all(batches).await {
+            match batch_results.as_ref() {
+                Ok(batch_results) => {
+                    for key in requested.iter() {
+                        if let Some(value) = batch_results.get(key) {
+                            result.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                Err(e) => {
+                    failure.get_or_insert_with(|| e.clone());
+                }
+            }
+        }
+
+        (result, failure)
+    }
+
+    /// Refetch stale keys in the background
+    ///
+    /// Keys already in flight are skipped; the cached values are replaced
+    /// when the refetch completes.
+    async fn revalidate(&self, keys: Vec<K>) {
+        if keys.is_empty() {
+            return;
+        }
+
+        let mut in_flight = self.in_flight.lock().await;
+        let mut seen = HashSet::new();
+        let keys: Vec<K> = keys
+            .into_iter()
+            .filter(|key| !in_flight.contains_key(key) && seen.insert(key.clone()))
+            .collect();
+        for batch in self.dispatch_chunks(&mut in_flight, keys) {
+            tokio::spawn(batch);
+        }
+    }
+
+    /// Dispatch keys in batches of at most `max_batch_size`, registering
+    /// each batch as in flight
+    fn dispatch_chunks(
+        &self,
+        in_flight: &mut InFlight<K, V>,
+        keys: Vec<K>,
+    ) -> Vec<BatchFuture<K, V>> {
+        let chunk_size = self.options.max_batch_size.unwrap_or(usize::MAX);
+        keys.chunks(chunk_size)
+            .map(|chunk| {
+                let id = NEXT_BATCH_ID.fetch_add(1, Ordering::Relaxed);
+                let batch = self.dispatch(id, chunk.to_vec());
+                for key in chunk {
+                    in_flight.insert(key.clone(), (id, batch.clone()));
+                }
+                batch
+            })
+            .collect()
+    }
+
+    /// Create a shared `try_load_batch` call that caches its results
+    fn dispatch(&self, id: u64, keys: Vec<K>) -> BatchFuture<K, V> {
+        let loader = self.loader.clone();
+        let cache = self.cache.clone();
+        let in_flight = self.in_flight.clone();
+        let metrics = self.metrics.clone();
+        let limiter = self.limiter.clone();
+
+        async move {
+            let _permit = acquire_permit(limiter).await;
+            let started = Instant::now();
+            let (results, elapsed) =
+                run_batch(loader.name(), keys.len(), loader.try_load_batch(&keys)).await;
+            metrics.record_batch(keys.len(), elapsed);
 
-            // Update cache and result
+            // Update cache for keys not invalidated meanwhile, dropping
+            // refreshed entries that no longer exist. Failures aren't
+            // cached, so refreshed entries keep their stale value.
             {
-                let mut cache = self.cache.lock().await;
-                for (k, v) in batch_results.iter() {
-                    cache.insert(k.clone(), v.clone());
-                    result.insert(k.clone(), v.clone());
+                let mut in_flight = in_flight.lock().await;
+                for key in &keys {
+                    if !matches!(in_flight.get(key), Some((current, _)) if *current == id) {
+                        continue;
+                    }
+                    in_flight.remove(key);
+                    match results.as_ref().map(|results| results.get(key)) {
+                        Ok(Some(Ok(value))) => cache.insert(key.clone(), value.clone()),
+                        Ok(None) => cache.remove_if_older(key, started),
+                        Ok(Some(Err(_))) | Err(_) => {}
+                    }
                 }
             }
+
+            Arc::new(results)
         }
+        .boxed()
+        .shared()
+    }
 
-        result
+    /// Fallibly load a single item by key
+    ///
+    /// Returns `Ok(None)` for missing keys. Failures are not cached, so the
+    /// next call retries.
+    pub async fn try_load(&self, key: K) -> Result<Option<V>, LoadError> {
+        let mut results = self.try_load_many(vec![key.clone()]).await?;
+        results.remove(&key).transpose()
+    }
+
+    /// Fallibly load multiple items by keys
+    ///
+    /// Only successfully loaded values are cached. A failed batch fails the
+    /// whole call. Shares batches in flight with the infallible loads.
+    pub async fn try_load_many(&self, keys: Vec<K>) -> Result<HashMap<K, Result<V, LoadError>>, LoadError> {
+        let mut result = HashMap::new();
+        let mut uncached_keys = Vec::new();
+
+        let lookups = keys.len();
+
+        // Check cache for each key
+        let mut stale = Vec::new();
+        for key in keys {
+            match self.cache.get(&key, &mut stale) {
+                Some(Some(value)) => {
+                    result.insert(key, Ok(value));
+                }
+                Some(None) => {}
+                None => uncached_keys.push(key),
+            }
+        }
+        self.revalidate(stale).await;
+        self.metrics.record_hits(lookups - uncached_keys.len());
+        self.metrics.record_misses(uncached_keys.len());
+
+        if !uncached_keys.is_empty() {
+            let (fetched, failure) = self.fetch(uncached_keys).await;
+            if let Some(e) = failure {
+                return Err(e);
+            }
+            result.extend(fetched);
+        }
+
+        Ok(result)
+    }
+
+    /// Snapshot of this loader's cache and batching counters
+    pub fn stats(&self) -> DataLoaderStats {
+        self.metrics.snapshot()
     }
 
     /// Clear the cache
+    ///
+    /// Batches in flight no longer update it when they complete.
     pub async fn clear(&self) {
-        let mut cache = self.cache.lock().await;
-        cache.clear();
+        self.in_flight.lock().await.clear();
+        self.cache.clear();
+    }
+
+    /// Evict a single key from the cache
+    ///
+    /// Call after a mutation updates the entity so later loads refetch it.
+    /// A batch already loading the key neither caches its result nor serves
+    /// later loads.
+    pub async fn invalidate(&self, key: &K) {
+        self.in_flight.lock().await.remove(key);
+        self.cache.remove(key);
+    }
+
+    /// Evict multiple keys from the cache
+    pub async fn invalidate_many(&self, keys: &[K]) {
+        let mut in_flight = self.in_flight.lock().await;
+        for key in keys {
+            in_flight.remove(key);
+            self.cache.remove(key);
+        }
     }
 
     /// Prime the cache with a value
     ///
     /// Useful for seeding the cache with data you already have.
     pub async fn prime(&self, key: K, value: V) {
-        let mut cache = self.cache.lock().await;
-        cache.insert(key, value);
+        self.cache.insert(key, value);
+    }
+
+    /// Prime the cache with multiple values
+    pub async fn prime_many(&self, values: HashMap<K, V>) {
+        for (key, value) in values {
+            self.cache.insert(key, value);
+        }
+    }
+
+    /// Prime the cache with the nodes of a paginated connection
+    ///
+    /// Nested resolvers that load the same entities by key then hit the
+    /// cache instead of querying again.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let posts: Connection<Post> = paginate_posts(&pool, &input).await?;
+    /// post_loader.prime_connection(&posts, |post| post.id).await;
+    /// ```
+    pub async fn prime_connection<F>(&self, connection: &Connection<V>, key_fn: F)
+    where
+        F: Fn(&V) -> K,
+    {
+        for edge in &connection.edges {
+            self.cache.insert(key_fn(&edge.node), edge.node.clone());
+        }
+    }
+
+    /// Prime the cache with a possibly absent value
+    ///
+    /// Priming `None` records the key as known-absent: loads return `None`
+    /// without calling the batch loader until the entry is invalidated.
+    pub async fn prime_option(&self, key: K, value: Option<V>) {
+        self.cache.insert_entry(key, value);
+    }
+}
+
+/// Wait for a batch slot when concurrency is limited
+async fn acquire_permit(limiter: Option<Arc<Semaphore>>) -> Option<OwnedSemaphorePermit> {
+    match limiter {
+        Some(limiter) => limiter.acquire_owned().await.ok(),
+        None => None,
     }
 }
 
+/// Run a `load_batch` call, timing it
+///
+/// With the `tracing` feature, the call runs in a `dataloader.load_batch`
+/// span with `loader`, `keys`, and `duration_ms` fields.
+async fn run_batch<F: Future>(loader: &str, keys: usize, batch: F) -> (F::Output, Duration) {
+    let started = Instant::now();
+
+    #[cfg(feature = "tracing")]
+    let output = {
+        use tracing::Instrument;
+
+        let span = tracing::info_span!(
+            "dataloader.load_batch",
+            loader,
+            keys,
+            duration_ms = tracing::field::Empty,
+        );
+        let output = batch.instrument(span.clone()).await;
+        span.record("duration_ms", started.elapsed().as_millis() as u64);
+        output
+    };
+
+    #[cfg(not(feature = "tracing"))]
+    let output = {
+        let _ = (loader, keys);
+        batch.await
+    };
+
+    (output, started.elapsed())
+}
+
 impl<K, V, L> Clone for DataLoader<K, V, L>
 where
     K: Send + Sync + Clone + Eq + Hash + 'static,
@@ -139,6 +629,10 @@ where
         Self {
             loader: self.loader.clone(),
             cache: self.cache.clone(),
+            in_flight: self.in_flight.clone(),
+            limiter: self.limiter.clone(),
+            options: self.options.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -192,6 +686,217 @@ mod tests {
         assert_eq!(results.get("key3"), Some(&"value-key3".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_dataloader_max_batch_size() {
+        #[derive(Default)]
+        struct RecordingLoader {
+            batches: std::sync::Mutex<Vec<usize>>,
+        }
+
+        #[async_trait]
+        impl BatchLoader<u32, u32> for RecordingLoader {
+            async fn load_batch(&self, keys: &[u32]) -> HashMap<u32, u32> {
+                self.batches.lock().unwrap().push(keys.len());
+                keys.iter().map(|k| (*k, k * 2)).collect()
+            }
+        }
+
+        let loader = DataLoader::builder(RecordingLoader::default())
+            .max_batch_size(2)
+            .build();
+        let results = loader.load_many((0..5).collect()).await;
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results.get(&4), Some(&8));
+        assert_eq!(*loader.loader.batches.lock().unwrap(), vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_try_load_does_not_cache_failures() {
+        struct FlakyLoader {
+            calls: std::sync::atomic::AtomicUsize,
+        }
+
+        #[async_trait]
+        impl BatchLoader<String, String> for FlakyLoader {
+            async fn load_batch(&self, keys: &[String]) -> HashMap<String, String> {
+                successes(self.try_load_batch(keys).await)
+            }
+
+            async fn try_load_batch(
+                &self,
+                keys: &[String],
+            ) -> Result<HashMap<String, Result<String, LoadError>>, LoadError> {
+                let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                if call == 0 {
+                    return Err(LoadError::Batch("database down".to_string()));
+                }
+                Ok(keys
+                    .iter()
+                    .filter(|k| k.as_str() != "missing")
+                    .map(|k| {
+                        let value = if k == "bad" {
+                            Err(LoadError::Key(k.clone()))
+                        } else {
+                            Ok(format!("value-{}", k))
+                        };
+                        (k.clone(), value)
+                    })
+                    .collect())
+            }
+        }
+
+        let loader = DataLoader::new(FlakyLoader {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+
+        assert!(loader.try_load("key1".to_string()).await.is_err());
+        assert_eq!(
+            loader.try_load("key1".to_string()).await.unwrap(),
+            Some("value-key1".to_string())
+        );
+        assert_eq!(loader.try_load("missing".to_string()).await.unwrap(), None);
+        assert_eq!(
+            loader.try_load("bad".to_string()).await,
+            Err(LoadError::Key("bad".to_string()))
+        );
+
+        // Infallible API drops failed keys
+        assert_eq!(loader.load("bad".to_string()).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_deduplicates_in_flight_loads() {
+        struct SlowLoader {
+            calls: std::sync::atomic::AtomicUsize,
+        }
+
+        #[async_trait]
+        impl BatchLoader<String, String> for SlowLoader {
+            async fn load_batch(&self, keys: &[String]) -> HashMap<String, String> {
+                self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                keys.iter()
+                    .map(|k| (k.clone(), format!("value-{}", k)))
+                    .collect()
+            }
+        }
+
+        let loader = DataLoader::new(SlowLoader {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+
+        let (a, b) = tokio::join!(loader.load("x".to_string()), loader.load("x".to_string()));
+        assert_eq!(a, Some("value-x".to_string()));
+        assert_eq!(b, Some("value-x".to_string()));
+        assert_eq!(loader.loader.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(loader.in_flight.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_invalidate_during_load() {
+        struct VersionedLoader {
+            calls: std::sync::atomic::AtomicUsize,
+        }
+
+        #[async_trait]
+        impl BatchLoader<String, String> for VersionedLoader {
+            async fn load_batch(&self, keys: &[String]) -> HashMap<String, String> {
+                let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                keys.iter()
+                    .map(|k| (k.clone(), format!("{}-v{}", k, call)))
+                    .collect()
+            }
+        }
+
+        let loader = DataLoader::new(VersionedLoader {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let key = "x".to_string();
+
+        let (stale, fresh) = tokio::join!(loader.load(key.clone()), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            loader.invalidate(&key).await;
+            loader.load(key.clone()).await
+        });
+        assert_eq!(stale, Some("x-v0".to_string()));
+        assert_eq!(fresh, Some("x-v1".to_string()));
+        assert_eq!(loader.load(key).await, Some("x-v1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_invalidate_during_try_load() {
+        struct VersionedLoader {
+            calls: std::sync::atomic::AtomicUsize,
+        }
+
+        #[async_trait]
+        impl BatchLoader<String, String> for VersionedLoader {
+            async fn load_batch(&self, keys: &[String]) -> HashMap<String, String> {
+                let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                // The first batch finishes after the refetch
+                if call == 0 {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                keys.iter()
+                    .map(|k| (k.clone(), format!("{}-v{}", k, call)))
+                    .collect()
+            }
+        }
+
+        let loader = DataLoader::new(VersionedLoader {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let key = "x".to_string();
+
+        let (stale, fresh) = tokio::join!(loader.try_load(key.clone()), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            loader.invalidate(&key).await;
+            loader.try_load(key.clone()).await
+        });
+        assert_eq!(stale, Ok(Some("x-v0".to_string())));
+        assert_eq!(fresh, Ok(Some("x-v1".to_string())));
+        assert_eq!(loader.try_load(key).await, Ok(Some("x-v1".to_string())));
+        assert_eq!(
+            loader
+                .loader
+                .calls
+                .load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_ttl_expiry() {
+        let loader = DataLoader::builder(TestLoader)
+            .ttl(Duration::from_millis(20))
+            .build();
+
+        loader.prime("key1".to_string(), "stale".to_string()).await;
+        assert_eq!(loader.load("key1".to_string()).await, Some("stale".to_string()));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(loader.load("key1".to_string()).await, Some("value-key1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_invalidate() {
+        let loader = DataLoader::new(TestLoader);
+
+        loader.prime("key1".to_string(), "old-1".to_string()).await;
+        loader.prime("key2".to_string(), "old-2".to_string()).await;
+        loader.prime("key3".to_string(), "old-3".to_string()).await;
+
+        loader.invalidate(&"key1".to_string()).await;
+        assert_eq!(loader.load("key1".to_string()).await, Some("value-key1".to_string()));
+        assert_eq!(loader.load("key2".to_string()).await, Some("old-2".to_string()));
+
+        loader.invalidate_many(&["key2".to_string(), "key3".to_string()]).await;
+        assert_eq!(loader.load("key2".to_string()).await, Some("value-key2".to_string()));
+        assert_eq!(loader.load("key3".to_string()).await, Some("value-key3".to_string()));
+    }
+
     #[tokio::test]
     async fn test_dataloader_prime() {
         let loader = DataLoader::new(TestLoader);
@@ -204,6 +909,177 @@ mod tests {
         assert_eq!(value, Some("custom-value".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_dataloader_prime_many_and_absent() {
+        let loader = DataLoader::new(TestLoader);
+
+        loader
+            .prime_many(HashMap::from([
+                ("key1".to_string(), "primed-1".to_string()),
+                ("key2".to_string(), "primed-2".to_string()),
+            ]))
+            .await;
+        loader.prime_option("gone".to_string(), None).await;
+
+        let results = loader
+            .load_many(vec!["key1".to_string(), "key2".to_string(), "gone".to_string()])
+            .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.get("key1"), Some(&"primed-1".to_string()));
+        assert_eq!(loader.load("gone".to_string()).await, None);
+
+        loader.invalidate(&"gone".to_string()).await;
+        assert_eq!(loader.load("gone".to_string()).await, Some("value-gone".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_load_many_ordered() {
+        let loader = DataLoader::new(TestLoader);
+        loader.prime_option("gone".to_string(), None).await;
+
+        let results = loader
+            .load_many_ordered(vec![
+                "b".to_string(),
+                "gone".to_string(),
+                "a".to_string(),
+                "b".to_string(),
+            ])
+            .await;
+        assert_eq!(
+            results,
+            vec![
+                Some("value-b".to_string()),
+                None,
+                Some("value-a".to_string()),
+                Some("value-b".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_stats() {
+        let metrics = Arc::new(LoaderMetrics::new());
+        let loader = DataLoader::builder(TestLoader)
+            .max_batch_size(2)
+            .metrics(metrics.clone())
+            .build();
+
+        loader
+            .load_many(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+            .await;
+        loader.load("a".to_string()).await;
+
+        let stats = loader.stats();
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.cache_misses, 3);
+        assert_eq!(stats.batches, 2);
+        assert_eq!(stats.keys_loaded, 3);
+
+        // Loaders sharing metrics aggregate into the same counters
+        let other = DataLoader::builder(TestLoader)
+            .metrics(metrics.clone())
+            .build();
+        other.load("z".to_string()).await;
+        assert_eq!(metrics.snapshot().batches, 3);
+    }
+
+    #[test]
+    fn test_default_loader_name() {
+        assert!(BatchLoader::name(&TestLoader).ends_with("TestLoader"));
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_refresh_after() {
+        let loader = DataLoader::builder(TestLoader)
+            .refresh_after(Duration::from_millis(20))
+            .build();
+
+        loader.prime("key1".to_string(), "stale".to_string()).await;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // Stale value is served immediately while a refetch runs
+        assert_eq!(
+            loader.load("key1".to_string()).await,
+            Some("stale".to_string())
+        );
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            loader.load("key1".to_string()).await,
+            Some("value-key1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_prime_connection() {
+        let loader = DataLoader::new(TestLoader);
+        let connection = Connection::new(
+            vec!["node-a".to_string(), "node-b".to_string()],
+            false,
+            false,
+        );
+
+        loader
+            .prime_connection(&connection, |node| {
+                node.trim_start_matches("node-").to_string()
+            })
+            .await;
+
+        assert_eq!(
+            loader.load("a".to_string()).await,
+            Some("node-a".to_string())
+        );
+        assert_eq!(
+            loader.load("b".to_string()).await,
+            Some("node-b".to_string())
+        );
+        assert_eq!(loader.stats().batches, 0);
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_max_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct ConcurrencyLoader {
+            active: AtomicUsize,
+            peak: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl BatchLoader<u32, u32> for ConcurrencyLoader {
+            async fn load_batch(&self, keys: &[u32]) -> HashMap<u32, u32> {
+                let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(active, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.active.fetch_sub(1, Ordering::SeqCst);
+                keys.iter().map(|k| (*k, *k)).collect()
+            }
+        }
+
+        let loader = DataLoader::builder(ConcurrencyLoader::default())
+            .max_batch_size(1)
+            .max_concurrency(2)
+            .build();
+        let results = loader.load_many((0..6).collect()).await;
+
+        assert_eq!(results.len(), 6);
+        assert_eq!(loader.loader.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dataloader_cache_shards() {
+        let loader = DataLoader::builder(TestLoader).cache_shards(16).build();
+
+        let keys: Vec<String> = (0..50).map(|i| i.to_string()).collect();
+        loader.load_many(keys.clone()).await;
+        let results = loader.load_many(keys).await;
+
+        assert_eq!(results.len(), 50);
+        assert_eq!(results.get("7"), Some(&"value-7".to_string()));
+        assert_eq!(loader.stats().batches, 1);
+    }
+
     #[tokio::test]
     async fn test_dataloader_clear() {
         let loader = DataLoader::new(TestLoader);
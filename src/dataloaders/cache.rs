@@ -0,0 +1,168 @@
+//! DataLoader cache storage
+//!
+//! Entries are split across independently locked shards so concurrent
+//! resolvers hitting the same loader don't serialize on one lock. Locks are
+//! never held across an `.await`, so plain `std` mutexes are used.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Cached value with insertion time
+struct CacheEntry<V> {
+    value: V,
+    inserted_at: Instant,
+}
+
+/// Single cache shard with optional per-entry TTL
+///
+/// `None` values record keys known to be absent.
+struct LoaderCache<K, V> {
+    entries: HashMap<K, CacheEntry<Option<V>>>,
+    ttl: Option<Duration>,
+    refresh_after: Option<Duration>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> LoaderCache<K, V> {
+    fn new(ttl: Option<Duration>, refresh_after: Option<Duration>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            refresh_after,
+        }
+    }
+
+    /// Get a fresh entry, evicting it if expired
+    ///
+    /// `Some(None)` means the key is cached as absent. Entries older than
+    /// `refresh_after` are still returned but pushed onto `stale`.
+    fn get(&mut self, key: &K, stale: &mut Vec<K>) -> Option<Option<V>> {
+        let entry = self.entries.get(key)?;
+        let age = entry.inserted_at.elapsed();
+        if let Some(ttl) = self.ttl {
+            if age >= ttl {
+                self.entries.remove(key);
+                return None;
+            }
+        }
+        let value = entry.value.clone();
+        if self.refresh_after.is_some_and(|refresh| age >= refresh) {
+            stale.push(key.clone());
+        }
+        Some(value)
+    }
+
+    /// Remove an entry unless it was (re)inserted after `since`
+    fn remove_if_older(&mut self, key: &K, since: Instant) {
+        if self
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.inserted_at < since)
+        {
+            self.entries.remove(key);
+        }
+    }
+
+    fn insert_entry(&mut self, key: K, value: Option<V>) {
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+}
+
+/// Loader cache split into hash-selected shards
+pub(crate) struct ShardedCache<K, V> {
+    shards: Box<[Mutex<LoaderCache<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> ShardedCache<K, V> {
+    pub(crate) fn new(
+        shards: usize,
+        ttl: Option<Duration>,
+        refresh_after: Option<Duration>,
+    ) -> Self {
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| Mutex::new(LoaderCache::new(ttl, refresh_after)))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard(&self, key: &K) -> MutexGuard<'_, LoaderCache<K, V>> {
+        let idx = if self.shards.len() == 1 {
+            0
+        } else {
+            (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+        };
+        lock(&self.shards[idx])
+    }
+
+    /// Get an entry; `Some(None)` means the key is cached as absent
+    ///
+    /// Keys due for a background refresh are pushed onto `stale`.
+    pub(crate) fn get(&self, key: &K, stale: &mut Vec<K>) -> Option<Option<V>> {
+        self.shard(key).get(key, stale)
+    }
+
+    pub(crate) fn insert(&self, key: K, value: V) {
+        self.insert_entry(key, Some(value));
+    }
+
+    pub(crate) fn insert_entry(&self, key: K, value: Option<V>) {
+        self.shard(&key).insert_entry(key, value);
+    }
+
+    pub(crate) fn remove(&self, key: &K) {
+        self.shard(key).entries.remove(key);
+    }
+
+    /// Remove an entry unless it was (re)inserted after `since`
+    pub(crate) fn remove_if_older(&self, key: &K, since: Instant) {
+        self.shard(key).remove_if_older(key, since);
+    }
+
+    pub(crate) fn clear(&self) {
+        for shard in self.shards.iter() {
+            lock(shard).entries.clear();
+        }
+    }
+}
+
+/// Lock a shard, recovering from poisoning (entries stay consistent)
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sharded_cache_routes_keys() {
+        let cache = ShardedCache::new(8, None, None);
+        let mut stale = Vec::new();
+        for key in 0..100u32 {
+            cache.insert(key, key * 2);
+        }
+        cache.insert_entry(100, None);
+
+        assert_eq!(cache.get(&42, &mut stale), Some(Some(84)));
+        assert_eq!(cache.get(&100, &mut stale), Some(None));
+        assert_eq!(cache.get(&101, &mut stale), None);
+
+        cache.remove(&42);
+        assert_eq!(cache.get(&42, &mut stale), None);
+
+        cache.clear();
+        assert_eq!(cache.get(&1, &mut stale), None);
+        assert!(stale.is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! Interop with `async_graphql::dataloader`
+//!
+//! Wraps loaders written against one API so they can be used with the other,
+//! letting services migrate between the two incrementally.
+
+use async_graphql::dataloader::Loader;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use super::{successes, BatchLoader, LoadError};
+
+/// Exposes a [`BatchLoader`] as an async-graphql [`Loader`]
+///
+/// Keys that fail individually are dropped; a failed batch is returned as
+/// the loader error.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use async_graphql::dataloader::DataLoader;
+/// use pleme_graphql_helpers::dataloaders::ToAsyncGraphqlLoader;
+///
+/// let loader = DataLoader::new(ToAsyncGraphqlLoader::new(UserLoader::new(pool)), tokio::spawn);
+/// ```
+pub struct ToAsyncGraphqlLoader<L, V> {
+    loader: L,
+    _marker: PhantomData<fn() -> V>,
+}
+
+impl<L, V> ToAsyncGraphqlLoader<L, V> {
+    /// Wrap a batch loader
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Unwrap the batch loader
+    pub fn into_inner(self) -> L {
+        self.loader
+    }
+}
+
+impl<K, V, L> Loader<K> for ToAsyncGraphqlLoader<L, V>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: BatchLoader<K, V> + 'static,
+{
+    type Value = V;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, V>, LoadError> {
+        Ok(self
+            .loader
+            .try_load_batch(keys)
+            .await?
+            .into_iter()
+            .filter_map(|(k, v)| v.ok().map(|v| (k, v)))
+            .collect())
+    }
+}
+
+/// Exposes an async-graphql [`Loader`] as a [`BatchLoader`]
+///
+/// Loader errors become [`LoadError::Batch`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::dataloaders::{DataLoader, FromAsyncGraphqlLoader};
+///
+/// let loader = DataLoader::new(FromAsyncGraphqlLoader::new(ExistingLoader::new(pool)));
+/// ```
+pub struct FromAsyncGraphqlLoader<L> {
+    loader: L,
+}
+
+impl<L> FromAsyncGraphqlLoader<L> {
+    /// Wrap an async-graphql loader
+    pub fn new(loader: L) -> Self {
+        Self { loader }
+    }
+
+    /// Unwrap the async-graphql loader
+    pub fn into_inner(self) -> L {
+        self.loader
+    }
+}
+
+#[async_trait]
+impl<K, L> BatchLoader<K, L::Value> for FromAsyncGraphqlLoader<L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    L: Loader<K>,
+    L::Error: Display,
+{
+    async fn load_batch(&self, keys: &[K]) -> HashMap<K, L::Value> {
+        successes(self.try_load_batch(keys).await)
+    }
+
+    async fn try_load_batch(
+        &self,
+        keys: &[K],
+    ) -> Result<HashMap<K, Result<L::Value, LoadError>>, LoadError> {
+        self.loader
+            .load(keys)
+            .await
+            .map(|results| results.into_iter().map(|(k, v)| (k, Ok(v))).collect())
+            .map_err(|e| LoadError::Batch(e.to_string()))
+    }
+
+    fn name(&self) -> &str {
+        std::any::type_name::<L>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataloaders::DataLoader;
+
+    struct DoubleLoader;
+
+    #[async_trait]
+    impl BatchLoader<u32, u32> for DoubleLoader {
+        async fn load_batch(&self, keys: &[u32]) -> HashMap<u32, u32> {
+            keys.iter()
+                .filter(|k| **k != 0)
+                .map(|k| (*k, k * 2))
+                .collect()
+        }
+    }
+
+    struct NativeLoader;
+
+    impl Loader<u32> for NativeLoader {
+        type Value = String;
+        type Error = String;
+
+        async fn load(&self, keys: &[u32]) -> Result<HashMap<u32, String>, String> {
+            if keys.contains(&13) {
+                return Err("unlucky".to_string());
+            }
+            Ok(keys.iter().map(|k| (*k, format!("native-{}", k))).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_loader_as_async_graphql_loader() {
+        let loader = async_graphql::dataloader::DataLoader::new(
+            ToAsyncGraphqlLoader::new(DoubleLoader),
+            tokio::spawn,
+        );
+
+        assert_eq!(loader.load_one(21).await, Ok(Some(42)));
+        assert_eq!(loader.load_one(0).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_async_graphql_loader_as_batch_loader() {
+        let loader = DataLoader::new(FromAsyncGraphqlLoader::new(NativeLoader));
+
+        assert_eq!(loader.load(1).await, Some("native-1".to_string()));
+        assert_eq!(
+            loader.try_load(13).await,
+            Err(LoadError::Batch("unlucky".to_string()))
+        );
+    }
+}
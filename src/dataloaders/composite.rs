@@ -0,0 +1,132 @@
+//! Composite loader keys
+//!
+//! Loaders keyed by `(company_id, entity_id)` and similar pairs. Serializes
+//! as `{"scope": .., "key": ..}`, so it also works inside structured cursors.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// Two-part key: a scope (e.g. tenant) and a key within it
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::dataloaders::CompositeKey;
+///
+/// let keys = vec![CompositeKey::new(1, "a"), CompositeKey::new(1, "b"), CompositeKey::new(2, "c")];
+/// let grouped = CompositeKey::group_by_scope(&keys);
+/// assert_eq!(grouped[&1], vec!["a", "b"]);
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct CompositeKey<S, K> {
+    pub scope: S,
+    pub key: K,
+}
+
+impl<S, K> CompositeKey<S, K> {
+    /// Create a composite key
+    pub fn new(scope: S, key: K) -> Self {
+        Self { scope, key }
+    }
+
+    /// Split into `(scope, key)`
+    pub fn into_parts(self) -> (S, K) {
+        (self.scope, self.key)
+    }
+}
+
+impl<S, K> CompositeKey<S, K>
+where
+    S: Clone + Eq + Hash,
+    K: Clone,
+{
+    /// Group keys by scope, preserving key order within each scope
+    ///
+    /// Lets a batch loader issue one query per scope, e.g.
+    /// `WHERE company_id = $1 AND id = ANY($2)`.
+    pub fn group_by_scope(keys: &[Self]) -> HashMap<S, Vec<K>> {
+        let mut grouped: HashMap<S, Vec<K>> = HashMap::new();
+        for key in keys {
+            grouped
+                .entry(key.scope.clone())
+                .or_default()
+                .push(key.key.clone());
+        }
+        grouped
+    }
+}
+
+impl<S, K> From<(S, K)> for CompositeKey<S, K> {
+    fn from((scope, key): (S, K)) -> Self {
+        Self::new(scope, key)
+    }
+}
+
+impl<S, K> From<CompositeKey<S, K>> for (S, K) {
+    fn from(key: CompositeKey<S, K>) -> Self {
+        key.into_parts()
+    }
+}
+
+impl<S: fmt::Display, K: fmt::Display> fmt::Display for CompositeKey<S, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.scope, self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataloaders::{BatchLoader, DataLoader};
+    use crate::pagination::CursorCodec;
+    use async_trait::async_trait;
+
+    type OrderKey = CompositeKey<u32, u32>;
+
+    struct OrderLoader {
+        calls: std::sync::Mutex<Vec<HashMap<u32, Vec<u32>>>>,
+    }
+
+    #[async_trait]
+    impl BatchLoader<OrderKey, String> for OrderLoader {
+        async fn load_batch(&self, keys: &[OrderKey]) -> HashMap<OrderKey, String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(CompositeKey::group_by_scope(keys));
+            keys.iter().map(|k| (*k, format!("order-{}", k))).collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_composite_key_loader() {
+        let loader = DataLoader::new(OrderLoader {
+            calls: Default::default(),
+        });
+
+        let results = loader
+            .load_many(vec![(1, 10).into(), (1, 11).into(), (2, 10).into()])
+            .await;
+        assert_eq!(
+            results.get(&CompositeKey::new(2, 10)),
+            Some(&"order-2:10".to_string())
+        );
+
+        let calls = loader.loader.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0][&1], vec![10, 11]);
+        assert_eq!(calls[0][&2], vec![10]);
+    }
+
+    #[test]
+    fn test_composite_key_cursor_roundtrip() {
+        let key = CompositeKey::new("company-1".to_string(), 42u64);
+        let cursor = CursorCodec::encode_structured(&key).unwrap();
+        let decoded: CompositeKey<String, u64> = CursorCodec::decode_structured(&cursor).unwrap();
+        assert_eq!(decoded, key);
+    }
+}
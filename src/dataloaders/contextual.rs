@@ -0,0 +1,146 @@
+//! Context-scoped batch loading
+//!
+//! For loaders that need a scoping value (e.g. the tenant's `company_id`) in
+//! every query. The context is fixed when the loader is built, so a loader
+//! and its cache can never serve another tenant's rows.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{BatchLoader, DataLoader, LoadError};
+
+/// Batch loader receiving a context value with every batch
+#[async_trait]
+pub trait ContextualBatchLoader<Ctx, K, V>: Send + Sync
+where
+    Ctx: Send + Sync,
+    K: Send + Sync + Clone + Eq + Hash,
+    V: Send + Sync + Clone,
+{
+    /// Load batch of items by keys within `ctx`
+    ///
+    /// Loaders whose queries can fail per key implement `try_load_batch`
+    /// and return [`successes`](super::successes) of it here.
+    async fn load_batch(&self, ctx: &Ctx, keys: &[K]) -> HashMap<K, V>;
+
+    /// Fallibly load batch of items by keys within `ctx`
+    ///
+    /// Defaults to `load_batch` with every key succeeding.
+    async fn try_load_batch(
+        &self,
+        ctx: &Ctx,
+        keys: &[K],
+    ) -> Result<HashMap<K, Result<V, LoadError>>, LoadError> {
+        Ok(self
+            .load_batch(ctx, keys)
+            .await
+            .into_iter()
+            .map(|(k, v)| (k, Ok(v)))
+            .collect())
+    }
+
+    /// Loader name used in tracing spans
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// A [`ContextualBatchLoader`] bound to one context value
+///
+/// Implements [`BatchLoader`], so it works with [`DataLoader::builder`].
+pub struct ScopedLoader<Ctx, L> {
+    ctx: Ctx,
+    loader: L,
+}
+
+impl<Ctx, L> ScopedLoader<Ctx, L> {
+    /// Bind a contextual loader to `ctx`
+    pub fn new(loader: L, ctx: Ctx) -> Self {
+        Self { ctx, loader }
+    }
+
+    /// The bound context
+    pub fn context(&self) -> &Ctx {
+        &self.ctx
+    }
+}
+
+#[async_trait]
+impl<Ctx, K, V, L> BatchLoader<K, V> for ScopedLoader<Ctx, L>
+where
+    Ctx: Send + Sync,
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: ContextualBatchLoader<Ctx, K, V>,
+{
+    async fn load_batch(&self, keys: &[K]) -> HashMap<K, V> {
+        self.loader.load_batch(&self.ctx, keys).await
+    }
+
+    async fn try_load_batch(
+        &self,
+        keys: &[K],
+    ) -> Result<HashMap<K, Result<V, LoadError>>, LoadError> {
+        self.loader.try_load_batch(&self.ctx, keys).await
+    }
+
+    fn name(&self) -> &str {
+        self.loader.name()
+    }
+}
+
+impl<Ctx, K, V, L> DataLoader<K, V, ScopedLoader<Ctx, L>>
+where
+    Ctx: Send + Sync + 'static,
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: ContextualBatchLoader<Ctx, K, V> + 'static,
+{
+    /// Create a DataLoader whose batches all run within `ctx`
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let orders = DataLoader::with_context(OrderLoader::new(pool), company_id);
+    /// ```
+    pub fn with_context(loader: L, ctx: Ctx) -> Self {
+        Self::new(ScopedLoader::new(loader, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows as (tenant, id, name)
+    struct TenantLoader {
+        rows: Vec<(u32, u32, &'static str)>,
+    }
+
+    #[async_trait]
+    impl ContextualBatchLoader<u32, u32, String> for TenantLoader {
+        async fn load_batch(&self, tenant: &u32, keys: &[u32]) -> HashMap<u32, String> {
+            self.rows
+                .iter()
+                .filter(|(t, id, _)| t == tenant && keys.contains(id))
+                .map(|(_, id, name)| (*id, name.to_string()))
+                .collect()
+        }
+    }
+
+    fn rows() -> Vec<(u32, u32, &'static str)> {
+        vec![(1, 10, "acme-order"), (2, 20, "globex-order")]
+    }
+
+    #[tokio::test]
+    async fn test_loads_are_scoped_to_context() {
+        let acme = DataLoader::with_context(TenantLoader { rows: rows() }, 1);
+        let globex = DataLoader::with_context(TenantLoader { rows: rows() }, 2);
+
+        assert_eq!(acme.load(10).await, Some("acme-order".to_string()));
+        assert_eq!(acme.load(20).await, None);
+        assert_eq!(globex.load(20).await, Some("globex-order".to_string()));
+        assert_eq!(globex.load(10).await, None);
+    }
+}
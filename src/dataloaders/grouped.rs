@@ -0,0 +1,180 @@
+//! One-to-many batch loading (K -> Vec<V>)
+//!
+//! For relations like "comments for post IDs", where each key maps to many
+//! values and keys without children should be cached as empty.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{BatchLoader, DataLoader};
+
+/// Batch loader returning multiple values per key
+#[async_trait]
+pub trait GroupedBatchLoader<K, V>: Send + Sync
+where
+    K: Send + Sync + Clone + Eq + Hash,
+    V: Send + Sync + Clone,
+{
+    /// Load all values for a batch of keys, grouped by key
+    ///
+    /// Keys without values may be omitted; they are cached as empty.
+    async fn load_grouped(&self, keys: &[K]) -> HashMap<K, Vec<V>>;
+}
+
+/// Adapts a [`GroupedBatchLoader`] into a [`BatchLoader`] over `Vec<V>`
+struct GroupedAdapter<L>(L);
+
+#[async_trait]
+impl<K, V, L> BatchLoader<K, Vec<V>> for GroupedAdapter<L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: GroupedBatchLoader<K, V>,
+{
+    async fn load_batch(&self, keys: &[K]) -> HashMap<K, Vec<V>> {
+        let mut results = self.0.load_grouped(keys).await;
+        for key in keys {
+            results.entry(key.clone()).or_default();
+        }
+        results
+    }
+
+    fn name(&self) -> &str {
+        std::any::type_name::<L>()
+    }
+}
+
+/// DataLoader for one-to-many relations
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use pleme_graphql_helpers::dataloaders::{GroupedBatchLoader, GroupedDataLoader};
+/// use std::collections::HashMap;
+///
+/// struct CommentsByPost;
+///
+/// #[async_trait]
+/// impl GroupedBatchLoader<u32, String> for CommentsByPost {
+///     async fn load_grouped(&self, keys: &[u32]) -> HashMap<u32, Vec<String>> {
+///         // SELECT * FROM comments WHERE post_id = ANY($1)
+///         keys.iter().filter(|k| **k == 1).map(|k| (*k, vec!["first!".to_string()])).collect()
+///     }
+/// }
+///
+/// # tokio_test::block_on(async {
+/// let loader = GroupedDataLoader::new(CommentsByPost);
+/// assert_eq!(loader.load(1).await, vec!["first!".to_string()]);
+/// assert!(loader.load(2).await.is_empty());
+/// # });
+/// ```
+pub struct GroupedDataLoader<K, V, L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: GroupedBatchLoader<K, V> + 'static,
+{
+    inner: DataLoader<K, Vec<V>, GroupedAdapter<L>>,
+}
+
+impl<K, V, L> GroupedDataLoader<K, V, L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: GroupedBatchLoader<K, V> + 'static,
+{
+    /// Create new GroupedDataLoader with a grouped batch loader
+    pub fn new(loader: L) -> Self {
+        Self {
+            inner: DataLoader::new(GroupedAdapter(loader)),
+        }
+    }
+
+    /// Create GroupedDataLoader splitting loads into batches of at most `size` keys
+    pub fn with_max_batch_size(loader: L, size: usize) -> Self {
+        Self {
+            inner: DataLoader::builder(GroupedAdapter(loader))
+                .max_batch_size(size)
+                .build(),
+        }
+    }
+
+    /// Load all values for a key (empty when the key has none)
+    pub async fn load(&self, key: K) -> Vec<V> {
+        self.inner.load(key).await.unwrap_or_default()
+    }
+
+    /// Load values for multiple keys
+    ///
+    /// Every requested key is present in the result.
+    pub async fn load_many(&self, keys: Vec<K>) -> HashMap<K, Vec<V>> {
+        self.inner.load_many(keys).await
+    }
+
+    /// Clear the cache
+    pub async fn clear(&self) {
+        self.inner.clear().await
+    }
+
+    /// Evict a key from the cache
+    pub async fn invalidate(&self, key: &K) {
+        self.inner.invalidate(key).await
+    }
+
+    /// Prime the cache with the values for a key
+    pub async fn prime(&self, key: K, values: Vec<V>) {
+        self.inner.prime(key, values).await
+    }
+}
+
+impl<K, V, L> Clone for GroupedDataLoader<K, V, L>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+    L: GroupedBatchLoader<K, V> + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CommentLoader {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl GroupedBatchLoader<u32, String> for CommentLoader {
+        async fn load_grouped(&self, keys: &[u32]) -> HashMap<u32, Vec<String>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            keys.iter()
+                .filter(|k| **k % 2 == 0)
+                .map(|k| (*k, vec![format!("comment-{}-a", k), format!("comment-{}-b", k)]))
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grouped_loader_caches_empty_groups() {
+        let loader = GroupedDataLoader::new(CommentLoader {
+            calls: AtomicUsize::new(0),
+        });
+
+        let results = loader.load_many(vec![1, 2, 3]).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[&2].len(), 2);
+        assert!(results[&1].is_empty());
+
+        // Empty groups are cached and don't trigger another batch
+        assert!(loader.load(3).await.is_empty());
+        assert_eq!(loader.inner.loader.0.calls.load(Ordering::SeqCst), 1);
+    }
+}
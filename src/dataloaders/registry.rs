@@ -0,0 +1,185 @@
+//! Per-request loader registry
+//!
+//! DataLoader caches must not outlive a request. A [`LoaderFactory`] builds a
+//! fresh [`LoaderRegistry`] for every request, which is stored in the request
+//! data and read back in resolvers with [`get_loader`].
+
+use async_graphql::Context;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Type-indexed set of loaders for a single request
+#[derive(Default)]
+pub struct LoaderRegistry {
+    loaders: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl LoaderRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a loader, replacing any loader of the same type
+    pub fn insert<L: Any + Send + Sync>(&mut self, loader: L) {
+        self.loaders.insert(TypeId::of::<L>(), Box::new(loader));
+    }
+
+    /// Register a loader (builder style)
+    pub fn with<L: Any + Send + Sync>(mut self, loader: L) -> Self {
+        self.insert(loader);
+        self
+    }
+
+    /// Get a loader by type
+    pub fn get<L: Any + Send + Sync>(&self) -> Option<&L> {
+        self.loaders
+            .get(&TypeId::of::<L>())
+            .and_then(|loader| loader.downcast_ref::<L>())
+    }
+
+    /// Number of registered loaders
+    pub fn len(&self) -> usize {
+        self.loaders.len()
+    }
+
+    /// Whether no loaders are registered
+    pub fn is_empty(&self) -> bool {
+        self.loaders.is_empty()
+    }
+}
+
+/// Builds the loaders for each request
+///
+/// Implemented for any `Fn(&mut LoaderRegistry)` closure.
+pub trait LoaderFactory: Send + Sync + 'static {
+    /// Register fresh loaders into the registry
+    fn register(&self, registry: &mut LoaderRegistry);
+
+    /// Build a new registry with this factory's loaders
+    fn build(&self) -> LoaderRegistry {
+        let mut registry = LoaderRegistry::new();
+        self.register(&mut registry);
+        registry
+    }
+}
+
+impl<F> LoaderFactory for F
+where
+    F: Fn(&mut LoaderRegistry) + Send + Sync + 'static,
+{
+    fn register(&self, registry: &mut LoaderRegistry) {
+        self(registry)
+    }
+}
+
+/// Shared loader factory, as stored in an axum `Extension`
+pub type SharedLoaderFactory = Arc<dyn LoaderFactory>;
+
+/// Get a per-request loader from GraphQL context
+///
+/// Returns `None` if no registry is in the request data or the loader type
+/// was not registered.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::Context;
+/// use pleme_graphql_helpers::dataloaders::{get_loader, BatchLoader, DataLoader};
+///
+/// # struct UserLoader;
+/// # #[async_trait::async_trait]
+/// # impl BatchLoader<u64, String> for UserLoader {
+/// #     async fn load_batch(&self, _keys: &[u64]) -> std::collections::HashMap<u64, String> {
+/// #         Default::default()
+/// #     }
+/// # }
+/// async fn resolver(ctx: &Context<'_>, id: u64) -> Option<String> {
+///     let loader = get_loader::<DataLoader<u64, String, UserLoader>>(ctx)?;
+///     loader.load(id).await
+/// }
+/// ```
+pub fn get_loader<'a, L: Any + Send + Sync>(ctx: &'a Context<'_>) -> Option<&'a L> {
+    ctx.data_opt::<LoaderRegistry>()
+        .and_then(|registry| registry.get::<L>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataloaders::{BatchLoader, DataLoader};
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+    use async_trait::async_trait;
+
+    struct NameLoader;
+
+    #[async_trait]
+    impl BatchLoader<u64, String> for NameLoader {
+        async fn load_batch(&self, keys: &[u64]) -> HashMap<u64, String> {
+            keys.iter().map(|k| (*k, format!("name-{}", k))).collect()
+        }
+    }
+
+    type NameDataLoader = DataLoader<u64, String, NameLoader>;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn name(&self, ctx: &Context<'_>, id: u64) -> Option<String> {
+            get_loader::<NameDataLoader>(ctx)?.load(id).await
+        }
+    }
+
+    #[test]
+    fn test_registry_get_by_type() {
+        let registry = LoaderRegistry::new().with(DataLoader::new(NameLoader));
+
+        assert_eq!(registry.len(), 1);
+        assert!(registry.get::<NameDataLoader>().is_some());
+        assert!(registry.get::<String>().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_factory_builds_fresh_loaders() {
+        let factory = |registry: &mut LoaderRegistry| {
+            registry.insert(DataLoader::new(NameLoader));
+        };
+
+        let first = factory.build();
+        first
+            .get::<NameDataLoader>()
+            .unwrap()
+            .prime(1, "cached".to_string())
+            .await;
+
+        let second = factory.build();
+        assert_eq!(
+            second.get::<NameDataLoader>().unwrap().load(1).await,
+            Some("name-1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_loader_from_context() {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let factory: SharedLoaderFactory = Arc::new(|registry: &mut LoaderRegistry| {
+            registry.insert(DataLoader::new(NameLoader));
+        });
+
+        let response = schema
+            .execute(Request::new("{ name(id: 7) }").data(factory.build()))
+            .await;
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "name": "name-7" })
+        );
+
+        let response = schema.execute("{ name(id: 7) }").await;
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "name": null })
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Generic sqlx-backed batch loader
+//!
+//! Covers the common `SELECT * FROM table WHERE id = ANY($1)` loader without
+//! a hand-written `BatchLoader` impl.
+
+use ::sqlx::postgres::PgRow;
+use ::sqlx::{FromRow, PgPool, Row};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+use super::{successes, BatchLoader, LoadError};
+
+/// Batch loader fetching rows of `T` by UUID key from a Postgres table
+///
+/// Table and column names are interpolated into the SQL as-is and must not
+/// come from user input.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::dataloaders::{sqlx::SqlxBatchLoader, DataLoader};
+///
+/// #[derive(sqlx::FromRow, Clone)]
+/// struct User { id: uuid::Uuid, email: String }
+///
+/// let users = DataLoader::new(SqlxBatchLoader::<User>::new(pool.clone(), "users", "id"));
+/// let user = users.load(user_id).await;
+/// ```
+pub struct SqlxBatchLoader<T> {
+    pool: PgPool,
+    table: String,
+    key_column: String,
+    columns: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> SqlxBatchLoader<T> {
+    /// Create a loader for `table`, keyed by `key_column`
+    pub fn new(pool: PgPool, table: impl Into<String>, key_column: impl Into<String>) -> Self {
+        Self {
+            pool,
+            table: table.into(),
+            key_column: key_column.into(),
+            columns: "*".to_string(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Select specific columns instead of `*`
+    ///
+    /// The key column is selected automatically if not listed.
+    pub fn with_columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut columns: Vec<String> = columns.into_iter().map(Into::into).collect();
+        if !columns.contains(&self.key_column) {
+            columns.push(self.key_column.clone());
+        }
+        self.columns = columns.join(", ");
+        self
+    }
+
+    /// The SQL query issued for each batch
+    pub fn sql(&self) -> String {
+        format!(
+            "SELECT {} FROM {} WHERE {} = ANY($1)",
+            self.columns, self.table, self.key_column
+        )
+    }
+}
+
+#[async_trait]
+impl<T> BatchLoader<Uuid, T> for SqlxBatchLoader<T>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Sync + Clone + Unpin + 'static,
+{
+    async fn load_batch(&self, keys: &[Uuid]) -> HashMap<Uuid, T> {
+        successes(self.try_load_batch(keys).await)
+    }
+
+    async fn try_load_batch(
+        &self,
+        keys: &[Uuid],
+    ) -> Result<HashMap<Uuid, Result<T, LoadError>>, LoadError> {
+        let rows = ::sqlx::query(&self.sql())
+            .bind(keys)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| LoadError::Batch(e.to_string()))?;
+
+        let mut results = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let key: Uuid = row
+                .try_get(self.key_column.as_str())
+                .map_err(|e| LoadError::Batch(e.to_string()))?;
+            let value = T::from_row(&row).map_err(|e| LoadError::Key(e.to_string()));
+            results.insert(key, value);
+        }
+
+        Ok(results)
+    }
+
+    fn name(&self) -> &str {
+        &self.table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::sqlx::postgres::PgPoolOptions;
+
+    #[derive(Clone)]
+    struct User;
+
+    #[tokio::test]
+    async fn test_batch_sql() {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/test")
+            .unwrap();
+
+        let loader = SqlxBatchLoader::<User>::new(pool.clone(), "users", "id");
+        assert_eq!(loader.sql(), "SELECT * FROM users WHERE id = ANY($1)");
+
+        let loader = SqlxBatchLoader::<User>::new(pool, "users", "id").with_columns(["email"]);
+        assert_eq!(
+            loader.sql(),
+            "SELECT email, id FROM users WHERE id = ANY($1)"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! DataLoader metrics
+//!
+//! Counters for cache hits, misses, and dispatched batches, used to check
+//! whether loaders are actually batching. Share one [`LoaderMetrics`] across
+//! per-request loaders with [`DataLoaderBuilder::metrics`] to aggregate them.
+//!
+//! [`DataLoaderBuilder::metrics`]: super::DataLoaderBuilder::metrics
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Live DataLoader counters
+#[derive(Debug, Default)]
+pub struct LoaderMetrics {
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    batches: AtomicU64,
+    keys_loaded: AtomicU64,
+    load_nanos: AtomicU64,
+}
+
+impl LoaderMetrics {
+    /// Create zeroed counters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record_hits(&self, count: usize) {
+        self.cache_hits.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_misses(&self, count: usize) {
+        self.cache_misses.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_batch(&self, keys: usize, elapsed: Duration) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.keys_loaded.fetch_add(keys as u64, Ordering::Relaxed);
+        self.load_nanos.fetch_add(
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Take a point-in-time snapshot of the counters
+    pub fn snapshot(&self) -> DataLoaderStats {
+        DataLoaderStats {
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+            keys_loaded: self.keys_loaded.load(Ordering::Relaxed),
+            total_load_time: Duration::from_nanos(self.load_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Reset all counters to zero
+    pub fn reset(&self) {
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.cache_misses.store(0, Ordering::Relaxed);
+        self.batches.store(0, Ordering::Relaxed);
+        self.keys_loaded.store(0, Ordering::Relaxed);
+        self.load_nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of DataLoader counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DataLoaderStats {
+    /// Keys served from the cache (including cached misses)
+    pub cache_hits: u64,
+    /// Keys not found in the cache
+    pub cache_misses: u64,
+    /// `load_batch` calls dispatched
+    pub batches: u64,
+    /// Keys passed to `load_batch` across all batches
+    pub keys_loaded: u64,
+    /// Total time spent in `load_batch`
+    pub total_load_time: Duration,
+}
+
+impl DataLoaderStats {
+    /// Average number of keys per batch
+    pub fn avg_batch_size(&self) -> f64 {
+        if self.batches == 0 {
+            return 0.0;
+        }
+        self.keys_loaded as f64 / self.batches as f64
+    }
+
+    /// Average `load_batch` latency
+    pub fn avg_load_latency(&self) -> Duration {
+        if self.batches == 0 {
+            return Duration::ZERO;
+        }
+        self.total_load_time / u32::try_from(self.batches).unwrap_or(u32::MAX)
+    }
+
+    /// Fraction of lookups served from the cache
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.cache_hits + self.cache_misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.cache_hits as f64 / lookups as f64
+    }
+}
+
+#[cfg(feature = "prometheus")]
+mod prometheus_export {
+    use super::LoaderMetrics;
+    use prometheus::core::{Collector, Desc};
+    use prometheus::proto::MetricFamily;
+    use prometheus::{Counter, IntCounter, Opts, Registry};
+    use std::sync::Arc;
+
+    /// Prometheus collector reading a [`LoaderMetrics`] at scrape time
+    struct LoaderCollector {
+        metrics: Arc<LoaderMetrics>,
+        cache_hits: IntCounter,
+        cache_misses: IntCounter,
+        batches: IntCounter,
+        keys_loaded: IntCounter,
+        load_seconds: Counter,
+    }
+
+    impl LoaderCollector {
+        fn new(metrics: Arc<LoaderMetrics>, loader: &str) -> prometheus::Result<Self> {
+            let opts = |name: &str, help: &str| Opts::new(name, help).const_label("loader", loader);
+            Ok(Self {
+                metrics,
+                cache_hits: IntCounter::with_opts(opts(
+                    "dataloader_cache_hits_total",
+                    "Keys served from the DataLoader cache",
+                ))?,
+                cache_misses: IntCounter::with_opts(opts(
+                    "dataloader_cache_misses_total",
+                    "Keys not found in the DataLoader cache",
+                ))?,
+                batches: IntCounter::with_opts(opts(
+                    "dataloader_batches_total",
+                    "Batch load calls dispatched",
+                ))?,
+                keys_loaded: IntCounter::with_opts(opts(
+                    "dataloader_batch_keys_total",
+                    "Keys passed to batch load calls",
+                ))?,
+                load_seconds: Counter::with_opts(opts(
+                    "dataloader_load_seconds_total",
+                    "Time spent in batch load calls",
+                ))?,
+            })
+        }
+    }
+
+    impl Collector for LoaderCollector {
+        fn desc(&self) -> Vec<&Desc> {
+            let mut descs = self.cache_hits.desc();
+            descs.extend(self.cache_misses.desc());
+            descs.extend(self.batches.desc());
+            descs.extend(self.keys_loaded.desc());
+            descs.extend(self.load_seconds.desc());
+            descs
+        }
+
+        fn collect(&self) -> Vec<MetricFamily> {
+            let stats = self.metrics.snapshot();
+            let sync = |counter: &IntCounter, value: u64| {
+                counter.reset();
+                counter.inc_by(value);
+            };
+            sync(&self.cache_hits, stats.cache_hits);
+            sync(&self.cache_misses, stats.cache_misses);
+            sync(&self.batches, stats.batches);
+            sync(&self.keys_loaded, stats.keys_loaded);
+            self.load_seconds.reset();
+            self.load_seconds
+                .inc_by(stats.total_load_time.as_secs_f64());
+
+            let mut families = self.cache_hits.collect();
+            families.extend(self.cache_misses.collect());
+            families.extend(self.batches.collect());
+            families.extend(self.keys_loaded.collect());
+            families.extend(self.load_seconds.collect());
+            families
+        }
+    }
+
+    impl LoaderMetrics {
+        /// Register these counters with a Prometheus registry
+        ///
+        /// Metrics are labelled `loader="<loader>"`; average batch size is
+        /// `dataloader_batch_keys_total / dataloader_batches_total`.
+        pub fn register(
+            self: &Arc<Self>,
+            registry: &Registry,
+            loader: &str,
+        ) -> prometheus::Result<()> {
+            registry.register(Box::new(LoaderCollector::new(self.clone(), loader)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_averages() {
+        let metrics = LoaderMetrics::new();
+        metrics.record_hits(3);
+        metrics.record_misses(1);
+        metrics.record_batch(4, Duration::from_millis(10));
+        metrics.record_batch(2, Duration::from_millis(20));
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.batches, 2);
+        assert_eq!(stats.avg_batch_size(), 3.0);
+        assert_eq!(stats.avg_load_latency(), Duration::from_millis(15));
+        assert_eq!(stats.hit_ratio(), 0.75);
+
+        metrics.reset();
+        assert_eq!(metrics.snapshot(), DataLoaderStats::default());
+        assert_eq!(DataLoaderStats::default().avg_batch_size(), 0.0);
+    }
+}
@@ -0,0 +1,333 @@
+//! Structured error codes
+//!
+//! Every error sent to clients carries `extensions.code` so clients can
+//! branch on the kind of failure instead of parsing messages. Expected
+//! input problems are returned as data instead, via
+//! [`MutationResult`](validation::MutationResult).
+//!
+//! sqlx and reqwest errors convert into [`GraphQLError`] with `?` (with the
+//! `sqlx` / `jwks` features). At the resolver boundary, convert with
+//! [`ErrorExtensions::extend`] rather than `?` so the code is kept.
+//! Internal and unavailable errors are sent with a generic message; the
+//! original error is kept as the GraphQL error's `source` for reporting.
+//!
+//! Messages can be localized per request with a [`MessageCatalog`].
+//!
+//! Errors also carry `extensions.retryable`, derived from the code, so
+//! gateways and clients know whether retrying the operation can succeed.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleme_graphql_helpers::error::{ErrorCode, ErrorExt};
+//!
+//! fn check_email(email: &str) -> async_graphql::Result<()> {
+//!     if !email.contains('@') {
+//!         return Err(async_graphql::Error::new("Invalid email")
+//!             .with_code(ErrorCode::ValidationFailed)
+//!             .with_field("email"));
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use async_graphql::ErrorExtensions;
+use std::fmt;
+
+use crate::GraphQLError;
+
+pub mod locale;
+pub mod validation;
+
+pub use locale::{Locale, MessageCatalog};
+pub use validation::{MutationResult, UserError, ValidationErrors};
+
+/// Standard `extensions.code` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    Unauthenticated,
+    Forbidden,
+    TokenExpired,
+    NotFound,
+    BadRequest,
+    ValidationFailed,
+    InvalidCursor,
+    QueryTooDeep,
+    QueryTooComplex,
+    TooManyAliases,
+    TooManyRootFields,
+    TooManyDirectives,
+    RequestTooLarge,
+    PersistedQueryNotFound,
+    OperationNotAllowed,
+    RateLimited,
+    Conflict,
+    Unavailable,
+    Timeout,
+    Internal,
+}
+
+impl ErrorCode {
+    /// The code as sent to clients
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Unauthenticated => "UNAUTHENTICATED",
+            Self::Forbidden => "FORBIDDEN",
+            Self::TokenExpired => "TOKEN_EXPIRED",
+            Self::NotFound => "NOT_FOUND",
+            Self::BadRequest => "BAD_REQUEST",
+            Self::ValidationFailed => "VALIDATION_FAILED",
+            Self::InvalidCursor => "INVALID_CURSOR",
+            Self::QueryTooDeep => "QUERY_TOO_DEEP",
+            Self::QueryTooComplex => "QUERY_TOO_COMPLEX",
+            Self::TooManyAliases => "TOO_MANY_ALIASES",
+            Self::TooManyRootFields => "TOO_MANY_ROOT_FIELDS",
+            Self::TooManyDirectives => "TOO_MANY_DIRECTIVES",
+            Self::RequestTooLarge => "REQUEST_TOO_LARGE",
+            Self::PersistedQueryNotFound => "PERSISTED_QUERY_NOT_FOUND",
+            Self::OperationNotAllowed => "OPERATION_NOT_ALLOWED",
+            Self::RateLimited => "RATE_LIMITED",
+            Self::Conflict => "CONFLICT",
+            Self::Unavailable => "UNAVAILABLE",
+            Self::Timeout => "TIMEOUT",
+            // Matches the code set on masked errors
+            Self::Internal => "INTERNAL_SERVER_ERROR",
+        }
+    }
+
+    /// Whether an operation failing with this code is safe to retry
+    ///
+    /// Only transient failures are; auth, input, and internal errors will
+    /// fail the same way again.
+    pub const fn is_retryable(self) -> bool {
+        matches!(self, Self::Unavailable | Self::Timeout | Self::RateLimited)
+    }
+
+    /// A GraphQL error with this code
+    pub fn error(self, message: impl Into<String>) -> async_graphql::Error {
+        async_graphql::Error::new(message).with_code(self)
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl GraphQLError {
+    /// The client-facing code for this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            GraphQLError::InvalidCursor(_) => ErrorCode::InvalidCursor,
+            GraphQLError::PaginationError(_) | GraphQLError::InvalidId(_) => ErrorCode::BadRequest,
+            GraphQLError::NotFound(_) => ErrorCode::NotFound,
+            GraphQLError::Conflict(_) => ErrorCode::Conflict,
+            GraphQLError::Unavailable(_) => ErrorCode::Unavailable,
+            GraphQLError::FederationError(_)
+            | GraphQLError::DatabaseError(_)
+            | GraphQLError::UpstreamError(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// The message sent to clients
+    ///
+    /// Internal and unavailable errors carry driver or upstream messages
+    /// (SQL, hostnames), so clients get a generic message; the full error
+    /// stays in the GraphQL error's `source` for reporters.
+    pub fn client_message(&self) -> String {
+        match self.code() {
+            ErrorCode::Internal => "Internal server error".to_string(),
+            ErrorCode::Unavailable => "Service unavailable".to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Missing rows, unique violations, and pool/connection failures get
+/// their own codes; other database errors stay internal. Constraint names
+/// aren't copied into the client-facing conflict message.
+#[cfg(feature = "sqlx")]
+impl From<sqlx::Error> for GraphQLError {
+    fn from(error: sqlx::Error) -> Self {
+        match &error {
+            sqlx::Error::RowNotFound => GraphQLError::NotFound("Record not found".to_string()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                GraphQLError::Conflict("Record already exists".to_string())
+            }
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) => {
+                GraphQLError::Unavailable(error.to_string())
+            }
+            _ => GraphQLError::DatabaseError(error.to_string()),
+        }
+    }
+}
+
+/// Timeouts and connection failures are `UNAVAILABLE`; `404` and `409`
+/// responses keep their meaning, without the upstream URL; anything else
+/// is an upstream failure
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for GraphQLError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() || error.is_connect() {
+            return GraphQLError::Unavailable(error.to_string());
+        }
+        match error.status().map(|status| status.as_u16()) {
+            Some(404) => GraphQLError::NotFound(error.without_url().to_string()),
+            Some(409) => GraphQLError::Conflict(error.without_url().to_string()),
+            Some(502..=504) => GraphQLError::Unavailable(error.to_string()),
+            _ => GraphQLError::UpstreamError(error.to_string()),
+        }
+    }
+}
+
+impl ErrorExtensions for GraphQLError {
+    fn extend(&self) -> async_graphql::Error {
+        self.clone().with_code(self.code())
+    }
+}
+
+/// Attach a code, offending field, or retry hint to an error
+pub trait ErrorExt {
+    type Output;
+
+    /// Set `extensions.code`, and `extensions.retryable` from the code
+    fn with_code(self, code: ErrorCode) -> Self::Output;
+
+    /// Override `extensions.retryable`
+    fn with_retryable(self, retryable: bool) -> Self::Output;
+
+    /// Set `extensions.field` to the input field that caused the error
+    fn with_field(self, field: &str) -> Self::Output;
+}
+
+impl ErrorExt for async_graphql::Error {
+    type Output = async_graphql::Error;
+
+    fn with_code(self, code: ErrorCode) -> Self::Output {
+        self.extend_with(|_, e| {
+            e.set("code", code.as_str());
+            e.set("retryable", code.is_retryable());
+        })
+    }
+
+    fn with_retryable(self, retryable: bool) -> Self::Output {
+        self.extend_with(|_, e| e.set("retryable", retryable))
+    }
+
+    fn with_field(self, field: &str) -> Self::Output {
+        self.extend_with(|_, e| e.set("field", field))
+    }
+}
+
+impl ErrorExt for GraphQLError {
+    type Output = async_graphql::Error;
+
+    fn with_code(self, code: ErrorCode) -> Self::Output {
+        let message = self.client_message();
+        async_graphql::Error {
+            message,
+            ..async_graphql::Error::new_with_source(self)
+        }
+        .with_code(code)
+    }
+
+    fn with_retryable(self, retryable: bool) -> Self::Output {
+        self.extend().with_retryable(retryable)
+    }
+
+    fn with_field(self, field: &str) -> Self::Output {
+        self.extend().with_field(field)
+    }
+}
+
+impl<T, E: ErrorExt<Output = async_graphql::Error>> ErrorExt for Result<T, E> {
+    type Output = async_graphql::Result<T>;
+
+    fn with_code(self, code: ErrorCode) -> Self::Output {
+        self.map_err(|e| e.with_code(code))
+    }
+
+    fn with_retryable(self, retryable: bool) -> Self::Output {
+        self.map_err(|e| e.with_retryable(retryable))
+    }
+
+    fn with_field(self, field: &str) -> Self::Output {
+        self.map_err(|e| e.with_field(field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::Value;
+
+    fn extension(error: &async_graphql::Error, key: &str) -> Option<Value> {
+        error.extensions.as_ref()?.get(key).cloned()
+    }
+
+    #[test]
+    fn test_graphql_error_codes() {
+        let error = GraphQLError::InvalidCursor("bad".to_string()).extend();
+        assert_eq!(
+            extension(&error, "code"),
+            Some(Value::from("INVALID_CURSOR"))
+        );
+
+        let error = GraphQLError::DatabaseError("timeout".to_string()).with_field("email");
+        assert_eq!(
+            extension(&error, "code"),
+            Some(Value::from("INTERNAL_SERVER_ERROR"))
+        );
+        assert_eq!(extension(&error, "field"), Some(Value::from("email")));
+    }
+
+    #[test]
+    fn test_internal_details_stay_in_source() {
+        let error = GraphQLError::DatabaseError("relation \"users\" does not exist".to_string());
+        let extended = error.extend();
+        assert_eq!(extended.message, "Internal server error");
+        assert!(extended
+            .source
+            .as_ref()
+            .and_then(|source| source.downcast_ref::<GraphQLError>())
+            .is_some_and(|source| source.to_string().contains("users")));
+
+        let error = GraphQLError::Unavailable("db-primary:5432 refused".to_string()).extend();
+        assert_eq!(error.message, "Service unavailable");
+
+        let error = GraphQLError::NotFound("Order 42".to_string()).extend();
+        assert_eq!(error.message, "Not found: Order 42");
+    }
+
+    #[test]
+    fn test_retryable() {
+        let error = GraphQLError::Unavailable("pool closed".to_string()).extend();
+        assert_eq!(extension(&error, "retryable"), Some(Value::from(true)));
+
+        let error = ErrorCode::ValidationFailed.error("Invalid email");
+        assert_eq!(extension(&error, "retryable"), Some(Value::from(false)));
+
+        let error = ErrorCode::Internal
+            .error("Deadlock detected")
+            .with_retryable(true);
+        assert_eq!(extension(&error, "retryable"), Some(Value::from(true)));
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[test]
+    fn test_from_sqlx_error() {
+        let error = GraphQLError::from(sqlx::Error::RowNotFound);
+        assert_eq!(error.code(), ErrorCode::NotFound);
+
+        let error = GraphQLError::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(error.code(), ErrorCode::Unavailable);
+    }
+
+    #[test]
+    fn test_result_with_code() {
+        let result: Result<(), _> = Err(async_graphql::Error::new("missing"));
+        let error = result.with_code(ErrorCode::NotFound).unwrap_err();
+        assert_eq!(extension(&error, "code"), Some(Value::from("NOT_FOUND")));
+    }
+}
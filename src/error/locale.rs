@@ -0,0 +1,170 @@
+//! Localized error messages
+//!
+//! The request locale is negotiated from `Accept-Language` and stored in
+//! [`ClientInfo`](crate::auth::ClientInfo). The
+//! [`LocalizeErrors`](crate::extensions::LocalizeErrors) extension then
+//! replaces the message of every coded error with the catalog entry for
+//! that locale, when there is one.
+
+use std::collections::HashMap;
+
+use super::ErrorCode;
+
+/// Locales user-facing messages are available in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    PtBr,
+}
+
+impl Locale {
+    /// BCP 47 tag
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::PtBr => "pt-BR",
+        }
+    }
+
+    /// Best supported locale for an `Accept-Language` header value
+    ///
+    /// Ranges are tried by descending `q`; unsupported ones are skipped and
+    /// English is the fallback.
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut ranges: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let q = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+                Some((tag, q))
+            })
+            .filter(|(tag, q)| !tag.is_empty() && *q > 0.0)
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| {
+                let primary = tag.split('-').next().unwrap_or_default();
+                if primary.eq_ignore_ascii_case("pt") {
+                    Some(Locale::PtBr)
+                } else if primary.eq_ignore_ascii_case("en") {
+                    Some(Locale::En)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Messages by locale and error code
+///
+/// The default catalog has Portuguese messages for every [`ErrorCode`].
+/// English messages are left to the errors themselves unless added.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::error::{ErrorCode, Locale, MessageCatalog};
+///
+/// let catalog = MessageCatalog::default()
+///     .with_message(Locale::PtBr, "PLAN_LIMIT", "Limite do plano atingido");
+/// assert_eq!(catalog.message(Locale::PtBr, "PLAN_LIMIT"), Some("Limite do plano atingido"));
+/// assert_eq!(catalog.message(Locale::En, ErrorCode::NotFound.as_str()), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    messages: HashMap<(Locale, String), String>,
+}
+
+impl MessageCatalog {
+    /// Catalog without any messages
+    pub fn empty() -> Self {
+        Self {
+            messages: HashMap::new(),
+        }
+    }
+
+    /// Set the message for `code` in `locale`
+    pub fn with_message(
+        mut self,
+        locale: Locale,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.messages.insert((locale, code.into()), message.into());
+        self
+    }
+
+    /// The message for `code` in `locale`
+    pub fn message(&self, locale: Locale, code: &str) -> Option<&str> {
+        self.messages
+            .get(&(locale, code.to_string()))
+            .map(String::as_str)
+    }
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        [
+            (ErrorCode::Unauthenticated, "Autenticação necessária"),
+            (ErrorCode::Forbidden, "Acesso negado"),
+            (ErrorCode::TokenExpired, "Sessão expirada"),
+            (ErrorCode::NotFound, "Registro não encontrado"),
+            (ErrorCode::BadRequest, "Requisição inválida"),
+            (ErrorCode::ValidationFailed, "Dados inválidos"),
+            (ErrorCode::InvalidCursor, "Cursor de paginação inválido"),
+            (ErrorCode::QueryTooDeep, "Consulta muito profunda"),
+            (ErrorCode::QueryTooComplex, "Consulta muito complexa"),
+            (ErrorCode::TooManyAliases, "Consulta com aliases demais"),
+            (
+                ErrorCode::TooManyRootFields,
+                "Consulta com campos raiz demais",
+            ),
+            (
+                ErrorCode::TooManyDirectives,
+                "Consulta com diretivas demais",
+            ),
+            (ErrorCode::RequestTooLarge, "Requisição muito grande"),
+            (
+                ErrorCode::PersistedQueryNotFound,
+                "Consulta persistida não encontrada",
+            ),
+            (ErrorCode::OperationNotAllowed, "Operação não permitida"),
+            (ErrorCode::RateLimited, "Limite de requisições excedido"),
+            (ErrorCode::Conflict, "O registro já existe"),
+            (
+                ErrorCode::Unavailable,
+                "Serviço temporariamente indisponível",
+            ),
+            (ErrorCode::Timeout, "Tempo limite excedido"),
+            (ErrorCode::Internal, "Erro interno do servidor"),
+        ]
+        .into_iter()
+        .fold(Self::empty(), |catalog, (code, message)| {
+            catalog.with_message(Locale::PtBr, code.as_str(), message)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_accept_language() {
+        assert_eq!(Locale::from_accept_language("pt-BR,pt;q=0.9"), Locale::PtBr);
+        assert_eq!(
+            Locale::from_accept_language("fr;q=1.0, en;q=0.5, pt;q=0.8"),
+            Locale::PtBr
+        );
+        assert_eq!(Locale::from_accept_language("de, en-US;q=0.7"), Locale::En);
+        assert_eq!(Locale::from_accept_language("pt;q=0, fr"), Locale::En);
+        assert_eq!(Locale::from_accept_language(""), Locale::En);
+    }
+}
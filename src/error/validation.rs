@@ -0,0 +1,251 @@
+//! Field-level validation errors for mutations
+//!
+//! Mutations return [`MutationResult<T>`]: the payload when it succeeded, or
+//! the [`UserError`]s a [`ValidationErrors`] collector gathered, in one
+//! shape shared by every service.
+
+use async_graphql::{Object, OutputType, SimpleObject, TypeName};
+use std::borrow::Cow;
+
+use super::{ErrorCode, ErrorExt};
+
+/// A user-facing error on a mutation input
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+pub struct UserError {
+    /// Input field the error applies to, if any
+    pub field: Option<String>,
+    pub message: String,
+    pub code: String,
+}
+
+impl UserError {
+    /// A `VALIDATION_FAILED` error on `field`
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.into()),
+            message: message.into(),
+            code: ErrorCode::ValidationFailed.as_str().to_string(),
+        }
+    }
+
+    /// Set the error code
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code.as_str().to_string();
+        self
+    }
+}
+
+/// Collects validation errors before a mutation runs
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::error::{MutationResult, ValidationErrors};
+///
+/// fn create_user(name: &str, email: &str) -> MutationResult<String> {
+///     let mut errors = ValidationErrors::new();
+///     errors.check(!name.is_empty(), "name", "Name is required");
+///     errors.check(email.contains('@'), "email", "Invalid email");
+///     if let Err(errors) = errors.into_result() {
+///         return errors.into();
+///     }
+///     MutationResult::ok(name.to_string())
+/// }
+///
+/// assert_eq!(create_user("", "x").errors.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<UserError>,
+}
+
+impl ValidationErrors {
+    /// Empty collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error on `field`
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) -> &mut Self {
+        self.push(UserError::new(field, message))
+    }
+
+    /// Record a prebuilt error
+    pub fn push(&mut self, error: UserError) -> &mut Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// Record an error on `field` unless `valid`
+    pub fn check(
+        &mut self,
+        valid: bool,
+        field: impl Into<String>,
+        message: impl Into<String>,
+    ) -> &mut Self {
+        if !valid {
+            self.add(field, message);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn errors(&self) -> &[UserError] {
+        &self.errors
+    }
+
+    /// `Ok` when nothing was recorded
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// A single `VALIDATION_FAILED` GraphQL error listing every field error
+    /// under `extensions.errors`, for mutations without a result payload
+    pub fn into_error(self) -> async_graphql::Error {
+        use async_graphql::ErrorExtensions;
+
+        let details: Vec<async_graphql::Value> = self
+            .errors
+            .iter()
+            .map(|e| {
+                async_graphql::Value::from_json(serde_json::json!({
+                    "field": e.field,
+                    "message": e.message,
+                    "code": e.code,
+                }))
+                .unwrap_or_default()
+            })
+            .collect();
+        async_graphql::Error::new("Validation failed")
+            .with_code(ErrorCode::ValidationFailed)
+            .extend_with(|_, e| e.set("errors", details))
+    }
+}
+
+/// Mutation payload: the result, or the user errors preventing it
+#[derive(Debug, Clone)]
+pub struct MutationResult<T> {
+    pub data: Option<T>,
+    pub errors: Vec<UserError>,
+}
+
+impl<T> MutationResult<T> {
+    /// Successful result
+    pub fn ok(data: T) -> Self {
+        Self {
+            data: Some(data),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Failed result
+    pub fn failed(errors: Vec<UserError>) -> Self {
+        Self { data: None, errors }
+    }
+}
+
+impl<T> From<ValidationErrors> for MutationResult<T> {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::failed(errors.errors)
+    }
+}
+
+impl<T> From<Result<T, ValidationErrors>> for MutationResult<T> {
+    fn from(result: Result<T, ValidationErrors>) -> Self {
+        match result {
+            Ok(data) => Self::ok(data),
+            Err(errors) => errors.into(),
+        }
+    }
+}
+
+/// Named after the payload type (`User` gives `UserResult`) so one schema
+/// can hold several mutation results
+impl<T: OutputType> TypeName for MutationResult<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!("{}Result", T::type_name()).into()
+    }
+}
+
+#[Object(name_type)]
+impl<T: OutputType> MutationResult<T> {
+    /// The mutation result, absent when it failed
+    async fn data(&self) -> Option<&T> {
+        self.data.as_ref()
+    }
+
+    /// Errors preventing the mutation
+    async fn errors(&self) -> &[UserError] {
+        &self.errors
+    }
+
+    /// Whether the mutation succeeded
+    async fn success(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptySubscription, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ping(&self) -> bool {
+            true
+        }
+    }
+
+    struct Mutation;
+
+    #[Object]
+    impl Mutation {
+        async fn rename(&self, name: String) -> MutationResult<String> {
+            let mut errors = ValidationErrors::new();
+            errors.check(name.len() >= 3, "name", "Name is too short");
+            errors.into_result().map(|_| name).into()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutation_result() {
+        let schema = Schema::new(Query, Mutation, EmptySubscription);
+        let response = schema
+            .execute(r#"mutation { rename(name: "x") { data success errors { field code } } }"#)
+            .await;
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({
+                "rename": {
+                    "data": null,
+                    "success": false,
+                    "errors": [{ "field": "name", "code": "VALIDATION_FAILED" }],
+                }
+            })
+        );
+        assert!(schema.sdl().contains("type StringResult"));
+    }
+
+    #[test]
+    fn test_into_error() {
+        let mut errors = ValidationErrors::new();
+        errors.add("email", "Invalid email");
+        let error = errors.into_error();
+        let extensions = error.extensions.unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("VALIDATION_FAILED"))
+        );
+        assert!(extensions.get("errors").is_some());
+    }
+}
@@ -0,0 +1,65 @@
+//! async-graphql schema extensions
+//!
+//! Provides:
+//! - Query depth limiting
+//! - Query cost analysis
+//! - Persisted operation allowlists
+//! - Response caching from cache hints
+//! - Introspection, suggestion, alias, and root field hardening
+//! - Per-caller rate limiting
+//! - Execution timeouts and slow query logging
+//! - N+1 detection for development
+//! - Operation logging with variable redaction
+//! - Prometheus execution metrics (with the `prometheus` feature)
+//! - Apollo GraphOS usage reporting (with the `apollo-reporting` feature)
+//! - Federated `ftv1` tracing (with the `federation-tracing` feature)
+//! - Masking of unexpected resolver errors, with error reporting (Sentry
+//!   with the `sentry` feature)
+//! - Localized error messages
+//! - Service name tagging of errors for federated gateways
+
+#[cfg(any(feature = "apollo-reporting", feature = "federation-tracing"))]
+pub mod apollo;
+pub mod cache;
+pub mod cost;
+pub mod depth;
+pub mod localize;
+pub mod logging;
+pub mod masking;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+pub mod n_plus_one;
+pub mod persisted;
+pub mod rate_limit;
+pub mod reporter;
+pub mod security;
+pub mod service;
+pub mod slow_query;
+pub mod timeout;
+
+#[cfg(feature = "apollo-reporting")]
+pub use apollo::ApolloReporting;
+#[cfg(feature = "federation-tracing")]
+pub use apollo::FederatedTracing;
+pub use cache::{CacheStore, CachedResponse, MemoryCacheStore, ResponseCache, SharedCacheStore};
+pub use cost::{cost_directive, CostAnalysis};
+pub use depth::DepthLimit;
+pub use localize::LocalizeErrors;
+pub use logging::{OperationLog, OperationLogHandler, OperationRecord};
+pub use masking::MaskErrors;
+#[cfg(feature = "prometheus")]
+pub use metrics::{metrics_handler, GraphQLMetrics};
+pub use n_plus_one::{NPlusOne, NPlusOneDetector, NPlusOneHandler};
+pub use persisted::{OperationRegistry, PersistedOperations, SharedOperationRegistry};
+#[cfg(feature = "redis")]
+pub use rate_limit::RedisRateLimitStore;
+pub use rate_limit::{
+    MemoryRateLimitStore, Quota, RateLimit, RateLimitStore, SharedRateLimitStore,
+};
+#[cfg(feature = "sentry")]
+pub use reporter::SentryReporter;
+pub use reporter::{ErrorReport, ErrorReporter, SharedErrorReporter};
+pub use security::SecurityConfig;
+pub use service::TagErrors;
+pub use slow_query::{SlowQuery, SlowQueryHandler, SlowQueryLog};
+pub use timeout::Timeout;
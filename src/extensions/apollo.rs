@@ -0,0 +1,25 @@
+//! Apollo GraphOS integrations
+//!
+//! - [`ApolloReporting`] - Usage reporting to GraphOS (with the
+//!   `apollo-reporting` feature)
+//! - [`FederatedTracing`] - Inline `ftv1` traces for the gateway (with the
+//!   `federation-tracing` feature)
+//!
+//! Both build the same resolver trace tree, in Apollo's protobuf format.
+
+#[cfg(feature = "federation-tracing")]
+mod ftv1;
+pub mod proto;
+#[cfg(feature = "apollo-reporting")]
+mod reporting;
+#[cfg(feature = "apollo-reporting")]
+mod stats;
+pub(crate) mod trace;
+
+#[cfg(feature = "federation-tracing")]
+pub use ftv1::{FederatedTracing, FTV1_EXTENSION};
+#[cfg(feature = "apollo-reporting")]
+pub use reporting::{
+    ApolloReporting, ReportError, APOLLO_GRAPH_REF_ENV, APOLLO_KEY_ENV, DEFAULT_ENDPOINT,
+    DEFAULT_REPORT_INTERVAL, DEFAULT_TRACE_SAMPLING,
+};
@@ -0,0 +1,138 @@
+//! Federated tracing
+//!
+//! A federation gateway asks subgraphs for inline traces with the
+//! `apollo-federation-include-trace: ftv1` header. [`FederatedTracing`]
+//! traces those operations resolver by resolver and returns the trace as
+//! base64-encoded protobuf in the `ftv1` response extension, which the
+//! gateway merges into its own trace. Other operations aren't traced.
+//!
+//! The handlers record the header in [`ClientInfo::include_trace`]; only
+//! register the extension on subgraphs reachable through the gateway alone.
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextResolve, ResolveInfo,
+};
+use async_graphql::{Response, ServerResult, Value};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use prost::Message;
+use std::sync::Arc;
+
+use super::trace::TraceBuilder;
+use crate::auth::ClientInfo;
+
+/// Response extension holding the trace
+pub const FTV1_EXTENSION: &str = "ftv1";
+
+/// Extension returning inline traces to a federation gateway
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+/// use pleme_graphql_helpers::extensions::FederatedTracing;
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     async fn ping(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///     .enable_federation()
+///     .extension(FederatedTracing)
+///     .finish();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FederatedTracing;
+
+impl ExtensionFactory for FederatedTracing {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(FederatedTracingExtension {
+            trace: TraceBuilder::new(),
+        })
+    }
+}
+
+struct FederatedTracingExtension {
+    trace: TraceBuilder,
+}
+
+/// Whether the gateway asked for a trace of this operation
+fn requested(ctx: &ExtensionContext<'_>) -> bool {
+    ctx.data_opt::<ClientInfo>()
+        .is_some_and(|client| client.include_trace)
+}
+
+#[async_trait::async_trait]
+impl Extension for FederatedTracingExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let mut response = next.run(ctx, operation_name).await;
+        if requested(ctx) {
+            let trace = self.trace.finish().encode_to_vec();
+            response.extensions.insert(
+                FTV1_EXTENSION.to_string(),
+                Value::from(BASE64.encode(trace)),
+            );
+        }
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if !requested(ctx) {
+            return next.run(ctx, info).await;
+        }
+        self.trace.resolve(ctx, info, next).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::apollo::proto::Trace;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ping(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_on_request() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(FederatedTracing)
+            .finish();
+
+        let response = schema.execute("{ ping }").await;
+        assert!(!response.extensions.contains_key(FTV1_EXTENSION));
+
+        let client = ClientInfo {
+            include_trace: true,
+            ..ClientInfo::default()
+        };
+        let response = schema.execute(Request::new("{ ping }").data(client)).await;
+        let Some(Value::String(encoded)) = response.extensions.get(FTV1_EXTENSION) else {
+            panic!("no ftv1 trace");
+        };
+        let trace = Trace::decode(BASE64.decode(encoded).unwrap().as_slice()).unwrap();
+        let root = trace.root.unwrap();
+        assert_eq!(root.child[0].parent_type, "Query");
+        assert_eq!(root.child[0].r#type, "Boolean!");
+    }
+}
@@ -0,0 +1,213 @@
+//! Apollo usage reporting protobuf messages
+//!
+//! The subset of Apollo's `reports.proto` the reporter sends, with the
+//! upstream field tags. Unset fields are left at their defaults.
+
+use prost::Message;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `google.protobuf.Timestamp`
+#[derive(Clone, Copy, PartialEq, Eq, Message)]
+pub struct Timestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self {
+            seconds: since_epoch.as_secs() as i64,
+            nanos: since_epoch.subsec_nanos() as i32,
+        }
+    }
+}
+
+/// A batch of usage data
+#[derive(Clone, PartialEq, Message)]
+pub struct Report {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<ReportHeader>,
+    #[prost(message, optional, tag = "2")]
+    pub end_time: Option<Timestamp>,
+    /// Usage by stats key (`# <operation name>\n<signature>`)
+    #[prost(map = "string, message", tag = "5")]
+    pub traces_per_query: HashMap<String, TracesAndStats>,
+    #[prost(uint64, tag = "6")]
+    pub operation_count: u64,
+}
+
+/// Who sent a report
+#[derive(Clone, PartialEq, Message)]
+pub struct ReportHeader {
+    #[prost(string, tag = "5")]
+    pub hostname: String,
+    #[prost(string, tag = "6")]
+    pub agent_version: String,
+    #[prost(string, tag = "7")]
+    pub service_version: String,
+    #[prost(string, tag = "8")]
+    pub runtime_version: String,
+    #[prost(string, tag = "9")]
+    pub uname: String,
+    /// SHA-256 of the schema SDL
+    #[prost(string, tag = "11")]
+    pub executable_schema_id: String,
+    #[prost(string, tag = "12")]
+    pub graph_ref: String,
+}
+
+/// Usage of one operation
+#[derive(Clone, PartialEq, Message)]
+pub struct TracesAndStats {
+    #[prost(message, repeated, tag = "1")]
+    pub trace: Vec<Trace>,
+    #[prost(message, repeated, tag = "2")]
+    pub stats_with_context: Vec<ContextualizedStats>,
+    #[prost(map = "string, message", tag = "4")]
+    pub referenced_fields_by_type: HashMap<String, ReferencedFieldsForType>,
+}
+
+/// Fields an operation selects on a type
+#[derive(Clone, PartialEq, Message)]
+pub struct ReferencedFieldsForType {
+    #[prost(string, repeated, tag = "1")]
+    pub field_names: Vec<String>,
+    #[prost(bool, tag = "2")]
+    pub is_interface: bool,
+}
+
+/// Aggregated stats for one client
+#[derive(Clone, PartialEq, Message)]
+pub struct ContextualizedStats {
+    #[prost(message, optional, tag = "1")]
+    pub context: Option<StatsContext>,
+    #[prost(message, optional, tag = "2")]
+    pub query_latency_stats: Option<QueryLatencyStats>,
+    #[prost(map = "string, message", tag = "3")]
+    pub per_type_stat: HashMap<String, TypeStat>,
+}
+
+/// The client stats are reported for
+#[derive(Clone, PartialEq, Message)]
+pub struct StatsContext {
+    #[prost(string, tag = "2")]
+    pub client_name: String,
+    #[prost(string, tag = "3")]
+    pub client_version: String,
+}
+
+/// Operation latency and counts
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryLatencyStats {
+    #[prost(uint64, tag = "2")]
+    pub request_count: u64,
+    #[prost(uint64, tag = "8")]
+    pub requests_with_errors_count: u64,
+    /// Run-length encoded latency histogram
+    #[prost(sint64, repeated, tag = "13")]
+    pub latency_count: Vec<i64>,
+}
+
+/// Stats of a type's fields
+#[derive(Clone, PartialEq, Message)]
+pub struct TypeStat {
+    #[prost(map = "string, message", tag = "3")]
+    pub per_field_stat: HashMap<String, FieldStat>,
+}
+
+/// Stats of one field
+#[derive(Clone, PartialEq, Message)]
+pub struct FieldStat {
+    #[prost(string, tag = "3")]
+    pub return_type: String,
+    #[prost(uint64, tag = "4")]
+    pub errors_count: u64,
+    #[prost(uint64, tag = "5")]
+    pub observed_execution_count: u64,
+    #[prost(uint64, tag = "6")]
+    pub requests_with_errors_count: u64,
+    /// Run-length encoded latency histogram
+    #[prost(sint64, repeated, tag = "9")]
+    pub latency_count: Vec<i64>,
+    #[prost(double, tag = "10")]
+    pub estimated_execution_count: f64,
+}
+
+/// Timing of a single operation, resolver by resolver
+#[derive(Clone, PartialEq, Message)]
+pub struct Trace {
+    #[prost(message, optional, tag = "3")]
+    pub end_time: Option<Timestamp>,
+    #[prost(message, optional, tag = "4")]
+    pub start_time: Option<Timestamp>,
+    #[prost(string, tag = "7")]
+    pub client_name: String,
+    #[prost(string, tag = "8")]
+    pub client_version: String,
+    #[prost(uint64, tag = "11")]
+    pub duration_ns: u64,
+    #[prost(message, optional, tag = "14")]
+    pub root: Option<trace::Node>,
+}
+
+pub mod trace {
+    use prost::Message;
+
+    /// A resolved field or list item
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Node {
+        #[prost(oneof = "node::Id", tags = "1, 2")]
+        pub id: Option<node::Id>,
+        #[prost(string, tag = "3")]
+        pub r#type: String,
+        /// Nanoseconds since the trace started
+        #[prost(uint64, tag = "8")]
+        pub start_time: u64,
+        #[prost(uint64, tag = "9")]
+        pub end_time: u64,
+        #[prost(message, repeated, tag = "11")]
+        pub error: Vec<Error>,
+        #[prost(message, repeated, tag = "12")]
+        pub child: Vec<Node>,
+        #[prost(string, tag = "13")]
+        pub parent_type: String,
+        /// Field name, when the response name is an alias
+        #[prost(string, tag = "14")]
+        pub original_field_name: String,
+    }
+
+    pub mod node {
+        /// Response key of a field, or position of a list item
+        #[derive(Clone, PartialEq, Eq, prost::Oneof)]
+        pub enum Id {
+            #[prost(string, tag = "1")]
+            ResponseName(String),
+            #[prost(uint32, tag = "2")]
+            Index(u32),
+        }
+    }
+
+    /// An error raised by a resolver
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Error {
+        #[prost(string, tag = "1")]
+        pub message: String,
+        #[prost(message, repeated, tag = "2")]
+        pub location: Vec<Location>,
+        /// The error as JSON
+        #[prost(string, tag = "4")]
+        pub json: String,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Message)]
+    pub struct Location {
+        #[prost(uint32, tag = "1")]
+        pub line: u32,
+        #[prost(uint32, tag = "2")]
+        pub column: u32,
+    }
+}
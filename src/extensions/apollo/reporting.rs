@@ -0,0 +1,373 @@
+//! Apollo usage reporting
+//!
+//! [`ApolloReporting`] sends field usage to Apollo GraphOS (Studio) with
+//! the usage reporting protocol. Every operation is traced resolver by
+//! resolver and aggregated into per-operation stats: request counts,
+//! latency histograms, and per-field execution counts, errors, and
+//! latencies. A sample of full traces is sent along.
+//!
+//! Reports are gzipped protobuf, uploaded every 20 seconds by default from
+//! a background task started with the first request. Operations are keyed
+//! by name and a simplified signature of the query text.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+//! use pleme_graphql_helpers::extensions::ApolloReporting;
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     async fn ping(&self) -> bool {
+//!         true
+//!     }
+//! }
+//!
+//! let mut builder = Schema::build(Query, EmptyMutation, EmptySubscription);
+//! // APOLLO_KEY and APOLLO_GRAPH_REF, if both are set
+//! if let Some(reporting) = ApolloReporting::from_env() {
+//!     builder = builder.extension(reporting.with_trace_sampling(0.05));
+//! }
+//! let schema = builder.finish();
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextPrepareRequest, NextResolve,
+    ResolveInfo,
+};
+use async_graphql::{Request, Response, SDLExportOptions, ServerResult, Value};
+use flate2::{write::GzEncoder, Compression};
+use prost::Message;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::proto::{Report, ReportHeader};
+use super::stats::{stats_key, OperationStats};
+use super::trace::TraceBuilder;
+
+/// Environment variable holding the Apollo API key
+pub const APOLLO_KEY_ENV: &str = "APOLLO_KEY";
+
+/// Environment variable holding the graph ref (`graph@variant`)
+pub const APOLLO_GRAPH_REF_ENV: &str = "APOLLO_GRAPH_REF";
+
+/// Apollo's usage reporting ingress
+pub const DEFAULT_ENDPOINT: &str =
+    "https://usage-reporting.api.apollographql.com/api/ingress/traces";
+
+/// Default time between reports
+pub const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Default fraction of operations sent as full traces
+pub const DEFAULT_TRACE_SAMPLING: f64 = 0.01;
+
+const AGENT_VERSION: &str = concat!("pleme-graphql-helpers ", env!("CARGO_PKG_VERSION"));
+
+/// Report upload errors
+#[derive(Debug, Error)]
+pub enum ReportError {
+    #[error("failed to compress usage report: {0}")]
+    Compress(#[from] std::io::Error),
+
+    #[error("failed to send usage report: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+/// Upload settings
+#[derive(Clone)]
+struct Uploader {
+    api_key: String,
+    graph_ref: String,
+    endpoint: String,
+    service_version: String,
+    client: reqwest::Client,
+}
+
+impl Uploader {
+    /// Send and clear the stats collected since the last report
+    async fn send(&self, state: &ReportState) -> Result<(), ReportError> {
+        let operations = std::mem::take(&mut *state.operations.lock().unwrap());
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        let report = Report {
+            header: Some(self.header(state)),
+            end_time: Some(SystemTime::now().into()),
+            operation_count: operations.values().map(OperationStats::requests).sum(),
+            traces_per_query: operations
+                .into_iter()
+                .map(|(key, stats)| (key, stats.into_proto()))
+                .collect(),
+        };
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&report.encode_to_vec())?;
+        let body = encoder.finish()?;
+
+        self.client
+            .post(&self.endpoint)
+            .header("X-Api-Key", &self.api_key)
+            .header(CONTENT_TYPE, "application/protobuf")
+            .header(CONTENT_ENCODING, "gzip")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn header(&self, state: &ReportState) -> ReportHeader {
+        ReportHeader {
+            graph_ref: self.graph_ref.clone(),
+            hostname: std::env::var("HOSTNAME").unwrap_or_default(),
+            agent_version: AGENT_VERSION.to_string(),
+            service_version: self.service_version.clone(),
+            runtime_version: "rust".to_string(),
+            uname: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            executable_schema_id: state.schema_id.get().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// Stats shared by the extension and the upload task
+#[derive(Default)]
+struct ReportState {
+    /// SHA-256 of the schema SDL
+    schema_id: OnceLock<String>,
+    /// Usage by stats key since the last report
+    operations: Mutex<HashMap<String, OperationStats>>,
+    /// Set once the upload task is running
+    started: OnceLock<()>,
+}
+
+/// Extension reporting usage to Apollo GraphOS
+#[derive(Clone)]
+pub struct ApolloReporting {
+    uploader: Uploader,
+    interval: Duration,
+    trace_sampling: f64,
+    state: Arc<ReportState>,
+}
+
+impl ApolloReporting {
+    /// Report to `graph_ref` (`graph@variant`) with `api_key`
+    pub fn new(api_key: impl Into<String>, graph_ref: impl Into<String>) -> Self {
+        Self {
+            uploader: Uploader {
+                api_key: api_key.into(),
+                graph_ref: graph_ref.into(),
+                endpoint: DEFAULT_ENDPOINT.to_string(),
+                service_version: String::new(),
+                client: reqwest::Client::new(),
+            },
+            interval: DEFAULT_REPORT_INTERVAL,
+            trace_sampling: DEFAULT_TRACE_SAMPLING,
+            state: Arc::default(),
+        }
+    }
+
+    /// Configure from `APOLLO_KEY` and `APOLLO_GRAPH_REF`, if both are set
+    pub fn from_env() -> Option<Self> {
+        let api_key = std::env::var(APOLLO_KEY_ENV).ok()?;
+        let graph_ref = std::env::var(APOLLO_GRAPH_REF_ENV).ok()?;
+        Some(Self::new(api_key, graph_ref))
+    }
+
+    /// Upload to `endpoint` instead of Apollo's ingress
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.uploader.endpoint = endpoint.into();
+        self
+    }
+
+    /// Set the time between reports
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Send `rate` (0.0 to 1.0) of operations as full traces
+    pub fn with_trace_sampling(mut self, rate: f64) -> Self {
+        self.trace_sampling = rate;
+        self
+    }
+
+    /// Report the service's version, e.g. a git SHA
+    pub fn with_service_version(mut self, version: impl Into<String>) -> Self {
+        self.uploader.service_version = version.into();
+        self
+    }
+
+    /// Use `client` for uploads
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.uploader.client = client;
+        self
+    }
+
+    /// Send the stats collected so far, e.g. before shutting down
+    pub async fn flush(&self) -> Result<(), ReportError> {
+        self.uploader.send(&self.state).await
+    }
+
+    /// Upload on an interval until the extension is dropped
+    fn start(&self) {
+        let uploader = self.uploader.clone();
+        let interval = self.interval;
+        let state: Weak<ReportState> = Arc::downgrade(&self.state);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(state) = state.upgrade() else { break };
+                if let Err(_e) = uploader.send(&state).await {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_e, "Apollo usage report failed");
+                }
+            }
+        });
+    }
+
+    fn sampled(&self) -> bool {
+        // Top 48 bits of a v4 UUID are random
+        let random = (Uuid::new_v4().as_u128() >> 80) as f64 / (1u64 << 48) as f64;
+        random < self.trace_sampling
+    }
+}
+
+impl ExtensionFactory for ApolloReporting {
+    fn create(&self) -> Arc<dyn Extension> {
+        self.state.started.get_or_init(|| self.start());
+        Arc::new(ApolloReportingExtension {
+            state: self.state.clone(),
+            keep_trace: self.sampled(),
+            trace: TraceBuilder::new(),
+            key: Mutex::default(),
+        })
+    }
+}
+
+struct ApolloReportingExtension {
+    state: Arc<ReportState>,
+    /// Whether this operation is sent as a full trace
+    keep_trace: bool,
+    trace: TraceBuilder,
+    /// Stats key of the prepared request
+    key: Mutex<Option<String>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for ApolloReportingExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        // Keyed after the other extensions ran, e.g. resolved persisted queries
+        let request = next.run(ctx, request).await?;
+        *self.key.lock().unwrap() =
+            Some(stats_key(request.operation_name.as_deref(), &request.query));
+        Ok(request)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        self.state.schema_id.get_or_init(|| {
+            let sdl = ctx.schema_env.registry.export_sdl(SDLExportOptions::new());
+            format!("{:x}", Sha256::digest(sdl))
+        });
+        let response = next.run(ctx, operation_name).await;
+
+        let key = self.key.lock().unwrap().take();
+        if let Some(key) = key {
+            let trace = self.trace.finish();
+            self.state
+                .operations
+                .lock()
+                .unwrap()
+                .entry(key)
+                .or_default()
+                .record(trace, response.errors.len(), self.keep_trace);
+        }
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        self.trace.resolve(ctx, info, next).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+
+    #[derive(SimpleObject)]
+    struct User {
+        name: String,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn users(&self) -> Vec<User> {
+            vec![
+                User {
+                    name: "a".to_string(),
+                },
+                User {
+                    name: "b".to_string(),
+                },
+            ]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aggregates_usage() {
+        let reporting = ApolloReporting::new("key", "graph@current").with_trace_sampling(1.0);
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(reporting.clone())
+            .finish();
+
+        schema.execute("query Users { users { name } }").await;
+        schema.execute("query Users {\n  users { name }\n}").await;
+
+        let mut operations = std::mem::take(&mut *reporting.state.operations.lock().unwrap());
+        assert_eq!(operations.len(), 1);
+        let usage = operations
+            .remove("# Users\nquery Users{users{name}}")
+            .unwrap()
+            .into_proto();
+
+        let stats = &usage.stats_with_context[0];
+        assert_eq!(stats.query_latency_stats.as_ref().unwrap().request_count, 2);
+        let name = &stats.per_type_stat["User"].per_field_stat["name"];
+        assert_eq!(name.observed_execution_count, 4);
+        assert_eq!(name.return_type, "String!");
+        assert_eq!(
+            usage.referenced_fields_by_type["Query"].field_names,
+            vec!["users"]
+        );
+
+        // users > [0, 1] > name
+        let root = usage.trace[0].root.as_ref().unwrap();
+        assert_eq!(root.child[0].child.len(), 2);
+        assert_eq!(root.child[0].child[1].child[0].parent_type, "User");
+    }
+}
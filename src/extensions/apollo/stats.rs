@@ -0,0 +1,289 @@
+//! Usage stats aggregation
+//!
+//! Operations are grouped by stats key: the operation name and a
+//! normalized signature of the query text. Field stats and referenced
+//! fields are taken from each operation's trace.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::proto::trace::{node::Id, Node};
+use super::proto::{
+    ContextualizedStats, FieldStat, QueryLatencyStats, ReferencedFieldsForType, StatsContext,
+    Trace, TracesAndStats, TypeStat,
+};
+
+/// Number of latency histogram buckets
+const BUCKETS: usize = 384;
+
+/// Latency histogram with Apollo's exponential buckets
+///
+/// Bucket `n` counts durations up to `1.1^n` microseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DurationHistogram {
+    buckets: Vec<i64>,
+}
+
+impl DurationHistogram {
+    pub(crate) fn record(&mut self, duration_ns: u64) {
+        let bucket = bucket(duration_ns);
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+    }
+
+    /// Counts with runs of two or more empty buckets as their negated length
+    pub(crate) fn encode(&self) -> Vec<i64> {
+        let mut encoded = Vec::with_capacity(self.buckets.len());
+        let mut empty = 0;
+        for &count in &self.buckets {
+            if count == 0 {
+                empty += 1;
+                continue;
+            }
+            match empty {
+                0 => {}
+                1 => encoded.push(0),
+                run => encoded.push(-run),
+            }
+            empty = 0;
+            encoded.push(count);
+        }
+        encoded
+    }
+}
+
+fn bucket(duration_ns: u64) -> usize {
+    let micros = duration_ns as f64 / 1000.0;
+    if micros <= 1.0 {
+        return 0;
+    }
+    let bucket = (micros.ln() / 1.1f64.ln()).ceil() as usize;
+    bucket.min(BUCKETS - 1)
+}
+
+#[derive(Debug, Default)]
+struct FieldStats {
+    return_type: String,
+    count: u64,
+    errors: u64,
+    requests_with_errors: u64,
+    latency: DurationHistogram,
+}
+
+/// Usage of one operation since the last report
+#[derive(Debug, Default)]
+pub(crate) struct OperationStats {
+    requests: u64,
+    requests_with_errors: u64,
+    latency: DurationHistogram,
+    /// Field stats by parent type and field name
+    fields: BTreeMap<String, BTreeMap<String, FieldStats>>,
+    traces: Vec<Trace>,
+}
+
+impl OperationStats {
+    pub(crate) fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// Add an operation's trace; `keep` also keeps the trace itself
+    pub(crate) fn record(&mut self, trace: Trace, errors: usize, keep: bool) {
+        self.requests += 1;
+        if errors > 0 {
+            self.requests_with_errors += 1;
+        }
+        self.latency.record(trace.duration_ns);
+        if let Some(root) = &trace.root {
+            self.record_node(root);
+        }
+        if keep {
+            self.traces.push(trace);
+        }
+    }
+
+    fn record_node(&mut self, node: &Node) {
+        for child in &node.child {
+            if let Some(Id::ResponseName(response_name)) = &child.id {
+                let name = match child.original_field_name.as_str() {
+                    "" => response_name.clone(),
+                    name => name.to_string(),
+                };
+                let field = self
+                    .fields
+                    .entry(child.parent_type.clone())
+                    .or_default()
+                    .entry(name)
+                    .or_default();
+                field.return_type.clone_from(&child.r#type);
+                field.count += 1;
+                field.errors += child.error.len() as u64;
+                if !child.error.is_empty() {
+                    field.requests_with_errors += 1;
+                }
+                field
+                    .latency
+                    .record(child.end_time.saturating_sub(child.start_time));
+            }
+            self.record_node(child);
+        }
+    }
+
+    pub(crate) fn into_proto(self) -> TracesAndStats {
+        let referenced_fields_by_type = self
+            .fields
+            .iter()
+            .map(|(ty, fields)| {
+                let field_names = fields.keys().cloned().collect::<BTreeSet<_>>();
+                let referenced = ReferencedFieldsForType {
+                    field_names: field_names.into_iter().collect(),
+                    is_interface: false,
+                };
+                (ty.clone(), referenced)
+            })
+            .collect();
+        let per_type_stat: HashMap<_, _> = self
+            .fields
+            .into_iter()
+            .map(|(ty, fields)| {
+                let per_field_stat = fields
+                    .into_iter()
+                    .map(|(name, field)| {
+                        let stat = FieldStat {
+                            return_type: field.return_type,
+                            errors_count: field.errors,
+                            observed_execution_count: field.count,
+                            requests_with_errors_count: field.requests_with_errors,
+                            latency_count: field.latency.encode(),
+                            estimated_execution_count: field.count as f64,
+                        };
+                        (name, stat)
+                    })
+                    .collect();
+                (ty, TypeStat { per_field_stat })
+            })
+            .collect();
+
+        TracesAndStats {
+            trace: self.traces,
+            stats_with_context: vec![ContextualizedStats {
+                context: Some(StatsContext::default()),
+                query_latency_stats: Some(QueryLatencyStats {
+                    request_count: self.requests,
+                    requests_with_errors_count: self.requests_with_errors,
+                    latency_count: self.latency.encode(),
+                }),
+                per_type_stat,
+            }],
+            referenced_fields_by_type,
+        }
+    }
+}
+
+/// Stats key of an operation: `# <name>\n<signature>`
+pub(crate) fn stats_key(operation_name: Option<&str>, query: &str) -> String {
+    format!("# {}\n{}", operation_name.unwrap_or("-"), signature(query))
+}
+
+/// Query text with comments removed, literals blanked, and whitespace
+/// collapsed
+///
+/// A simplified form of Apollo's operation signature: operations differing
+/// only in formatting or inline values share a key.
+pub(crate) fn signature(query: &str) -> String {
+    let bytes = query.as_bytes();
+    let mut signature = String::with_capacity(query.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &query[i..];
+        match bytes[i] {
+            b'#' => i += rest.find('\n').unwrap_or(rest.len()),
+            b if b.is_ascii_whitespace() || b == b',' => i += 1,
+            b'"' => {
+                i += string_len(rest);
+                push_token(&mut signature, "\"\"");
+            }
+            b if b.is_ascii_digit() || b == b'-' => {
+                i += rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')))
+                    .unwrap_or(rest.len());
+                push_token(&mut signature, "0");
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                push_token(&mut signature, &rest[..len]);
+                i += len;
+            }
+            _ => {
+                let c = rest.chars().next().unwrap_or_default();
+                signature.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    signature
+}
+
+/// Length of the string or block string literal `rest` starts with
+fn string_len(rest: &str) -> usize {
+    if let Some(block) = rest.strip_prefix("\"\"\"") {
+        return block.find("\"\"\"").map_or(rest.len(), |end| end + 6);
+    }
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i + 1,
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// Append a token, separated from a preceding word
+fn push_token(signature: &mut String, token: &str) {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '"';
+    let after_word = signature.chars().next_back().is_some_and(is_word);
+    if after_word && token.starts_with(is_word) {
+        signature.push(' ');
+    }
+    signature.push_str(token);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_encoding() {
+        let mut histogram = DurationHistogram::default();
+        histogram.record(500);
+        histogram.record(1_100 * 1000);
+        histogram.record(1_100 * 1000);
+        assert_eq!(bucket(1_000), 0);
+        assert_eq!(bucket(1_050), 1);
+
+        let encoded = histogram.encode();
+        assert_eq!(encoded.first(), Some(&1));
+        assert_eq!(encoded.last(), Some(&2));
+        assert!(encoded[1] < 0);
+    }
+
+    #[test]
+    fn test_signature() {
+        let a = "query Find($id: ID!) {\n  # lookup\n  user(id: $id, limit: 10) { name }\n}";
+        let b = "query Find($id: ID!) { user(id: $id, limit: 20) { name } }";
+        assert_eq!(signature(a), signature(b));
+        assert_eq!(
+            signature(b),
+            "query Find($id:ID!){user(id:$id limit:0){name}}"
+        );
+        assert_eq!(
+            signature(r#"{ search(q: "a \" b", t: """x""") }"#),
+            r#"{search(q:"" t:"")}"#
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Resolver timing trees
+//!
+//! [`TraceBuilder`] records every resolved field of one operation, keyed by
+//! its response path, and assembles them into an Apollo [`Trace`].
+
+use async_graphql::extensions::{ExtensionContext, NextResolve, ResolveInfo};
+use async_graphql::{QueryPathNode, QueryPathSegment, ServerError, ServerResult, Value};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+use super::proto::trace::{node::Id, Error, Location, Node};
+use super::proto::{Timestamp, Trace};
+
+/// Collects field timings for one operation
+pub(crate) struct TraceBuilder {
+    started_at: SystemTime,
+    started: Instant,
+    /// Recorded fields with their response paths
+    fields: Mutex<Vec<(Vec<Id>, Node)>>,
+}
+
+impl TraceBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            started_at: SystemTime::now(),
+            started: Instant::now(),
+            fields: Mutex::default(),
+        }
+    }
+
+    fn elapsed_ns(&self) -> u64 {
+        self.started.elapsed().as_nanos() as u64
+    }
+
+    /// Run a resolver, recording its timing and errors
+    pub(crate) async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if info.is_for_introspection {
+            return next.run(ctx, info).await;
+        }
+        let path = path(info.path_node);
+        let mut node = Node {
+            r#type: info.return_type.to_string(),
+            parent_type: info.parent_type.to_string(),
+            original_field_name: match info.alias {
+                Some(_) => info.name.to_string(),
+                None => String::new(),
+            },
+            start_time: self.elapsed_ns(),
+            ..Default::default()
+        };
+
+        let result = next.run(ctx, info).await;
+        node.end_time = self.elapsed_ns();
+        if let Err(error) = &result {
+            node.error.push(trace_error(error));
+        }
+        self.fields.lock().unwrap().push((path, node));
+        result
+    }
+
+    /// The trace of all recorded fields
+    pub(crate) fn finish(&self) -> Trace {
+        let mut fields = std::mem::take(&mut *self.fields.lock().unwrap());
+        // Parents before children
+        fields.sort_by_key(|(path, _)| path.len());
+
+        let mut root = Node::default();
+        for (mut path, mut node) in fields {
+            let Some(id) = path.pop() else { continue };
+            node.id = Some(id);
+            let mut parent = &mut root;
+            for id in path {
+                let position = match parent.child.iter().position(|c| c.id.as_ref() == Some(&id)) {
+                    Some(position) => position,
+                    None => {
+                        // List items have no resolver of their own
+                        parent.child.push(Node {
+                            id: Some(id),
+                            ..Default::default()
+                        });
+                        parent.child.len() - 1
+                    }
+                };
+                parent = &mut parent.child[position];
+            }
+            parent.child.push(node);
+        }
+
+        Trace {
+            start_time: Some(self.started_at.into()),
+            end_time: Some(Timestamp::from(SystemTime::now())),
+            duration_ns: self.elapsed_ns(),
+            root: Some(root),
+            ..Default::default()
+        }
+    }
+}
+
+/// Response path of a field, from the root
+fn path(node: &QueryPathNode<'_>) -> Vec<Id> {
+    let mut path = Vec::new();
+    let mut current = Some(node);
+    while let Some(node) = current {
+        path.push(match &node.segment {
+            QueryPathSegment::Index(index) => Id::Index(*index as u32),
+            QueryPathSegment::Name(name) => Id::ResponseName(name.to_string()),
+        });
+        current = node.parent;
+    }
+    path.reverse();
+    path
+}
+
+fn trace_error(error: &ServerError) -> Error {
+    Error {
+        message: error.message.clone(),
+        location: error
+            .locations
+            .iter()
+            .map(|pos| Location {
+                line: pos.line as u32,
+                column: pos.column as u32,
+            })
+            .collect(),
+        json: serde_json::to_string(error).unwrap_or_default(),
+    }
+}
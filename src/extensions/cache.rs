@@ -0,0 +1,446 @@
+//! Response caching with per-field cache hints
+//!
+//! Cache hints are declared with async-graphql's `cache_control` attribute,
+//! the equivalent of Apollo's `@cacheControl(maxAge, scope)`: `max_age` in
+//! seconds, and `private` for the `PRIVATE` scope. The policy of an
+//! operation is the lowest `max_age` of the hints it selects, and private
+//! if any of them is.
+//!
+//! As in Apollo, root fields and fields returning objects, interfaces, or
+//! unions are uncacheable unless they or their type carry a hint; other
+//! fields inherit their parent's. [`ResponseCache`] only caches queries
+//! whose every such field is hinted, so an unhinted field (say `me`) never
+//! ends up in a response served to other callers.
+//!
+//! [`ResponseCache`] stores successful query responses with a non-zero
+//! `max_age` in a [`CacheStore`], keyed by query, operation name, and
+//! variables. Private responses are also keyed by user and company, and
+//! aren't cached for anonymous callers. The handlers send the policy as a
+//! `Cache-Control` header.
+//!
+//! # Example
+//!
+//! ```rust
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+//! use pleme_graphql_helpers::extensions::{MemoryCacheStore, ResponseCache};
+//! use std::sync::Arc;
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     #[graphql(cache_control(max_age = 300))]
+//!     async fn plans(&self) -> Vec<String> {
+//!         vec!["basic".to_string(), "pro".to_string()]
+//!     }
+//!
+//!     #[graphql(cache_control(max_age = 60, private))]
+//!     async fn cart_total(&self) -> i32 {
+//!         0
+//!     }
+//! }
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .extension(ResponseCache::new(Arc::new(MemoryCacheStore::new())))
+//!     .finish();
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextPrepareRequest,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType, Selection, SelectionSet};
+use async_graphql::registry::{MetaType, Registry};
+use async_graphql::{CacheControl, Name, Request, Response, ServerResult, Value};
+use async_trait::async_trait;
+use axum::http::{header::CACHE_CONTROL, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::{select_operation, AuthContext};
+
+/// A cached response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub data: Value,
+    /// Seconds the response may be cached for
+    pub max_age: i32,
+    /// Whether shared caches may store the response
+    pub public: bool,
+}
+
+impl CachedResponse {
+    fn cache_control(&self) -> CacheControl {
+        CacheControl {
+            public: self.public,
+            max_age: self.max_age,
+        }
+    }
+}
+
+/// Storage for cached responses
+///
+/// Implement for Redis or another shared store so replicas share a cache;
+/// [`MemoryCacheStore`] keeps entries per process.
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// The unexpired entry for `key`
+    async fn get(&self, key: &str) -> Option<CachedResponse>;
+
+    /// Store `response` under `key` for `ttl`
+    async fn set(&self, key: &str, response: CachedResponse, ttl: Duration);
+}
+
+/// Shared cache store handle
+pub type SharedCacheStore = Arc<dyn CacheStore>;
+
+/// In-process [`CacheStore`]
+///
+/// Expired entries are dropped when read.
+#[derive(Debug, Default)]
+pub struct MemoryCacheStore {
+    entries: Mutex<HashMap<String, (Instant, CachedResponse)>>,
+}
+
+impl MemoryCacheStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((expires, response)) if *expires > Instant::now() => Some(response.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn set(&self, key: &str, response: CachedResponse, ttl: Duration) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (Instant::now() + ttl, response));
+    }
+}
+
+/// Extension caching query responses per their cache hints
+#[derive(Clone)]
+pub struct ResponseCache {
+    store: SharedCacheStore,
+}
+
+impl ResponseCache {
+    pub fn new(store: SharedCacheStore) -> Self {
+        Self { store }
+    }
+}
+
+impl ExtensionFactory for ResponseCache {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ResponseCacheExtension {
+            store: self.store.clone(),
+            key: Mutex::new(None),
+        })
+    }
+}
+
+struct ResponseCacheExtension {
+    store: SharedCacheStore,
+    /// Hash of the prepared query, operation name, and variables
+    key: Mutex<Option<String>>,
+}
+
+#[async_trait]
+impl Extension for ResponseCacheExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        // Keyed after the other extensions ran, e.g. resolved persisted queries
+        let request = next.run(ctx, request).await?;
+        if is_cacheable(&ctx.schema_env.registry, &request) {
+            *self.key.lock().unwrap() = Some(request_key(&request));
+        }
+        Ok(request)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let key = self.key.lock().unwrap().take();
+        let Some(key) = key else {
+            return next.run(ctx, operation_name).await;
+        };
+        let public_key = format!("public:{key}");
+        let private_key = ctx
+            .data_opt::<AuthContext>()
+            .and_then(|auth| auth.user_id.map(|user| (user, auth.company_id)))
+            .map(|(user, company)| match company {
+                Some(company) => format!("private:{user}:{company}:{key}"),
+                None => format!("private:{user}:{key}"),
+            });
+
+        for key in std::iter::once(&public_key).chain(private_key.as_ref()) {
+            if let Some(cached) = self.store.get(key).await {
+                let cache_control = cached.cache_control();
+                let mut response = Response::new(cached.data);
+                response.cache_control = cache_control;
+                return response;
+            }
+        }
+
+        let response = next.run(ctx, operation_name).await;
+        let CacheControl { public, max_age } = response.cache_control;
+        if response.is_ok() && max_age > 0 {
+            let key = if public {
+                Some(&public_key)
+            } else {
+                private_key.as_ref()
+            };
+            if let Some(key) = key {
+                let cached = CachedResponse {
+                    data: response.data.clone(),
+                    max_age,
+                    public,
+                };
+                let ttl = Duration::from_secs(max_age.unsigned_abs().into());
+                self.store.set(key, cached, ttl).await;
+            }
+        }
+        response
+    }
+}
+
+/// Whether `request` is a query whose every field is covered by a hint
+fn is_cacheable(registry: &Registry, request: &Request) -> bool {
+    let Ok(document) = async_graphql::parser::parse_query(&request.query) else {
+        return false;
+    };
+    let Some(operation) = select_operation(&document, request.operation_name.as_deref()) else {
+        return false;
+    };
+    if operation.ty != OperationType::Query {
+        return false;
+    }
+
+    let hints = HintCheck {
+        document: &document,
+        registry,
+    };
+    hints.covered(
+        &operation.selection_set.node,
+        &registry.query_type,
+        true,
+        &mut HashMap::new(),
+    )
+}
+
+/// Whether each fragment is covered, by name and whether it's spread at
+/// the root
+///
+/// `true` while the fragment is being checked, so a cycle (which
+/// validation rejects) doesn't recurse forever.
+type FragmentHints<'a> = HashMap<(&'a Name, bool), bool>;
+
+struct HintCheck<'a> {
+    document: &'a ExecutableDocument,
+    registry: &'a Registry,
+}
+
+impl<'a> HintCheck<'a> {
+    /// Whether every field in `selection_set` on `parent` is hinted, or
+    /// inherits its parent's hint
+    fn covered(
+        &self,
+        selection_set: &'a SelectionSet,
+        parent: &str,
+        root: bool,
+        fragments: &mut FragmentHints<'a>,
+    ) -> bool {
+        selection_set.items.iter().all(|selection| match &selection.node {
+            Selection::Field(field) => {
+                let field = &field.node;
+                let meta = self
+                    .registry
+                    .types
+                    .get(parent)
+                    .and_then(|ty| ty.field_by_name(&field.name.node));
+                // `__typename`, and unknown fields validation will reject
+                let Some(meta) = meta else {
+                    return true;
+                };
+                let ty_name = meta.ty.trim_matches(|c| c == '[' || c == ']' || c == '!');
+                let ty = self.registry.types.get(ty_name);
+                let type_hinted = matches!(
+                    ty,
+                    Some(MetaType::Object { cache_control, .. }) if cache_control.max_age != 0
+                );
+                let inherits = !root && !ty.is_some_and(MetaType::is_composite);
+                (meta.cache_control.max_age != 0 || type_hinted || inherits)
+                    && self.covered(&field.selection_set.node, ty_name, false, fragments)
+            }
+            Selection::InlineFragment(fragment) => {
+                let ty = fragment
+                    .node
+                    .type_condition
+                    .as_ref()
+                    .map_or(parent, |condition| condition.node.on.node.as_str());
+                self.covered(&fragment.node.selection_set.node, ty, root, fragments)
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                if let Some(covered) = fragments.get(&(name, root)) {
+                    return *covered;
+                }
+                let Some(fragment) = self.document.fragments.get(name) else {
+                    return true;
+                };
+                fragments.insert((name, root), true);
+                let covered = self.covered(
+                    &fragment.node.selection_set.node,
+                    fragment.node.type_condition.node.on.node.as_str(),
+                    root,
+                    fragments,
+                );
+                fragments.insert((name, root), covered);
+                covered
+            }
+        })
+    }
+}
+
+/// Hash of the query, operation name, and variables
+fn request_key(request: &Request) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(request.query.as_bytes());
+    hasher.update([0]);
+    hasher.update(request.operation_name.as_deref().unwrap_or_default());
+    hasher.update([0]);
+    hasher.update(serde_json::to_vec(&request.variables).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Set `Cache-Control` from a successful response's cache policy
+pub(crate) fn insert_cache_header(headers: &mut HeaderMap, ok: bool, cache_control: CacheControl) {
+    let value = cache_control
+        .value()
+        .filter(|_| ok)
+        .and_then(|value| HeaderValue::from_str(&value).ok());
+    if let Some(value) = value {
+        headers.insert(CACHE_CONTROL, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Query {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[Object]
+    impl Query {
+        #[graphql(cache_control(max_age = 60))]
+        async fn plans(&self) -> i32 {
+            self.calls.fetch_add(1, Ordering::SeqCst) as i32
+        }
+
+        #[graphql(cache_control(max_age = 60, private))]
+        async fn cart(&self) -> i32 {
+            self.calls.fetch_add(1, Ordering::SeqCst) as i32
+        }
+
+        async fn me(&self, ctx: &async_graphql::Context<'_>) -> Me {
+            let user = ctx.data_opt::<AuthContext>().and_then(|auth| auth.user_id);
+            Me {
+                email: user.map(|user| format!("{user}@example.com")),
+            }
+        }
+    }
+
+    #[derive(async_graphql::SimpleObject)]
+    struct Me {
+        email: Option<String>,
+    }
+
+    #[tokio::test]
+    async fn test_caches_public_responses() {
+        let query = Query::default();
+        let calls = query.calls.clone();
+        let schema = Schema::build(query, EmptyMutation, EmptySubscription)
+            .extension(ResponseCache::new(Arc::new(MemoryCacheStore::new())))
+            .finish();
+
+        let first = schema.execute("{ plans }").await;
+        let second = schema.execute("{ plans }").await;
+        assert_eq!(first.data, second.data);
+        assert_eq!(second.cache_control.value().as_deref(), Some("max-age=60"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Private and anonymous: not cached
+        schema.execute("{ cart }").await;
+        schema.execute("{ cart }").await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unhinted_fields_not_cached() {
+        let schema = Schema::build(Query::default(), EmptyMutation, EmptySubscription)
+            .extension(ResponseCache::new(Arc::new(MemoryCacheStore::new())))
+            .finish();
+        let as_user = |user| {
+            Request::new("{ plans me { email } }").data(AuthContext {
+                user_id: Some(user),
+                ..AuthContext::default()
+            })
+        };
+        let (alice, bob) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+
+        schema.execute(as_user(alice)).await;
+        let response = schema.execute(as_user(bob)).await;
+        let email = format!("{bob}@example.com");
+        assert_eq!(
+            response.data.into_json().unwrap()["me"]["email"],
+            serde_json::json!(email)
+        );
+
+        // Nothing cached for anonymous callers either
+        let response = schema.execute("{ plans me { email } }").await;
+        assert_eq!(
+            response.data.into_json().unwrap()["me"]["email"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_cache_header() {
+        let mut headers = HeaderMap::new();
+        let private = CacheControl {
+            public: false,
+            max_age: 30,
+        };
+        insert_cache_header(&mut headers, false, private);
+        assert!(headers.get(CACHE_CONTROL).is_none());
+
+        insert_cache_header(&mut headers, true, private);
+        assert_eq!(headers[CACHE_CONTROL], "max-age=30, private");
+    }
+}
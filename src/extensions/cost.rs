@@ -0,0 +1,473 @@
+//! Query cost analysis
+//!
+//! Every field costs its `@cost` weight (1 by default), multiplied by the
+//! number of times it resolves. List fields resolve their selection once
+//! per item: the page size is read from `first` / `last` (flat arguments
+//! or a [`PaginationInput`](crate::pagination::PaginationInput) argument),
+//! capped at [`PaginationConfig::max_page_size`], and otherwise assumed to
+//! be [`PaginationConfig::default_page_size`]. A connection's page size
+//! carries down to its `edges` list.
+//!
+//! Operations over budget are rejected before execution with code
+//! `QUERY_TOO_COMPLEX`. The computed cost is returned under
+//! `extensions.cost` either way.
+//!
+//! # Example
+//!
+//! ```rust
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+//! use pleme_graphql_helpers::extensions::{cost_directive, CostAnalysis};
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     #[graphql(directive = cost_directive::apply(10))]
+//!     async fn report(&self) -> String {
+//!         String::new()
+//!     }
+//! }
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .extension(CostAnalysis::new(1000))
+//!     .finish();
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery,
+};
+use async_graphql::parser::types::{
+    ExecutableDocument, Field, OperationType, Selection, SelectionSet,
+};
+use async_graphql::registry::{MetaField, Registry};
+use async_graphql::{Name, Pos, Response, ServerResult, TypeDirective, Value, Variables};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::ErrorCode;
+use crate::pagination::PaginationConfig;
+
+/// Declare the cost of resolving a field once
+#[TypeDirective(name = "cost", location = "FieldDefinition")]
+pub fn cost_directive(weight: u32) {}
+
+/// Extension rejecting operations whose cost exceeds `budget`
+#[derive(Debug, Clone, Copy)]
+pub struct CostAnalysis {
+    budget: usize,
+}
+
+impl CostAnalysis {
+    pub fn new(budget: usize) -> Self {
+        Self { budget }
+    }
+}
+
+impl ExtensionFactory for CostAnalysis {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(CostAnalysisExtension {
+            budget: self.budget,
+            costs: Mutex::default(),
+        })
+    }
+}
+
+struct CostAnalysisExtension {
+    budget: usize,
+    costs: Mutex<OperationCosts>,
+}
+
+#[async_trait::async_trait]
+impl Extension for CostAnalysisExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        let costs = OperationCosts::compute(ctx, &document, variables);
+        *self.costs.lock().unwrap() = costs;
+
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let cost = self.costs.lock().unwrap().get(operation_name);
+        let Some(cost) = cost else {
+            return next.run(ctx, operation_name).await;
+        };
+
+        let mut response = if cost > self.budget {
+            let error = ErrorCode::QueryTooComplex
+                .error(format!(
+                    "Query cost {cost} exceeds the budget of {}",
+                    self.budget
+                ))
+                .into_server_error(Pos::default());
+            Response::from_errors(vec![error])
+        } else {
+            next.run(ctx, operation_name).await
+        };
+
+        let mut summary = async_graphql::indexmap::IndexMap::new();
+        summary.insert(Name::new("requested"), Value::from(cost as u64));
+        summary.insert(Name::new("budget"), Value::from(self.budget as u64));
+        response
+            .extensions
+            .insert("cost".to_string(), Value::Object(summary));
+        response
+    }
+}
+
+/// Cost of each operation in a document, by operation name
+#[derive(Debug, Default)]
+pub(crate) struct OperationCosts(Vec<(Option<Name>, usize)>);
+
+impl OperationCosts {
+    pub(crate) fn compute(
+        ctx: &ExtensionContext<'_>,
+        document: &ExecutableDocument,
+        variables: &Variables,
+    ) -> Self {
+        let registry = &ctx.schema_env.registry;
+        let analyzer = Analyzer {
+            document,
+            registry,
+            variables,
+            pagination: ctx
+                .data_opt::<PaginationConfig>()
+                .copied()
+                .unwrap_or_default(),
+        };
+        let costs = document
+            .operations
+            .iter()
+            .map(|(name, operation)| {
+                let root = match operation.node.ty {
+                    OperationType::Query => Some(registry.query_type.as_str()),
+                    OperationType::Mutation => registry.mutation_type.as_deref(),
+                    OperationType::Subscription => registry.subscription_type.as_deref(),
+                };
+                let cost = analyzer.cost(
+                    &operation.node.selection_set.node,
+                    root,
+                    1,
+                    None,
+                    &mut HashMap::new(),
+                );
+                (name.cloned(), cost)
+            })
+            .collect();
+        Self(costs)
+    }
+
+    /// Cost of the operation `operation_name` selects
+    pub(crate) fn get(&self, operation_name: Option<&str>) -> Option<usize> {
+        match operation_name {
+            Some(name) => self
+                .0
+                .iter()
+                .find(|(op, _)| op.as_deref() == Some(name))
+                .map(|(_, cost)| *cost),
+            None if self.0.len() == 1 => Some(self.0[0].1),
+            None => None,
+        }
+    }
+}
+
+/// Cost of resolving each fragment once, by name and enclosing page size
+///
+/// `None` while the fragment is being analyzed, so a cycle counts as free.
+type FragmentCosts<'a> = HashMap<(&'a Name, Option<usize>), Option<usize>>;
+
+struct Analyzer<'a> {
+    document: &'a ExecutableDocument,
+    registry: &'a Registry,
+    variables: &'a Variables,
+    pagination: PaginationConfig,
+}
+
+impl<'a> Analyzer<'a> {
+    /// Cost of `selection_set` on `parent`, resolved `multiplier` times
+    ///
+    /// `page` is the page size requested by an enclosing connection that
+    /// hasn't reached its list yet. Cost is linear in `multiplier`, so each
+    /// fragment is analyzed once per page size and its cost memoized in
+    /// `fragments`; this also ends fragment cycles, which validation only
+    /// rejects later.
+    fn cost(
+        &self,
+        selection_set: &'a SelectionSet,
+        parent: Option<&str>,
+        multiplier: usize,
+        page: Option<usize>,
+        fragments: &mut FragmentCosts<'a>,
+    ) -> usize {
+        selection_set.items.iter().fold(0usize, |total, selection| {
+            let cost = match &selection.node {
+                Selection::Field(field) => {
+                    let field = &field.node;
+                    let meta = parent
+                        .and_then(|ty| self.registry.types.get(ty))
+                        .and_then(|ty| ty.field_by_name(&field.name.node));
+                    match meta {
+                        Some(meta) => self.field_cost(field, meta, multiplier, page, fragments),
+                        // Meta fields, and unknown fields validation will reject
+                        None => multiplier,
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let ty = fragment
+                        .node
+                        .type_condition
+                        .as_ref()
+                        .map(|condition| condition.node.on.node.as_str())
+                        .or(parent);
+                    self.cost(
+                        &fragment.node.selection_set.node,
+                        ty,
+                        multiplier,
+                        page,
+                        fragments,
+                    )
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = &spread.node.fragment_name.node;
+                    self.fragment_cost(name, page, fragments)
+                        .saturating_mul(multiplier)
+                }
+            };
+            total.saturating_add(cost)
+        })
+    }
+
+    /// Cost of resolving the fragment `name` once
+    fn fragment_cost(
+        &self,
+        name: &'a Name,
+        page: Option<usize>,
+        fragments: &mut FragmentCosts<'a>,
+    ) -> usize {
+        if let Some(cost) = fragments.get(&(name, page)) {
+            return cost.unwrap_or(0);
+        }
+        let Some(fragment) = self.document.fragments.get(name) else {
+            return 0;
+        };
+
+        fragments.insert((name, page), None);
+        let cost = self.cost(
+            &fragment.node.selection_set.node,
+            Some(fragment.node.type_condition.node.on.node.as_str()),
+            1,
+            page,
+            fragments,
+        );
+        fragments.insert((name, page), Some(cost));
+        cost
+    }
+
+    /// Cost of `field` and its selection
+    fn field_cost(
+        &self,
+        field: &'a Field,
+        meta: &'a MetaField,
+        multiplier: usize,
+        page: Option<usize>,
+        fragments: &mut FragmentCosts<'a>,
+    ) -> usize {
+        let page = self.page_size(field, meta).or(page);
+        let (children, page) = if meta.ty.starts_with('[') {
+            let size = page.unwrap_or(self.default_page_size());
+            (multiplier.saturating_mul(size), None)
+        } else {
+            (multiplier, page)
+        };
+
+        let selection = self.cost(
+            &field.selection_set.node,
+            Some(named_type(&meta.ty)),
+            children,
+            page,
+            fragments,
+        );
+        weight(meta)
+            .saturating_mul(multiplier)
+            .saturating_add(selection)
+    }
+
+    /// Page size from `first` / `last`, for fields accepting them
+    fn page_size(&self, field: &Field, meta: &MetaField) -> Option<usize> {
+        let paginated = ["first", "last", "pagination"]
+            .iter()
+            .any(|arg| meta.args.contains_key(*arg));
+        if !paginated {
+            return None;
+        }
+
+        let requested = field.arguments.iter().find_map(|(name, value)| {
+            let value = value
+                .node
+                .clone()
+                .into_const_with(|var| self.variables.get(&var).cloned().ok_or(()))
+                .ok()?;
+            match name.node.as_str() {
+                "first" | "last" => int(&value),
+                "pagination" => match value {
+                    Value::Object(input) => input
+                        .get("first")
+                        .and_then(int)
+                        .or_else(|| input.get("last").and_then(int)),
+                    _ => None,
+                },
+                _ => None,
+            }
+        });
+        let max = usize::try_from(self.pagination.max_page_size).unwrap_or(0);
+        Some(requested.unwrap_or(self.default_page_size()).min(max))
+    }
+
+    fn default_page_size(&self) -> usize {
+        usize::try_from(self.pagination.default_page_size).unwrap_or(0)
+    }
+}
+
+/// The field's `@cost` weight, 1 without one
+fn weight(field: &MetaField) -> usize {
+    field
+        .directive_invocations
+        .iter()
+        .find(|directive| directive.name == "cost")
+        .and_then(|directive| directive.args.get("weight"))
+        .and_then(int)
+        .unwrap_or(1)
+}
+
+fn int(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(n) => n.as_u64().and_then(|n| usize::try_from(n).ok()),
+        _ => None,
+    }
+}
+
+/// `User` for `[User!]!`
+fn named_type(ty: &str) -> &str {
+    ty.trim_matches(|c| c == '[' || c == ']' || c == '!')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pagination::{Connection, PaginationInput};
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+
+    #[derive(SimpleObject, Clone)]
+    struct User {
+        id: i32,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn users(&self, first: Option<i32>) -> Vec<User> {
+            vec![User {
+                id: first.unwrap_or_default(),
+            }]
+        }
+
+        async fn members(&self, pagination: Option<PaginationInput>) -> Connection<User> {
+            let _ = pagination;
+            Connection::empty()
+        }
+
+        #[graphql(directive = cost_directive::apply(50))]
+        async fn report(&self) -> bool {
+            true
+        }
+    }
+
+    fn schema(budget: usize) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(CostAnalysis::new(budget))
+            .finish()
+    }
+
+    fn requested(response: &Response) -> Value {
+        let Some(Value::Object(cost)) = response.extensions.get("cost") else {
+            panic!("missing cost extension");
+        };
+        cost["requested"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_list_cost_weighted_by_page_size() {
+        let schema = schema(1000);
+
+        // users + 10 ids
+        let response = schema.execute("{ users(first: 10) { id } }").await;
+        assert!(response.is_ok());
+        assert_eq!(requested(&response), Value::from(11));
+
+        // members + edges + 5 * (node + id)
+        let response = schema
+            .execute("{ members(pagination: { first: 5 }) { edges { node { id } } } }")
+            .await;
+        assert_eq!(requested(&response), Value::from(12));
+
+        let response = schema.execute("{ report }").await;
+        assert_eq!(requested(&response), Value::from(50));
+    }
+
+    #[test]
+    fn test_repeated_fragments_analyzed_once() {
+        // Every fragment spreads the next one twice: 2^40 expansions unless
+        // fragment costs are memoized
+        let mut query = String::from("{ ...F0 }");
+        for i in 0..40 {
+            query.push_str(&format!(
+                " fragment F{} on Query {{ report ...F{} ...F{} }}",
+                i,
+                i + 1,
+                i + 1
+            ));
+        }
+        query.push_str(" fragment F40 on Query { report }");
+
+        let document = async_graphql::parser::parse_query(&query).unwrap();
+        let analyzer = Analyzer {
+            document: &document,
+            registry: &Registry::default(),
+            variables: &Variables::default(),
+            pagination: PaginationConfig::default(),
+        };
+        let (_, operation) = document.operations.iter().next().unwrap();
+        let cost = analyzer.cost(
+            &operation.node.selection_set.node,
+            Some("Query"),
+            1,
+            None,
+            &mut HashMap::new(),
+        );
+        // 2^41 - 1 `report` fields
+        assert_eq!(cost, (1usize << 41) - 1);
+    }
+
+    #[tokio::test]
+    async fn test_over_budget_rejected() {
+        let response = schema(20).execute("{ users(first: 50) { id } }").await;
+        assert_eq!(
+            response.errors[0].extensions.as_ref().unwrap().get("code"),
+            Some(&Value::from("QUERY_TOO_COMPLEX"))
+        );
+        assert_eq!(requested(&response), Value::from(51));
+    }
+}
@@ -0,0 +1,189 @@
+//! Query depth limit
+//!
+//! Deeply nested selections (recursive relations, introspection chains of
+//! `ofType`) can make a subgraph resolve an unbounded amount of data. The
+//! [`DepthLimit`] extension rejects them right after parsing, before any
+//! resolver runs, with code `QUERY_TOO_DEEP`.
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery};
+use async_graphql::parser::types::{ExecutableDocument, Selection, SelectionSet};
+use async_graphql::{Name, Pos, ServerResult, Variables};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::ErrorCode;
+use crate::schema::DEFAULT_MAX_DEPTH;
+
+/// Extension rejecting operations nested deeper than `max_depth` fields
+///
+/// Fragments are expanded in place and don't add a level.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthLimit {
+    max_depth: usize,
+}
+
+impl DepthLimit {
+    pub fn new(max_depth: usize) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Default for DepthLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DEPTH)
+    }
+}
+
+impl ExtensionFactory for DepthLimit {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(*self)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for DepthLimit {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        let mut fragments = HashMap::new();
+        for (_, operation) in document.operations.iter() {
+            let selection_set = &operation.node.selection_set.node;
+            if let Some((_, pos)) = selection_depth(&document, selection_set, &mut fragments)
+                .filter(|(depth, _)| *depth > self.max_depth)
+            {
+                return Err(ErrorCode::QueryTooDeep
+                    .error(format!(
+                        "Query exceeds the maximum depth of {}",
+                        self.max_depth
+                    ))
+                    .into_server_error(pos));
+            }
+        }
+        Ok(document)
+    }
+}
+
+/// Nesting depth of a selection set and the position of its deepest field
+///
+/// Fragment depths are memoized in `fragments`, so each fragment is walked
+/// once however often it's spread. A fragment spread while it is still
+/// being walked (a cycle, which validation only rejects later) counts as
+/// empty.
+fn selection_depth<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    fragments: &mut HashMap<&'a Name, Option<(usize, Pos)>>,
+) -> Option<(usize, Pos)> {
+    selection_set
+        .items
+        .iter()
+        .filter_map(|selection| match &selection.node {
+            Selection::Field(field) => Some(
+                match selection_depth(document, &field.node.selection_set.node, fragments) {
+                    Some((depth, pos)) => (depth + 1, pos),
+                    None => (1, field.pos),
+                },
+            ),
+            Selection::InlineFragment(fragment) => {
+                selection_depth(document, &fragment.node.selection_set.node, fragments)
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                if let Some(depth) = fragments.get(name) {
+                    return *depth;
+                }
+                let fragment = document.fragments.get(name)?;
+                fragments.insert(name, None);
+                let depth = selection_depth(document, &fragment.node.selection_set.node, fragments);
+                fragments.insert(name, depth);
+                depth
+            }
+        })
+        .max_by_key(|(depth, _)| *depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject, Value};
+
+    #[derive(SimpleObject)]
+    struct Node {
+        id: i32,
+        child: Option<Box<Node>>,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn node(&self) -> Node {
+            Node { id: 0, child: None }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_depth_limit() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(DepthLimit::new(2))
+            .finish();
+
+        assert!(schema.execute("{ node { id } }").await.is_ok());
+        assert!(schema
+            .execute("{ node { ...Fields } } fragment Fields on Node { id }")
+            .await
+            .is_ok());
+
+        let response = schema
+            .execute("{ node { ... on Node { child { id } } } }")
+            .await;
+        let error = &response.errors[0];
+        assert_eq!(
+            error.extensions.as_ref().unwrap().get("code"),
+            Some(&Value::from("QUERY_TOO_DEEP"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fragment_cycle_terminates() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(DepthLimit::new(5))
+            .finish();
+
+        let response = schema
+            .execute("{ node { ...A } } fragment A on Node { ...B } fragment B on Node { ...A }")
+            .await;
+        assert!(response.is_err());
+    }
+
+    #[test]
+    fn test_repeated_fragments_walked_once() {
+        // Every fragment spreads the next one twice: 2^40 expansions unless
+        // fragment depths are memoized
+        let mut query = String::from("{ node { ...F0 } }");
+        for i in 0..40 {
+            query.push_str(&format!(
+                " fragment F{} on Node {{ child {{ ...F{} }} other: child {{ ...F{} }} }}",
+                i,
+                i + 1,
+                i + 1
+            ));
+        }
+        query.push_str(" fragment F40 on Node { id }");
+
+        let document = async_graphql::parser::parse_query(&query).unwrap();
+        let (_, operation) = document.operations.iter().next().unwrap();
+        let depth = selection_depth(
+            &document,
+            &operation.node.selection_set.node,
+            &mut HashMap::new(),
+        );
+        assert_eq!(depth.map(|(depth, _)| depth), Some(42));
+    }
+}
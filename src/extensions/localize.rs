@@ -0,0 +1,130 @@
+//! Locale-aware error messages
+//!
+//! Replaces the message of every error with a `code` extension by the
+//! [`MessageCatalog`] entry for the request's [`Locale`]. Errors without a
+//! catalog entry keep their message.
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute};
+use async_graphql::{Response, Value};
+use std::sync::Arc;
+
+use crate::auth::ClientInfo;
+use crate::error::{Locale, MessageCatalog};
+
+/// Extension localizing coded error messages
+///
+/// The locale comes from the [`ClientInfo`] the handlers inject. Register
+/// it before [`MaskErrors`](super::MaskErrors) so masked errors are
+/// localized too.
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+/// use pleme_graphql_helpers::error::MessageCatalog;
+/// use pleme_graphql_helpers::extensions::{LocalizeErrors, MaskErrors};
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     async fn ping(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///     .extension(LocalizeErrors::new(MessageCatalog::default()))
+///     .extension(MaskErrors::new())
+///     .finish();
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalizeErrors {
+    catalog: Arc<MessageCatalog>,
+}
+
+impl LocalizeErrors {
+    pub fn new(catalog: MessageCatalog) -> Self {
+        Self {
+            catalog: Arc::new(catalog),
+        }
+    }
+}
+
+impl Default for LocalizeErrors {
+    fn default() -> Self {
+        Self::new(MessageCatalog::default())
+    }
+}
+
+impl ExtensionFactory for LocalizeErrors {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(LocalizeErrorsExtension {
+            catalog: self.catalog.clone(),
+        })
+    }
+}
+
+struct LocalizeErrorsExtension {
+    catalog: Arc<MessageCatalog>,
+}
+
+#[async_trait::async_trait]
+impl Extension for LocalizeErrorsExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let mut response = next.run(ctx, operation_name).await;
+        let locale = ctx
+            .data_opt::<ClientInfo>()
+            .map_or(Locale::default(), |client| client.locale);
+
+        for error in &mut response.errors {
+            let code = match error.extensions.as_ref().and_then(|ext| ext.get("code")) {
+                Some(Value::String(code)) => code.clone(),
+                _ => continue,
+            };
+            if let Some(message) = self.catalog.message(locale, &code) {
+                error.message = message.to_string();
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::RoleRequired;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(guard = "RoleRequired(\"admin\")")]
+        async fn admin(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_localizes_coded_errors() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(LocalizeErrors::default())
+            .finish();
+        let client = ClientInfo {
+            locale: Locale::PtBr,
+            ..ClientInfo::default()
+        };
+
+        let response = schema.execute(Request::new("{ admin }").data(client)).await;
+        assert_eq!(response.errors[0].message, "Autenticação necessária");
+
+        let response = schema.execute("{ admin }").await;
+        assert_eq!(response.errors[0].message, "Authentication required");
+    }
+}
@@ -0,0 +1,280 @@
+//! Structured operation logging
+//!
+//! [`OperationLog`] records every executed operation: name, type,
+//! duration, error count, user, request ID, and variables. Variables whose
+//! name contains one of the sensitive field names (case-insensitive, at any
+//! nesting level) are replaced with `[REDACTED]` before they reach a log.
+//! Records are logged as a `tracing` event (with the `tracing` feature) or
+//! handed to a custom handler.
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextPrepareRequest,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType};
+use async_graphql::{Request, Response, ServerResult, Value, Variables};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::auth::{AuthContext, RequestId};
+
+/// Variable names redacted by default
+pub const DEFAULT_REDACTED_FIELDS: &[&str] = &["password", "token", "document"];
+
+/// Replacement for redacted variable values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// An executed operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationRecord {
+    pub operation_name: Option<String>,
+    pub operation_type: Option<OperationType>,
+    pub duration: Duration,
+    pub error_count: usize,
+    pub user_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    /// Variables with sensitive values redacted
+    pub variables: Value,
+}
+
+/// Handler for operation records
+pub type OperationLogHandler = Arc<dyn Fn(&OperationRecord) + Send + Sync>;
+
+/// Extension logging every executed operation
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::extensions::OperationLog;
+///
+/// let log = OperationLog::new().with_redacted_field("cpf");
+/// ```
+#[derive(Clone)]
+pub struct OperationLog {
+    redacted: Arc<Vec<String>>,
+    handler: Option<OperationLogHandler>,
+}
+
+impl Default for OperationLog {
+    fn default() -> Self {
+        Self {
+            redacted: Arc::new(
+                DEFAULT_REDACTED_FIELDS
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect(),
+            ),
+            handler: None,
+        }
+    }
+}
+
+impl OperationLog {
+    /// Log with [`DEFAULT_REDACTED_FIELDS`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the redacted variable names
+    pub fn with_redacted_fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.redacted = Arc::new(
+            fields
+                .into_iter()
+                .map(|field| field.as_ref().to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// Also redact variables named like `field`
+    pub fn with_redacted_field(mut self, field: impl AsRef<str>) -> Self {
+        Arc::make_mut(&mut self.redacted).push(field.as_ref().to_lowercase());
+        self
+    }
+
+    /// Send records to `handler` instead of `tracing`
+    pub fn with_handler(mut self, handler: OperationLogHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    /// `value` with sensitive entries replaced by [`REDACTED`]
+    fn redact(&self, value: Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let name_lower = name.to_lowercase();
+                        let value = if self.redacted.iter().any(|f| name_lower.contains(f)) {
+                            Value::from(REDACTED)
+                        } else {
+                            self.redact(value)
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+            ),
+            Value::List(items) => Value::List(items.into_iter().map(|v| self.redact(v)).collect()),
+            value => value,
+        }
+    }
+
+    fn report(&self, record: &OperationRecord) {
+        let Some(handler) = &self.handler else {
+            #[cfg(feature = "tracing")]
+            tracing::info!(
+                operation_name = record.operation_name.as_deref(),
+                operation_type = ?record.operation_type,
+                duration_ms = record.duration.as_millis() as u64,
+                error_count = record.error_count,
+                user_id = ?record.user_id,
+                request_id = record.request_id.as_deref(),
+                variables = %record.variables,
+                "GraphQL operation"
+            );
+            return;
+        };
+        handler(record);
+    }
+}
+
+impl ExtensionFactory for OperationLog {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(OperationLogExtension {
+            log: self.clone(),
+            variables: Mutex::new(Value::Null),
+            operations: Mutex::default(),
+        })
+    }
+}
+
+struct OperationLogExtension {
+    log: OperationLog,
+    /// Redacted variables of the request
+    variables: Mutex<Value>,
+    /// Type of each operation in the document, by operation name
+    operations: Mutex<Vec<(Option<String>, OperationType)>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for OperationLogExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let request = next.run(ctx, request).await?;
+        *self.variables.lock().unwrap() = self.log.redact(request.variables.clone().into_value());
+        Ok(request)
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        *self.operations.lock().unwrap() = document
+            .operations
+            .iter()
+            .map(|(name, operation)| (name.map(|name| name.to_string()), operation.node.ty))
+            .collect();
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let started = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+        let duration = started.elapsed();
+
+        // Named from the document when the request didn't name it
+        let (name, ty) = self
+            .operations
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(name, _)| operation_name.is_none() || name.as_deref() == operation_name)
+            .map(|(name, ty)| (name.clone(), Some(*ty)))
+            .unwrap_or_default();
+        let auth = ctx.data_opt::<AuthContext>();
+        let request_id = ctx
+            .data_opt::<RequestId>()
+            .map(|id| id.0.clone())
+            .or_else(|| auth.and_then(|auth| auth.request_id.clone()));
+        let variables = std::mem::replace(&mut *self.variables.lock().unwrap(), Value::Null);
+        self.log.report(&OperationRecord {
+            operation_name: operation_name.map(str::to_string).or(name),
+            operation_type: ty,
+            duration,
+            error_count: response.errors.len(),
+            user_id: auth.and_then(|auth| auth.user_id),
+            request_id,
+            variables,
+        });
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ping(&self) -> bool {
+            true
+        }
+    }
+
+    struct Mutation;
+
+    #[Object]
+    impl Mutation {
+        async fn sign_in(&self, email: String, password: String) -> bool {
+            !email.is_empty() && !password.is_empty()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logs_redacted_operations() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let log = OperationLog::new().with_handler(Arc::new(move |record| {
+            sink.lock().unwrap().push(record.clone())
+        }));
+        let schema = Schema::build(Query, Mutation, EmptySubscription)
+            .extension(log)
+            .finish();
+
+        let variables = serde_json::json!({ "email": "a@b.c", "input": { "newPassword": "x" } });
+        let request = Request::new(
+            "mutation SignIn($email: String!) { signIn(email: $email, password: \"secret\") }",
+        )
+        .variables(Variables::from_json(variables));
+        schema.execute(request).await;
+
+        let records = records.lock().unwrap();
+        assert_eq!(records[0].operation_name.as_deref(), Some("SignIn"));
+        assert_eq!(records[0].operation_type, Some(OperationType::Mutation));
+        assert_eq!(records[0].error_count, 0);
+        let expected =
+            serde_json::json!({ "email": "a@b.c", "input": { "newPassword": REDACTED } });
+        assert_eq!(records[0].variables.clone().into_json().unwrap(), expected);
+    }
+}
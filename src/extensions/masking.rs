@@ -0,0 +1,202 @@
+//! Masking of unexpected resolver errors
+//!
+//! Errors raised deliberately carry a `code` extension (`UNAUTHENTICATED`,
+//! `FORBIDDEN`, ...). Anything else is an unexpected failure whose message
+//! may leak internals (SQL, hostnames), so it's replaced with a generic
+//! message before reaching the client. Masked and `INTERNAL` errors can be
+//! sent to an [`ErrorReporter`](super::ErrorReporter) first.
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute};
+use async_graphql::{Response, ServerError, Value};
+use std::sync::Arc;
+
+use super::reporter::{source_chain, ErrorReport, SharedErrorReporter};
+use crate::auth::{AuthContext, RequestId};
+use crate::error::ErrorCode;
+
+/// Message sent in place of a masked error
+pub const MASKED_MESSAGE: &str = "Internal server error";
+
+/// Code set on masked errors
+pub const MASKED_CODE: &str = ErrorCode::Internal.as_str();
+
+/// Extension masking resolver errors without a `code` extension
+///
+/// Paths and locations are kept so clients can still tell which field
+/// failed.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::extensions::{ErrorReport, ErrorReporter, MaskErrors};
+/// use std::sync::Arc;
+///
+/// struct Stderr;
+///
+/// impl ErrorReporter for Stderr {
+///     fn report(&self, report: &ErrorReport<'_>) {
+///         eprintln!("{:?}: {:?}", report.request_id, report.source_chain);
+///     }
+/// }
+///
+/// let masking = MaskErrors::new().with_reporter(Arc::new(Stderr));
+/// ```
+#[derive(Clone, Default)]
+pub struct MaskErrors {
+    reporter: Option<SharedErrorReporter>,
+}
+
+impl MaskErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report masked and `INTERNAL` errors to `reporter`
+    pub fn with_reporter(mut self, reporter: SharedErrorReporter) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+}
+
+impl ExtensionFactory for MaskErrors {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(MaskErrorsExtension {
+            reporter: self.reporter.clone(),
+        })
+    }
+}
+
+struct MaskErrorsExtension {
+    reporter: Option<SharedErrorReporter>,
+}
+
+impl MaskErrorsExtension {
+    fn report(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        error: &ServerError,
+    ) {
+        let Some(reporter) = &self.reporter else {
+            return;
+        };
+        let auth = ctx.data_opt::<AuthContext>();
+        let request_id = ctx
+            .data_opt::<RequestId>()
+            .map(|id| id.0.as_str())
+            .or_else(|| auth.and_then(|auth| auth.request_id.as_deref()));
+        reporter.report(&ErrorReport {
+            error,
+            operation_name,
+            user_id: auth.and_then(|auth| auth.user_id),
+            request_id,
+            source_chain: source_chain(error),
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for MaskErrorsExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let mut response = next.run(ctx, operation_name).await;
+        for error in &mut response.errors {
+            if !has_code(error) {
+                self.report(ctx, operation_name, error);
+                mask(error);
+            } else if is_internal(error) {
+                self.report(ctx, operation_name, error);
+            }
+        }
+        response
+    }
+}
+
+fn has_code(error: &ServerError) -> bool {
+    error
+        .extensions
+        .as_ref()
+        .is_some_and(|ext| ext.get("code").is_some())
+}
+
+fn is_internal(error: &ServerError) -> bool {
+    let code = error.extensions.as_ref().and_then(|ext| ext.get("code"));
+    matches!(code, Some(Value::String(code)) if code == MASKED_CODE)
+}
+
+fn mask(error: &mut ServerError) {
+    #[cfg(feature = "tracing")]
+    tracing::error!(message = %error.message, path = ?error.path, "masked GraphQL error");
+
+    error.message = MASKED_MESSAGE.to_string();
+    error.source = None;
+    let extensions = error.extensions.get_or_insert_with(Default::default);
+    extensions.set("code", MASKED_CODE);
+    extensions.set("retryable", false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, ErrorExtensions, Object, Schema, Value};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn broken(&self) -> async_graphql::Result<i32> {
+            Err("connection to db-primary:5432 refused".into())
+        }
+
+        async fn denied(&self) -> async_graphql::Result<i32> {
+            Err(async_graphql::Error::new("Not allowed")
+                .extend_with(|_, e| e.set("code", "FORBIDDEN")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_masks_errors_without_code() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(MaskErrors::new())
+            .finish();
+
+        let response = schema.execute("{ broken }").await;
+        assert_eq!(response.errors[0].message, MASKED_MESSAGE);
+
+        let response = schema.execute("{ denied }").await;
+        assert_eq!(response.errors[0].message, "Not allowed");
+        assert_eq!(
+            response.errors[0].extensions.as_ref().unwrap().get("code"),
+            Some(&Value::from("FORBIDDEN"))
+        );
+    }
+
+    struct Collect(std::sync::Mutex<Vec<Vec<String>>>);
+
+    impl crate::extensions::ErrorReporter for Collect {
+        fn report(&self, report: &ErrorReport<'_>) {
+            self.0.lock().unwrap().push(report.source_chain.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_masked_errors() {
+        let reporter = Arc::new(Collect(Default::default()));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(MaskErrors::new().with_reporter(reporter.clone()))
+            .finish();
+
+        schema.execute("{ broken }").await;
+        schema.execute("{ denied }").await;
+
+        let reports = reporter.0.lock().unwrap();
+        assert_eq!(
+            *reports,
+            vec![vec!["connection to db-primary:5432 refused".to_string()]]
+        );
+    }
+}
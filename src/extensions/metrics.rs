@@ -0,0 +1,323 @@
+//! Prometheus metrics for GraphQL execution
+//!
+//! [`GraphQLMetrics`] records, per operation name and type:
+//!
+//! - `graphql_requests_total`
+//! - `graphql_request_duration_seconds` (histogram)
+//! - `graphql_errors_total`, also labelled by error `code`
+//! - `graphql_requests_in_flight`
+//! - `graphql_resolver_duration_seconds` for the fields listed with
+//!   [`GraphQLMetrics::with_resolver_timing`], labelled `field="Type.field"`
+//!
+//! Unnamed operations are labelled `anonymous`. Serve the registry with
+//! [`metrics_handler`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use axum::{routing::get, Extension, Router};
+//! use pleme_graphql_helpers::extensions::metrics::{metrics_handler, GraphQLMetrics};
+//! use prometheus::Registry;
+//!
+//! let registry = Registry::new();
+//! let metrics = GraphQLMetrics::new()?.with_resolver_timing("Query.search");
+//! metrics.register(&registry)?;
+//!
+//! // Schema::build(..).extension(metrics)
+//! let app: Router = Router::new()
+//!     .route("/metrics", get(metrics_handler))
+//!     .layer(Extension(registry));
+//! # Ok::<(), prometheus::Error>(())
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+    NextResolve, ResolveInfo,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType};
+use async_graphql::{Response, ServerResult, Value, Variables};
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+    Extension as AxumExtension,
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Label for operations without a name
+const ANONYMOUS: &str = "anonymous";
+
+/// Label for errors without a `code` extension (e.g. validation errors)
+const UNCODED: &str = "NONE";
+
+struct Collectors {
+    requests: IntCounterVec,
+    errors: IntCounterVec,
+    duration: HistogramVec,
+    in_flight: IntGauge,
+    resolvers: HistogramVec,
+}
+
+/// GraphQL execution metrics and the extension recording them
+#[derive(Clone)]
+pub struct GraphQLMetrics {
+    collectors: Arc<Collectors>,
+    timed_fields: Arc<HashSet<String>>,
+}
+
+impl GraphQLMetrics {
+    /// Create the collectors; register them with [`register`](Self::register)
+    pub fn new() -> prometheus::Result<Self> {
+        let operation = &["operation_name", "operation_type"];
+        Ok(Self {
+            collectors: Arc::new(Collectors {
+                requests: IntCounterVec::new(
+                    Opts::new("graphql_requests_total", "GraphQL operations executed"),
+                    operation,
+                )?,
+                errors: IntCounterVec::new(
+                    Opts::new("graphql_errors_total", "GraphQL errors by code"),
+                    &["operation_name", "operation_type", "code"],
+                )?,
+                duration: HistogramVec::new(
+                    HistogramOpts::new(
+                        "graphql_request_duration_seconds",
+                        "GraphQL operation latency",
+                    ),
+                    operation,
+                )?,
+                in_flight: IntGauge::new(
+                    "graphql_requests_in_flight",
+                    "GraphQL operations currently executing",
+                )?,
+                resolvers: HistogramVec::new(
+                    HistogramOpts::new(
+                        "graphql_resolver_duration_seconds",
+                        "Latency of selected resolvers",
+                    ),
+                    &["field"],
+                )?,
+            }),
+            timed_fields: Arc::new(HashSet::new()),
+        })
+    }
+
+    /// Time resolvers of `field`, given as `Type.field`
+    pub fn with_resolver_timing(mut self, field: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.timed_fields).insert(field.into());
+        self
+    }
+
+    /// Register the collectors with `registry`
+    pub fn register(&self, registry: &Registry) -> prometheus::Result<()> {
+        let collectors = &self.collectors;
+        registry.register(Box::new(collectors.requests.clone()))?;
+        registry.register(Box::new(collectors.errors.clone()))?;
+        registry.register(Box::new(collectors.duration.clone()))?;
+        registry.register(Box::new(collectors.in_flight.clone()))?;
+        registry.register(Box::new(collectors.resolvers.clone()))
+    }
+}
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(MetricsExtension {
+            metrics: self.clone(),
+            operations: Mutex::default(),
+            operation_name: Mutex::default(),
+        })
+    }
+}
+
+struct MetricsExtension {
+    metrics: GraphQLMetrics,
+    /// Type of each operation in the document, by operation name
+    operations: Mutex<Vec<(Option<String>, OperationType)>>,
+    /// Operation name the request selected
+    operation_name: Mutex<Option<String>>,
+}
+
+impl MetricsExtension {
+    /// Name and type labels for the executed operation
+    fn labels(&self) -> (String, &'static str) {
+        let name = self.operation_name.lock().unwrap().clone();
+        let operations = self.operations.lock().unwrap();
+        let operation = operations
+            .iter()
+            .find(|(op, _)| name.is_none() || *op == name);
+        let label = name
+            .or_else(|| operation.and_then(|(op, _)| op.clone()))
+            .unwrap_or_else(|| ANONYMOUS.to_string());
+        let ty = match operation.map(|(_, ty)| ty) {
+            Some(OperationType::Query) => "query",
+            Some(OperationType::Mutation) => "mutation",
+            Some(OperationType::Subscription) => "subscription",
+            None => "unknown",
+        };
+        (label, ty)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for MetricsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let collectors = &self.metrics.collectors;
+        collectors.in_flight.inc();
+        let started = Instant::now();
+        let response = next.run(ctx).await;
+        let elapsed = started.elapsed().as_secs_f64();
+        collectors.in_flight.dec();
+
+        let (name, ty) = self.labels();
+        collectors
+            .requests
+            .with_label_values(&[name.as_str(), ty])
+            .inc();
+        collectors
+            .duration
+            .with_label_values(&[name.as_str(), ty])
+            .observe(elapsed);
+        for error in &response.errors {
+            let code = match error.extensions.as_ref().and_then(|ext| ext.get("code")) {
+                Some(Value::String(code)) => code.as_str(),
+                _ => UNCODED,
+            };
+            collectors
+                .errors
+                .with_label_values(&[name.as_str(), ty, code])
+                .inc();
+        }
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        *self.operations.lock().unwrap() = document
+            .operations
+            .iter()
+            .map(|(name, operation)| (name.map(|name| name.to_string()), operation.node.ty))
+            .collect();
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        *self.operation_name.lock().unwrap() = operation_name.map(str::to_string);
+        next.run(ctx, operation_name).await
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if self.metrics.timed_fields.is_empty() {
+            return next.run(ctx, info).await;
+        }
+        let field = format!("{}.{}", info.parent_type, info.name);
+        if !self.metrics.timed_fields.contains(&field) {
+            return next.run(ctx, info).await;
+        }
+
+        let started = Instant::now();
+        let result = next.run(ctx, info).await;
+        self.metrics
+            .collectors
+            .resolvers
+            .with_label_values(&[field.as_str()])
+            .observe(started.elapsed().as_secs_f64());
+        result
+    }
+}
+
+/// Serve a Prometheus registry in the text exposition format
+///
+/// Expects the [`Registry`] as an axum `Extension`.
+pub async fn metrics_handler(
+    AxumExtension(registry): AxumExtension<Registry>,
+) -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    match encoder.encode(&registry.gather(), &mut body) {
+        Ok(()) => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, encoder.format_type().to_string())],
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ping(&self) -> bool {
+            true
+        }
+
+        async fn fail(&self) -> async_graphql::Result<bool> {
+            Err(crate::error::ErrorCode::NotFound.error("missing"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_operations() {
+        let registry = Registry::new();
+        let metrics = GraphQLMetrics::new()
+            .unwrap()
+            .with_resolver_timing("Query.ping");
+        metrics.register(&registry).unwrap();
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(metrics.clone())
+            .finish();
+
+        schema.execute("query Ping { ping }").await;
+        schema.execute("{ fail }").await;
+
+        let collectors = &metrics.collectors;
+        assert_eq!(
+            collectors
+                .requests
+                .with_label_values(&["Ping", "query"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            collectors
+                .errors
+                .with_label_values(&[ANONYMOUS, "query", "NOT_FOUND"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            collectors
+                .resolvers
+                .with_label_values(&["Query.ping"])
+                .get_sample_count(),
+            1
+        );
+        assert_eq!(collectors.in_flight.get(), 0);
+    }
+}
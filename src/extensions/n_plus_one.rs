@@ -0,0 +1,294 @@
+//! N+1 query detection for development
+//!
+//! [`NPlusOneDetector`] counts how often each field resolves per request,
+//! by path shape (`users[].orders`: the `orders` field of every user). A
+//! field resolved under a list that completes in many separate rounds is
+//! fetching per item: resolvers sharing a DataLoader batch complete
+//! together, in one round. Fields completing in more rounds than the
+//! threshold are reported as a `tracing` warning (with the `tracing`
+//! feature) or to a custom handler. Executions that finish within the batch
+//! window, e.g. fields read from their parent's data, aren't counted.
+//!
+//! Resolution of every field is timed, so register the detector in
+//! development and test builds only.
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextResolve, ResolveInfo,
+};
+use async_graphql::{QueryPathNode, QueryPathSegment, Response, ServerResult, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default maximum rounds per field and request
+pub const DEFAULT_MAX_ROUNDS: usize = 10;
+
+/// Default time within which completions count as one round
+pub const DEFAULT_BATCH_WINDOW: Duration = Duration::from_millis(1);
+
+/// A field that appears to load per item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NPlusOne {
+    pub operation_name: Option<String>,
+    /// The field as `Type.field`
+    pub field: String,
+    /// Response path shape, e.g. `users[].orders`
+    pub path: String,
+    /// Times the field resolved
+    pub executions: usize,
+    /// Separate rounds the executions completed in
+    pub rounds: usize,
+}
+
+/// Handler for N+1 reports
+pub type NPlusOneHandler = Arc<dyn Fn(&NPlusOne) + Send + Sync>;
+
+/// Extension reporting fields that resolve per item without batching
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::extensions::NPlusOneDetector;
+///
+/// let detector = NPlusOneDetector::new().with_max_rounds(5);
+/// ```
+#[derive(Clone)]
+pub struct NPlusOneDetector {
+    max_rounds: usize,
+    batch_window: Duration,
+    handler: Option<NPlusOneHandler>,
+}
+
+impl Default for NPlusOneDetector {
+    fn default() -> Self {
+        Self {
+            max_rounds: DEFAULT_MAX_ROUNDS,
+            batch_window: DEFAULT_BATCH_WINDOW,
+            handler: None,
+        }
+    }
+}
+
+impl NPlusOneDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report fields completing in more than `max_rounds` rounds
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Treat completions within `window` of each other as one round
+    pub fn with_batch_window(mut self, window: Duration) -> Self {
+        self.batch_window = window;
+        self
+    }
+
+    /// Send reports to `handler` instead of `tracing`
+    pub fn with_handler(mut self, handler: NPlusOneHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    fn report(&self, n_plus_one: &NPlusOne) {
+        let Some(handler) = &self.handler else {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                operation_name = n_plus_one.operation_name.as_deref(),
+                field = %n_plus_one.field,
+                path = %n_plus_one.path,
+                executions = n_plus_one.executions,
+                rounds = n_plus_one.rounds,
+                "possible N+1: field resolves per item without batching"
+            );
+            return;
+        };
+        handler(n_plus_one);
+    }
+}
+
+impl ExtensionFactory for NPlusOneDetector {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(NPlusOneExtension {
+            detector: self.clone(),
+            fields: Mutex::default(),
+        })
+    }
+}
+
+/// Executions of a field under a list
+struct FieldLoads {
+    field: String,
+    completed: Vec<Instant>,
+}
+
+struct NPlusOneExtension {
+    detector: NPlusOneDetector,
+    /// Executions by path shape
+    fields: Mutex<HashMap<String, FieldLoads>>,
+}
+
+impl NPlusOneExtension {
+    /// Number of rounds `completed` fall into
+    fn rounds(&self, completed: &mut [Instant]) -> usize {
+        completed.sort();
+        let mut rounds = 0;
+        let mut round_start = None;
+        for &at in completed.iter() {
+            match round_start {
+                Some(start) if at.duration_since(start) <= self.detector.batch_window => {}
+                _ => {
+                    rounds += 1;
+                    round_start = Some(at);
+                }
+            }
+        }
+        rounds
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for NPlusOneExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let response = next.run(ctx, operation_name).await;
+
+        let fields = std::mem::take(&mut *self.fields.lock().unwrap());
+        for (path, mut loads) in fields {
+            let rounds = self.rounds(&mut loads.completed);
+            if rounds > self.detector.max_rounds {
+                self.detector.report(&NPlusOne {
+                    operation_name: operation_name.map(str::to_string),
+                    field: loads.field,
+                    path,
+                    executions: loads.completed.len(),
+                    rounds,
+                });
+            }
+        }
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        let Some(path) = list_path(info.path_node) else {
+            return next.run(ctx, info).await;
+        };
+        let field = format!("{}.{}", info.parent_type, info.name);
+
+        let started = Instant::now();
+        let result = next.run(ctx, info).await;
+        let completed = Instant::now();
+        // Resolved from the parent's data, not loaded
+        if completed.duration_since(started) <= self.detector.batch_window {
+            return result;
+        }
+        self.fields
+            .lock()
+            .unwrap()
+            .entry(path)
+            .or_insert_with(|| FieldLoads {
+                field,
+                completed: Vec::new(),
+            })
+            .completed
+            .push(completed);
+        result
+    }
+}
+
+/// Path shape of a field under a list, with `[]` for list items
+///
+/// List items resolve through the same hook and are skipped: their time is
+/// their fields' time.
+fn list_path(node: &QueryPathNode<'_>) -> Option<String> {
+    if let QueryPathSegment::Index(_) = node.segment {
+        return None;
+    }
+    let mut segments = Vec::new();
+    let mut in_list = false;
+    let mut current = Some(node);
+    while let Some(node) = current {
+        match &node.segment {
+            QueryPathSegment::Index(_) => {
+                in_list = true;
+                segments.push("[]".to_string());
+            }
+            QueryPathSegment::Name(name) => segments.push(format!(".{name}")),
+        }
+        current = node.parent;
+    }
+    if !in_list {
+        return None;
+    }
+    let path: String = segments.into_iter().rev().collect();
+    Some(path.trim_start_matches('.').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    struct Order;
+
+    #[Object]
+    impl Order {
+        async fn id(&self) -> i32 {
+            1
+        }
+    }
+
+    struct User(u64);
+
+    #[Object]
+    impl User {
+        /// Loads per user, finishing at different times
+        async fn orders(&self) -> Vec<Order> {
+            tokio::time::sleep(Duration::from_millis(5 * self.0)).await;
+            vec![Order]
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn users(&self) -> Vec<User> {
+            (1..=4).map(User).collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_unbatched_fields() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let detector = NPlusOneDetector::new()
+            .with_max_rounds(2)
+            .with_handler(Arc::new(move |report| {
+                sink.lock().unwrap().push(report.clone())
+            }));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(detector)
+            .finish();
+
+        schema.execute("{ users { orders { id } } }").await;
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].field, "User.orders");
+        assert_eq!(reports[0].path, "users[].orders");
+        assert_eq!(reports[0].executions, 4);
+        assert_eq!(reports[0].rounds, 4);
+    }
+}
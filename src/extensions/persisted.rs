@@ -0,0 +1,330 @@
+//! Persisted operation allowlist
+//!
+//! Public-facing services only execute operations registered ahead of
+//! time. Clients send the SHA-256 hash of a registered operation in the
+//! Apollo `persistedQuery` request extension, or the full query text when
+//! its hash is registered. Anything else is rejected with
+//! `OPERATION_NOT_ALLOWED`; unknown hashes with `PERSISTED_QUERY_NOT_FOUND`.
+//!
+//! Enforcement can be relaxed per environment: with enforcement off,
+//! registered hashes still resolve but arbitrary queries run too, and
+//! introspection can be allowed on its own for development tooling.
+//!
+//! The handlers check request policies (anonymous access, tenant
+//! enforcement, the GET mutation block, auditing) before the extension
+//! runs. Install the registry as an `Extension` too, so those checks see
+//! the resolved operation; hash-only requests the handlers can't resolve
+//! are rejected with `PERSISTED_QUERY_NOT_FOUND`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use axum::{Extension, Router};
+//! use pleme_graphql_helpers::extensions::{OperationRegistry, PersistedOperations};
+//!
+//! let registry = OperationRegistry::from_file("persisted-query-manifest.json")?;
+//! let extension = PersistedOperations::from_env(registry);
+//! let app: Router = Router::new().layer(Extension(extension.registry()));
+//! # Ok::<(), pleme_graphql_helpers::extensions::persisted::ManifestError>(())
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest,
+};
+use async_graphql::{Pos, Request, ServerResult, Value};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::auth::OperationInfo;
+use crate::error::ErrorCode;
+
+/// Environment variable setting enforcement (`enforce` or `off`)
+pub const PERSISTED_OPERATIONS_ENV: &str = "PLEME_PERSISTED_OPERATIONS";
+/// Environment variable allowing introspection (`true` or `false`)
+pub const ALLOW_INTROSPECTION_ENV: &str = "PLEME_ALLOW_INTROSPECTION";
+
+/// Errors loading an operation manifest
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("Failed to read manifest: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid manifest: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "reqwest")]
+    #[error("Failed to fetch manifest: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+/// Manifest formats: Apollo's persisted query manifest, or a plain
+/// `{ "<sha256>": "<query>" }` map
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Manifest {
+    Apollo { operations: Vec<ManifestOperation> },
+    Map(HashMap<String, String>),
+}
+
+#[derive(Deserialize)]
+struct ManifestOperation {
+    id: String,
+    body: String,
+}
+
+/// Registered operations by SHA-256 hash
+#[derive(Debug, Clone, Default)]
+pub struct OperationRegistry {
+    operations: HashMap<String, String>,
+}
+
+impl OperationRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry from a JSON manifest
+    pub fn from_manifest(json: &str) -> Result<Self, ManifestError> {
+        let operations = match serde_json::from_str(json)? {
+            Manifest::Apollo { operations } => operations
+                .into_iter()
+                .map(|operation| (operation.id, operation.body))
+                .collect(),
+            Manifest::Map(operations) => operations,
+        };
+        Ok(Self { operations })
+    }
+
+    /// Registry from a JSON manifest file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        Self::from_manifest(&std::fs::read_to_string(path)?)
+    }
+
+    /// Registry from a JSON manifest served at `url`
+    #[cfg(feature = "reqwest")]
+    pub async fn fetch(url: &str) -> Result<Self, ManifestError> {
+        let manifest = reqwest::get(url).await?.error_for_status()?.text().await?;
+        Self::from_manifest(&manifest)
+    }
+
+    /// Register `query` under its hash
+    pub fn with_operation(mut self, query: impl Into<String>) -> Self {
+        let query = query.into();
+        self.operations.insert(hash(&query), query);
+        self
+    }
+
+    /// The query registered under `hash`
+    pub fn get(&self, hash: &str) -> Option<&str> {
+        self.operations.get(hash).map(String::as_str)
+    }
+
+    /// Whether `query` is registered
+    pub fn contains_query(&self, query: &str) -> bool {
+        self.operations.contains_key(&hash(query))
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// Shared operation registry, installed as an axum `Extension` so the
+/// handlers resolve persisted hashes before checking request policies
+pub type SharedOperationRegistry = Arc<OperationRegistry>;
+
+/// Hex-encoded SHA-256 of a query, as sent in `persistedQuery.sha256Hash`
+pub fn hash(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+/// Extension executing only operations in an [`OperationRegistry`]
+#[derive(Debug, Clone)]
+pub struct PersistedOperations {
+    registry: SharedOperationRegistry,
+    enforce: bool,
+    allow_introspection: bool,
+}
+
+impl PersistedOperations {
+    /// Enforce `registry`, introspection included
+    pub fn new(registry: OperationRegistry) -> Self {
+        Self {
+            registry: Arc::new(registry),
+            enforce: true,
+            allow_introspection: false,
+        }
+    }
+
+    /// Enforcement per `PLEME_PERSISTED_OPERATIONS` (on unless `off`) and
+    /// `PLEME_ALLOW_INTROSPECTION` (off unless `true`)
+    pub fn from_env(registry: OperationRegistry) -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        Self::new(registry)
+            .with_enforce(!var(PERSISTED_OPERATIONS_ENV).eq_ignore_ascii_case("off"))
+            .with_introspection(var(ALLOW_INTROSPECTION_ENV).eq_ignore_ascii_case("true"))
+    }
+
+    /// The registry, for installing alongside the extension
+    pub fn registry(&self) -> SharedOperationRegistry {
+        self.registry.clone()
+    }
+
+    /// Reject unregistered queries, or only resolve registered hashes
+    pub fn with_enforce(mut self, enforce: bool) -> Self {
+        self.enforce = enforce;
+        self
+    }
+
+    /// Allow unregistered introspection-only queries
+    pub fn with_introspection(mut self, allow: bool) -> Self {
+        self.allow_introspection = allow;
+        self
+    }
+
+    fn allows(&self, request: &Request) -> bool {
+        !self.enforce
+            || self.registry.contains_query(&request.query)
+            || (self.allow_introspection
+                && OperationInfo::parse(request).is_some_and(|op| op.is_introspection()))
+    }
+}
+
+impl ExtensionFactory for PersistedOperations {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(self.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for PersistedOperations {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        match requested_hash(&request) {
+            Some(hash) => match self.registry.get(&hash) {
+                Some(query) => request.query = query.to_string(),
+                None if !self.enforce && !request.query.is_empty() => {}
+                None => {
+                    return Err(ErrorCode::PersistedQueryNotFound
+                        .error("PersistedQueryNotFound")
+                        .into_server_error(Pos::default()))
+                }
+            },
+            None if self.allows(&request) => {}
+            None => {
+                return Err(ErrorCode::OperationNotAllowed
+                    .error("Operation is not in the allowlist")
+                    .into_server_error(Pos::default()))
+            }
+        }
+        next.run(ctx, request).await
+    }
+}
+
+/// `extensions.persistedQuery.sha256Hash`
+pub(crate) fn requested_hash(request: &Request) -> Option<String> {
+    match request.extensions.get("persistedQuery")? {
+        Value::Object(persisted) => match persisted.get("sha256Hash")? {
+            Value::String(hash) => Some(hash.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ping(&self) -> bool {
+            true
+        }
+    }
+
+    const PING: &str = "{ ping }";
+
+    fn schema(extension: PersistedOperations) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(extension)
+            .finish()
+    }
+
+    fn by_hash(hash: &str) -> Request {
+        let mut request = Request::new("");
+        request.extensions.insert(
+            "persistedQuery".to_string(),
+            Value::from_json(serde_json::json!({ "version": 1, "sha256Hash": hash })).unwrap(),
+        );
+        request
+    }
+
+    fn code(response: &async_graphql::Response) -> Option<&Value> {
+        response.errors.first()?.extensions.as_ref()?.get("code")
+    }
+
+    #[test]
+    fn test_from_manifest() {
+        let manifest = serde_json::json!({
+            "format": "apollo-persisted-query-manifest",
+            "version": 1,
+            "operations": [{ "id": hash(PING), "name": "Ping", "type": "query", "body": PING }],
+        });
+        let registry = OperationRegistry::from_manifest(&manifest.to_string()).unwrap();
+        assert_eq!(registry.get(&hash(PING)), Some(PING));
+
+        let manifest = serde_json::json!({ hash(PING): PING });
+        let registry = OperationRegistry::from_manifest(&manifest.to_string()).unwrap();
+        assert!(registry.contains_query(PING));
+    }
+
+    #[tokio::test]
+    async fn test_enforced_allowlist() {
+        let schema = schema(PersistedOperations::new(
+            OperationRegistry::new().with_operation(PING),
+        ));
+
+        assert!(schema.execute(by_hash(&hash(PING))).await.is_ok());
+        assert!(schema.execute(PING).await.is_ok());
+
+        let response = schema.execute("{ __typename }").await;
+        assert_eq!(code(&response), Some(&Value::from("OPERATION_NOT_ALLOWED")));
+
+        let response = schema.execute(by_hash("unknown")).await;
+        assert_eq!(
+            code(&response),
+            Some(&Value::from("PERSISTED_QUERY_NOT_FOUND"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relaxed_modes() {
+        let dev =
+            schema(PersistedOperations::new(OperationRegistry::new()).with_introspection(true));
+        assert!(dev
+            .execute("{ __schema { queryType { name } } }")
+            .await
+            .is_ok());
+        assert!(dev.execute(PING).await.is_err());
+
+        let relaxed =
+            schema(PersistedOperations::new(OperationRegistry::new()).with_enforce(false));
+        assert!(relaxed.execute(PING).await.is_ok());
+    }
+}
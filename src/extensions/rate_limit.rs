@@ -0,0 +1,563 @@
+//! Per-caller rate limiting
+//!
+//! [`RateLimit`] keeps token buckets per caller: the user ID, else the API
+//! key's service, else the client IP. Each operation takes one token per
+//! selection of a limited root field from that field's bucket, or one token
+//! from the default bucket when it selects none, and with a cost quota also
+//! takes its [cost](super::cost) from a cost bucket. Quotas match root
+//! fields rather than operation names, which clients choose freely. Operations over a limit
+//! fail before execution with code `RATE_LIMITED` and
+//! `extensions.retryAfter` in seconds.
+//!
+//! Buckets live in a [`RateLimitStore`]: [`MemoryRateLimitStore`] per
+//! process, or `RedisRateLimitStore` (`redis` feature) shared by replicas.
+//!
+//! # Example
+//!
+//! ```rust
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+//! use pleme_graphql_helpers::extensions::{MemoryRateLimitStore, Quota, RateLimit};
+//! use std::sync::Arc;
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     async fn ping(&self) -> bool {
+//!         true
+//!     }
+//! }
+//!
+//! let limits = RateLimit::new(Arc::new(MemoryRateLimitStore::new()), Quota::per_minute(600))
+//!     .with_field("exportReport", Quota::per_minute(5))
+//!     .with_cost_quota(Quota::per_minute(20_000));
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .extension(limits)
+//!     .finish();
+//! ```
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery,
+};
+use async_graphql::parser::types::ExecutableDocument;
+use async_graphql::{ErrorExtensions, Name, Pos, Response, ServerResult, Variables};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::cost::OperationCosts;
+use crate::auth::{AuthContext, ClientInfo, OperationInfo};
+use crate::error::ErrorCode;
+
+/// Token bucket size and refill period
+///
+/// A bucket holds `capacity` tokens and refills completely over `period`.
+/// A quota with zero capacity rejects every operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Self { capacity, period }
+    }
+
+    pub fn per_second(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(60))
+    }
+
+    pub fn per_hour(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(3600))
+    }
+
+    /// Time until `missing` tokens are refilled
+    ///
+    /// A zero-capacity bucket never refills; callers are told to retry
+    /// after a full period.
+    fn refill_time(&self, missing: f64) -> Duration {
+        let seconds = missing * self.period.as_secs_f64() / f64::from(self.capacity);
+        Duration::try_from_secs_f64(seconds).unwrap_or(self.period)
+    }
+}
+
+/// Storage for token buckets
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Take `tokens` from the bucket at `key`, or return how long until
+    /// enough are available
+    async fn acquire(&self, key: &str, tokens: u32, quota: Quota) -> Result<(), Duration>;
+}
+
+/// Shared rate limit store handle
+pub type SharedRateLimitStore = Arc<dyn RateLimitStore>;
+
+/// Minimum time between sweeps for idle buckets
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// When the bucket is full again, after which it can be dropped
+    full_at: Instant,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    buckets: HashMap<String, Bucket>,
+    pruned: Instant,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            pruned: Instant::now(),
+        }
+    }
+}
+
+impl Buckets {
+    /// Drop refilled buckets, at most once per [`PRUNE_INTERVAL`]
+    ///
+    /// A full bucket behaves like a missing one, so callers that went idle
+    /// don't keep memory.
+    fn prune(&mut self, now: Instant) {
+        if now.duration_since(self.pruned) < PRUNE_INTERVAL {
+            return;
+        }
+        self.buckets.retain(|_, bucket| bucket.full_at > now);
+        self.pruned = now;
+    }
+}
+
+/// In-process [`RateLimitStore`]
+///
+/// Idle buckets are dropped once refilled.
+#[derive(Debug, Default)]
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<Buckets>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of buckets held
+    pub fn len(&self) -> usize {
+        self.buckets.lock().unwrap().buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take `tokens` as of `now`
+    fn acquire_at(
+        &self,
+        key: &str,
+        tokens: u32,
+        quota: Quota,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let capacity = f64::from(quota.capacity);
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.prune(now);
+        let bucket = buckets.buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            full_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        let refilled = elapsed * capacity / quota.period.as_secs_f64();
+        bucket.tokens = (bucket.tokens + refilled).min(capacity);
+        bucket.updated = now;
+
+        let requested = f64::from(tokens);
+        let acquired = if bucket.tokens >= requested {
+            bucket.tokens -= requested;
+            Ok(())
+        } else {
+            Err(quota.refill_time(requested - bucket.tokens))
+        };
+        bucket.full_at = now + quota.refill_time(capacity - bucket.tokens);
+        acquired
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn acquire(&self, key: &str, tokens: u32, quota: Quota) -> Result<(), Duration> {
+        self.acquire_at(key, tokens, quota, Instant::now())
+    }
+}
+
+/// Redis-backed [`RateLimitStore`] shared by all replicas
+///
+/// Buckets are updated atomically by a Lua script and expire once idle for
+/// a full period.
+#[cfg(feature = "redis")]
+#[derive(Clone)]
+pub struct RedisRateLimitStore {
+    connection: redis::aio::ConnectionManager,
+    script: redis::Script,
+    prefix: String,
+}
+
+#[cfg(feature = "redis")]
+impl RedisRateLimitStore {
+    const SCRIPT: &'static str = r"
+        local capacity = tonumber(ARGV[1])
+        local period = tonumber(ARGV[2])
+        local requested = tonumber(ARGV[3])
+        local now = tonumber(ARGV[4])
+        local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated')
+        local tokens = tonumber(bucket[1]) or capacity
+        local updated = tonumber(bucket[2]) or now
+        tokens = math.min(capacity, tokens + (now - updated) * capacity / period)
+        local wait = 0
+        if tokens >= requested then
+            tokens = tokens - requested
+        else
+            wait = math.ceil((requested - tokens) * period / capacity)
+        end
+        redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated', now)
+        redis.call('PEXPIRE', KEYS[1], period)
+        return wait
+    ";
+
+    /// Store keys are prefixed with `rate_limit:`
+    pub fn new(connection: redis::aio::ConnectionManager) -> Self {
+        Self {
+            connection,
+            script: redis::Script::new(Self::SCRIPT),
+            prefix: "rate_limit:".to_string(),
+        }
+    }
+
+    /// Set the key prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn acquire(&self, key: &str, tokens: u32, quota: Quota) -> Result<(), Duration> {
+        // The script divides by capacity
+        if quota.capacity == 0 {
+            return Err(quota.period);
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let wait: redis::RedisResult<u64> = self
+            .script
+            .key(format!("{}{key}", self.prefix))
+            .arg(quota.capacity)
+            .arg(quota.period.as_millis() as u64)
+            .arg(tokens)
+            .arg(now)
+            .invoke_async(&mut self.connection.clone())
+            .await;
+
+        match wait {
+            Ok(0) => Ok(()),
+            Ok(ms) => Err(Duration::from_millis(ms)),
+            // Fail open: an unavailable store shouldn't take the API down
+            Err(_e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %_e, "rate limit store unavailable");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Extension enforcing per-caller quotas
+#[derive(Clone)]
+pub struct RateLimit {
+    store: SharedRateLimitStore,
+    default: Quota,
+    fields: Arc<HashMap<String, Quota>>,
+    cost: Option<Quota>,
+}
+
+impl RateLimit {
+    /// Limit every operation to `default`
+    pub fn new(store: SharedRateLimitStore, default: Quota) -> Self {
+        Self {
+            store,
+            default,
+            fields: Arc::new(HashMap::new()),
+            cost: None,
+        }
+    }
+
+    /// Use `quota` for the root field `field`, in its own bucket
+    ///
+    /// Every selection of the field takes a token, so aliasing it several
+    /// times in one operation doesn't get around the quota.
+    pub fn with_field(mut self, field: impl Into<String>, quota: Quota) -> Self {
+        Arc::make_mut(&mut self.fields).insert(field.into(), quota);
+        self
+    }
+
+    /// Also limit the total cost of a caller's operations
+    pub fn with_cost_quota(mut self, quota: Quota) -> Self {
+        self.cost = Some(quota);
+        self
+    }
+}
+
+impl ExtensionFactory for RateLimit {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RateLimitExtension {
+            limits: self.clone(),
+            root_fields: Mutex::default(),
+            costs: Mutex::default(),
+        })
+    }
+}
+
+struct RateLimitExtension {
+    limits: RateLimit,
+    root_fields: Mutex<OperationFields>,
+    costs: Mutex<OperationCosts>,
+}
+
+impl RateLimitExtension {
+    /// Take the operation's tokens, or return how long to wait
+    async fn acquire(&self, caller: &str, operation_name: Option<&str>) -> Result<(), Duration> {
+        let limits = &self.limits;
+        let root_fields = self.root_fields.lock().unwrap().get(operation_name);
+        let mut selections = BTreeMap::<&str, u32>::new();
+        for field in &root_fields {
+            if limits.fields.contains_key(field) {
+                *selections.entry(field.as_str()).or_default() += 1;
+            }
+        }
+        if selections.is_empty() {
+            limits
+                .store
+                .acquire(&format!("{caller}:default"), 1, limits.default)
+                .await?;
+        }
+        for (field, tokens) in selections {
+            limits
+                .store
+                .acquire(&format!("{caller}:field:{field}"), tokens, limits.fields[field])
+                .await?;
+        }
+
+        let cost = self.costs.lock().unwrap().get(operation_name);
+        match (limits.cost, cost) {
+            (Some(quota), Some(cost)) => {
+                let tokens = u32::try_from(cost).unwrap_or(u32::MAX);
+                limits
+                    .store
+                    .acquire(&format!("{caller}:cost"), tokens, quota)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Extension for RateLimitExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        if !self.limits.fields.is_empty() {
+            *self.root_fields.lock().unwrap() = OperationFields::compute(&document);
+        }
+        if self.limits.cost.is_some() {
+            *self.costs.lock().unwrap() = OperationCosts::compute(ctx, &document, variables);
+        }
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let caller = caller_key(ctx.data_opt::<AuthContext>(), ctx.data_opt::<ClientInfo>());
+        match self.acquire(&caller, operation_name).await {
+            Ok(()) => next.run(ctx, operation_name).await,
+            Err(wait) => {
+                Response::from_errors(vec![rate_limited(wait).into_server_error(Pos::default())])
+            }
+        }
+    }
+}
+
+/// Root fields of each operation in a document, by operation name
+#[derive(Debug, Default)]
+struct OperationFields(Vec<(Option<Name>, Vec<String>)>);
+
+impl OperationFields {
+    fn compute(document: &ExecutableDocument) -> Self {
+        let fields = document
+            .operations
+            .iter()
+            .map(|(name, operation)| {
+                let info = OperationInfo::from_operation(document, &operation.node);
+                (name.cloned(), info.root_fields)
+            })
+            .collect();
+        Self(fields)
+    }
+
+    /// Root fields of the operation `operation_name` selects
+    fn get(&self, operation_name: Option<&str>) -> Vec<String> {
+        let fields = match operation_name {
+            Some(name) => self.0.iter().find(|(op, _)| op.as_deref() == Some(name)),
+            None if self.0.len() == 1 => self.0.first(),
+            None => None,
+        };
+        fields.map(|(_, fields)| fields.clone()).unwrap_or_default()
+    }
+}
+
+/// Bucket key prefix for the caller
+fn caller_key(auth: Option<&AuthContext>, client: Option<&ClientInfo>) -> String {
+    if let Some(user) = auth.and_then(|auth| auth.user_id) {
+        return format!("user:{user}");
+    }
+    if let Some(service) = auth.and_then(|auth| auth.service.as_deref()) {
+        return format!("service:{service}");
+    }
+    match client.and_then(|client| client.ip) {
+        Some(ip) => format!("ip:{ip}"),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// `RATE_LIMITED` error with `retryAfter` in whole seconds
+fn rate_limited(wait: Duration) -> async_graphql::Error {
+    let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    ErrorCode::RateLimited
+        .error(format!("Rate limit exceeded, retry in {retry_after}s"))
+        .extend_with(|_, e| e.set("retryAfter", retry_after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema, Value};
+    use uuid::Uuid;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ping(&self) -> bool {
+            true
+        }
+
+        async fn pong(&self) -> bool {
+            true
+        }
+    }
+
+    fn as_user(query: &str, user: Uuid) -> Request {
+        Request::new(query).data(AuthContext {
+            user_id: Some(user),
+            ..AuthContext::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_per_user_buckets() {
+        let limits = RateLimit::new(Arc::new(MemoryRateLimitStore::new()), Quota::per_hour(2));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(limits)
+            .finish();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(schema.execute(as_user("{ ping }", alice)).await.is_ok());
+        assert!(schema.execute(as_user("{ ping }", alice)).await.is_ok());
+        let response = schema.execute(as_user("{ ping }", alice)).await;
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("code"), Some(&Value::from("RATE_LIMITED")));
+        assert_eq!(extensions.get("retryable"), Some(&Value::from(true)));
+        assert_eq!(extensions.get("retryAfter"), Some(&Value::from(1800)));
+
+        assert!(schema.execute(as_user("{ ping }", bob)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_field_and_cost_quotas() {
+        let limits = RateLimit::new(Arc::new(MemoryRateLimitStore::new()), Quota::per_hour(100))
+            .with_field("ping", Quota::per_hour(1))
+            .with_cost_quota(Quota::per_hour(3));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(limits)
+            .finish();
+        let user = Uuid::new_v4();
+
+        assert!(schema
+            .execute(as_user("query Ping { ping }", user))
+            .await
+            .is_ok());
+        // Renaming the operation doesn't escape the field's bucket
+        assert!(schema
+            .execute(as_user("query Anything { ping }", user))
+            .await
+            .is_err());
+
+        // Cost 3, with two tokens left in the cost bucket
+        let response = schema
+            .execute(as_user("{ a: pong b: pong c: pong }", user))
+            .await;
+        assert!(response.is_err());
+    }
+
+    #[test]
+    fn test_zero_capacity_rejects() {
+        let store = MemoryRateLimitStore::new();
+        let quota = Quota::new(0, Duration::from_secs(60));
+        assert_eq!(
+            store.acquire_at("user", 1, quota, Instant::now()),
+            Err(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn test_idle_buckets_pruned() {
+        let store = MemoryRateLimitStore::new();
+        let quota = Quota::per_second(10);
+        let start = Instant::now();
+
+        store.acquire_at("alice", 1, quota, start).unwrap();
+        store.acquire_at("bob", 1, quota, start).unwrap();
+        assert_eq!(store.len(), 2);
+
+        // Both buckets refilled long before the next sweep
+        store
+            .acquire_at("carol", 1, quota, start + PRUNE_INTERVAL)
+            .unwrap();
+        assert_eq!(store.len(), 1);
+    }
+}
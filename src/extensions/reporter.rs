@@ -0,0 +1,136 @@
+//! Reporting of internal errors
+//!
+//! [`MaskErrors`](super::MaskErrors) hands every error it masks, and every
+//! error already coded `INTERNAL`, to an [`ErrorReporter`] before the
+//! details are stripped. [`SentryReporter`] (with the `sentry` feature)
+//! captures them as Sentry events.
+
+use async_graphql::ServerError;
+use std::error::Error as StdError;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::GraphQLError;
+
+/// An internal error and the request it happened in
+#[derive(Debug)]
+pub struct ErrorReport<'a> {
+    /// The error before masking
+    pub error: &'a ServerError,
+    pub operation_name: Option<&'a str>,
+    pub user_id: Option<Uuid>,
+    pub request_id: Option<&'a str>,
+    /// Messages of the error and its sources, outermost first
+    pub source_chain: Vec<String>,
+}
+
+/// Receiver of internal errors, e.g. an error tracker
+pub trait ErrorReporter: Send + Sync {
+    fn report(&self, report: &ErrorReport<'_>);
+}
+
+/// Shared error reporter handle
+pub type SharedErrorReporter = Arc<dyn ErrorReporter>;
+
+/// Messages of `error` and the sources of its underlying error
+///
+/// async-graphql keeps the source as `Any`, so the chain is followed for
+/// [`GraphQLError`], `std::io::Error`, and boxed errors.
+pub(crate) fn source_chain(error: &ServerError) -> Vec<String> {
+    let source: Option<&(dyn StdError + 'static)> = error
+        .source::<GraphQLError>()
+        .map(|e| e as &(dyn StdError + 'static))
+        .or_else(|| error.source::<std::io::Error>().map(|e| e as _))
+        .or_else(|| {
+            error
+                .source::<Box<dyn StdError + Send + Sync>>()
+                .map(|e| &**e as _)
+        });
+
+    let mut chain = vec![error.message.clone()];
+    // Client messages can be generic, with the detail only in the source
+    chain.extend(
+        source
+            .map(ToString::to_string)
+            .filter(|source| *source != error.message),
+    );
+    let mut next = source.and_then(StdError::source);
+    while let Some(error) = next {
+        chain.push(error.to_string());
+        next = error.source();
+    }
+    chain
+}
+
+/// [`ErrorReporter`] capturing errors with the current Sentry hub
+///
+/// The operation name is the event's transaction, the user ID its user,
+/// and the request ID a `request_id` tag. Initialize Sentry with
+/// `sentry::init` as usual.
+#[cfg(feature = "sentry")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SentryReporter;
+
+#[cfg(feature = "sentry")]
+impl ErrorReporter for SentryReporter {
+    fn report(&self, report: &ErrorReport<'_>) {
+        use sentry_core::protocol::{Event, Exception, Level, User};
+
+        // Sentry lists the innermost exception first
+        let exceptions: Vec<_> = report
+            .source_chain
+            .iter()
+            .rev()
+            .map(|message| Exception {
+                ty: "GraphQLError".to_string(),
+                value: Some(message.clone()),
+                ..Default::default()
+            })
+            .collect();
+        let mut event = Event {
+            level: Level::Error,
+            message: Some(report.error.message.clone()),
+            exception: exceptions.into(),
+            transaction: report.operation_name.map(str::to_string),
+            user: report.user_id.map(|id| User {
+                id: Some(id.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        if let Some(request_id) = report.request_id {
+            event
+                .tags
+                .insert("request_id".to_string(), request_id.to_string());
+        }
+        if !report.error.path.is_empty() {
+            let path = serde_json::to_value(&report.error.path).unwrap_or_default();
+            event.extra.insert("path".to_string(), path);
+        }
+        sentry_core::capture_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::Pos;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("query failed")]
+    struct QueryFailed(#[source] std::io::Error);
+
+    #[test]
+    fn test_source_chain() {
+        let source: Box<dyn StdError + Send + Sync> = Box::new(QueryFailed(std::io::Error::new(
+            std::io::ErrorKind::ConnectionRefused,
+            "connection refused",
+        )));
+        let error = async_graphql::Error::new_with_source(source).into_server_error(Pos::default());
+
+        assert_eq!(
+            source_chain(&error),
+            vec!["query failed", "connection refused"]
+        );
+    }
+}
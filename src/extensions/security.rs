@@ -0,0 +1,351 @@
+//! Hardening for internet-facing schemas
+//!
+//! [`SecurityConfig`] toggles introspection, "did you mean" field
+//! suggestions in validation errors, and limits on aliases, root fields,
+//! and directives per operation. [`SecurityConfig::hardened`] is meant for
+//! production; the default changes nothing.
+//!
+//! Introspection is disabled on the schema builder by
+//! [`SchemaBuilderExt::with_defaults`](crate::schema::SchemaBuilderExt::with_defaults);
+//! the rest is enforced by registering the config as an extension.
+//! Operations over a limit are rejected after parsing, before validation,
+//! with code `TOO_MANY_ALIASES`, `TOO_MANY_ROOT_FIELDS`, or
+//! `TOO_MANY_DIRECTIVES`. The request body size is limited by the handlers,
+//! see [`UploadConfig`](crate::auth::UploadConfig).
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextRequest,
+};
+use async_graphql::parser::types::{ExecutableDocument, Selection, SelectionSet};
+use async_graphql::{Name, Response, ServerResult, Variables};
+use std::sync::Arc;
+
+use crate::auth::OperationInfo;
+use crate::error::ErrorCode;
+use crate::extensions::persisted::ALLOW_INTROSPECTION_ENV;
+
+/// Maximum aliases per operation in [`SecurityConfig::hardened`]
+pub const DEFAULT_MAX_ALIASES: usize = 30;
+
+/// Maximum root fields per operation in [`SecurityConfig::hardened`]
+pub const DEFAULT_MAX_ROOT_FIELDS: usize = 10;
+
+/// Maximum directives per operation in [`SecurityConfig::hardened`]
+pub const DEFAULT_MAX_DIRECTIVES: usize = 50;
+
+/// Start of the suggestion async-graphql appends to validation errors
+const SUGGESTION: &str = " Did you mean";
+
+/// Introspection, suggestion, and operation shape settings
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object};
+/// use pleme_graphql_helpers::extensions::SecurityConfig;
+/// use pleme_graphql_helpers::schema::{build_schema, SchemaConfig};
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     async fn ping(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// let config = SchemaConfig::new().with_security(SecurityConfig::from_env());
+/// let schema = build_schema(Query, EmptyMutation, EmptySubscription, &config).finish();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityConfig {
+    /// Serve `__schema` and `__type`
+    pub introspection: bool,
+    /// Keep "did you mean" suggestions in validation errors
+    pub suggestions: bool,
+    /// Maximum aliased fields per operation, fragments expanded
+    pub max_aliases: Option<usize>,
+    /// Maximum root fields per operation
+    pub max_root_fields: Option<usize>,
+    /// Maximum directives per operation, fragments expanded
+    pub max_directives: Option<usize>,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            introspection: true,
+            suggestions: true,
+            max_aliases: None,
+            max_root_fields: None,
+            max_directives: None,
+        }
+    }
+}
+
+impl SecurityConfig {
+    /// Everything allowed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// No introspection or suggestions, default alias, root field, and
+    /// directive limits
+    pub fn hardened() -> Self {
+        Self {
+            introspection: false,
+            suggestions: false,
+            max_aliases: Some(DEFAULT_MAX_ALIASES),
+            max_root_fields: Some(DEFAULT_MAX_ROOT_FIELDS),
+            max_directives: Some(DEFAULT_MAX_DIRECTIVES),
+        }
+    }
+
+    /// [`hardened`](Self::hardened), with introspection allowed when
+    /// `PLEME_ALLOW_INTROSPECTION` is `true`
+    pub fn from_env() -> Self {
+        let allow = std::env::var(ALLOW_INTROSPECTION_ENV).unwrap_or_default();
+        Self::hardened().with_introspection(allow.eq_ignore_ascii_case("true"))
+    }
+
+    /// Enable or disable introspection
+    pub fn with_introspection(mut self, introspection: bool) -> Self {
+        self.introspection = introspection;
+        self
+    }
+
+    /// Keep or strip field suggestions in validation errors
+    pub fn with_suggestions(mut self, suggestions: bool) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    /// Set the maximum aliases per operation
+    pub fn with_max_aliases(mut self, max: usize) -> Self {
+        self.max_aliases = Some(max);
+        self
+    }
+
+    /// Set the maximum root fields per operation
+    pub fn with_max_root_fields(mut self, max: usize) -> Self {
+        self.max_root_fields = Some(max);
+        self
+    }
+
+    /// Set the maximum directives per operation
+    pub fn with_max_directives(mut self, max: usize) -> Self {
+        self.max_directives = Some(max);
+        self
+    }
+
+    fn check(&self, document: &ExecutableDocument) -> ServerResult<()> {
+        for (_, operation) in document.operations.iter() {
+            let reject = |code: ErrorCode, message: String| {
+                Err(code.error(message).into_server_error(operation.pos))
+            };
+            let selection_set = &operation.node.selection_set.node;
+            if let Some(max) = self.max_root_fields {
+                let root_fields = OperationInfo::from_operation(document, &operation.node)
+                    .root_fields
+                    .len();
+                if root_fields > max {
+                    return reject(
+                        ErrorCode::TooManyRootFields,
+                        format!("Operation selects more than {max} root fields"),
+                    );
+                }
+            }
+            if let Some(max) = self.max_aliases {
+                let aliases = count(
+                    document,
+                    selection_set,
+                    max,
+                    &mut Vec::new(),
+                    &|selection| match selection {
+                        Selection::Field(field) => usize::from(field.node.alias.is_some()),
+                        _ => 0,
+                    },
+                );
+                if aliases > max {
+                    return reject(
+                        ErrorCode::TooManyAliases,
+                        format!("Operation uses more than {max} aliases"),
+                    );
+                }
+            }
+            if let Some(max) = self.max_directives {
+                let directives = operation.node.directives.len()
+                    + count(
+                        document,
+                        selection_set,
+                        max,
+                        &mut Vec::new(),
+                        &|selection| match selection {
+                            Selection::Field(field) => field.node.directives.len(),
+                            Selection::FragmentSpread(spread) => spread.node.directives.len(),
+                            Selection::InlineFragment(fragment) => fragment.node.directives.len(),
+                        },
+                    );
+                if directives > max {
+                    return reject(
+                        ErrorCode::TooManyDirectives,
+                        format!("Operation uses more than {max} directives"),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sum of `weigh` over a selection set, counting up to just past `max`
+///
+/// Fragments count at every spread, so repeating one can't hide what it
+/// selects. `visiting` guards against fragment cycles, which validation
+/// only rejects later.
+fn count<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    max: usize,
+    visiting: &mut Vec<&'a Name>,
+    weigh: &dyn Fn(&Selection) -> usize,
+) -> usize {
+    let mut total = 0;
+    for selection in &selection_set.items {
+        if total > max {
+            break;
+        }
+        total += weigh(&selection.node);
+        let budget = max.saturating_sub(total);
+        total += match &selection.node {
+            Selection::Field(field) => count(
+                document,
+                &field.node.selection_set.node,
+                budget,
+                visiting,
+                weigh,
+            ),
+            Selection::InlineFragment(fragment) => count(
+                document,
+                &fragment.node.selection_set.node,
+                budget,
+                visiting,
+                weigh,
+            ),
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                match document.fragments.get(name) {
+                    Some(fragment) if !visiting.contains(&name) => {
+                        visiting.push(name);
+                        let total = count(
+                            document,
+                            &fragment.node.selection_set.node,
+                            budget,
+                            visiting,
+                            weigh,
+                        );
+                        visiting.pop();
+                        total
+                    }
+                    _ => 0,
+                }
+            }
+        };
+    }
+    total
+}
+
+impl ExtensionFactory for SecurityConfig {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(*self)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for SecurityConfig {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let mut response = next.run(ctx).await;
+        if !self.suggestions {
+            for error in &mut response.errors {
+                if let Some(start) = error.message.find(SUGGESTION) {
+                    error.message.truncate(start);
+                }
+            }
+        }
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        self.check(&document)?;
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, Value};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn price(&self) -> i32 {
+            1
+        }
+
+        async fn stock(&self) -> i32 {
+            2
+        }
+    }
+
+    fn code(response: &Response) -> Option<&Value> {
+        response.errors[0].extensions.as_ref()?.get("code")
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let security = SecurityConfig::new()
+            .with_max_aliases(2)
+            .with_max_root_fields(3)
+            .with_max_directives(1);
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(security)
+            .finish();
+
+        assert!(schema.execute("{ a: price b: price stock }").await.is_ok());
+
+        let aliases = schema
+            .execute("{ x: price ...F } fragment F on Query { a: price b: stock }")
+            .await;
+        assert_eq!(code(&aliases), Some(&Value::from("TOO_MANY_ALIASES")));
+
+        let root_fields = schema.execute("{ price stock x: price y: stock }").await;
+        assert_eq!(
+            code(&root_fields),
+            Some(&Value::from("TOO_MANY_ROOT_FIELDS"))
+        );
+
+        let directives = schema
+            .execute("{ price @include(if: true) stock @skip(if: false) }")
+            .await;
+        assert_eq!(code(&directives), Some(&Value::from("TOO_MANY_DIRECTIVES")));
+    }
+
+    #[tokio::test]
+    async fn test_strips_suggestions() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(SecurityConfig::hardened())
+            .finish();
+
+        let response = schema.execute("{ pric }").await;
+        assert!(!response.errors[0].message.contains("price"));
+    }
+}
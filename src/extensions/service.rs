@@ -0,0 +1,133 @@
+//! Service name tagging for federated errors
+//!
+//! A gateway merges subgraph errors into one response, so clients and logs
+//! can't tell which subgraph raised one. [`TagErrors`] stamps every error,
+//! including parse and validation errors, with the subgraph's `service`
+//! name, a `code`, and the `requestId`:
+//!
+//! ```json
+//! { "extensions": { "service": "orders", "code": "NOT_FOUND", "requestId": "..." } }
+//! ```
+//!
+//! Errors still without a code get `BAD_REQUEST` if they were raised before
+//! execution and `INTERNAL_SERVER_ERROR` otherwise. Enable it with
+//! [`SchemaConfig::with_service_name`](crate::schema::SchemaConfig::with_service_name).
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextRequest,
+};
+use async_graphql::{Response, ServerError};
+use std::sync::{Arc, Mutex};
+
+use crate::auth::{AuthContext, RequestId};
+use crate::error::ErrorCode;
+
+/// Extension adding `service`, `code`, and `requestId` to every error
+#[derive(Debug, Clone)]
+pub struct TagErrors {
+    service: Arc<str>,
+}
+
+impl TagErrors {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into().into(),
+        }
+    }
+}
+
+impl ExtensionFactory for TagErrors {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(TagErrorsExtension {
+            service: self.service.clone(),
+            request_id: Mutex::default(),
+        })
+    }
+}
+
+struct TagErrorsExtension {
+    service: Arc<str>,
+    /// Read during execution, when the request data is available
+    request_id: Mutex<Option<String>>,
+}
+
+impl TagErrorsExtension {
+    fn tag(&self, error: &mut ServerError) {
+        let request_id = self.request_id.lock().unwrap().clone();
+        let default_code = if error.path.is_empty() {
+            ErrorCode::BadRequest
+        } else {
+            ErrorCode::Internal
+        };
+        let extensions = error.extensions.get_or_insert_with(Default::default);
+        extensions.set("service", &*self.service);
+        if extensions.get("code").is_none() {
+            extensions.set("code", default_code.as_str());
+            extensions.set("retryable", default_code.is_retryable());
+        }
+        if let Some(request_id) = request_id {
+            if extensions.get("requestId").is_none() {
+                extensions.set("requestId", request_id);
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for TagErrorsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let mut response = next.run(ctx).await;
+        for error in &mut response.errors {
+            self.tag(error);
+        }
+        response
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let request_id = ctx
+            .data_opt::<RequestId>()
+            .map(|id| id.0.clone())
+            .or_else(|| ctx.data_opt::<AuthContext>()?.request_id.clone());
+        *self.request_id.lock().unwrap() = request_id;
+        next.run(ctx, operation_name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema, Value};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn order(&self) -> async_graphql::Result<i32> {
+            Err(ErrorCode::NotFound.error("Order not found"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tags_errors() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(TagErrors::new("orders"))
+            .finish();
+
+        let request = Request::new("{ order }").data(RequestId("req-1".to_string()));
+        let response = schema.execute(request).await;
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("service"), Some(&Value::from("orders")));
+        assert_eq!(extensions.get("code"), Some(&Value::from("NOT_FOUND")));
+        assert_eq!(extensions.get("requestId"), Some(&Value::from("req-1")));
+
+        let response = schema.execute("{ missing }").await;
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("service"), Some(&Value::from("orders")));
+        assert_eq!(extensions.get("code"), Some(&Value::from("BAD_REQUEST")));
+    }
+}
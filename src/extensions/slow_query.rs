@@ -0,0 +1,191 @@
+//! Slow query logging
+//!
+//! [`SlowQueryLog`] reports operations whose execution takes longer than a
+//! threshold, with the operation name, duration, user, and top-level
+//! fields. Reports are logged as a `tracing` warning (with the `tracing`
+//! feature) or handed to a custom handler.
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery,
+};
+use async_graphql::parser::types::ExecutableDocument;
+use async_graphql::{Response, ServerResult, Variables};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::auth::{AuthContext, OperationInfo};
+
+/// An operation that exceeded the slow threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowQuery {
+    pub operation_name: Option<String>,
+    pub duration: Duration,
+    pub user_id: Option<Uuid>,
+    pub company_id: Option<Uuid>,
+    /// Root fields the operation selected
+    pub root_fields: Vec<String>,
+}
+
+/// Handler for slow query reports
+pub type SlowQueryHandler = Arc<dyn Fn(&SlowQuery) + Send + Sync>;
+
+/// Extension reporting operations slower than `threshold`
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::extensions::SlowQueryLog;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let log = SlowQueryLog::new(Duration::from_millis(500)).with_handler(Arc::new(|slow| {
+///     eprintln!("{:?} took {:?}", slow.operation_name, slow.duration);
+/// }));
+/// ```
+#[derive(Clone)]
+pub struct SlowQueryLog {
+    threshold: Duration,
+    handler: Option<SlowQueryHandler>,
+}
+
+impl SlowQueryLog {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            handler: None,
+        }
+    }
+
+    /// Send reports to `handler` instead of `tracing`
+    pub fn with_handler(mut self, handler: SlowQueryHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    fn report(&self, slow: &SlowQuery) {
+        let Some(handler) = &self.handler else {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                operation_name = slow.operation_name.as_deref(),
+                duration_ms = slow.duration.as_millis() as u64,
+                user_id = ?slow.user_id,
+                company_id = ?slow.company_id,
+                root_fields = ?slow.root_fields,
+                "slow GraphQL operation"
+            );
+            return;
+        };
+        handler(slow);
+    }
+}
+
+impl ExtensionFactory for SlowQueryLog {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(SlowQueryLogExtension {
+            log: self.clone(),
+            operations: Mutex::default(),
+        })
+    }
+}
+
+struct SlowQueryLogExtension {
+    log: SlowQueryLog,
+    /// Root fields of each operation in the document, by operation name
+    operations: Mutex<Vec<(Option<String>, Vec<String>)>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for SlowQueryLogExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        *self.operations.lock().unwrap() = document
+            .operations
+            .iter()
+            .map(|(name, operation)| {
+                let info = OperationInfo::from_operation(&document, &operation.node);
+                (name.map(|name| name.to_string()), info.root_fields)
+            })
+            .collect();
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let started = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+        let duration = started.elapsed();
+
+        if duration > self.log.threshold {
+            // Named from the document when the request didn't name it
+            let (name, root_fields) = self
+                .operations
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(name, _)| operation_name.is_none() || name.as_deref() == operation_name)
+                .cloned()
+                .unwrap_or_default();
+            let auth = ctx.data_opt::<AuthContext>();
+            self.log.report(&SlowQuery {
+                operation_name: operation_name.map(str::to_string).or(name),
+                duration,
+                user_id: auth.and_then(|auth| auth.user_id),
+                company_id: auth.and_then(|auth| auth.company_id),
+                root_fields,
+            });
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn fast(&self) -> bool {
+            true
+        }
+
+        async fn slow(&self) -> bool {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_slow_operations() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let log =
+            SlowQueryLog::new(Duration::from_millis(10)).with_handler(Arc::new(move |slow| {
+                sink.lock().unwrap().push(slow.clone())
+            }));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(log)
+            .finish();
+
+        schema.execute("{ fast }").await;
+        schema.execute("query Report { slow fast }").await;
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].operation_name.as_deref(), Some("Report"));
+        assert_eq!(reports[0].root_fields, vec!["slow", "fast"]);
+    }
+}
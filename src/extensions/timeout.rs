@@ -0,0 +1,103 @@
+//! Per-request execution timeout
+//!
+//! [`Timeout`] stops resolving an operation once its time is up and
+//! returns a `TIMEOUT` error instead. Resolver futures still in flight are
+//! dropped, so a mutation may have been partially applied; make mutations
+//! idempotent or keep their budget generous.
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute};
+use async_graphql::{Pos, Response};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::ErrorCode;
+
+/// Extension cancelling operations that run longer than `duration`
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+/// use pleme_graphql_helpers::extensions::Timeout;
+/// use std::time::Duration;
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     async fn ping(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///     .extension(Timeout::new(Duration::from_secs(10)))
+///     .finish();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Timeout {
+    duration: Duration,
+}
+
+impl Timeout {
+    pub fn new(duration: Duration) -> Self {
+        Self { duration }
+    }
+}
+
+impl ExtensionFactory for Timeout {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(*self)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for Timeout {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        match tokio::time::timeout(self.duration, next.run(ctx, operation_name)).await {
+            Ok(response) => response,
+            Err(_) => {
+                let error = ErrorCode::Timeout
+                    .error(format!(
+                        "Request timed out after {}ms",
+                        self.duration.as_millis()
+                    ))
+                    .into_server_error(Pos::default());
+                Response::from_errors(vec![error])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, Value};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn slow(&self) -> bool {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(Timeout::new(Duration::from_millis(10)))
+            .finish();
+
+        let response = schema.execute("{ slow }").await;
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("code"), Some(&Value::from("TIMEOUT")));
+        assert_eq!(extensions.get("retryable"), Some(&Value::from(true)));
+    }
+}
@@ -1,13 +1,36 @@
 //! Apollo Federation v2 utilities
+//!
+//! - [`EntityResolver`] - Typed entity lookup from `_entities` representations
+//! - [`EntityRegistry`] - Entity resolvers by typename
+//! - [`Representation`] - Typed access to `@requires` fields of a representation
+//! - [`GlobalId`] and [`NodeRegistry`] - Relay `node(id:)` backed by the
+//!   entity resolvers
+//! - [`InterfaceObject`] - `@interfaceObject` types contributing fields to
+//!   an interface owned by another subgraph
+//! - [`federation_sdl`] - Subgraph SDL with the federation `@link` header
+//! - [`ContractDirectives`] and [`Contract`] - `@tag`/`@inaccessible` for
+//!   GraphOS contracts
+//! - [`OverrideRegistry`] - `@override` declarations for migrating fields
+//!   between subgraphs
+//! - [`validate_subgraph`] - Composition checks for a subgraph SDL
 
-use async_trait::async_trait;
+mod batch;
+pub mod contract;
+pub mod entity;
+pub mod interface;
+pub mod node;
+pub mod overrides;
+pub mod registry;
+pub mod representation;
+pub mod sdl;
+pub mod validate;
 
-/// Entity resolver trait for Apollo Federation
-#[async_trait]
-pub trait EntityResolver: Send + Sync {
-    /// Resolve entity by key
-    async fn resolve_reference(&self, key: &str) -> Option<String>;
-}
-
-// Federation helper macros would go here
-// For now, keeping it minimal
+pub use contract::{Contract, ContractDirectives, ContractViolation};
+pub use entity::{parse_representation, representation_typename, EntityResolver};
+pub use interface::{with_interface_key, InterfaceObject, InterfaceObjectResolver};
+pub use node::{resolve_node, GlobalId, NodeRegistry};
+pub use overrides::{overrides_handler, FieldOverride, OverrideRegistry};
+pub use registry::{entity_registry, resolve_entity, EntityRegistry};
+pub use representation::{FieldSet, Representation};
+pub use sdl::{federation_sdl, federation_sdl_with_version, sdl_handler, FederationVersion};
+pub use validate::{validate_subgraph, Diagnostic, Severity};
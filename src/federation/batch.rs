@@ -0,0 +1,237 @@
+//! Batching of concurrent entity lookups
+//!
+//! async-graphql resolves the representations of one `_entities` call
+//! concurrently. [`EntityBatcher`] holds the first lookup of a type until a
+//! scheduler yield passes without new lookups joining, then resolves all
+//! their keys with a single [`EntityResolver::resolve_batch`] call. A
+//! [`DataLoader`] registered as a resolver makes that a single
+//! `load_batch`; since its cache lives as long as the loader, it's only
+//! accepted in a registry built per request.
+
+use async_graphql::OutputType;
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use serde::de::DeserializeOwned;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use super::entity::EntityResolver;
+use crate::dataloaders::{BatchLoader, DataLoader};
+use crate::Result;
+
+/// Entities of a batch, taken by the lookup at their index
+type BatchResult<E> = Arc<Result<Vec<Mutex<Option<E>>>>>;
+
+/// Shared handle to a dispatched `resolve_batch` call
+type BatchFuture<E> = Shared<BoxFuture<'static, BatchResult<E>>>;
+
+/// Keys collected for the next `resolve_batch` call
+struct PendingBatch<R: EntityResolver> {
+    keys: Vec<R::Key>,
+    future: BatchFuture<R::Entity>,
+}
+
+/// Collects concurrent lookups of one entity type into batches
+pub(crate) struct EntityBatcher<R: EntityResolver> {
+    resolver: Arc<R>,
+    pending: Arc<Mutex<Option<PendingBatch<R>>>>,
+}
+
+impl<R: EntityResolver + 'static> EntityBatcher<R> {
+    pub(crate) fn new(resolver: R) -> Self {
+        Self {
+            resolver: Arc::new(resolver),
+            pending: Arc::default(),
+        }
+    }
+
+    pub(crate) fn resolver(&self) -> &R {
+        &self.resolver
+    }
+
+    /// Load the entity with `key` in the current batch
+    pub(crate) async fn load(&self, key: R::Key) -> Result<Option<R::Entity>> {
+        let (future, index) = {
+            let mut pending = self.pending.lock().unwrap();
+            let batch = pending.get_or_insert_with(|| PendingBatch {
+                keys: Vec::new(),
+                future: self.dispatch(),
+            });
+            batch.keys.push(key);
+            (batch.future.clone(), batch.keys.len() - 1)
+        };
+        match &*future.await {
+            Ok(entities) => Ok(entities
+                .get(index)
+                .and_then(|entity| entity.lock().unwrap().take())),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// Create the call resolving the keys collected until it first runs
+    fn dispatch(&self) -> BatchFuture<R::Entity> {
+        let resolver = self.resolver.clone();
+        let pending = self.pending.clone();
+        async move {
+            // Let the other lookups of the same `_entities` call join. Each
+            // lookup polling the shared future resumes it, so keep yielding
+            // until a pass adds no keys.
+            let mut collected = 0;
+            loop {
+                tokio::task::yield_now().await;
+                let keys = pending
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map_or(0, |batch| batch.keys.len());
+                if keys == collected {
+                    break;
+                }
+                collected = keys;
+            }
+            let keys = pending
+                .lock()
+                .unwrap()
+                .take()
+                .map(|batch| batch.keys)
+                .unwrap_or_default();
+            let entities = resolver.resolve_batch(keys).await;
+            Arc::new(entities.map(|entities| entities.into_iter().map(Mutex::new).collect()))
+        }
+        .boxed()
+        .shared()
+    }
+}
+
+/// Entities loaded by key; keys the loader doesn't return resolve to `null`
+#[async_trait]
+impl<K, V, L> EntityResolver for DataLoader<K, V, L>
+where
+    K: DeserializeOwned + Send + Sync + Clone + Eq + Hash + 'static,
+    V: OutputType + Clone + 'static,
+    L: BatchLoader<K, V> + 'static,
+{
+    type Key = K;
+    type Entity = V;
+
+    async fn resolve(&self, key: K) -> Result<Option<V>> {
+        Ok(self.load(key).await)
+    }
+
+    async fn resolve_batch(&self, keys: Vec<K>) -> Result<Vec<Option<V>>> {
+        Ok(self.load_many_ordered(keys).await)
+    }
+
+    fn caches(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataloaders::{BatchLoader, DataLoader, LoaderRegistry};
+    use crate::federation::{resolve_entity, EntityRegistry};
+    use async_graphql::{
+        Context, EmptyMutation, EmptySubscription, Object, Request, Schema, SimpleObject, Value,
+    };
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+    struct ProductKey {
+        sku: String,
+    }
+
+    #[derive(Clone, SimpleObject)]
+    struct Product {
+        sku: String,
+    }
+
+    struct ProductLoader {
+        batches: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BatchLoader<ProductKey, Product> for ProductLoader {
+        async fn load_batch(&self, keys: &[ProductKey]) -> HashMap<ProductKey, Product> {
+            self.batches.lock().unwrap().push(keys.len());
+            keys.iter()
+                .map(|key| {
+                    (
+                        key.clone(),
+                        Product {
+                            sku: key.sku.clone(),
+                        },
+                    )
+                })
+                .collect()
+        }
+    }
+
+    type Products = DataLoader<ProductKey, Product, ProductLoader>;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn version(&self) -> &str {
+            "1"
+        }
+
+        #[graphql(entity)]
+        async fn find_product(
+            &self,
+            ctx: &Context<'_>,
+            sku: String,
+        ) -> async_graphql::Result<Option<Product>> {
+            resolve_entity::<Products>(ctx, ProductKey { sku }).await
+        }
+    }
+
+    const ENTITIES: &str = r#"{ _entities(representations: [
+        { __typename: "Product", sku: "a" },
+        { __typename: "Product", sku: "b" },
+        { __typename: "Product", sku: "c" }
+    ]) { ... on Product { sku } } }"#;
+
+    #[tokio::test]
+    async fn test_entities_load_in_one_batch() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let loader = ProductLoader {
+            batches: batches.clone(),
+        };
+        let registry = EntityRegistry::new().with_resolver(Products::new(loader));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .finish();
+
+        let request = Request::new(ENTITIES).data(LoaderRegistry::new().with(registry));
+        let response = schema.execute(request).await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "_entities": [{ "sku": "a" }, { "sku": "b" }, { "sku": "c" }] })
+        );
+        assert_eq!(*batches.lock().unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_shared_data_loader_rejected() {
+        let loader = ProductLoader {
+            batches: Arc::default(),
+        };
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .data(EntityRegistry::new().with_resolver(Products::new(loader)))
+            .finish();
+
+        let response = schema.execute(ENTITIES).await;
+        let error = &response.errors[0];
+        assert_eq!(
+            error.extensions.as_ref().unwrap().get("code"),
+            Some(&Value::from("INTERNAL_SERVER_ERROR"))
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! GraphOS contract support
+//!
+//! Contracts filter a supergraph by `@tag`. [`ContractDirectives`] adds
+//! `@tag(name:)` and `@inaccessible` to types and fields of an exported SDL
+//! when they can't be set with the `#[graphql(tag = "..", inaccessible)]`
+//! attributes, e.g. for types from another crate. [`Contract`] checks a
+//! subgraph SDL against a contract's tag filter and flags fields the
+//! contract keeps whose type it hides, which fails contract composition.
+
+use async_graphql::parser::types::{
+    BaseType, ConstDirective, TypeDefinition, TypeKind, TypeSystemDefinition,
+};
+use async_graphql::Positioned;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::{GraphQLError, Result};
+
+/// `@tag` and `@inaccessible` for types (`Product`) and fields
+/// (`Product.price`), applied to SDL text
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::federation::ContractDirectives;
+///
+/// let directives = ContractDirectives::new()
+///     .with_tag("Product", "public")
+///     .with_inaccessible("Product.cost");
+/// let sdl = directives.apply("type Product {\n\tid: ID!\n\tcost: Int!\n}\n");
+/// assert!(sdl.contains("type Product @tag(name: \"public\") {"));
+/// assert!(sdl.contains("\tcost: Int! @inaccessible"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractDirectives {
+    tags: BTreeMap<String, BTreeSet<String>>,
+    inaccessible: BTreeSet<String>,
+}
+
+impl ContractDirectives {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tag a type or `Type.field` with `name`
+    pub fn with_tag(mut self, target: impl Into<String>, name: impl Into<String>) -> Self {
+        self.tags
+            .entry(target.into())
+            .or_default()
+            .insert(name.into());
+        self
+    }
+
+    /// Mark a type or `Type.field` `@inaccessible`
+    pub fn with_inaccessible(mut self, target: impl Into<String>) -> Self {
+        self.inaccessible.insert(target.into());
+        self
+    }
+
+    /// Directives to add to `target`, with a leading space
+    fn directives(&self, target: &str) -> String {
+        let mut directives = String::new();
+        for tag in self.tags.get(target).into_iter().flatten() {
+            directives.push_str(&format!(" @tag(name: \"{tag}\")"));
+        }
+        if self.inaccessible.contains(target) {
+            directives.push_str(" @inaccessible");
+        }
+        directives
+    }
+
+    /// Add the directives to an SDL as exported by async-graphql
+    pub fn apply(&self, sdl: &str) -> String {
+        apply_directives(sdl, |target| self.directives(target))
+    }
+}
+
+/// Append `directives(target)` to each type and `Type.field` definition of
+/// an SDL as exported by async-graphql
+pub(super) fn apply_directives(sdl: &str, directives: impl Fn(&str) -> String) -> String {
+    let mut output = String::with_capacity(sdl.len());
+    let mut current_type: Option<String> = None;
+    // Field whose arguments span lines, until its closing `)`
+    let mut open_field: Option<String> = None;
+    let mut in_description = false;
+
+    for line in sdl.lines() {
+        let trimmed = line.trim();
+        let mut line = line.to_string();
+        if trimmed.starts_with("\"\"\"") || in_description {
+            let block_quotes = trimmed.matches("\"\"\"").count();
+            in_description ^= block_quotes % 2 == 1;
+            output.push_str(&line);
+            output.push('\n');
+            continue;
+        }
+        match &current_type {
+            None => {
+                if let Some(name) = defined_type(trimmed) {
+                    insert_type_directives(&mut line, &directives(name));
+                    if trimmed.ends_with('{') {
+                        current_type = Some(name.to_string());
+                    }
+                }
+            }
+            Some(_) if trimmed == "}" => current_type = None,
+            Some(type_name) => {
+                let depth = line.len() - line.trim_start_matches('\t').len();
+                if let Some(field) = open_field.as_ref().filter(|_| depth == 1) {
+                    if trimmed.starts_with(')') {
+                        line.push_str(&directives(&format!("{type_name}.{field}")));
+                        open_field = None;
+                    }
+                } else if depth == 1 && trimmed.starts_with(is_name_start) {
+                    let field = name_prefix(trimmed);
+                    if trimmed.ends_with('(') {
+                        open_field = Some(field.to_string());
+                    } else {
+                        line.push_str(&directives(&format!("{type_name}.{field}")));
+                    }
+                }
+            }
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+/// Leading GraphQL name of `text`
+fn name_prefix(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Name of the type a definition line starts
+pub(super) fn defined_type(line: &str) -> Option<&str> {
+    let line = line.strip_prefix("extend ").unwrap_or(line);
+    let (keyword, rest) = line.split_once(' ')?;
+    matches!(
+        keyword,
+        "type" | "interface" | "input" | "enum" | "union" | "scalar"
+    )
+    .then(|| name_prefix(rest))
+    .filter(|name| !name.is_empty())
+}
+
+/// Insert directives before a definition's body or union members
+pub(super) fn insert_type_directives(line: &mut String, directives: &str) {
+    if directives.is_empty() {
+        return;
+    }
+    let position = line
+        .find(" =")
+        .or_else(|| {
+            line.trim_end()
+                .strip_suffix('{')
+                .map(|s| s.trim_end().len())
+        })
+        .unwrap_or(line.trim_end().len());
+    line.insert_str(position, directives);
+}
+
+/// A contract's tag filter
+///
+/// With include tags, only tagged types and fields are kept; fields inherit
+/// their type's tags. Exclude tags and `@inaccessible` hide elements.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contract {
+    include: BTreeSet<String>,
+    exclude: BTreeSet<String>,
+}
+
+/// A field kept by a contract whose type the contract hides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractViolation {
+    /// The field as `Type.field`
+    pub field: String,
+    /// The hidden type
+    pub type_name: String,
+}
+
+impl fmt::Display for ContractViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is in the contract but its type {} is not",
+            self.field, self.type_name
+        )
+    }
+}
+
+/// Tags and visibility of a type or field
+#[derive(Debug, Default)]
+struct Element {
+    tags: BTreeSet<String>,
+    inaccessible: bool,
+}
+
+impl Element {
+    fn from_directives(directives: &[Positioned<ConstDirective>]) -> Self {
+        let mut element = Self::default();
+        for directive in directives {
+            match directive.node.name.node.as_str() {
+                "inaccessible" => element.inaccessible = true,
+                "tag" => {
+                    if let Some(async_graphql::Value::String(name)) =
+                        directive.node.get_argument("name").map(|value| &value.node)
+                    {
+                        element.tags.insert(name.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        element
+    }
+}
+
+impl Contract {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep only elements tagged `tag` (or any other include tag)
+    pub fn with_include(mut self, tag: impl Into<String>) -> Self {
+        self.include.insert(tag.into());
+        self
+    }
+
+    /// Hide elements tagged `tag`
+    pub fn with_exclude(mut self, tag: impl Into<String>) -> Self {
+        self.exclude.insert(tag.into());
+        self
+    }
+
+    fn excluded(&self, element: &Element) -> bool {
+        element.inaccessible || !element.tags.is_disjoint(&self.exclude)
+    }
+
+    fn included(&self, element: &Element) -> bool {
+        self.include.is_empty() || !element.tags.is_disjoint(&self.include)
+    }
+
+    /// Fields of `sdl` the contract keeps while hiding their type
+    pub fn validate(&self, sdl: &str) -> Result<Vec<ContractViolation>> {
+        let document = async_graphql::parser::parse_schema(sdl)
+            .map_err(|e| GraphQLError::FederationError(format!("Invalid SDL: {e}")))?;
+        let types: Vec<&TypeDefinition> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                TypeSystemDefinition::Type(ty) => Some(&ty.node),
+                _ => None,
+            })
+            .collect();
+
+        // (type, fields as (name, element, return type))
+        let mut elements = Vec::new();
+        for ty in &types {
+            let fields: Vec<_> = match &ty.kind {
+                TypeKind::Object(object) => object
+                    .fields
+                    .iter()
+                    .map(|f| (&f.node.name.node, &f.node.directives, &f.node.ty.node))
+                    .collect(),
+                TypeKind::Interface(interface) => interface
+                    .fields
+                    .iter()
+                    .map(|f| (&f.node.name.node, &f.node.directives, &f.node.ty.node))
+                    .collect(),
+                TypeKind::InputObject(input) => input
+                    .fields
+                    .iter()
+                    .map(|f| (&f.node.name.node, &f.node.directives, &f.node.ty.node))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let fields: Vec<_> = fields
+                .into_iter()
+                .map(|(name, directives, ty)| (name, Element::from_directives(directives), ty))
+                .collect();
+            elements.push((ty, Element::from_directives(&ty.directives), fields));
+        }
+
+        let visible: HashMap<&str, bool> = elements
+            .iter()
+            .map(|(ty, element, fields)| {
+                let included = self.included(element)
+                    || fields.iter().any(|(_, field, _)| self.included(field));
+                (ty.name.node.as_str(), included && !self.excluded(element))
+            })
+            .collect();
+
+        let mut violations = Vec::new();
+        for (ty, element, fields) in &elements {
+            if !visible[ty.name.node.as_str()] {
+                continue;
+            }
+            for (name, field, field_type) in fields {
+                let kept =
+                    !self.excluded(field) && (self.included(element) || self.included(field));
+                let mut base = &field_type.base;
+                while let BaseType::List(inner) = base {
+                    base = &inner.base;
+                }
+                let BaseType::Named(type_name) = base else {
+                    continue;
+                };
+                if kept && visible.get(type_name.as_str()) == Some(&false) {
+                    violations.push(ContractViolation {
+                        field: format!("{}.{name}", ty.name.node),
+                        type_name: type_name.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDL: &str = "type Product {\n\tid: ID!\n\tprice(\n\t\tcurrency: String\n\t): Money!\n\tcost: Cost\n}\n\ntype Money {\n\tamount: Int!\n}\n\ntype Cost {\n\tamount: Int!\n}\n\ntype Query {\n\tproduct: Product\n}\n";
+
+    #[test]
+    fn test_apply_directives() {
+        let sdl = ContractDirectives::new()
+            .with_tag("Product", "public")
+            .with_tag("Product.price", "public")
+            .with_inaccessible("Cost")
+            .apply(SDL);
+
+        assert!(sdl.contains("type Product @tag(name: \"public\") {"));
+        assert!(sdl.contains("\t): Money! @tag(name: \"public\")\n"));
+        assert!(sdl.contains("type Cost @inaccessible {"));
+        assert!(sdl.contains("\tid: ID!\n"));
+    }
+
+    #[test]
+    fn test_validate_contract() {
+        let sdl = ContractDirectives::new()
+            .with_tag("Product", "public")
+            .with_tag("Query", "public")
+            .with_tag("Money", "public")
+            .with_inaccessible("Cost")
+            .apply(SDL);
+        let contract = Contract::new().with_include("public");
+
+        let violations = contract.validate(&sdl).unwrap();
+        assert_eq!(
+            violations,
+            vec![ContractViolation {
+                field: "Product.cost".to_string(),
+                type_name: "Cost".to_string(),
+            }]
+        );
+
+        let sdl = ContractDirectives::new()
+            .with_inaccessible("Product.cost")
+            .apply(&sdl);
+        assert!(contract.validate(&sdl).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Typed entity resolution
+//!
+//! The router calls `_entities` with representations: `_Any` maps holding
+//! `__typename` and the entity's key fields. An [`EntityResolver`]
+//! deserializes the key fields into its `Key` struct and loads the entity.
+
+use async_graphql::{Any, OutputType, Value};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+
+use crate::{GraphQLError, Result};
+
+/// Field of a representation naming the entity type
+const TYPENAME: &str = "__typename";
+
+/// Resolver for one entity type
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::EntityResolver;
+///
+/// #[derive(serde::Deserialize)]
+/// struct UserKey {
+///     id: Uuid,
+/// }
+///
+/// struct UserResolver(PgPool);
+///
+/// #[async_trait::async_trait]
+/// impl EntityResolver for UserResolver {
+///     type Key = UserKey;
+///     type Entity = User;
+///
+///     async fn resolve(&self, key: UserKey) -> pleme_graphql_helpers::Result<Option<User>> {
+///         Ok(find_user(&self.0, key.id).await?)
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait EntityResolver: Send + Sync {
+    /// Key fields of the entity, deserialized from the representation
+    type Key: DeserializeOwned + Send;
+
+    /// The resolved entity
+    type Entity: OutputType;
+
+    /// Load the entity with `key`, `None` if it doesn't exist
+    async fn resolve(&self, key: Self::Key) -> Result<Option<Self::Entity>>;
+
+    /// Load the entities with `keys`, in order
+    ///
+    /// Called with the keys of one `_entities` call collected by the
+    /// [`EntityRegistry`](super::EntityRegistry). Defaults to resolving each
+    /// key concurrently; override to load them in one query.
+    async fn resolve_batch(&self, keys: Vec<Self::Key>) -> Result<Vec<Option<Self::Entity>>> {
+        futures::future::try_join_all(keys.into_iter().map(|key| self.resolve(key))).await
+    }
+
+    /// Whether the resolver caches entities between calls
+    ///
+    /// A caching resolver must be registered in an
+    /// [`EntityRegistry`](super::EntityRegistry) built per request, so
+    /// cached entities never reach another request or tenant.
+    fn caches(&self) -> bool {
+        false
+    }
+
+    /// Parse a representation's key fields and load the entity
+    async fn resolve_representation(&self, representation: &Any) -> Result<Option<Self::Entity>> {
+        let key = parse_representation(representation)?;
+        self.resolve(key).await
+    }
+}
+
+/// Deserialize the key fields of a representation, ignoring `__typename`
+pub fn parse_representation<K: DeserializeOwned>(representation: &Any) -> Result<K> {
+    let invalid = |e: serde_json::Error| {
+        GraphQLError::FederationError(format!("Invalid entity representation: {e}"))
+    };
+    let mut json = representation.0.clone().into_json().map_err(invalid)?;
+    if let Some(fields) = json.as_object_mut() {
+        fields.remove(TYPENAME);
+    }
+    serde_json::from_value(json).map_err(invalid)
+}
+
+/// The `__typename` of a representation
+pub fn representation_typename(representation: &Any) -> Option<&str> {
+    match &representation.0 {
+        Value::Object(fields) => match fields.get(TYPENAME) {
+            Some(Value::String(typename)) => Some(typename),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::SimpleObject;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ProductKey {
+        sku: String,
+        store_id: u32,
+    }
+
+    #[derive(SimpleObject)]
+    struct Product {
+        sku: String,
+    }
+
+    struct ProductResolver;
+
+    #[async_trait]
+    impl EntityResolver for ProductResolver {
+        type Key = ProductKey;
+        type Entity = Product;
+
+        async fn resolve(&self, key: ProductKey) -> Result<Option<Product>> {
+            Ok((key.store_id == 1).then_some(Product { sku: key.sku }))
+        }
+    }
+
+    fn representation(json: serde_json::Value) -> Any {
+        Any(Value::from_json(json).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_representation() {
+        let found = representation(serde_json::json!({
+            "__typename": "Product",
+            "sku": "abc",
+            "storeId": 1,
+        }));
+        assert_eq!(representation_typename(&found), Some("Product"));
+        let product = ProductResolver
+            .resolve_representation(&found)
+            .await
+            .unwrap();
+        assert_eq!(product.unwrap().sku, "abc");
+
+        let missing = representation(serde_json::json!({ "sku": "abc", "storeId": 2 }));
+        assert!(ProductResolver
+            .resolve_representation(&missing)
+            .await
+            .unwrap()
+            .is_none());
+
+        let invalid = representation(serde_json::json!({ "__typename": "Product", "sku": "abc" }));
+        assert!(matches!(
+            ProductResolver.resolve_representation(&invalid).await,
+            Err(GraphQLError::FederationError(_))
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+//! Federated interfaces
+//!
+//! An interface owned by one subgraph (an entity interface, with `@key`)
+//! can get fields from other subgraphs that declare it as an
+//! `@interfaceObject`: an object type with the interface's name, its key
+//! fields, and the contributed fields. Declare one with
+//! `#[Object(interface_object)]` and implement [`InterfaceObject`]; the
+//! router sends its representations with the interface's `__typename`, and
+//! [`InterfaceObjectResolver`] builds the object from the key, since the
+//! contributed fields only need the key.
+//!
+//! The owning subgraph marks the interface with [`with_interface_key`] and
+//! registers a resolver returning the interface in the
+//! [`EntityRegistry`](super::EntityRegistry), so the router can ask it for
+//! the concrete type.
+
+use async_graphql::OutputType;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+use super::contract::{defined_type, insert_type_directives};
+use super::entity::EntityResolver;
+use crate::Result;
+
+/// An `@interfaceObject` type, built from its key
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(serde::Deserialize)]
+/// struct MediaKey {
+///     id: String,
+/// }
+///
+/// /// `Media` interface owned by the catalog subgraph
+/// struct Media {
+///     id: String,
+/// }
+///
+/// #[Object(interface_object)]
+/// impl Media {
+///     async fn id(&self) -> &str {
+///         &self.id
+///     }
+///
+///     /// Contributed by this subgraph to every `Media` implementation
+///     async fn reviews(&self, ctx: &Context<'_>) -> Result<Vec<Review>> {
+///         reviews_for(ctx, &self.id).await
+///     }
+/// }
+///
+/// impl InterfaceObject for Media {
+///     type Key = MediaKey;
+///
+///     fn from_key(key: MediaKey) -> Self {
+///         Media { id: key.id }
+///     }
+/// }
+///
+/// let registry = EntityRegistry::new().with_resolver(InterfaceObjectResolver::<Media>::new());
+/// ```
+pub trait InterfaceObject: OutputType + Sized {
+    /// Key fields of the interface
+    type Key: DeserializeOwned + Send;
+
+    fn from_key(key: Self::Key) -> Self;
+}
+
+/// [`EntityResolver`] building an [`InterfaceObject`] from its key
+pub struct InterfaceObjectResolver<T>(PhantomData<fn() -> T>);
+
+impl<T> InterfaceObjectResolver<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for InterfaceObjectResolver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<T: InterfaceObject> EntityResolver for InterfaceObjectResolver<T> {
+    type Key = T::Key;
+    type Entity = T;
+
+    async fn resolve(&self, key: T::Key) -> Result<Option<T>> {
+        Ok(Some(T::from_key(key)))
+    }
+}
+
+/// Add `@key(fields:)` to an interface of an SDL, making it an entity
+/// interface other subgraphs can contribute to
+pub fn with_interface_key(sdl: &str, interface: &str, fields: &str) -> String {
+    let directive = format!(" @key(fields: \"{fields}\")");
+    let mut output = String::with_capacity(sdl.len() + directive.len());
+    for line in sdl.lines() {
+        let mut line = line.to_string();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("interface ") && defined_type(trimmed) == Some(interface) {
+            insert_type_directives(&mut line, &directive);
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::federation::{federation_sdl, resolve_entity, EntityRegistry};
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct MediaKey {
+        id: String,
+    }
+
+    struct Media {
+        id: String,
+    }
+
+    #[Object(interface_object)]
+    impl Media {
+        async fn id(&self) -> &str {
+            &self.id
+        }
+
+        async fn review_count(&self) -> usize {
+            self.id.len()
+        }
+    }
+
+    impl InterfaceObject for Media {
+        type Key = MediaKey;
+
+        fn from_key(key: MediaKey) -> Self {
+            Media { id: key.id }
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn version(&self) -> &str {
+            "1"
+        }
+
+        #[graphql(entity)]
+        async fn find_media(
+            &self,
+            ctx: &Context<'_>,
+            id: String,
+        ) -> async_graphql::Result<Option<Media>> {
+            resolve_entity::<InterfaceObjectResolver<Media>>(ctx, MediaKey { id }).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interface_object() {
+        let registry = EntityRegistry::new().with_resolver(InterfaceObjectResolver::<Media>::new());
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .data(registry)
+            .finish();
+        assert!(federation_sdl(&schema).contains("@interfaceObject"));
+
+        let response = schema
+            .execute(
+                r#"{ _entities(representations: [{ __typename: "Media", id: "abc" }]) {
+                    ... on Media { reviewCount }
+                } }"#,
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "_entities": [{ "reviewCount": 3 }] })
+        );
+    }
+
+    #[test]
+    fn test_interface_key() {
+        let sdl = "interface Media {\n\tid: ID!\n}\n\ninterface MediaItem {\n\tid: ID!\n}\n";
+        let sdl = with_interface_key(sdl, "Media", "id");
+        assert!(sdl.contains("interface Media @key(fields: \"id\") {"));
+        assert!(sdl.contains("interface MediaItem {"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Relay global object identification
+//!
+//! A [`GlobalId`] is an entity's typename and ID, encoded like our cursors
+//! so clients treat it as opaque. A [`NodeRegistry`] dispatches `node(id:)`
+//! to the [`EntityResolver`] registered for the typename in the
+//! [`EntityRegistry`], so the same fetchers, and the same batching, back
+//! both `node()` and `_entities`.
+//!
+//! async-graphql interfaces are enums of their implementers, so the
+//! service declares its `Node` interface and the registry converts
+//! entities into it.
+
+use async_graphql::{Context, ErrorExtensions, OutputType, ID};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use super::entity::EntityResolver;
+use super::registry::{entity_registry, EntityRegistry};
+use crate::pagination::CursorCodec;
+use crate::{GraphQLError, Result};
+
+/// Opaque `ID` identifying an object across types
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlobalId {
+    pub typename: String,
+    pub id: String,
+}
+
+impl GlobalId {
+    pub fn new(typename: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            typename: typename.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Global ID of the object of type `T` with `id`
+    pub fn of<T: OutputType>(id: impl fmt::Display) -> Self {
+        Self::new(T::type_name(), id.to_string())
+    }
+
+    /// Encode as an opaque string
+    pub fn encode(&self) -> String {
+        CursorCodec::encode(&format!("{}:{}", self.typename, self.id))
+    }
+
+    /// Decode an encoded global ID
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let invalid = || GraphQLError::InvalidId(encoded.to_string());
+        let decoded = CursorCodec::decode(encoded).map_err(|_| invalid())?;
+        let (typename, id) = decoded.split_once(':').ok_or_else(invalid)?;
+        if typename.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self::new(typename, id))
+    }
+}
+
+impl fmt::Display for GlobalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+/// Fetcher of one node type, converting into the `Node` interface `N`
+#[async_trait]
+trait NodeFetcher<N>: Send + Sync {
+    async fn fetch(&self, registry: &EntityRegistry, id: &str) -> Result<Option<N>>;
+}
+
+struct Fetcher<R>(PhantomData<fn() -> R>);
+
+#[async_trait]
+impl<R, N> NodeFetcher<N> for Fetcher<R>
+where
+    R: EntityResolver + 'static,
+    R::Entity: Into<N>,
+    N: Send + 'static,
+{
+    async fn fetch(&self, registry: &EntityRegistry, id: &str) -> Result<Option<N>> {
+        // The key an `_entities` representation with `id` would have
+        let key = serde_json::from_value(json!({ "id": id }))
+            .map_err(|e| GraphQLError::InvalidId(format!("{id}: {e}")))?;
+        let entity = registry.resolve::<R>(key).await?;
+        Ok(entity.map(Into::into))
+    }
+}
+
+/// Node types by typename, resolving into the service's `Node` interface
+///
+/// Each type's entity resolver must be registered in the [`EntityRegistry`]
+/// in the schema data and key the entity by a single `id` field.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::federation::{resolve_node, EntityRegistry, NodeRegistry};
+///
+/// #[derive(Interface)]
+/// #[graphql(field(name = "id", ty = "ID"))]
+/// enum Node {
+///     User(User),
+///     Product(Product),
+/// }
+///
+/// #[Object]
+/// impl Query {
+///     async fn node(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Node>> {
+///         resolve_node::<Node>(ctx, &id).await
+///     }
+/// }
+///
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///     .data(EntityRegistry::new().with_resolver(UserResolver).with_resolver(ProductResolver))
+///     .data(
+///         NodeRegistry::<Node>::new()
+///             .with_type::<UserResolver>()
+///             .with_type::<ProductResolver>(),
+///     )
+///     .finish();
+/// ```
+pub struct NodeRegistry<N> {
+    fetchers: HashMap<String, Arc<dyn NodeFetcher<N>>>,
+}
+
+impl<N> Clone for NodeRegistry<N> {
+    fn clone(&self) -> Self {
+        Self {
+            fetchers: self.fetchers.clone(),
+        }
+    }
+}
+
+impl<N> Default for NodeRegistry<N> {
+    fn default() -> Self {
+        Self {
+            fetchers: HashMap::new(),
+        }
+    }
+}
+
+impl<N: Send + 'static> NodeRegistry<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch nodes of `R`'s entity type with its registered resolver
+    pub fn with_type<R>(mut self) -> Self
+    where
+        R: EntityResolver + 'static,
+        R::Entity: Into<N>,
+    {
+        self.fetchers.insert(
+            R::Entity::type_name().into_owned(),
+            Arc::new(Fetcher::<R>(PhantomData)),
+        );
+        self
+    }
+
+    /// Typenames that can be fetched as nodes
+    pub fn typenames(&self) -> impl Iterator<Item = &str> {
+        self.fetchers.keys().map(String::as_str)
+    }
+
+    /// Fetch the node with global ID `id`
+    ///
+    /// Returns `None` for unknown typenames and missing objects, as Relay
+    /// expects.
+    pub async fn resolve(&self, registry: &EntityRegistry, id: &str) -> Result<Option<N>> {
+        let id = GlobalId::decode(id)?;
+        match self.fetchers.get(&id.typename) {
+            Some(fetcher) => fetcher.fetch(registry, &id.id).await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// Fetch a node with the [`NodeRegistry`] in the schema data and the
+/// request's [`EntityRegistry`]
+///
+/// For the body of a `node(id:)` resolver.
+pub async fn resolve_node<N: Send + Sync + 'static>(
+    ctx: &Context<'_>,
+    id: &ID,
+) -> async_graphql::Result<Option<N>> {
+    ctx.data::<NodeRegistry<N>>()?
+        .resolve(entity_registry(ctx)?, id)
+        .await
+        .map_err(|e| e.extend())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{
+        EmptyMutation, EmptySubscription, Interface, Object, Schema, SimpleObject,
+    };
+    use serde::Deserialize;
+
+    #[test]
+    fn test_global_id_roundtrip() {
+        let id = GlobalId::new("Product", "42");
+        assert_eq!(GlobalId::decode(&id.encode()).unwrap(), id);
+        assert!(matches!(
+            GlobalId::decode("not base64!"),
+            Err(GraphQLError::InvalidId(_))
+        ));
+        let no_typename = CursorCodec::encode("42");
+        assert!(GlobalId::decode(&no_typename).is_err());
+    }
+
+    #[derive(Deserialize)]
+    struct ProductKey {
+        id: String,
+    }
+
+    #[derive(SimpleObject)]
+    struct Product {
+        id: ID,
+        name: String,
+    }
+
+    struct ProductResolver;
+
+    #[async_trait]
+    impl EntityResolver for ProductResolver {
+        type Key = ProductKey;
+        type Entity = Product;
+
+        async fn resolve(&self, key: ProductKey) -> Result<Option<Product>> {
+            Ok(Some(Product {
+                id: GlobalId::of::<Product>(&key.id).into(),
+                name: format!("Product {}", key.id),
+            }))
+        }
+    }
+
+    #[derive(Interface)]
+    #[graphql(field(name = "id", ty = "&ID"))]
+    enum Node {
+        Product(Product),
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn node(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Node>> {
+            resolve_node::<Node>(ctx, &id).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_node_query() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data(EntityRegistry::new().with_resolver(ProductResolver))
+            .data(NodeRegistry::<Node>::new().with_type::<ProductResolver>())
+            .finish();
+
+        let id = GlobalId::new("Product", "7").encode();
+        let query = format!(r#"{{ node(id: "{id}") {{ id ... on Product {{ name }} }} }}"#);
+        let response = schema.execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "node": { "id": id, "name": "Product 7" } })
+        );
+
+        let id = GlobalId::new("User", "7").encode();
+        let response = schema
+            .execute(format!(r#"{{ node(id: "{id}") {{ id }} }}"#))
+            .await;
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "node": null })
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! `@override` for migrating fields between subgraphs
+//!
+//! [`OverrideRegistry`] declares the fields this subgraph takes over from
+//! another one, optionally progressively with a `percent(N)` label, and
+//! adds the `@override` directives to the exported SDL. The registry also
+//! documents the migration state at runtime: [`overrides_handler`] serves
+//! it as JSON for operational dashboards.
+//!
+//! Progressive labels need [`FederationVersion::V2_7`]; [`sdl_handler`]
+//! links it when the registry has any.
+//!
+//! [`sdl_handler`]: super::sdl_handler
+
+use axum::{Extension, Json};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::contract::apply_directives;
+use super::sdl::FederationVersion;
+
+/// A field taken over from another subgraph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldOverride {
+    /// The field as `Type.field`
+    pub field: String,
+    /// Subgraph the field is taken from
+    pub from: String,
+    /// Progressive override label, e.g. `percent(25)`
+    pub label: Option<String>,
+}
+
+impl FieldOverride {
+    /// Share of traffic routed to this subgraph, for `percent(N)` labels
+    pub fn percent(&self) -> Option<f64> {
+        self.label
+            .as_deref()?
+            .strip_prefix("percent(")?
+            .strip_suffix(')')?
+            .parse()
+            .ok()
+    }
+
+    fn directive(&self) -> String {
+        match &self.label {
+            Some(label) => format!(" @override(from: \"{}\", label: \"{label}\")", self.from),
+            None => format!(" @override(from: \"{}\")", self.from),
+        }
+    }
+}
+
+/// Fields this subgraph overrides, by `Type.field`
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::federation::OverrideRegistry;
+///
+/// let overrides = OverrideRegistry::new()
+///     .with_override("Product.name", "legacy")
+///     .with_progressive_override("Product.price", "legacy", 25);
+/// let sdl = overrides.apply("type Product {\n\tname: String!\n\tprice: Int!\n}\n");
+/// assert!(sdl.contains("\tprice: Int! @override(from: \"legacy\", label: \"percent(25)\")"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverrideRegistry {
+    overrides: BTreeMap<String, FieldOverride>,
+}
+
+impl OverrideRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take `field` over from the `from` subgraph entirely
+    pub fn with_override(self, field: impl Into<String>, from: impl Into<String>) -> Self {
+        self.insert(field.into(), from.into(), None)
+    }
+
+    /// Take `percent` of the traffic for `field` over from `from`
+    ///
+    /// Percentages above 100 are capped.
+    pub fn with_progressive_override(
+        self,
+        field: impl Into<String>,
+        from: impl Into<String>,
+        percent: u8,
+    ) -> Self {
+        let label = format!("percent({})", percent.min(100));
+        self.insert(field.into(), from.into(), Some(label))
+    }
+
+    /// Take `field` over from `from` when the router enables `label`
+    pub fn with_label(
+        self,
+        field: impl Into<String>,
+        from: impl Into<String>,
+        label: impl Into<String>,
+    ) -> Self {
+        self.insert(field.into(), from.into(), Some(label.into()))
+    }
+
+    fn insert(mut self, field: String, from: String, label: Option<String>) -> Self {
+        self.overrides
+            .insert(field.clone(), FieldOverride { field, from, label });
+        self
+    }
+
+    /// The override declared for `Type.field`
+    pub fn get(&self, field: &str) -> Option<&FieldOverride> {
+        self.overrides.get(field)
+    }
+
+    /// Declared overrides, ordered by field
+    pub fn overrides(&self) -> impl Iterator<Item = &FieldOverride> {
+        self.overrides.values()
+    }
+
+    /// Lowest federation version supporting the declared overrides
+    pub fn min_version(&self) -> FederationVersion {
+        if self.overrides().any(|o| o.label.is_some()) {
+            FederationVersion::V2_7
+        } else {
+            FederationVersion::V2_0
+        }
+    }
+
+    /// Add the `@override` directives to an SDL as exported by async-graphql
+    pub fn apply(&self, sdl: &str) -> String {
+        apply_directives(sdl, |target| {
+            self.get(target)
+                .map(FieldOverride::directive)
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Serve the declared overrides as JSON
+///
+/// Reads an optional `Extension<OverrideRegistry>`; without one the list
+/// is empty.
+pub async fn overrides_handler(
+    registry: Option<Extension<OverrideRegistry>>,
+) -> Json<Vec<FieldOverride>> {
+    let overrides = registry
+        .map(|Extension(registry)| registry.overrides().cloned().collect())
+        .unwrap_or_default();
+    Json(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_overrides() {
+        let overrides = OverrideRegistry::new()
+            .with_override("Product.name", "legacy")
+            .with_progressive_override("Product.price", "legacy", 150);
+        let sdl = overrides.apply(
+            "type Product {\n\tid: ID!\n\tname: String!\n\tprice(\n\t\tcurrency: String\n\t): Int!\n}\n",
+        );
+
+        assert!(sdl.contains("\tid: ID!\n"));
+        assert!(sdl.contains("\tname: String! @override(from: \"legacy\")\n"));
+        assert!(sdl.contains("\t): Int! @override(from: \"legacy\", label: \"percent(100)\")\n"));
+        assert_eq!(
+            overrides.get("Product.price").unwrap().percent(),
+            Some(100.0)
+        );
+        assert_eq!(overrides.min_version(), FederationVersion::V2_7);
+    }
+}
@@ -0,0 +1,278 @@
+//! Entity resolvers by typename
+//!
+//! An [`EntityRegistry`] holds one [`EntityResolver`] per entity type. Add
+//! it to the schema data and `#[graphql(entity)]` functions delegate to it
+//! with [`resolve_entity`]; a hand-written `_entities` resolver can instead
+//! dispatch whole representations on `__typename` with
+//! [`EntityRegistry::find_entity`] on the [`entity_registry`]. Either way, concurrent lookups of one
+//! type are batched into a single [`EntityResolver::resolve_batch`] call.
+//!
+//! Resolvers that cache entities, like a [`DataLoader`](crate::dataloaders::DataLoader),
+//! must not be shared between requests. Register those in a registry built
+//! by the [`LoaderFactory`](crate::dataloaders::LoaderFactory) for each
+//! request; [`entity_registry`] prefers it over the schema data, and
+//! rejects a schema-wide registry holding caching resolvers.
+
+use async_graphql::{Any, Context, ErrorExtensions, OutputType, ServerResult, Value};
+use async_trait::async_trait;
+use std::any::Any as StdAny;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::batch::EntityBatcher;
+use super::entity::{parse_representation, representation_typename, EntityResolver};
+use crate::dataloaders::get_loader;
+use crate::{GraphQLError, Result};
+
+/// Resolver for any entity type, producing the resolved value
+#[async_trait]
+trait ErasedResolver: Send + Sync {
+    async fn find(&self, ctx: &Context<'_>, representation: &Any) -> ServerResult<Option<Value>>;
+}
+
+#[async_trait]
+impl<R: EntityResolver + 'static> ErasedResolver for EntityBatcher<R> {
+    async fn find(&self, ctx: &Context<'_>, representation: &Any) -> ServerResult<Option<Value>> {
+        let server_error = |e: GraphQLError| e.extend().into_server_error(ctx.item.pos);
+        let key = parse_representation(representation).map_err(server_error)?;
+        let Some(entity) = self.load(key).await.map_err(server_error)? else {
+            return Ok(None);
+        };
+        // Resolved against the `... on Type` fragments of `_entities`
+        let ctx_obj = ctx.with_selection_set(&ctx.item.node.selection_set);
+        OutputType::resolve(&entity, &ctx_obj, ctx.item)
+            .await
+            .map(Some)
+    }
+}
+
+#[derive(Clone)]
+struct Registered {
+    typed: Arc<dyn StdAny + Send + Sync>,
+    erased: Arc<dyn ErasedResolver>,
+    caches: bool,
+}
+
+/// Entity resolvers keyed by the entity's typename
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::dataloaders::{DataLoader, LoaderRegistry, SharedLoaderFactory};
+/// use pleme_graphql_helpers::federation::{resolve_entity, EntityRegistry};
+///
+/// #[Object]
+/// impl Query {
+///     #[graphql(entity)]
+///     async fn find_user(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<User>> {
+///         resolve_entity::<UserResolver>(ctx, UserKey { id }).await
+///     }
+/// }
+///
+/// let registry = EntityRegistry::new().with_resolver(UserResolver(pool.clone()));
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///     .enable_federation()
+///     .data(registry)
+///     .finish();
+///
+/// // Caching resolvers go in a registry built per request, served with
+/// // `graphql_handler_with_loaders`
+/// let factory: SharedLoaderFactory = Arc::new(move |loaders: &mut LoaderRegistry| {
+///     loaders.insert(EntityRegistry::new().with_resolver(DataLoader::new(UserLoader(pool.clone()))));
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct EntityRegistry {
+    resolvers: HashMap<String, Registered>,
+}
+
+impl EntityRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `resolver` for its entity type, replacing any previous one
+    pub fn register<R: EntityResolver + 'static>(&mut self, resolver: R) {
+        let caches = resolver.caches();
+        let batcher = Arc::new(EntityBatcher::new(resolver));
+        self.resolvers.insert(
+            R::Entity::type_name().into_owned(),
+            Registered {
+                typed: batcher.clone(),
+                erased: batcher,
+                caches,
+            },
+        );
+    }
+
+    /// Builder form of [`register`](Self::register)
+    pub fn with_resolver<R: EntityResolver + 'static>(mut self, resolver: R) -> Self {
+        self.register(resolver);
+        self
+    }
+
+    /// Typenames with a registered resolver
+    pub fn typenames(&self) -> impl Iterator<Item = &str> {
+        self.resolvers.keys().map(String::as_str)
+    }
+
+    /// Whether any registered resolver caches entities
+    pub fn caches(&self) -> bool {
+        self.resolvers.values().any(|registered| registered.caches)
+    }
+
+    /// The registered resolver of type `R`
+    pub fn get<R: EntityResolver + 'static>(&self) -> Option<&R> {
+        self.batcher::<R>().map(EntityBatcher::resolver)
+    }
+
+    fn batcher<R: EntityResolver + 'static>(&self) -> Option<&EntityBatcher<R>> {
+        self.resolvers
+            .get(R::Entity::type_name().as_ref())
+            .and_then(|registered| registered.typed.downcast_ref())
+    }
+
+    /// Load an entity with the registered resolver of type `R`, batched with
+    /// concurrent lookups
+    pub async fn resolve<R: EntityResolver + 'static>(
+        &self,
+        key: R::Key,
+    ) -> Result<Option<R::Entity>> {
+        let batcher = self.batcher::<R>().ok_or_else(|| {
+            GraphQLError::FederationError(format!(
+                "No entity resolver registered for {}",
+                R::Entity::type_name()
+            ))
+        })?;
+        batcher.load(key).await
+    }
+
+    /// Resolve a representation with the resolver for its `__typename`
+    ///
+    /// Returns `None` for typenames without a resolver, so other sources can
+    /// be tried.
+    pub async fn find_entity(
+        &self,
+        ctx: &Context<'_>,
+        representation: &Any,
+    ) -> ServerResult<Option<Value>> {
+        let Some(typename) = representation_typename(representation) else {
+            let error = GraphQLError::FederationError(
+                "Entity representation has no __typename".to_string(),
+            );
+            return Err(error.extend().into_server_error(ctx.item.pos));
+        };
+        match self.resolvers.get(typename) {
+            Some(registered) => registered.erased.find(ctx, representation).await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// The request's [`EntityRegistry`]
+///
+/// Reads the registry from the request's
+/// [`LoaderRegistry`](crate::dataloaders::LoaderRegistry), falling back to
+/// the schema data. A registry from the schema data is shared by every
+/// request, so it's rejected when it holds caching resolvers.
+pub fn entity_registry<'a>(ctx: &'a Context<'_>) -> async_graphql::Result<&'a EntityRegistry> {
+    if let Some(registry) = get_loader::<EntityRegistry>(ctx) {
+        return Ok(registry);
+    }
+    let registry = ctx.data::<EntityRegistry>()?;
+    if registry.caches() {
+        return Err(GraphQLError::FederationError(
+            "Caching entity resolvers must be registered per request".to_string(),
+        )
+        .extend());
+    }
+    Ok(registry)
+}
+
+/// Load an entity with the request's [`EntityRegistry`]
+///
+/// For the body of a `#[graphql(entity)]` function.
+pub async fn resolve_entity<R: EntityResolver + 'static>(
+    ctx: &Context<'_>,
+    key: R::Key,
+) -> async_graphql::Result<Option<R::Entity>> {
+    entity_registry(ctx)?
+        .resolve::<R>(key)
+        .await
+        .map_err(|e| e.extend())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct ProductKey {
+        sku: String,
+    }
+
+    #[derive(SimpleObject)]
+    struct Product {
+        sku: String,
+        name: String,
+    }
+
+    struct ProductResolver;
+
+    #[async_trait]
+    impl EntityResolver for ProductResolver {
+        type Key = ProductKey;
+        type Entity = Product;
+
+        async fn resolve(&self, key: ProductKey) -> Result<Option<Product>> {
+            Ok(Some(Product {
+                name: format!("Product {}", key.sku),
+                sku: key.sku,
+            }))
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn version(&self) -> &str {
+            "1"
+        }
+
+        #[graphql(entity)]
+        async fn find_product(
+            &self,
+            ctx: &Context<'_>,
+            sku: String,
+        ) -> async_graphql::Result<Option<Product>> {
+            resolve_entity::<ProductResolver>(ctx, ProductKey { sku }).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entities_use_registry() {
+        let registry = EntityRegistry::new().with_resolver(ProductResolver);
+        assert_eq!(registry.typenames().collect::<Vec<_>>(), vec!["Product"]);
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .data(registry)
+            .finish();
+
+        let response = schema
+            .execute(
+                r#"{ _entities(representations: [{ __typename: "Product", sku: "abc" }]) {
+                    ... on Product { name }
+                } }"#,
+            )
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            serde_json::json!({ "_entities": [{ "name": "Product abc" }] })
+        );
+    }
+}
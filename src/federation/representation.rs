@@ -0,0 +1,253 @@
+//! Entity representations with `@requires` fields
+//!
+//! For a field declared `@requires(fields: "weight dimensions { width }")`
+//! the router sends the external fields along with the key fields of the
+//! representation. [`Representation::requires`] checks they were all sent,
+//! so a misdeclared dependency fails with the missing paths instead of a
+//! deserialization error deep in a resolver, and [`Representation::get`]
+//! reads them typed.
+
+use async_graphql::Any;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::{GraphQLError, Result};
+
+/// A field set, as in `@key`, `@requires`, and `@provides` arguments
+///
+/// Supports field names and nested selections, e.g.
+/// `id dimensions { width height }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSet(Vec<(String, FieldSet)>);
+
+impl FieldSet {
+    pub fn parse(fields: &str) -> Result<Self> {
+        let mut tokens = tokenize(fields).into_iter().peekable();
+        let set = Self::parse_selections(&mut tokens, fields)?;
+        match tokens.next() {
+            None => Ok(set),
+            Some(_) => Err(invalid_field_set(fields)),
+        }
+    }
+
+    /// Selected fields with their nested selections
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &FieldSet)> {
+        self.0.iter().map(|(name, nested)| (name.as_str(), nested))
+    }
+
+    fn parse_selections(
+        tokens: &mut std::iter::Peekable<std::vec::IntoIter<&str>>,
+        fields: &str,
+    ) -> Result<Self> {
+        let mut selections = Vec::new();
+        while let Some(&token) = tokens.peek() {
+            match token {
+                "}" => break,
+                "{" => return Err(invalid_field_set(fields)),
+                name => {
+                    tokens.next();
+                    let mut nested = FieldSet::default();
+                    if tokens.peek() == Some(&"{") {
+                        tokens.next();
+                        nested = Self::parse_selections(tokens, fields)?;
+                        if tokens.next() != Some("}") || nested.0.is_empty() {
+                            return Err(invalid_field_set(fields));
+                        }
+                    }
+                    selections.push((name.to_string(), nested));
+                }
+            }
+        }
+        Ok(Self(selections))
+    }
+
+    /// Paths of the fields missing from `object`, e.g. `dimensions.width`
+    ///
+    /// Nested selections are checked in every list item; `null` values have
+    /// nothing to check.
+    pub fn missing(&self, object: &Map<String, JsonValue>) -> Vec<String> {
+        let mut missing = Vec::new();
+        self.collect_missing(object, "", &mut missing);
+        missing
+    }
+
+    fn collect_missing(
+        &self,
+        object: &Map<String, JsonValue>,
+        prefix: &str,
+        missing: &mut Vec<String>,
+    ) {
+        for (name, nested) in &self.0 {
+            let path = format!("{prefix}{name}");
+            match object.get(name) {
+                None => missing.push(path),
+                Some(value) if !nested.0.is_empty() => {
+                    nested.collect_missing_in(value, &format!("{path}."), missing)
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn collect_missing_in(&self, value: &JsonValue, prefix: &str, missing: &mut Vec<String>) {
+        match value {
+            JsonValue::Object(object) => self.collect_missing(object, prefix, missing),
+            JsonValue::Array(items) => {
+                for item in items {
+                    self.collect_missing_in(item, prefix, missing);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Field set tokens: names and braces
+fn tokenize(fields: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in fields.char_indices() {
+        let is_name = c.is_ascii_alphanumeric() || c == '_';
+        match start {
+            Some(s) if !is_name => {
+                tokens.push(&fields[s..i]);
+                start = None;
+            }
+            None if is_name => start = Some(i),
+            _ => {}
+        }
+        if matches!(c, '{' | '}') {
+            tokens.push(&fields[i..i + 1]);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&fields[s..]);
+    }
+    tokens
+}
+
+fn invalid_field_set(fields: &str) -> GraphQLError {
+    GraphQLError::FederationError(format!("Invalid field set: {fields}"))
+}
+
+/// An `_entities` representation with typed field access
+#[derive(Debug, Clone, PartialEq)]
+pub struct Representation {
+    typename: String,
+    fields: Map<String, JsonValue>,
+}
+
+impl Representation {
+    pub fn parse(representation: &Any) -> Result<Self> {
+        let invalid = |message: &str| {
+            GraphQLError::FederationError(format!("Invalid entity representation: {message}"))
+        };
+        let json = representation
+            .0
+            .clone()
+            .into_json()
+            .map_err(|e| invalid(&e.to_string()))?;
+        let JsonValue::Object(mut fields) = json else {
+            return Err(invalid("not an object"));
+        };
+        let Some(JsonValue::String(typename)) = fields.remove("__typename") else {
+            return Err(invalid("no __typename"));
+        };
+        Ok(Self { typename, fields })
+    }
+
+    pub fn typename(&self) -> &str {
+        &self.typename
+    }
+
+    /// Check that every field of a `@requires` field set was sent
+    pub fn requires(self, fields: &str) -> Result<Self> {
+        let missing = FieldSet::parse(fields)?.missing(&self.fields);
+        if missing.is_empty() {
+            return Ok(self);
+        }
+        Err(GraphQLError::FederationError(format!(
+            "Representation of {} is missing required fields: {}",
+            self.typename,
+            missing.join(", ")
+        )))
+    }
+
+    /// Deserialize a field, failing if it wasn't sent
+    pub fn get<T: DeserializeOwned>(&self, field: &str) -> Result<T> {
+        self.get_optional(field)?.ok_or_else(|| {
+            GraphQLError::FederationError(format!(
+                "Representation of {} is missing field {field}",
+                self.typename
+            ))
+        })
+    }
+
+    /// Deserialize a field, `None` if it wasn't sent
+    pub fn get_optional<T: DeserializeOwned>(&self, field: &str) -> Result<Option<T>> {
+        self.fields
+            .get(field)
+            .map(|value| {
+                serde_json::from_value(value.clone()).map_err(|e| {
+                    GraphQLError::FederationError(format!(
+                        "Invalid field {field} in representation of {}: {e}",
+                        self.typename
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Deserialize the fields into a struct, e.g. an entity key
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_value(JsonValue::Object(self.fields.clone())).map_err(|e| {
+            GraphQLError::FederationError(format!(
+                "Invalid representation of {}: {e}",
+                self.typename
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::Value;
+
+    fn representation(json: JsonValue) -> Representation {
+        Representation::parse(&Any(Value::from_json(json).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_requires() {
+        let shipment = representation(serde_json::json!({
+            "__typename": "Product",
+            "id": "1",
+            "weight": 2.5,
+            "dimensions": [{ "width": 1 }, { "width": 2, "height": 3 }],
+        }));
+        assert_eq!(shipment.typename(), "Product");
+
+        let shipment = shipment
+            .clone()
+            .requires("weight dimensions { width }")
+            .unwrap();
+        assert_eq!(shipment.get::<f64>("weight").unwrap(), 2.5);
+        assert_eq!(shipment.get_optional::<f64>("volume").unwrap(), None);
+
+        let Err(GraphQLError::FederationError(message)) =
+            shipment.requires("weight volume dimensions { width height }")
+        else {
+            panic!("missing fields accepted");
+        };
+        assert!(message.ends_with("volume, dimensions.height"), "{message}");
+    }
+
+    #[test]
+    fn test_field_set_parsing() {
+        assert!(FieldSet::parse("id dimensions { width }").is_ok());
+        assert!(FieldSet::parse("id dimensions {").is_err());
+        assert!(FieldSet::parse("id }").is_err());
+        assert!(FieldSet::parse("dimensions { }").is_err());
+    }
+}
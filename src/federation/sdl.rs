@@ -0,0 +1,235 @@
+//! Federation v2 subgraph SDL
+//!
+//! [`federation_sdl`] exports a schema for composition: federation
+//! directives (`@key`, `@shareable`, `@external`, ...) are kept on their
+//! types and fields, and the `@link` header imports them from the chosen
+//! [`FederationVersion`]. Serve it to composition tooling with
+//! [`sdl_handler`].
+
+use async_graphql::{ObjectType, SDLExportOptions, Schema, SubscriptionType};
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse, Extension};
+
+use super::contract::ContractDirectives;
+use super::overrides::OverrideRegistry;
+
+/// Federation spec version linked by the SDL header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FederationVersion {
+    V2_0,
+    V2_1,
+    /// Adds `@interfaceObject`
+    #[default]
+    V2_3,
+    /// Adds `@authenticated` and `@requiresScopes`
+    V2_5,
+    /// Adds `@policy` and progressive `@override` labels
+    V2_7,
+}
+
+impl FederationVersion {
+    /// The version as it appears in the spec URL, e.g. `v2.3`
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::V2_0 => "v2.0",
+            Self::V2_1 => "v2.1",
+            Self::V2_3 => "v2.3",
+            Self::V2_5 => "v2.5",
+            Self::V2_7 => "v2.7",
+        }
+    }
+
+    /// Parse a version as it appears in the spec URL
+    pub fn parse(version: &str) -> Option<Self> {
+        [Self::V2_0, Self::V2_1, Self::V2_3, Self::V2_5, Self::V2_7]
+            .into_iter()
+            .find(|v| v.as_str() == version)
+    }
+
+    /// Directives the version defines
+    pub fn directives(self) -> Vec<&'static str> {
+        let mut directives = vec![
+            "@key",
+            "@shareable",
+            "@external",
+            "@provides",
+            "@requires",
+            "@tag",
+            "@inaccessible",
+            "@override",
+            "@extends",
+        ];
+        if self >= Self::V2_1 {
+            directives.push("@composeDirective");
+        }
+        if self >= Self::V2_3 {
+            directives.push("@interfaceObject");
+        }
+        if self >= Self::V2_5 {
+            directives.extend(["@authenticated", "@requiresScopes"]);
+        }
+        if self >= Self::V2_7 {
+            directives.push("@policy");
+        }
+        directives
+    }
+
+    /// `extend schema @link(...)` importing the version's directives
+    pub fn link_header(self) -> String {
+        let imports: Vec<String> = self
+            .directives()
+            .into_iter()
+            .map(|directive| format!("\"{directive}\""))
+            .collect();
+        let url = format!("https://specs.apollo.dev/federation/{}", self.as_str());
+        format!(
+            "extend schema @link(\n\turl: \"{url}\",\n\timport: [{}]\n)\n",
+            imports.join(", ")
+        )
+    }
+}
+
+/// Subgraph SDL linking [`FederationVersion::default`]
+pub fn federation_sdl<Query, Mutation, Subscription>(
+    schema: &Schema<Query, Mutation, Subscription>,
+) -> String
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    federation_sdl_with_version(schema, FederationVersion::default())
+}
+
+/// Subgraph SDL linking `version`
+pub fn federation_sdl_with_version<Query, Mutation, Subscription>(
+    schema: &Schema<Query, Mutation, Subscription>,
+    version: FederationVersion,
+) -> String
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    let sdl = schema.sdl_with_options(SDLExportOptions::new().federation());
+    format!(
+        "{}\n{}",
+        version.link_header(),
+        strip_link_header(&sdl).trim_start()
+    )
+}
+
+/// SDL without the federation `@link` async-graphql generates
+///
+/// The header is the `extend schema` block naming a federation spec URL,
+/// up to its closing parenthesis. It isn't always preceded by a blank line,
+/// so it's located by text rather than by definition.
+fn strip_link_header(sdl: &str) -> String {
+    let mut sdl = sdl.to_string();
+    let mut from = 0;
+    while let Some(offset) = sdl[from..].find("extend schema") {
+        let start = from + offset;
+        let end = sdl[start..].find(')').map_or(sdl.len(), |i| start + i + 1);
+        if sdl[start..end].contains("specs.apollo.dev/federation") {
+            sdl.replace_range(start..end, "");
+        } else {
+            from = end;
+        }
+    }
+    sdl
+}
+
+/// Serve the subgraph SDL as plain text
+///
+/// Expects the schema as an axum `Extension`, like the GraphQL handlers.
+/// The spec version is read from an optional `Extension<FederationVersion>`,
+/// and optional `Extension<ContractDirectives>` and
+/// `Extension<OverrideRegistry>` are applied to the SDL. Progressive
+/// overrides raise the version to the one supporting them.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription};
+/// use axum::{routing::get, Router};
+/// use pleme_graphql_helpers::federation::sdl_handler;
+/// # struct Query;
+/// # #[async_graphql::Object]
+/// # impl Query {
+/// #     async fn ping(&self) -> bool {
+/// #         true
+/// #     }
+/// # }
+///
+/// let app: Router = Router::new().route(
+///     "/sdl",
+///     get(sdl_handler::<Query, EmptyMutation, EmptySubscription>),
+/// );
+/// ```
+pub async fn sdl_handler<Query, Mutation, Subscription>(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    version: Option<Extension<FederationVersion>>,
+    directives: Option<Extension<ContractDirectives>>,
+    overrides: Option<Extension<OverrideRegistry>>,
+) -> impl IntoResponse
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    let mut version = version
+        .map(|Extension(version)| version)
+        .unwrap_or_default();
+    if let Some(Extension(overrides)) = &overrides {
+        version = version.max(overrides.min_version());
+    }
+    let mut sdl = federation_sdl_with_version(&schema, version);
+    if let Some(Extension(directives)) = directives {
+        sdl = directives.apply(&sdl);
+    }
+    if let Some(Extension(overrides)) = overrides {
+        sdl = overrides.apply(&sdl);
+    }
+    ([(CONTENT_TYPE, "text/plain; charset=utf-8")], sdl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, SimpleObject, ID};
+
+    #[derive(SimpleObject)]
+    #[graphql(shareable)]
+    struct Product {
+        id: ID,
+        #[graphql(external)]
+        price: i32,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(entity)]
+        async fn find_product(&self, id: ID) -> Product {
+            Product { id, price: 0 }
+        }
+    }
+
+    #[test]
+    fn test_federation_sdl() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .finish();
+
+        let sdl = federation_sdl_with_version(&schema, FederationVersion::V2_1);
+        assert!(sdl.starts_with(
+            "extend schema @link(\n\turl: \"https://specs.apollo.dev/federation/v2.1\""
+        ));
+        assert_eq!(sdl.matches("@link(").count(), 1);
+        assert!(sdl.contains("\"@composeDirective\""));
+        assert!(!sdl.contains("\"@interfaceObject\""));
+        assert!(sdl.contains("@key(fields: \"id\")"));
+        assert!(sdl.contains("@shareable"));
+        assert!(sdl.contains("price: Int! @external"));
+    }
+}
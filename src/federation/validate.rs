@@ -0,0 +1,437 @@
+//! Subgraph composition checks
+//!
+//! [`validate_subgraph`] runs a subset of the checks composition runs on a
+//! subgraph SDL, so a misdeclared subgraph can fail at startup instead of
+//! at publish time: the federation `@link`, `@key` field sets, `@external`
+//! fields being used, `@requires`/`@provides` field sets, and `@override`
+//! arguments.
+
+use async_graphql::parser::types::{
+    BaseType, ConstDirective, FieldDefinition, ServiceDocument, TypeDefinition, TypeKind,
+    TypeSystemDefinition,
+};
+use async_graphql::{Positioned, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::representation::FieldSet;
+use super::sdl::FederationVersion;
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Composition will fail
+    Error,
+    /// Composes, but likely not as intended
+    Warning,
+}
+
+/// A problem found in a subgraph SDL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable code, e.g. `INVALID_KEY_FIELDS`
+    pub code: &'static str,
+    /// Schema coordinate of the element, e.g. `Product.price`
+    pub coordinate: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(code: &'static str, coordinate: impl Into<String>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            coordinate: coordinate.into(),
+            message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.code, self.coordinate, self.message)
+    }
+}
+
+/// Check a subgraph SDL for common composition errors
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let diagnostics = validate_subgraph(&federation_sdl(&schema));
+/// if diagnostics.iter().any(Diagnostic::is_error) {
+///     panic!("invalid subgraph: {diagnostics:#?}");
+/// }
+/// ```
+pub fn validate_subgraph(sdl: &str) -> Vec<Diagnostic> {
+    let document = match async_graphql::parser::parse_schema(sdl) {
+        Ok(document) => document,
+        Err(e) => return vec![Diagnostic::error("INVALID_GRAPHQL", "", e.to_string())],
+    };
+    let mut diagnostics = Vec::new();
+    check_link(&document, &mut diagnostics);
+    Subgraph::new(&document).check(&mut diagnostics);
+    diagnostics
+}
+
+/// The federation spec linked by the schema
+fn check_link(document: &ServiceDocument, diagnostics: &mut Vec<Diagnostic>) {
+    let url = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            TypeSystemDefinition::Schema(schema) => Some(&schema.node.directives),
+            _ => None,
+        })
+        .flat_map(|directives| named(directives, "link"))
+        .filter_map(|link| string_argument(link, "url"))
+        .find(|url| url.contains("specs.apollo.dev/federation/"));
+
+    let Some(url) = url else {
+        diagnostics.push(Diagnostic::error(
+            "MISSING_FEDERATION_LINK",
+            "schema",
+            "No @link to the federation spec; the subgraph would compose as federation 1"
+                .to_string(),
+        ));
+        return;
+    };
+    let version = url.rsplit('/').next().unwrap_or_default();
+    if FederationVersion::parse(version).is_none() {
+        diagnostics.push(Diagnostic::error(
+            "UNSUPPORTED_FEDERATION_VERSION",
+            "schema",
+            format!("Federation spec {version} is not supported"),
+        ));
+    }
+}
+
+/// Directives of `directives` named `name`
+fn named<'a>(
+    directives: &'a [Positioned<ConstDirective>],
+    name: &'a str,
+) -> impl Iterator<Item = &'a ConstDirective> {
+    directives
+        .iter()
+        .map(|directive| &directive.node)
+        .filter(move |directive| directive.name.node == name)
+}
+
+fn string_argument<'a>(directive: &'a ConstDirective, name: &str) -> Option<&'a str> {
+    match directive.get_argument(name).map(|value| &value.node) {
+        Some(Value::String(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Name of a field's type without list and non-null wrappers
+fn named_type(field: &FieldDefinition) -> &str {
+    let mut base = &field.ty.node.base;
+    while let BaseType::List(inner) = base {
+        base = &inner.base;
+    }
+    match base {
+        BaseType::Named(name) => name.as_str(),
+        BaseType::List(_) => unreachable!(),
+    }
+}
+
+/// Object and interface types with their fields
+struct Subgraph<'a> {
+    types: Vec<(&'a TypeDefinition, &'a [Positioned<FieldDefinition>])>,
+    fields: HashMap<&'a str, HashMap<&'a str, &'a FieldDefinition>>,
+}
+
+impl<'a> Subgraph<'a> {
+    fn new(document: &'a ServiceDocument) -> Self {
+        let types: Vec<_> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                TypeSystemDefinition::Type(ty) => Some(&ty.node),
+                _ => None,
+            })
+            .filter_map(|ty| match &ty.kind {
+                TypeKind::Object(object) => Some((ty, object.fields.as_slice())),
+                TypeKind::Interface(interface) => Some((ty, interface.fields.as_slice())),
+                _ => None,
+            })
+            .collect();
+        let mut fields: HashMap<&str, HashMap<&str, &FieldDefinition>> = HashMap::new();
+        for (ty, type_fields) in &types {
+            fields.entry(ty.name.node.as_str()).or_default().extend(
+                type_fields
+                    .iter()
+                    .map(|field| (field.node.name.node.as_str(), &field.node)),
+            );
+        }
+        Self { types, fields }
+    }
+
+    /// Paths of `set` missing from `type_name`
+    fn missing(&self, type_name: &str, set: &FieldSet, prefix: &str) -> Vec<String> {
+        let mut missing = Vec::new();
+        for (name, nested) in set.fields() {
+            let path = format!("{prefix}{name}");
+            match self
+                .fields
+                .get(type_name)
+                .and_then(|fields| fields.get(name))
+            {
+                None => missing.push(path),
+                Some(field) if nested.fields().next().is_some() => {
+                    missing.extend(self.missing(named_type(field), nested, &format!("{path}.")))
+                }
+                Some(_) => {}
+            }
+        }
+        missing
+    }
+
+    /// Parse a field set argument, reporting invalid or missing fields
+    fn field_set(
+        &self,
+        directive: &ConstDirective,
+        type_name: &str,
+        coordinate: &str,
+        code: &'static str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<FieldSet> {
+        let name = &directive.name.node;
+        let Some(fields) = string_argument(directive, "fields") else {
+            diagnostics.push(Diagnostic::error(
+                code,
+                coordinate,
+                format!("@{name} has no fields argument"),
+            ));
+            return None;
+        };
+        let Ok(set) = FieldSet::parse(fields) else {
+            diagnostics.push(Diagnostic::error(
+                code,
+                coordinate,
+                format!("@{name}(fields: \"{fields}\") is not a valid field set"),
+            ));
+            return None;
+        };
+        let missing = self.missing(type_name, &set, "");
+        if !missing.is_empty() {
+            diagnostics.push(Diagnostic::error(
+                code,
+                coordinate,
+                format!(
+                    "@{name}(fields: \"{fields}\") selects fields {type_name} doesn't have: {}",
+                    missing.join(", ")
+                ),
+            ));
+        }
+        Some(set)
+    }
+
+    fn check(&self, diagnostics: &mut Vec<Diagnostic>) {
+        // (type, field) pairs a key, @requires, or @provides selects
+        let mut used: HashSet<(&str, &str)> = HashSet::new();
+
+        for (ty, fields) in &self.types {
+            let type_name = ty.name.node.as_str();
+            let keys: Vec<_> = named(&ty.directives, "key").collect();
+            for key in &keys {
+                if let Some(set) =
+                    self.field_set(key, type_name, type_name, "INVALID_KEY_FIELDS", diagnostics)
+                {
+                    for (name, _) in set.fields() {
+                        if let Some((field, _)) = self.fields[type_name].get_key_value(name) {
+                            used.insert((type_name, *field));
+                        }
+                    }
+                }
+            }
+            let resolvable = |key: &&ConstDirective| {
+                !matches!(
+                    key.get_argument("resolvable").map(|v| &v.node),
+                    Some(Value::Boolean(false))
+                )
+            };
+            let interface_object = named(&ty.directives, "interfaceObject").next().is_some();
+            if !keys.is_empty() && !keys.iter().any(resolvable) && !interface_object {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "NO_RESOLVABLE_KEY",
+                    coordinate: type_name.to_string(),
+                    message: format!(
+                        "Every @key of {type_name} has resolvable: false; this subgraph can't resolve it"
+                    ),
+                });
+            }
+
+            for field in fields.iter().map(|field| &field.node) {
+                let coordinate = format!("{type_name}.{}", field.name.node);
+                let external = named(&field.directives, "external").next().is_some();
+
+                for directive in named(&field.directives, "override") {
+                    match string_argument(directive, "from") {
+                        Some(from) if !from.is_empty() => {
+                            if external {
+                                diagnostics.push(Diagnostic::error(
+                                    "OVERRIDE_COLLISION_WITH_EXTERNAL",
+                                    &coordinate,
+                                    "@override on an @external field".to_string(),
+                                ));
+                            }
+                        }
+                        _ => diagnostics.push(Diagnostic::error(
+                            "INVALID_OVERRIDE",
+                            &coordinate,
+                            "@override needs the name of the subgraph it takes the field from"
+                                .to_string(),
+                        )),
+                    }
+                }
+
+                for requires in named(&field.directives, "requires") {
+                    let Some(set) = self.field_set(
+                        requires,
+                        type_name,
+                        &coordinate,
+                        "REQUIRES_INVALID_FIELDS",
+                        diagnostics,
+                    ) else {
+                        continue;
+                    };
+                    for (name, _) in set.fields() {
+                        let Some((name, required)) = self.fields[type_name].get_key_value(name)
+                        else {
+                            continue;
+                        };
+                        used.insert((type_name, *name));
+                        if named(&required.directives, "external").next().is_none() {
+                            diagnostics.push(Diagnostic::error(
+                                "REQUIRES_FIELDS_MISSING_EXTERNAL",
+                                &coordinate,
+                                format!(
+                                    "@requires selects {type_name}.{name}, which isn't @external"
+                                ),
+                            ));
+                        }
+                    }
+                }
+
+                for provides in named(&field.directives, "provides") {
+                    let target = named_type(field);
+                    let Some(set) = self.field_set(
+                        provides,
+                        target,
+                        &coordinate,
+                        "PROVIDES_INVALID_FIELDS",
+                        diagnostics,
+                    ) else {
+                        continue;
+                    };
+                    for (name, _) in set.fields() {
+                        if let Some((name, _)) = self
+                            .fields
+                            .get(target)
+                            .and_then(|fields| fields.get_key_value(name))
+                        {
+                            used.insert((target, *name));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (ty, fields) in &self.types {
+            let type_name = ty.name.node.as_str();
+            for field in fields.iter().map(|field| &field.node) {
+                let name = field.name.node.as_str();
+                if named(&field.directives, "external").next().is_some()
+                    && !used.contains(&(type_name, name))
+                {
+                    diagnostics.push(Diagnostic::error(
+                        "EXTERNAL_UNUSED",
+                        format!("{type_name}.{name}"),
+                        "@external field isn't used by a @key, @requires, or @provides".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::federation::federation_sdl;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject, ID};
+
+    const LINK: &str =
+        "extend schema @link(url: \"https://specs.apollo.dev/federation/v2.3\", import: [\"@key\"])\n";
+
+    fn codes(sdl: &str) -> Vec<&'static str> {
+        validate_subgraph(sdl).into_iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let sdl = format!(
+            "{LINK}
+            type Product @key(fields: \"id\") {{
+                id: ID!
+                weight: Int @external
+                shipping: Int @requires(fields: \"weight size\")
+                size: Int
+                stock: Int @override(from: \"\")
+                label: String @external
+            }}
+            type Review @key(fields: \"sku\") {{
+                id: ID!
+            }}"
+        );
+        assert_eq!(
+            codes(&sdl),
+            vec![
+                "REQUIRES_FIELDS_MISSING_EXTERNAL",
+                "INVALID_OVERRIDE",
+                "INVALID_KEY_FIELDS",
+                "EXTERNAL_UNUSED",
+            ]
+        );
+
+        assert_eq!(
+            codes("type Query { a: Int }"),
+            vec!["MISSING_FEDERATION_LINK"]
+        );
+        assert_eq!(
+            codes(&LINK.replace("v2.3", "v9.0")),
+            vec!["UNSUPPORTED_FEDERATION_VERSION"]
+        );
+    }
+
+    #[derive(SimpleObject)]
+    struct Product {
+        id: ID,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(entity)]
+        async fn find_product(&self, id: ID) -> Product {
+            Product { id }
+        }
+    }
+
+    #[test]
+    fn test_exported_sdl_is_valid() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .finish();
+        assert_eq!(validate_subgraph(&federation_sdl(&schema)), vec![]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Interactive GraphQL IDE routes
+//!
+//! Serves GraphiQL or Apollo Sandbox for a GraphQL endpoint. IDEs are
+//! enabled by default in debug builds only; disabled routes return 404.
+
+use async_graphql::http::GraphiQLSource;
+use axum::{
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::{get, MethodRouter},
+};
+
+/// Apollo Sandbox embed script
+const SANDBOX_SCRIPT: &str =
+    "https://embeddable-sandbox.cdn.apollographql.com/_latest/embeddable-sandbox.umd.production.min.js";
+
+/// IDE route configuration
+#[derive(Debug, Clone)]
+pub struct IdeConfig {
+    /// GraphQL endpoint the IDE sends requests to
+    pub endpoint: String,
+    /// WebSocket endpoint for subscriptions
+    pub subscription_endpoint: Option<String>,
+    /// Serve the IDE (defaults to `true` in debug builds only)
+    pub enabled: bool,
+}
+
+impl IdeConfig {
+    /// IDE for `endpoint`, enabled in debug builds only
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            subscription_endpoint: None,
+            enabled: cfg!(debug_assertions),
+        }
+    }
+
+    /// Set the subscription endpoint
+    pub fn with_subscription_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.subscription_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Explicitly enable or disable the IDE
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+/// GraphiQL page HTML
+pub fn graphiql_html(config: &IdeConfig) -> String {
+    let source = GraphiQLSource::build().endpoint(&config.endpoint);
+    match &config.subscription_endpoint {
+        Some(ws) => source.subscription_endpoint(ws).finish(),
+        None => source.finish(),
+    }
+}
+
+/// Apollo Sandbox page HTML
+pub fn sandbox_html(config: &IdeConfig) -> String {
+    // JSON-encode and escape `<` so the endpoint can't close the script tag
+    let endpoint = serde_json::to_string(&config.endpoint)
+        .unwrap_or_default()
+        .replace('<', "\\u003c");
+    format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Apollo Sandbox</title>
+</head>
+<body style="margin: 0; overflow: hidden;">
+  <div id="sandbox" style="width: 100vw; height: 100vh;"></div>
+  <script src="{SANDBOX_SCRIPT}"></script>
+  <script>
+    new window.EmbeddedSandbox({{
+      target: "#sandbox",
+      initialEndpoint: new URL({endpoint}, window.location.href).href,
+    }});
+  </script>
+</body>
+</html>"##
+    )
+}
+
+/// GET route serving GraphiQL for `endpoint` (debug builds only)
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use axum::Router;
+/// use pleme_graphql_helpers::http::graphiql_handler;
+///
+/// let app: Router = Router::new().route("/graphiql", graphiql_handler("/graphql"));
+/// ```
+pub fn graphiql_handler<S>(endpoint: &str) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    graphiql_handler_with(IdeConfig::new(endpoint))
+}
+
+/// GET route serving GraphiQL with explicit configuration
+pub fn graphiql_handler_with<S>(config: IdeConfig) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    ide_route(config.enabled.then(|| graphiql_html(&config)))
+}
+
+/// GET route serving Apollo Sandbox for `endpoint` (debug builds only)
+pub fn sandbox_handler<S>(endpoint: &str) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    sandbox_handler_with(IdeConfig::new(endpoint))
+}
+
+/// GET route serving Apollo Sandbox with explicit configuration
+pub fn sandbox_handler_with<S>(config: IdeConfig) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    ide_route(config.enabled.then(|| sandbox_html(&config)))
+}
+
+/// Serve `html`, or 404 when the IDE is disabled
+fn ide_route<S>(html: Option<String>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    get(move || {
+        let html = html.clone();
+        async move { ide_response(html) }
+    })
+}
+
+/// The IDE page, or a 404 when disabled
+fn ide_response(html: Option<String>) -> Response {
+    match html {
+        Some(html) => Html(html).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphiql_html_endpoint() {
+        let config = IdeConfig::new("/graphql").with_subscription_endpoint("/ws");
+        let html = graphiql_html(&config);
+        assert!(html.contains("/graphql"));
+        assert!(html.contains("/ws"));
+    }
+
+    #[test]
+    fn test_sandbox_html_escapes_endpoint() {
+        let html = sandbox_html(&IdeConfig::new(r#"/graphql"</script>"#));
+        assert!(html.contains(r#"new URL("/graphql\"\u003c/script>""#));
+    }
+
+    #[test]
+    fn test_disabled_ide_is_not_found() {
+        let response = ide_response(None);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,258 @@
+//! AWS Lambda adapter for API Gateway proxy events
+//!
+//! Runs a subgraph serverlessly: [`GraphQLLambda`] converts an API Gateway
+//! proxy event into a GraphQL request, authenticates it like the axum
+//! handlers, and maps the result back to a proxy response. Only `POST`
+//! JSON requests are accepted.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use lambda_runtime::{service_fn, LambdaEvent};
+//! use pleme_graphql_helpers::lambda::GraphQLLambda;
+//! use std::sync::OnceLock;
+//!
+//! static HANDLER: OnceLock<GraphQLLambda<Query, Mutation, EmptySubscription>> = OnceLock::new();
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), lambda_runtime::Error> {
+//!     let handler = HANDLER.get_or_init(|| GraphQLLambda::new(build_schema()));
+//!     lambda_runtime::run(service_fn(|event: LambdaEvent<ApiGatewayProxyRequest>| async {
+//!         Ok::<_, lambda_runtime::Error>(handler.handle(event.payload).await)
+//!     }))
+//!     .await
+//! }
+//! ```
+
+use async_graphql::{BatchRequest, ObjectType, Schema, SubscriptionType};
+use aws_lambda_events::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse};
+use aws_lambda_events::encodings::Body;
+use axum::http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, Method, StatusCode};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+
+#[cfg(feature = "jwks")]
+use crate::auth::JwtVerifier;
+use crate::auth::{
+    authenticate, execute_batch, request_id, AnonymousAccess, AuthHeaderConfig, AuthSettings,
+    BatchLimits, ClientInfo, ExecutionOptions, ExpiredTokens, GatewayVerifier,
+    SharedApiKeyResolver, SharedAuditSink, TenantEnforcement,
+};
+use crate::extensions::cache::insert_cache_header;
+use crate::extensions::SharedOperationRegistry;
+
+/// GraphQL handler for API Gateway proxy events
+pub struct GraphQLLambda<Query, Mutation, Subscription> {
+    schema: Schema<Query, Mutation, Subscription>,
+    settings: AuthSettings,
+    options: ExecutionOptions,
+}
+
+impl<Query, Mutation, Subscription> GraphQLLambda<Query, Mutation, Subscription>
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    /// Handler for `schema` with default auth settings
+    pub fn new(schema: Schema<Query, Mutation, Subscription>) -> Self {
+        Self {
+            schema,
+            settings: AuthSettings::default(),
+            options: ExecutionOptions::default(),
+        }
+    }
+
+    /// Use custom identity header names
+    pub fn with_header_config(mut self, config: AuthHeaderConfig) -> Self {
+        self.settings.headers = config;
+        self
+    }
+
+    /// Resolve `x-api-key` requests with `resolver`
+    pub fn with_api_keys(mut self, resolver: SharedApiKeyResolver) -> Self {
+        self.settings.api_keys = Some(resolver);
+        self
+    }
+
+    /// Require gateway-signed identity headers
+    pub fn with_gateway(mut self, verifier: GatewayVerifier) -> Self {
+        self.settings.gateway = Some(verifier);
+        self
+    }
+
+    /// Handle expired bearer tokens per `policy`
+    pub fn with_expired_tokens(mut self, policy: ExpiredTokens) -> Self {
+        self.settings.expired_tokens = policy;
+        self
+    }
+
+    /// Identify callers by a bearer token verified with `verifier`
+    #[cfg(feature = "jwks")]
+    pub fn with_jwt_verifier(mut self, verifier: JwtVerifier) -> Self {
+        self.settings.jwt = Some(std::sync::Arc::new(verifier));
+        self
+    }
+
+    /// Record mutations to `sink`
+    pub fn with_audit_sink(mut self, sink: SharedAuditSink) -> Self {
+        self.options.audit = Some(sink);
+        self
+    }
+
+    /// Require authentication except for allowlisted operations
+    pub fn with_anonymous_access(mut self, access: AnonymousAccess) -> Self {
+        self.options.anonymous = Some(access);
+        self
+    }
+
+    /// Require a company context except for allowlisted operations
+    pub fn with_tenant_enforcement(mut self, tenant: TenantEnforcement) -> Self {
+        self.options.tenant = Some(tenant);
+        self
+    }
+
+    /// Resolve persisted query hashes from `registry` before policy checks
+    pub fn with_operation_registry(mut self, registry: SharedOperationRegistry) -> Self {
+        self.options.operations = Some(registry);
+        self
+    }
+
+    /// Limit the size and concurrency of batched requests
+    pub fn with_batch_limits(mut self, limits: BatchLimits) -> Self {
+        self.options.batch = limits;
+        self
+    }
+
+    /// Execute the GraphQL request in a proxy event
+    pub async fn handle(&self, event: ApiGatewayProxyRequest) -> ApiGatewayProxyResponse {
+        if event.http_method != Method::POST {
+            return text_response(StatusCode::METHOD_NOT_ALLOWED, "Only POST is supported");
+        }
+
+        let path = event.path.as_deref().unwrap_or("/");
+        let auth = match authenticate(
+            event.http_method.as_str(),
+            path,
+            &event.headers,
+            &self.settings,
+        )
+        .await
+        {
+            Ok(auth) => auth,
+            Err(rejection) => {
+                return json_response(rejection.status, HeaderMap::new(), &rejection.to_json())
+            }
+        };
+
+        let batch = match parse_body(&event) {
+            Ok(batch) => batch,
+            Err(message) => return text_response(StatusCode::BAD_REQUEST, &message),
+        };
+
+        let mut client = ClientInfo::from_headers(&event.headers);
+        client.ip = event
+            .request_context
+            .identity
+            .source_ip
+            .as_deref()
+            .and_then(|ip| ip.parse().ok());
+
+        let mut headers = request_id::response_headers(&auth);
+        let response = match execute_batch(&self.schema, batch, auth, &self.options, |request| {
+            request.data(client.clone())
+        })
+        .await
+        {
+            Ok(response) => response,
+            Err(rejection) => {
+                return json_response(rejection.status, headers, &rejection.to_json())
+            }
+        };
+        insert_cache_header(&mut headers, response.is_ok(), response.cache_control());
+
+        json_response(StatusCode::OK, headers, &response)
+    }
+}
+
+/// Decode the (possibly base64-encoded) JSON body
+fn parse_body(event: &ApiGatewayProxyRequest) -> Result<BatchRequest, String> {
+    let body = event.body.as_deref().unwrap_or_default();
+    let bytes = if event.is_base64_encoded {
+        BASE64.decode(body).map_err(|e| e.to_string())?
+    } else {
+        body.as_bytes().to_vec()
+    };
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+fn json_response(
+    status: StatusCode,
+    mut headers: HeaderMap,
+    body: &impl Serialize,
+) -> ApiGatewayProxyResponse {
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    ApiGatewayProxyResponse {
+        status_code: status.as_u16().into(),
+        headers,
+        body: serde_json::to_string(body).ok().map(Body::Text),
+        ..Default::default()
+    }
+}
+
+fn text_response(status: StatusCode, message: &str) -> ApiGatewayProxyResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    ApiGatewayProxyResponse {
+        status_code: status.as_u16().into(),
+        headers,
+        body: Some(Body::Text(message.to_string())),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::get_user_id;
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object};
+    use uuid::Uuid;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn user_id(&self, ctx: &Context<'_>) -> Option<String> {
+            get_user_id(ctx).map(|id| id.to_string())
+        }
+    }
+
+    fn event(body: &str) -> ApiGatewayProxyRequest {
+        ApiGatewayProxyRequest {
+            http_method: Method::POST,
+            body: Some(body.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_proxy_event() {
+        let handler = GraphQLLambda::new(Schema::new(Query, EmptyMutation, EmptySubscription));
+        let user_id = Uuid::new_v4();
+
+        let mut request = event(r#"{"query":"{ userId }"}"#);
+        request
+            .headers
+            .insert("x-user-id", user_id.to_string().parse().unwrap());
+        let response = handler.handle(request).await;
+        assert_eq!(response.status_code, 200);
+        let Some(Body::Text(body)) = response.body else {
+            panic!("expected text body");
+        };
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["data"]["userId"], user_id.to_string());
+
+        let response = handler.handle(event("not json")).await;
+        assert_eq!(response.status_code, 400);
+    }
+}
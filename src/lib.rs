@@ -9,11 +9,19 @@
 //! - **Common Types** - Reusable GraphQL types
 //! - **DataLoader** - Batch loading for N+1 prevention
 //! - **Auth Middleware** - JWT and context extraction for GraphQL handlers
+//! - **GraphQL IDE** - GraphiQL and Apollo Sandbox routes
+//! - **Schema Defaults** - Federation, limits, and error masking in one call
+//! - **Error Codes** - `extensions.code` on every client-facing error
+//! - **Localized Errors** - pt-BR / en messages negotiated from `Accept-Language`
+//! - **actix-web** - Handler and GraphiQL route for actix services (`actix` feature)
+//! - **AWS Lambda** - API Gateway proxy event adapter (`lambda` feature)
 //!
 //! ## Usage
 //!
 //! ```rust
 //! use pleme_graphql_helpers::pagination::{Connection, Edge};
+//! # let items = vec!["a".to_string(), "b".to_string()];
+//! # let (has_next_page, has_previous_page) = (true, false);
 //!
 //! // Create paginated response
 //! let connection = Connection::new(items, has_next_page, has_previous_page);
@@ -24,17 +32,40 @@ pub mod federation;
 pub mod types;
 pub mod dataloaders;
 pub mod auth;
+pub mod http;
+pub mod extensions;
+pub mod schema;
+pub mod testing;
+pub mod error;
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "lambda")]
+pub mod lambda;
 
-pub use pagination::{Connection, Edge, PageInfo, CursorCodec, PaginationInput};
-pub use federation::EntityResolver;
-pub use types::{DateTime, Upload};
-pub use dataloaders::{BatchLoader, DataLoader};
-pub use auth::{graphql_handler, extract_user_id, extract_company_id, extract_authz};
+pub use pagination::{
+    Connection, Edge, PageInfo, CursorCodec, PaginationInput, PaginationConfig,
+    KeysetPaginator, SortColumn, SortDirection, SortKey, CursorPayload,
+    CursorConfig, SignedCursorCodec, EncryptedCursorCodec,
+    OffsetPage, OffsetPaginationInput, CountLoader,
+};
+pub use federation::{parse_representation, EntityRegistry, EntityResolver};
+pub use types::{DateTime, Json, JsonLimits, Upload};
+pub use schema::{build_schema, SchemaBuilderExt, SchemaConfig};
+pub use error::{
+    ErrorCode, ErrorExt, Locale, MessageCatalog, MutationResult, UserError, ValidationErrors,
+};
+pub use dataloaders::{
+    BatchLoader, DataLoader, DataLoaderBuilder, LoadError, LoaderFactory, LoaderRegistry,
+};
+pub use auth::{
+    graphql_handler, graphql_handler_with_loaders, graphql_get_handler, graphql_ws_handler, GraphQLBatchRequest, GraphQLRequest, UploadConfig, extract_user_id, extract_company_id,
+    extract_authz, AuthContext, AuthHeaderConfig, Authenticated,
+};
 
 use thiserror::Error;
 
 /// GraphQL errors
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum GraphQLError {
     #[error("Invalid cursor: {0}")]
     InvalidCursor(String),
@@ -42,9 +73,35 @@ pub enum GraphQLError {
     #[error("Pagination error: {0}")]
     PaginationError(String),
 
+    #[error("Invalid ID: {0}")]
+    InvalidId(String),
+
     #[error("Federation error: {0}")]
     FederationError(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Upstream error: {0}")]
+    UpstreamError(String),
 }
 
 /// Result type for GraphQL operations
 pub type Result<T> = std::result::Result<T, GraphQLError>;
+
+/// Dependencies referenced by derive macro output
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    #[cfg(feature = "sqlx")]
+    pub use sqlx;
+}
@@ -1,8 +1,36 @@
 //! Relay-style cursor pagination
 
-use async_graphql::{Object, SimpleObject, InputObject};
+use async_graphql::{Context, Object, SimpleObject, InputObject};
 use serde::{Serialize, Deserialize};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use futures::{Stream, StreamExt, TryStreamExt};
+
+pub mod aggregates;
+pub mod count;
+pub mod custom_edge;
+pub mod encrypted;
+pub mod interop;
+pub mod keyset;
+#[cfg(feature = "mongodb")]
+pub mod mongodb;
+pub mod offset;
+pub mod relay;
+#[cfg(feature = "sea-orm")]
+pub mod sea_orm;
+pub mod signed;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+pub mod versioned;
+
+pub use aggregates::{Aggregate, ConnectionAggregates};
+pub use count::CountLoader;
+pub use custom_edge::{ConnectionOf, EdgeLike};
+pub use encrypted::EncryptedCursorCodec;
+pub use interop::StructuredCursor;
+pub use keyset::{CursorPayload, KeysetPaginator, KeysetQuery, PageDecoder, SortColumn, SortDirection, SortKey};
+pub use offset::{OffsetPage, OffsetPaginationInput};
+pub use signed::{CursorConfig, SignedCursorCodec};
+pub use versioned::{CursorEnvelope, CursorMigrator};
 
 /// Page information
 #[derive(SimpleObject, Debug, Clone)]
@@ -36,6 +64,12 @@ impl<T: async_graphql::OutputType> Edge<T> {
 pub struct Connection<T> {
     pub edges: Vec<Edge<T>>,
     pub page_info: PageInfo,
+
+    /// Lazily computed total count, exposed as `totalCount`
+    pub total_count: Option<CountLoader>,
+
+    /// Aggregate resolvers, exposed as `aggregates`
+    pub aggregates: ConnectionAggregates,
 }
 
 #[Object]
@@ -47,6 +81,19 @@ impl<T: async_graphql::OutputType> Connection<T> {
     async fn page_info(&self) -> &PageInfo {
         &self.page_info
     }
+
+    /// Total number of items across all pages, if the service provides it
+    async fn total_count(&self) -> async_graphql::Result<Option<i64>> {
+        match &self.total_count {
+            Some(count) => count.load().await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Aggregates over the full result set, optionally filtered by name
+    async fn aggregates(&self, names: Option<Vec<String>>) -> async_graphql::Result<Vec<Aggregate>> {
+        self.aggregates.resolve(names.as_deref()).await
+    }
 }
 
 impl<T> Connection<T> {
@@ -64,6 +111,52 @@ impl<T> Connection<T> {
             })
             .collect();
 
+        Self::from_edges(edges, has_next, has_previous)
+    }
+
+    /// Create connection with cursors derived from item fields
+    ///
+    /// Unlike [`Connection::new`], cursors stay stable when data changes
+    /// between pages. Cursor values are encoded with
+    /// [`CursorCodec::encode_structured`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pleme_graphql_helpers::pagination::Connection;
+    ///
+    /// struct Post { id: u32, created_at: String }
+    ///
+    /// let posts = vec![Post { id: 1, created_at: "2024-01-01T00:00:00Z".into() }];
+    /// let conn = Connection::with_cursor_fn(posts, false, false, |p| {
+    ///     (p.created_at.clone(), p.id)
+    /// })
+    /// .unwrap();
+    /// assert_eq!(conn.edges.len(), 1);
+    /// ```
+    pub fn with_cursor_fn<C, F>(
+        items: Vec<T>,
+        has_next: bool,
+        has_previous: bool,
+        cursor_fn: F,
+    ) -> crate::Result<Self>
+    where
+        C: Serialize,
+        F: Fn(&T) -> C,
+    {
+        let edges = items
+            .into_iter()
+            .map(|node| {
+                let cursor = CursorCodec::encode_structured(&cursor_fn(&node))?;
+                Ok(Edge { cursor, node })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Self::from_edges(edges, has_next, has_previous))
+    }
+
+    /// Create connection from pre-built edges
+    pub fn from_edges(edges: Vec<Edge<T>>, has_next: bool, has_previous: bool) -> Self {
         let start_cursor = edges.first().map(|e| e.cursor.clone());
         let end_cursor = edges.last().map(|e| e.cursor.clone());
 
@@ -75,9 +168,148 @@ impl<T> Connection<T> {
                 start_cursor,
                 end_cursor,
             },
+            total_count: None,
+            aggregates: ConnectionAggregates::default(),
         }
     }
 
+    /// Create connection from a `limit + 1` fetch
+    ///
+    /// Query `pagination.limit() + 1` rows; if the extra row comes back it is
+    /// dropped and signals another page in the direction of travel. For
+    /// backward pagination pass rows in fetched (reversed) order - they are
+    /// flipped back into natural order here.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pleme_graphql_helpers::pagination::{Connection, PaginationInput};
+    ///
+    /// let input = PaginationInput { first: Some(2), ..Default::default() };
+    /// let rows = vec![1, 2, 3]; // fetched with LIMIT 3
+    /// let conn = Connection::from_overfetched(rows, &input, |n| n.to_string());
+    /// assert_eq!(conn.edges.len(), 2);
+    /// assert!(conn.page_info.has_next_page);
+    /// ```
+    pub fn from_overfetched<F>(items: Vec<T>, pagination: &PaginationInput, cursor_fn: F) -> Self
+    where
+        F: Fn(&T) -> String,
+    {
+        Self::from_overfetched_with(items, pagination, &PaginationConfig::default(), cursor_fn)
+    }
+
+    /// Create connection from a `limit + 1` fetch using custom page size limits
+    ///
+    /// Rows must have been fetched with `pagination.limit_with(config) + 1`.
+    pub fn from_overfetched_with<F>(
+        mut items: Vec<T>,
+        pagination: &PaginationInput,
+        config: &PaginationConfig,
+        cursor_fn: F,
+    ) -> Self
+    where
+        F: Fn(&T) -> String,
+    {
+        let limit = pagination.limit_with(config).max(0) as usize;
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+
+        let backward = pagination.paginates_backward();
+        if backward {
+            items.reverse();
+        }
+
+        let edges: Vec<Edge<T>> = items
+            .into_iter()
+            .map(|node| Edge {
+                cursor: cursor_fn(&node),
+                node,
+            })
+            .collect();
+
+        if backward {
+            Self::from_edges(edges, pagination.before.is_some(), has_more)
+        } else {
+            Self::from_edges(edges, has_more, pagination.after.is_some())
+        }
+    }
+
+    /// Create connection from a stream, consuming at most `limit + 1` items
+    ///
+    /// The stream is dropped as soon as enough items have been read, so
+    /// large exports don't have to be collected up front. Item order follows
+    /// the same rules as [`Connection::from_overfetched`].
+    pub async fn from_stream<S, F>(stream: S, pagination: &PaginationInput, cursor_fn: F) -> Self
+    where
+        S: Stream<Item = T>,
+        F: Fn(&T) -> String,
+    {
+        let limit = pagination.limit().max(0) as usize;
+        let items: Vec<T> = stream.take(limit + 1).collect().await;
+        Self::from_overfetched(items, pagination, cursor_fn)
+    }
+
+    /// Create connection from a fallible stream, stopping at the first error
+    pub async fn try_from_stream<S, E, F>(
+        stream: S,
+        pagination: &PaginationInput,
+        cursor_fn: F,
+    ) -> std::result::Result<Self, E>
+    where
+        S: Stream<Item = std::result::Result<T, E>>,
+        F: Fn(&T) -> String,
+    {
+        let limit = pagination.limit().max(0) as usize;
+        let items: Vec<T> = stream.take(limit + 1).try_collect().await?;
+        Ok(Self::from_overfetched(items, pagination, cursor_fn))
+    }
+
+    /// Transform node type, preserving cursors and page info
+    pub fn map<U, F>(self, f: F) -> Connection<U>
+    where
+        F: Fn(T) -> U,
+    {
+        Connection {
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| Edge {
+                    cursor: e.cursor,
+                    node: f(e.node),
+                })
+                .collect(),
+            page_info: self.page_info,
+            total_count: self.total_count,
+            aggregates: self.aggregates,
+        }
+    }
+
+    /// Fallibly transform node type, preserving cursors and page info
+    ///
+    /// Stops at the first error.
+    pub fn try_map<U, E, F>(self, f: F) -> std::result::Result<Connection<U>, E>
+    where
+        F: Fn(T) -> std::result::Result<U, E>,
+    {
+        let edges = self
+            .edges
+            .into_iter()
+            .map(|e| {
+                Ok(Edge {
+                    cursor: e.cursor,
+                    node: f(e.node)?,
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, E>>()?;
+
+        Ok(Connection {
+            edges,
+            page_info: self.page_info,
+            total_count: self.total_count,
+            aggregates: self.aggregates,
+        })
+    }
+
     /// Create empty connection
     pub fn empty() -> Self {
         Self {
@@ -88,10 +320,28 @@ impl<T> Connection<T> {
                 start_cursor: None,
                 end_cursor: None,
             },
+            total_count: None,
+            aggregates: ConnectionAggregates::default(),
         }
     }
+
+    /// Attach a lazily computed total count
+    pub fn with_total_count(mut self, count: CountLoader) -> Self {
+        self.total_count = Some(count);
+        self
+    }
+
+    /// Attach aggregate resolvers
+    pub fn with_aggregates(mut self, aggregates: ConnectionAggregates) -> Self {
+        self.aggregates = aggregates;
+        self
+    }
 }
 
+/// Leading byte of compact cursors (reserved, never used by MessagePack)
+#[cfg(feature = "compact-cursors")]
+const COMPACT_CURSOR_MARKER: u8 = 0xc1;
+
 /// Cursor encoding/decoding
 pub struct CursorCodec;
 
@@ -127,6 +377,86 @@ impl CursorCodec {
         serde_json::from_str(&json)
             .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))
     }
+
+    /// Encode structured cursor as MessagePack
+    ///
+    /// Noticeably shorter than [`CursorCodec::encode_structured`] for
+    /// multi-field cursors. The payload is prefixed with a marker byte that
+    /// MessagePack never emits, so [`CursorCodec::decode_compact`] can tell
+    /// it apart from legacy JSON cursors.
+    #[cfg(feature = "compact-cursors")]
+    pub fn encode_compact<T: Serialize>(value: &T) -> crate::Result<String> {
+        let mut bytes = vec![COMPACT_CURSOR_MARKER];
+        rmp_serde::encode::write(&mut bytes, value)
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))?;
+        Ok(BASE64.encode(bytes))
+    }
+
+    /// Decode compact cursor, falling back to legacy JSON cursors
+    #[cfg(feature = "compact-cursors")]
+    pub fn decode_compact<T: for<'de> Deserialize<'de>>(cursor: &str) -> crate::Result<T> {
+        let bytes = BASE64
+            .decode(cursor.as_bytes())
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))?;
+
+        match bytes.split_first() {
+            Some((&COMPACT_CURSOR_MARKER, payload)) => rmp_serde::from_slice(payload)
+                .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string())),
+            _ => serde_json::from_slice(&bytes)
+                .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string())),
+        }
+    }
+}
+
+/// Page size limits
+///
+/// Pass per call via [`PaginationInput::validate_with`] or register in the
+/// GraphQL context with `Schema::build(..).data(config)` and read it back
+/// with [`PaginationConfig::from_context`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaginationConfig {
+    /// Page size when neither `first` nor `last` is given
+    pub default_page_size: i32,
+
+    /// Maximum allowed `first`/`last`
+    pub max_page_size: i32,
+
+    /// Reject mixed-direction arguments (`first` + `before`, `last` + `after`)
+    pub strict: bool,
+}
+
+impl PaginationConfig {
+    /// Create config with custom page sizes
+    pub fn new(default_page_size: i32, max_page_size: i32) -> Self {
+        Self {
+            default_page_size,
+            max_page_size,
+            strict: false,
+        }
+    }
+
+    /// Enable or disable strict validation
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Get config registered in GraphQL context, falling back to defaults
+    pub fn from_context(ctx: &Context<'_>) -> Self {
+        ctx.data_opt::<PaginationConfig>()
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            default_page_size: 20,
+            max_page_size: 100,
+            strict: false,
+        }
+    }
 }
 
 /// Pagination input for GraphQL queries
@@ -151,22 +481,39 @@ pub struct PaginationInput {
 impl PaginationInput {
     /// Validate pagination input
     pub fn validate(&self) -> crate::Result<()> {
+        self.validate_with(&PaginationConfig::default())
+    }
+
+    /// Validate pagination input in strict mode
+    ///
+    /// Additionally rejects mixed-direction arguments and empty windows.
+    pub fn validate_strict(&self) -> crate::Result<()> {
+        self.validate_with(&PaginationConfig::default().with_strict(true))
+    }
+
+    /// Validate pagination input against custom page size limits
+    pub fn validate_with(&self, config: &PaginationConfig) -> crate::Result<()> {
         if self.first.is_some() && self.last.is_some() {
             return Err(crate::GraphQLError::PaginationError(
                 "Cannot specify both 'first' and 'last'".to_string(),
             ));
         }
 
+        if config.strict {
+            self.validate_direction()?;
+        }
+
         if let Some(first) = self.first {
             if first < 0 {
                 return Err(crate::GraphQLError::PaginationError(
                     "'first' must be non-negative".to_string(),
                 ));
             }
-            if first > 100 {
-                return Err(crate::GraphQLError::PaginationError(
-                    "'first' cannot exceed 100".to_string(),
-                ));
+            if first > config.max_page_size {
+                return Err(crate::GraphQLError::PaginationError(format!(
+                    "'first' cannot exceed {}",
+                    config.max_page_size
+                )));
             }
         }
 
@@ -176,22 +523,82 @@ impl PaginationInput {
                     "'last' must be non-negative".to_string(),
                 ));
             }
-            if last > 100 {
-                return Err(crate::GraphQLError::PaginationError(
-                    "'last' cannot exceed 100".to_string(),
-                ));
+            if last > config.max_page_size {
+                return Err(crate::GraphQLError::PaginationError(format!(
+                    "'last' cannot exceed {}",
+                    config.max_page_size
+                )));
             }
         }
 
         Ok(())
     }
 
+    /// Strict-mode checks for mixed directions and empty windows
+    fn validate_direction(&self) -> crate::Result<()> {
+        if self.first.is_some() && self.before.is_some() {
+            return Err(crate::GraphQLError::PaginationError(
+                "Cannot combine 'first' with 'before'; use 'last' to paginate backward".to_string(),
+            ));
+        }
+
+        if self.last.is_some() && self.after.is_some() {
+            return Err(crate::GraphQLError::PaginationError(
+                "Cannot combine 'last' with 'after'; use 'first' to paginate forward".to_string(),
+            ));
+        }
+
+        if self.after.is_some() && self.after == self.before {
+            return Err(crate::GraphQLError::PaginationError(
+                "'after' and 'before' are the same cursor, selecting an empty window".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Get limit for database query
     pub fn limit(&self) -> i32 {
+        self.limit_with(&PaginationConfig::default())
+    }
+
+    /// Get limit for database query using custom page size limits
+    pub fn limit_with(&self, config: &PaginationConfig) -> i32 {
         self.first
             .or(self.last)
-            .unwrap_or(20)
-            .min(100) // Cap at 100
+            .unwrap_or(config.default_page_size)
+            .min(config.max_page_size)
+    }
+
+    /// Decode `after` as a structured cursor
+    ///
+    /// Returns `Ok(None)` when no cursor was given. Decode failures become
+    /// `InvalidCursor`, which converts into a GraphQL error via `?`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pleme_graphql_helpers::pagination::{CursorCodec, PaginationInput};
+    ///
+    /// let input = PaginationInput {
+    ///     after: Some(CursorCodec::encode_structured(&42u64).unwrap()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(input.decode_after::<u64>().unwrap(), Some(42));
+    /// ```
+    pub fn decode_after<C: for<'de> Deserialize<'de>>(&self) -> crate::Result<Option<C>> {
+        self.after
+            .as_deref()
+            .map(CursorCodec::decode_structured)
+            .transpose()
+    }
+
+    /// Decode `before` as a structured cursor
+    pub fn decode_before<C: for<'de> Deserialize<'de>>(&self) -> crate::Result<Option<C>> {
+        self.before
+            .as_deref()
+            .map(CursorCodec::decode_structured)
+            .transpose()
     }
 
     /// Check if forward pagination
@@ -203,6 +610,11 @@ impl PaginationInput {
     pub fn is_backward(&self) -> bool {
         self.last.is_some() || self.before.is_some()
     }
+
+    /// Whether the page is fetched backward (backward args only)
+    pub(crate) fn paginates_backward(&self) -> bool {
+        self.is_backward() && !self.is_forward()
+    }
 }
 
 impl Default for PaginationInput {
@@ -233,6 +645,20 @@ mod tests {
         assert_eq!(original, decoded);
     }
 
+    #[cfg(feature = "compact-cursors")]
+    #[test]
+    fn test_compact_cursor_roundtrip_and_legacy() {
+        let value = ("2024-01-01T00:00:00Z".to_string(), 42u64, "open".to_string());
+        let compact = CursorCodec::encode_compact(&value).unwrap();
+        let legacy = CursorCodec::encode_structured(&value).unwrap();
+        assert!(compact.len() < legacy.len());
+
+        let decoded: (String, u64, String) = CursorCodec::decode_compact(&compact).unwrap();
+        assert_eq!(decoded, value);
+        let decoded: (String, u64, String) = CursorCodec::decode_compact(&legacy).unwrap();
+        assert_eq!(decoded, value);
+    }
+
     #[test]
     fn test_connection_creation() {
         let items = vec![
@@ -244,4 +670,148 @@ mod tests {
         assert!(conn.page_info.has_next_page);
         assert!(!conn.page_info.has_previous_page);
     }
+
+    #[test]
+    fn test_with_cursor_fn() {
+        let items = vec![
+            Item { id: "a".to_string() },
+            Item { id: "b".to_string() },
+        ];
+        let conn = Connection::with_cursor_fn(items, false, false, |item| item.id.clone()).unwrap();
+        let decoded: String = CursorCodec::decode_structured(&conn.edges[1].cursor).unwrap();
+        assert_eq!(decoded, "b");
+        assert_eq!(conn.page_info.end_cursor, Some(conn.edges[1].cursor.clone()));
+    }
+
+    #[test]
+    fn test_connection_map() {
+        let conn = Connection::from_overfetched(vec![1, 2, 3], &PaginationInput {
+            first: Some(2),
+            ..Default::default()
+        }, |n| n.to_string());
+
+        let mapped = conn.clone().map(|n| n * 10);
+        assert_eq!(mapped.edges[1].node, 20);
+        assert_eq!(mapped.edges[1].cursor, "2");
+        assert!(mapped.page_info.has_next_page);
+
+        let failed: std::result::Result<Connection<i32>, String> =
+            conn.try_map(|n| if n == 2 { Err("bad".to_string()) } else { Ok(n) });
+        assert_eq!(failed.unwrap_err(), "bad");
+    }
+
+    #[test]
+    fn test_decode_typed_cursors() {
+        let input = PaginationInput {
+            first: None,
+            after: Some(CursorCodec::encode_structured(&("2024-01-01", 7)).unwrap()),
+            last: None,
+            before: Some("not base64!".to_string()),
+        };
+        let after: Option<(String, i32)> = input.decode_after().unwrap();
+        assert_eq!(after, Some(("2024-01-01".to_string(), 7)));
+        assert!(matches!(
+            input.decode_before::<(String, i32)>(),
+            Err(crate::GraphQLError::InvalidCursor(_))
+        ));
+        assert_eq!(PaginationInput::default().decode_after::<i32>().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_from_stream_short_circuits() {
+        let input = PaginationInput {
+            first: Some(3),
+            ..Default::default()
+        };
+        let conn = Connection::from_stream(futures::stream::iter(1..), &input, |n| n.to_string()).await;
+        let nodes: Vec<i32> = conn.edges.iter().map(|e| e.node).collect();
+        assert_eq!(nodes, vec![1, 2, 3]);
+        assert!(conn.page_info.has_next_page);
+
+        let failing = futures::stream::iter(vec![Ok(1), Err("boom"), Ok(3)]);
+        let result = Connection::try_from_stream(failing, &input, |n: &i32| n.to_string()).await;
+        assert_eq!(result.unwrap_err(), "boom");
+    }
+
+    #[test]
+    fn test_strict_validation_rejects_mixed_directions() {
+        let first_before = PaginationInput {
+            first: Some(10),
+            after: None,
+            last: None,
+            before: Some("c".to_string()),
+        };
+        assert!(first_before.validate().is_ok());
+        assert!(first_before.validate_strict().is_err());
+
+        let last_after = PaginationInput {
+            first: None,
+            after: Some("c".to_string()),
+            last: Some(10),
+            before: None,
+        };
+        assert!(last_after.validate_strict().is_err());
+
+        let forward = PaginationInput {
+            first: Some(10),
+            after: Some("c".to_string()),
+            last: None,
+            before: None,
+        };
+        assert!(forward.validate_with(&PaginationConfig::default().with_strict(true)).is_ok());
+    }
+
+    #[test]
+    fn test_pagination_config_limits() {
+        let input = PaginationInput {
+            first: Some(250),
+            ..Default::default()
+        };
+        assert!(input.validate().is_err());
+        assert_eq!(input.limit(), 100);
+
+        let config = PaginationConfig::new(50, 500);
+        assert!(input.validate_with(&config).is_ok());
+        assert_eq!(input.limit_with(&config), 250);
+
+        let input = PaginationInput {
+            first: None,
+            ..Default::default()
+        };
+        assert_eq!(input.limit_with(&config), 50);
+        assert_eq!(input.limit_with(&PaginationConfig::new(10, 5)), 5);
+    }
+
+    #[test]
+    fn test_from_overfetched_forward() {
+        let input = PaginationInput {
+            first: Some(2),
+            after: Some(CursorCodec::encode("0")),
+            ..Default::default()
+        };
+        let conn = Connection::from_overfetched(vec![1, 2, 3], &input, |n| n.to_string());
+        assert_eq!(conn.edges.len(), 2);
+        assert!(conn.page_info.has_next_page);
+        assert!(conn.page_info.has_previous_page);
+        assert_eq!(conn.page_info.end_cursor.as_deref(), Some("2"));
+
+        let conn = Connection::from_overfetched(vec![1, 2], &input, |n| n.to_string());
+        assert!(!conn.page_info.has_next_page);
+    }
+
+    #[test]
+    fn test_from_overfetched_backward() {
+        let input = PaginationInput {
+            first: None,
+            after: None,
+            last: Some(2),
+            before: None,
+        };
+        // Rows fetched in reverse order: newest first
+        let conn = Connection::from_overfetched(vec![5, 4, 3], &input, |n| n.to_string());
+        let nodes: Vec<i32> = conn.edges.iter().map(|e| e.node).collect();
+        assert_eq!(nodes, vec![4, 5]);
+        assert!(conn.page_info.has_previous_page);
+        assert!(!conn.page_info.has_next_page);
+    }
 }
@@ -0,0 +1,137 @@
+//! Aggregate fields on connections
+//!
+//! Services register named aggregate resolvers (e.g. the total value of all
+//! filtered invoices) that are exposed under `aggregates` on a connection and
+//! only run when requested.
+
+use async_graphql::SimpleObject;
+use futures::future::try_join_all;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::types::Json;
+
+type AggregateFuture = Pin<Box<dyn Future<Output = async_graphql::Result<Value>> + Send>>;
+type AggregateFn = Arc<dyn Fn() -> AggregateFuture + Send + Sync>;
+
+/// Computed aggregate value
+#[derive(SimpleObject, Debug, Clone)]
+pub struct Aggregate {
+    pub name: String,
+    pub value: Json,
+}
+
+/// Registered aggregate resolvers for a connection
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{Connection, ConnectionAggregates};
+/// use serde_json::json;
+///
+/// let aggregates = ConnectionAggregates::new()
+///     .register("totalValue", || async {
+///         // SELECT SUM(amount)::text FROM invoices WHERE ...
+///         Ok(json!("1520.75"))
+///     })
+///     .register("count", || async { Ok(json!(12)) });
+///
+/// let conn = Connection::<i32>::empty().with_aggregates(aggregates);
+/// assert_eq!(conn.aggregates.names().collect::<Vec<_>>(), vec!["totalValue", "count"]);
+/// ```
+#[derive(Clone, Default)]
+pub struct ConnectionAggregates {
+    resolvers: Vec<(String, AggregateFn)>,
+}
+
+impl ConnectionAggregates {
+    /// Create empty aggregate set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an aggregate resolver
+    ///
+    /// Registering an existing name replaces the previous resolver.
+    pub fn register<F, Fut>(mut self, name: impl Into<String>, resolver: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = async_graphql::Result<Value>> + Send + 'static,
+    {
+        let name = name.into();
+        let resolver: AggregateFn = Arc::new(move || Box::pin(resolver()) as AggregateFuture);
+        match self.resolvers.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = resolver,
+            None => self.resolvers.push((name, resolver)),
+        }
+        self
+    }
+
+    /// Registered aggregate names, in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.resolvers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Whether no aggregates are registered
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+
+    /// Resolve aggregates concurrently
+    ///
+    /// With `names`, only those aggregates run; unknown names are an error.
+    pub async fn resolve(&self, names: Option<&[String]>) -> async_graphql::Result<Vec<Aggregate>> {
+        let selected: Vec<&(String, AggregateFn)> = match names {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    self.resolvers
+                        .iter()
+                        .find(|(n, _)| n == name)
+                        .ok_or_else(|| async_graphql::Error::new(format!("Unknown aggregate '{}'", name)))
+                })
+                .collect::<async_graphql::Result<_>>()?,
+            None => self.resolvers.iter().collect(),
+        };
+
+        try_join_all(selected.into_iter().map(|(name, resolver)| async move {
+            Ok::<_, async_graphql::Error>(Aggregate {
+                name: name.clone(),
+                value: Json(resolver().await?),
+            })
+        }))
+        .await
+    }
+}
+
+impl std::fmt::Debug for ConnectionAggregates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionAggregates")
+            .field("names", &self.names().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_aggregates_resolve_selected() {
+        let aggregates = ConnectionAggregates::new()
+            .register("sum", || async { Ok(json!("10.50")) })
+            .register("avg", || async { Ok(json!(2.1)) });
+
+        let all = aggregates.resolve(None).await.unwrap();
+        assert_eq!(all.len(), 2);
+
+        let only_sum = aggregates.resolve(Some(&["sum".to_string()])).await.unwrap();
+        assert_eq!(only_sum.len(), 1);
+        assert_eq!(only_sum[0].value.0, json!("10.50"));
+
+        assert!(aggregates.resolve(Some(&["max".to_string()])).await.is_err());
+    }
+}
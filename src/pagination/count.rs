@@ -0,0 +1,107 @@
+//! Lazy `totalCount` resolution
+//!
+//! `COUNT(*)` is expensive, so connections carry a [`CountLoader`] that only
+//! runs when the client actually selects `totalCount`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+type CountFuture = Pin<Box<dyn Future<Output = async_graphql::Result<i64>> + Send>>;
+
+/// Deferred total count for a connection
+///
+/// The count is computed at most once; successful results are memoized so
+/// aliased `totalCount` selections share a single query.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{Connection, CountLoader};
+///
+/// let conn = Connection::<i32>::empty().with_total_count(CountLoader::new(|| async {
+///     // SELECT COUNT(*) FROM ...
+///     Ok(42)
+/// }));
+/// assert!(conn.total_count.is_some());
+/// ```
+#[derive(Clone)]
+pub struct CountLoader {
+    loader: Arc<dyn Fn() -> CountFuture + Send + Sync>,
+    value: Arc<OnceCell<i64>>,
+}
+
+impl CountLoader {
+    /// Create count loader from an async closure
+    pub fn new<F, Fut>(loader: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = async_graphql::Result<i64>> + Send + 'static,
+    {
+        Self {
+            loader: Arc::new(move || Box::pin(loader()) as CountFuture),
+            value: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Create count loader from an already known count
+    pub fn fixed(count: i64) -> Self {
+        Self {
+            loader: Arc::new(move || Box::pin(async move { Ok(count) }) as CountFuture),
+            value: Arc::new(OnceCell::new_with(Some(count))),
+        }
+    }
+
+    /// Resolve the count, running the loader on first use
+    pub async fn load(&self) -> async_graphql::Result<i64> {
+        self.value
+            .get_or_try_init(|| (self.loader)())
+            .await
+            .copied()
+    }
+
+    /// Whether the count has already been computed
+    pub fn is_loaded(&self) -> bool {
+        self.value.initialized()
+    }
+}
+
+impl std::fmt::Debug for CountLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CountLoader")
+            .field("value", &self.value.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_count_loader_runs_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let loader = CountLoader::new(move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(7)
+            }
+        });
+
+        assert!(!loader.is_loaded());
+        assert_eq!(loader.load().await.unwrap(), 7);
+        assert_eq!(loader.clone().load().await.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_count_loader_fixed() {
+        let loader = CountLoader::fixed(3);
+        assert!(loader.is_loaded());
+        assert_eq!(loader.load().await.unwrap(), 3);
+    }
+}
@@ -0,0 +1,173 @@
+//! Connections with custom edge types
+//!
+//! [`Edge`] only carries `cursor` and `node`. Connections that need
+//! edge-level metadata (`role` on a membership edge, `score` on a search
+//! edge) define their own edge type and use [`ConnectionOf`].
+
+use async_graphql::Object;
+
+use super::{Aggregate, Connection, ConnectionAggregates, CountLoader, Edge, PageInfo};
+
+/// Edge type usable in [`ConnectionOf`]
+pub trait EdgeLike {
+    /// Opaque cursor for this edge
+    fn cursor(&self) -> &str;
+}
+
+impl<T> EdgeLike for Edge<T> {
+    fn cursor(&self) -> &str {
+        &self.cursor
+    }
+}
+
+/// Connection over a custom edge type
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::SimpleObject;
+/// use pleme_graphql_helpers::pagination::{ConnectionOf, EdgeLike};
+///
+/// #[derive(SimpleObject, Clone)]
+/// struct Member { name: String }
+///
+/// #[derive(SimpleObject, Clone)]
+/// struct MembershipEdge {
+///     cursor: String,
+///     node: Member,
+///     role: String,
+/// }
+///
+/// impl EdgeLike for MembershipEdge {
+///     fn cursor(&self) -> &str { &self.cursor }
+/// }
+///
+/// let edges = vec![MembershipEdge {
+///     cursor: "c1".into(),
+///     node: Member { name: "Ana".into() },
+///     role: "ADMIN".into(),
+/// }];
+/// let conn = ConnectionOf::new(edges, false, false);
+/// assert_eq!(conn.page_info.start_cursor.as_deref(), Some("c1"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectionOf<E> {
+    pub edges: Vec<E>,
+    pub page_info: PageInfo,
+
+    /// Lazily computed total count, exposed as `totalCount`
+    pub total_count: Option<CountLoader>,
+
+    /// Aggregate resolvers, exposed as `aggregates`
+    pub aggregates: ConnectionAggregates,
+}
+
+#[Object]
+impl<E: async_graphql::OutputType + EdgeLike> ConnectionOf<E> {
+    async fn edges(&self) -> &[E] {
+        &self.edges
+    }
+
+    async fn page_info(&self) -> &PageInfo {
+        &self.page_info
+    }
+
+    /// Total number of items across all pages, if the service provides it
+    async fn total_count(&self) -> async_graphql::Result<Option<i64>> {
+        match &self.total_count {
+            Some(count) => count.load().await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Aggregates over the full result set, optionally filtered by name
+    async fn aggregates(&self, names: Option<Vec<String>>) -> async_graphql::Result<Vec<Aggregate>> {
+        self.aggregates.resolve(names.as_deref()).await
+    }
+}
+
+impl<E: EdgeLike> ConnectionOf<E> {
+    /// Create connection from custom edges
+    pub fn new(edges: Vec<E>, has_next: bool, has_previous: bool) -> Self {
+        let start_cursor = edges.first().map(|e| e.cursor().to_string());
+        let end_cursor = edges.last().map(|e| e.cursor().to_string());
+
+        Self {
+            edges,
+            page_info: PageInfo {
+                has_next_page: has_next,
+                has_previous_page: has_previous,
+                start_cursor,
+                end_cursor,
+            },
+            total_count: None,
+            aggregates: ConnectionAggregates::default(),
+        }
+    }
+
+    /// Attach a lazily computed total count
+    pub fn with_total_count(mut self, count: CountLoader) -> Self {
+        self.total_count = Some(count);
+        self
+    }
+
+    /// Attach aggregate resolvers
+    pub fn with_aggregates(mut self, aggregates: ConnectionAggregates) -> Self {
+        self.aggregates = aggregates;
+        self
+    }
+}
+
+impl<T> Connection<T> {
+    /// Convert edges into a custom edge type, preserving page info
+    ///
+    /// Lets services reuse the standard builders (`from_overfetched`,
+    /// `relay::paginate_slice`, ...) and attach edge metadata afterwards.
+    pub fn with_edge_type<E, F>(self, f: F) -> ConnectionOf<E>
+    where
+        F: Fn(Edge<T>) -> E,
+    {
+        ConnectionOf {
+            edges: self.edges.into_iter().map(f).collect(),
+            page_info: self.page_info,
+            total_count: self.total_count,
+            aggregates: self.aggregates,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScoredEdge {
+        cursor: String,
+        score: f64,
+    }
+
+    impl EdgeLike for ScoredEdge {
+        fn cursor(&self) -> &str {
+            &self.cursor
+        }
+    }
+
+    #[test]
+    fn test_with_edge_type_preserves_page_info() {
+        let conn = Connection::from_edges(
+            vec![
+                Edge { cursor: "a".to_string(), node: 1 },
+                Edge { cursor: "b".to_string(), node: 2 },
+            ],
+            true,
+            false,
+        );
+
+        let scored = conn.with_edge_type(|e| ScoredEdge {
+            cursor: e.cursor,
+            score: e.node as f64 / 2.0,
+        });
+        assert_eq!(scored.edges[1].score, 1.0);
+        assert!(scored.page_info.has_next_page);
+        assert_eq!(scored.page_info.end_cursor.as_deref(), Some("b"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Encrypted cursors
+//!
+//! Signed cursors still expose their payload (internal sequence IDs,
+//! timestamps) as plaintext base64. Encrypted cursors are fully opaque:
+//! AES-256-GCM with a random nonce per cursor.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use hmac::{Hmac, Mac};
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+
+use super::CursorConfig;
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// Domain separation label for deriving the encryption key
+const KEY_DERIVATION_LABEL: &[u8] = b"pleme-graphql-helpers/cursor-encryption";
+
+/// Cursor codec that encrypts cursors with AES-256-GCM
+///
+/// The encryption key is derived from the [`CursorConfig`] secret, so the
+/// same config can back both signed and encrypted cursors.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{CursorConfig, EncryptedCursorCodec};
+///
+/// let codec = EncryptedCursorCodec::new(&CursorConfig::new("secret"));
+/// let cursor = codec.encode("seq:1042").unwrap();
+/// assert!(!cursor.contains("1042"));
+/// assert_eq!(codec.decode(&cursor).unwrap(), "seq:1042");
+/// ```
+#[derive(Clone)]
+pub struct EncryptedCursorCodec {
+    cipher: Aes256Gcm,
+}
+
+impl EncryptedCursorCodec {
+    /// Create codec from config
+    pub fn new(config: &CursorConfig) -> Self {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(config.secret())
+            .expect("HMAC accepts keys of any length");
+        mac.update(KEY_DERIVATION_LABEL);
+        let key = mac.finalize().into_bytes();
+
+        Self {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    /// Encrypt cursor
+    pub fn encode(&self, value: &str) -> crate::Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|_| crate::GraphQLError::InvalidCursor("Cursor encryption failed".to_string()))?;
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(bytes))
+    }
+
+    /// Decrypt cursor
+    pub fn decode(&self, cursor: &str) -> crate::Result<String> {
+        let bytes = BASE64
+            .decode(cursor.as_bytes())
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))?;
+
+        let (nonce, ciphertext) = bytes
+            .split_first_chunk::<NONCE_LEN>()
+            .ok_or_else(|| crate::GraphQLError::InvalidCursor("Cursor is too short".to_string()))?;
+
+        let plaintext = self
+            .cipher
+            .decrypt(&Nonce::from(*nonce), ciphertext)
+            .map_err(|_| crate::GraphQLError::InvalidCursor("Cursor decryption failed".to_string()))?;
+        String::from_utf8(plaintext)
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))
+    }
+
+    /// Encrypt structured cursor
+    pub fn encode_structured<T: Serialize>(&self, value: &T) -> crate::Result<String> {
+        let json = serde_json::to_string(value)
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))?;
+        self.encode(&json)
+    }
+
+    /// Decrypt structured cursor
+    pub fn decode_structured<T: for<'de> Deserialize<'de>>(&self, cursor: &str) -> crate::Result<T> {
+        let json = self.decode(cursor)?;
+        serde_json::from_str(&json)
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))
+    }
+}
+
+impl std::fmt::Debug for EncryptedCursorCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedCursorCodec").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_cursor_roundtrip() {
+        let codec = EncryptedCursorCodec::new(&CursorConfig::new("secret"));
+        let cursor = codec.encode_structured(&("2024-01-01", 99)).unwrap();
+        let decoded: (String, i32) = codec.decode_structured(&cursor).unwrap();
+        assert_eq!(decoded, ("2024-01-01".to_string(), 99));
+
+        // Random nonces make equal payloads encrypt differently
+        assert_ne!(codec.encode("x").unwrap(), codec.encode("x").unwrap());
+    }
+
+    #[test]
+    fn test_encrypted_cursor_rejects_tampering() {
+        let codec = EncryptedCursorCodec::new(&CursorConfig::new("secret"));
+        let cursor = codec.encode("42").unwrap();
+
+        let mut bytes = BASE64.decode(&cursor).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        assert!(codec.decode(&BASE64.encode(bytes)).is_err());
+
+        let other = EncryptedCursorCodec::new(&CursorConfig::new("other"));
+        assert!(other.decode(&cursor).is_err());
+        assert!(codec.decode("c2hvcnQ=").is_err());
+    }
+}
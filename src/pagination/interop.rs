@@ -0,0 +1,114 @@
+//! Interop with async-graphql's built-in connection types
+//!
+//! Lets services that build `async_graphql::connection::Connection` directly
+//! mix with this crate's [`Connection`] in the same federated graph.
+
+use async_graphql::connection::{self, CursorType};
+use serde::{Serialize, Deserialize};
+
+use super::{Connection, CursorCodec, Edge};
+
+/// Structured cursor usable as an async-graphql [`CursorType`]
+///
+/// Encodes with [`CursorCodec::encode_structured`], so cursors produced by
+/// `async_graphql::connection::query` and by this crate are interchangeable.
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::connection::CursorType;
+/// use pleme_graphql_helpers::pagination::{CursorCodec, StructuredCursor};
+///
+/// let cursor = StructuredCursor(("2024-01-01".to_string(), 7u64));
+/// let encoded = cursor.encode_cursor();
+/// assert_eq!(encoded, CursorCodec::encode_structured(&cursor.0).unwrap());
+///
+/// let decoded = StructuredCursor::<(String, u64)>::decode_cursor(&encoded).unwrap();
+/// assert_eq!(decoded.0 .1, 7);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredCursor<T>(pub T);
+
+impl<T> CursorType for StructuredCursor<T>
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    type Error = crate::GraphQLError;
+
+    fn decode_cursor(s: &str) -> std::result::Result<Self, Self::Error> {
+        CursorCodec::decode_structured(s).map(StructuredCursor)
+    }
+
+    fn encode_cursor(&self) -> String {
+        // Serialization only fails for non-string map keys, which cursor
+        // payloads don't use
+        CursorCodec::encode_structured(&self.0).unwrap_or_default()
+    }
+}
+
+impl<T> From<Connection<T>> for connection::Connection<String, T>
+where
+    T: async_graphql::OutputType,
+{
+    fn from(conn: Connection<T>) -> Self {
+        let mut converted =
+            connection::Connection::new(conn.page_info.has_previous_page, conn.page_info.has_next_page);
+        converted.edges.extend(
+            conn.edges
+                .into_iter()
+                .map(|e| connection::Edge::new(e.cursor, e.node)),
+        );
+        converted
+    }
+}
+
+impl<T> From<connection::Connection<String, T>> for Connection<T>
+where
+    T: async_graphql::OutputType,
+{
+    fn from(conn: connection::Connection<String, T>) -> Self {
+        let has_next = conn.has_next_page;
+        let has_previous = conn.has_previous_page;
+        let edges = conn
+            .edges
+            .into_iter()
+            .map(|e| Edge {
+                cursor: e.cursor,
+                node: e.node,
+            })
+            .collect();
+
+        Connection::from_edges(edges, has_next, has_previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_roundtrip_through_async_graphql() {
+        let conn = Connection::from_edges(
+            vec![
+                Edge { cursor: "a".to_string(), node: 1 },
+                Edge { cursor: "b".to_string(), node: 2 },
+            ],
+            true,
+            false,
+        );
+
+        let builtin: connection::Connection<String, i32> = conn.into();
+        assert!(builtin.has_next_page);
+        assert_eq!(builtin.edges.len(), 2);
+
+        let back: Connection<i32> = builtin.into();
+        assert_eq!(back.page_info.end_cursor.as_deref(), Some("b"));
+        assert!(back.page_info.has_next_page);
+        assert!(!back.page_info.has_previous_page);
+    }
+
+    #[test]
+    fn test_structured_cursor_rejects_garbage() {
+        assert!(StructuredCursor::<u64>::decode_cursor("!!!").is_err());
+    }
+}
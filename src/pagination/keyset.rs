@@ -0,0 +1,802 @@
+//! Keyset (seek) pagination SQL generation
+//!
+//! Turns a decoded structured cursor plus sort column descriptors into the
+//! `WHERE`/`ORDER BY` fragments needed for keyset pagination, so services
+//! stop hand-writing `(created_at, id) < ($1, $2)` clauses.
+
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+use super::{Connection, CursorCodec, PaginationConfig, PaginationInput};
+
+/// Sort direction of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    /// Opposite direction
+    pub fn reverse(self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+
+    /// SQL keyword for this direction
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+
+    /// Comparison operator that seeks past a cursor in this direction
+    fn seek_operator(self) -> &'static str {
+        match self {
+            SortDirection::Asc => ">",
+            SortDirection::Desc => "<",
+        }
+    }
+}
+
+/// Sort column descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortColumn {
+    /// Column name (also the field name looked up in the cursor)
+    pub name: String,
+    pub direction: SortDirection,
+
+    /// SQL type to cast cursor parameters to (e.g. `timestamptz`, `uuid`)
+    ///
+    /// Cursor values travel as JSON strings/numbers, so typed columns need an
+    /// explicit cast for the comparison to type-check.
+    pub sql_type: Option<String>,
+}
+
+impl SortColumn {
+    /// Ascending sort column
+    pub fn asc(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            direction: SortDirection::Asc,
+            sql_type: None,
+        }
+    }
+
+    /// Descending sort column
+    pub fn desc(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            direction: SortDirection::Desc,
+            sql_type: None,
+        }
+    }
+
+    /// Cast cursor parameters for this column to `sql_type`
+    pub fn with_sql_type(mut self, sql_type: impl Into<String>) -> Self {
+        self.sql_type = Some(sql_type.into());
+        self
+    }
+
+    /// Cast suffix for parameters (e.g. `::timestamptz`), empty when untyped
+    pub fn cast_suffix(&self) -> String {
+        self.sql_type
+            .as_deref()
+            .map(|t| format!("::{}", t))
+            .unwrap_or_default()
+    }
+}
+
+/// SQL fragments produced by [`KeysetPaginator`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeysetQuery {
+    /// `WHERE` predicate (without the keyword), `None` on the first page
+    pub predicate: Option<String>,
+
+    /// Bind parameters referenced by the predicate, in placeholder order
+    pub params: Vec<Value>,
+
+    /// `ORDER BY` clause (without the keyword)
+    pub order_by: String,
+
+    /// Whether fetched rows must be reversed before building a connection
+    ///
+    /// Backward pagination queries in the opposite order, so rows come
+    /// back last-to-first.
+    pub reverse_results: bool,
+}
+
+/// Keyset paginator
+///
+/// Generates Postgres-style (`$1`, `$2`, ...) predicates. Uses row-value
+/// comparison when all columns sort descending and the cursor has no null
+/// values, and the expanded `OR` form otherwise.
+///
+/// Nullable sort columns follow Postgres' default ordering (`NULL` sorts
+/// last ascending and first descending), so null cursor values seek with
+/// `IS NULL`/`IS NOT NULL` and bind no parameter. The sqlx and SeaORM
+/// adapters build on the same predicate.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{KeysetPaginator, SortColumn};
+/// use serde_json::json;
+///
+/// let paginator = KeysetPaginator::new(vec![
+///     SortColumn::desc("created_at"),
+///     SortColumn::desc("id"),
+/// ]);
+///
+/// let cursor = json!({ "created_at": "2024-01-01T00:00:00Z", "id": "abc" });
+/// let query = paginator.build(Some(&cursor), false).unwrap();
+/// assert_eq!(query.predicate.as_deref(), Some("(created_at, id) < ($1, $2)"));
+/// assert_eq!(query.order_by, "created_at DESC, id DESC");
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeysetPaginator {
+    columns: Vec<SortColumn>,
+    param_offset: usize,
+}
+
+impl KeysetPaginator {
+    /// Create new paginator from sort columns
+    ///
+    /// The last column should be unique (e.g., `id`) to act as a tiebreaker.
+    pub fn new(columns: Vec<SortColumn>) -> Self {
+        Self {
+            columns,
+            param_offset: 0,
+        }
+    }
+
+    /// Start placeholders after `offset` existing bind parameters
+    pub fn with_param_offset(mut self, offset: usize) -> Self {
+        self.param_offset = offset;
+        self
+    }
+
+    /// Sort columns
+    pub fn columns(&self) -> &[SortColumn] {
+        &self.columns
+    }
+
+    /// Build query fragments for a page
+    ///
+    /// `cursor` is a decoded structured cursor whose fields are named after
+    /// the sort columns. Set `backward` for `last`/`before` pagination.
+    pub fn build<C: Serialize>(&self, cursor: Option<&C>, backward: bool) -> crate::Result<KeysetQuery> {
+        if self.columns.is_empty() {
+            return Err(crate::GraphQLError::PaginationError(
+                "Keyset pagination requires at least one sort column".to_string(),
+            ));
+        }
+
+        let directions: Vec<SortDirection> = self
+            .columns
+            .iter()
+            .map(|c| if backward { c.direction.reverse() } else { c.direction })
+            .collect();
+
+        let order_by = self
+            .columns
+            .iter()
+            .zip(&directions)
+            .map(|(c, d)| format!("{} {}", c.name, d.as_sql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let (predicate, params) = match cursor {
+            Some(cursor) => {
+                let values = self.cursor_values(cursor)?;
+                let (predicate, params) = self.predicate(&directions, values);
+                (Some(predicate), params)
+            }
+            None => (None, Vec::new()),
+        };
+
+        Ok(KeysetQuery {
+            predicate,
+            params,
+            order_by,
+            reverse_results: backward,
+        })
+    }
+
+    /// Extract cursor values in column order
+    fn cursor_values<C: Serialize>(&self, cursor: &C) -> crate::Result<Vec<Value>> {
+        let value = serde_json::to_value(cursor)
+            .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))?;
+        let object = value.as_object().ok_or_else(|| {
+            crate::GraphQLError::InvalidCursor("Keyset cursor must be an object".to_string())
+        })?;
+
+        self.columns
+            .iter()
+            .map(|c| {
+                object.get(&c.name).cloned().ok_or_else(|| {
+                    crate::GraphQLError::InvalidCursor(format!("Cursor is missing field '{}'", c.name))
+                })
+            })
+            .collect()
+    }
+
+    /// Build the seek predicate for the effective directions, with the
+    /// cursor values it binds
+    fn predicate(&self, directions: &[SortDirection], values: Vec<Value>) -> (String, Vec<Value>) {
+        let parts = self.seek_parts(directions, &values);
+
+        // Placeholder per column, `None` for null values
+        let mut placeholders = Vec::with_capacity(values.len());
+        let mut params = Vec::new();
+        for (column, value) in self.columns.iter().zip(values) {
+            if value.is_null() {
+                placeholders.push(None);
+            } else {
+                params.push(value);
+                placeholders.push(Some(format!(
+                    "${}{}",
+                    self.param_offset + params.len(),
+                    column.cast_suffix()
+                )));
+            }
+        }
+
+        let predicate = parts
+            .into_iter()
+            .map(|part| match part {
+                SeekPart::Sql(sql) => sql,
+                SeekPart::Bind(i) => placeholders[i].clone().unwrap_or_default(),
+            })
+            .collect();
+        (predicate, params)
+    }
+
+    /// The seek predicate for the effective directions, as SQL text and
+    /// the cursor values to bind in between
+    ///
+    /// Adapters render the parts with their own bind syntax.
+    pub(crate) fn seek_parts(&self, directions: &[SortDirection], values: &[Value]) -> Vec<SeekPart> {
+        let mut parts = SeekParts::default();
+
+        // Ascending rows may trail with nulls a row comparison never matches
+        if directions.iter().all(|d| *d == SortDirection::Desc) && !values.iter().any(Value::is_null) {
+            let columns = self
+                .columns
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            parts.sql(&format!("({}) {} (", columns, SortDirection::Desc.seek_operator()));
+            for i in 0..self.columns.len() {
+                if i > 0 {
+                    parts.sql(", ");
+                }
+                parts.bind(i);
+            }
+            parts.sql(")");
+            return parts.0;
+        }
+
+        // (a > $1 OR a IS NULL) OR (a = $1 AND b < $2) OR ...
+        parts.sql("(");
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                parts.sql(" OR ");
+            }
+            parts.sql("(");
+            for (j, prev) in self.columns[..i].iter().enumerate() {
+                if values[j].is_null() {
+                    parts.sql(&format!("{} IS NULL", prev.name));
+                } else {
+                    parts.sql(&format!("{} = ", prev.name));
+                    parts.bind(j);
+                }
+                parts.sql(" AND ");
+            }
+            parts.seek(i, &column.name, directions[i], values[i].is_null());
+            parts.sql(")");
+        }
+        parts.sql(")");
+        parts.0
+    }
+}
+
+/// Piece of a seek predicate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SeekPart {
+    Sql(String),
+    /// The cursor value of the sort column at this index, never null
+    Bind(usize),
+}
+
+#[derive(Default)]
+struct SeekParts(Vec<SeekPart>);
+
+impl SeekParts {
+    fn sql(&mut self, sql: &str) {
+        match self.0.last_mut() {
+            Some(SeekPart::Sql(text)) => text.push_str(sql),
+            _ => self.0.push(SeekPart::Sql(sql.to_string())),
+        }
+    }
+
+    fn bind(&mut self, column: usize) {
+        self.0.push(SeekPart::Bind(column));
+    }
+
+    /// Comparison seeking past the cursor value of column `i` in
+    /// `direction`, with `NULL` sorting last ascending and first descending
+    fn seek(&mut self, i: usize, column: &str, direction: SortDirection, null: bool) {
+        match (direction, null) {
+            // Nothing sorts after the trailing nulls
+            (SortDirection::Asc, true) => self.sql("FALSE"),
+            (SortDirection::Asc, false) => {
+                self.sql(&format!("({column} > "));
+                self.bind(i);
+                self.sql(&format!(" OR {column} IS NULL)"));
+            }
+            (SortDirection::Desc, true) => self.sql(&format!("{column} IS NOT NULL")),
+            (SortDirection::Desc, false) => {
+                self.sql(&format!("{column} < "));
+                self.bind(i);
+            }
+        }
+    }
+}
+
+/// Sort field value captured in a cursor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub field: String,
+    pub direction: SortDirection,
+    pub value: Value,
+}
+
+impl SortKey {
+    /// Create sort key
+    pub fn new(field: impl Into<String>, direction: SortDirection, value: impl Into<Value>) -> Self {
+        Self {
+            field: field.into(),
+            direction,
+            value: value.into(),
+        }
+    }
+
+    /// Whether this key names `column` in its direction
+    pub fn matches(&self, column: &SortColumn) -> bool {
+        self.field == column.name && self.direction == column.direction
+    }
+}
+
+/// Multi-column cursor payload
+///
+/// Captures ordered sort fields plus a unique tiebreaker (usually `id`), so
+/// lists sorted by e.g. `(status, priority, created_at)` have a stable
+/// position. Encode with [`CursorCodec::encode_structured`](super::CursorCodec::encode_structured).
+///
+/// Decoded payloads come from clients, so their field names and directions
+/// never reach SQL directly: [`columns`](Self::columns) and
+/// [`order_by`](Self::order_by) resolve them against the server's sort
+/// columns and reject cursors that don't match.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{
+///     CursorPayload, KeysetPaginator, SortColumn, SortDirection, SortKey,
+/// };
+///
+/// let sort = [
+///     SortColumn::asc("status"),
+///     SortColumn::desc("priority"),
+///     SortColumn::asc("id"),
+/// ];
+/// let payload = CursorPayload::new(SortKey::new("id", SortDirection::Asc, "abc"))
+///     .with_key(SortKey::new("status", SortDirection::Asc, "open"))
+///     .with_key(SortKey::new("priority", SortDirection::Desc, 2));
+///
+/// assert_eq!(payload.order_by(&sort, false).unwrap(), "status ASC, priority DESC, id ASC");
+///
+/// let query = KeysetPaginator::new(payload.columns(&sort).unwrap())
+///     .build(Some(&payload.values()), false)
+///     .unwrap();
+/// assert_eq!(query.params.len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CursorPayload {
+    /// Sort keys in priority order
+    pub keys: Vec<SortKey>,
+
+    /// Unique tiebreaker, always compared last
+    pub tiebreaker: SortKey,
+}
+
+impl CursorPayload {
+    /// Create payload with only a tiebreaker
+    pub fn new(tiebreaker: SortKey) -> Self {
+        Self {
+            keys: Vec::new(),
+            tiebreaker,
+        }
+    }
+
+    /// Append a sort key (before the tiebreaker)
+    pub fn with_key(mut self, key: SortKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Build payload from a row's fields
+    ///
+    /// The last column is used as the tiebreaker.
+    pub fn from_row<R: Serialize>(row: &R, columns: &[SortColumn]) -> crate::Result<Self> {
+        let (tiebreaker, keys) = columns.split_last().ok_or_else(|| {
+            crate::GraphQLError::PaginationError("Cursor payload requires at least one sort column".to_string())
+        })?;
+
+        let row = row_object(row)?;
+        let key = |column: &SortColumn| -> crate::Result<SortKey> {
+            let value = row.get(&column.name).cloned().ok_or_else(|| {
+                crate::GraphQLError::PaginationError(format!("Row is missing field '{}'", column.name))
+            })?;
+            Ok(SortKey::new(column.name.clone(), column.direction, value))
+        };
+
+        Ok(Self {
+            keys: keys.iter().map(key).collect::<crate::Result<Vec<_>>>()?,
+            tiebreaker: key(tiebreaker)?,
+        })
+    }
+
+    /// Sort columns of this cursor, tiebreaker last
+    ///
+    /// Every key must match the column of `sort` at its position; the
+    /// returned columns are `sort`'s own, `sql_type` included.
+    pub fn columns(&self, sort: &[SortColumn]) -> crate::Result<Vec<SortColumn>> {
+        if self.keys.len() + 1 != sort.len() || !self.all_keys().zip(sort).all(|(k, c)| k.matches(c)) {
+            return Err(crate::GraphQLError::InvalidCursor(
+                "Cursor does not match the sort order".to_string(),
+            ));
+        }
+        Ok(sort.to_vec())
+    }
+
+    /// Cursor values keyed by field name, for [`KeysetPaginator::build`]
+    pub fn values(&self) -> Value {
+        Value::Object(
+            self.all_keys()
+                .map(|k| (k.field.clone(), k.value.clone()))
+                .collect(),
+        )
+    }
+
+    /// SQL `ORDER BY` clause (without the keyword), checked against `sort`
+    pub fn order_by(&self, sort: &[SortColumn], backward: bool) -> crate::Result<String> {
+        Ok(self
+            .columns(sort)?
+            .iter()
+            .map(|c| {
+                let direction = if backward { c.direction.reverse() } else { c.direction };
+                format!("{} {}", c.name, direction.as_sql())
+            })
+            .collect::<Vec<_>>()
+            .join(", "))
+    }
+
+    /// Compare a row against this cursor position
+    ///
+    /// Returns `Greater` when the row sorts after the cursor, honoring each
+    /// key's direction.
+    pub fn compare_row<R: Serialize>(&self, row: &R) -> crate::Result<Ordering> {
+        let row = row_object(row)?;
+        for key in self.all_keys() {
+            let value = row.get(&key.field).ok_or_else(|| {
+                crate::GraphQLError::PaginationError(format!("Row is missing field '{}'", key.field))
+            })?;
+            let ordering = match key.direction {
+                SortDirection::Asc => compare_values(value, &key.value),
+                SortDirection::Desc => compare_values(&key.value, value),
+            };
+            if ordering != Ordering::Equal {
+                return Ok(ordering);
+            }
+        }
+        Ok(Ordering::Equal)
+    }
+
+    fn all_keys(&self) -> impl Iterator<Item = &SortKey> {
+        self.keys.iter().chain(std::iter::once(&self.tiebreaker))
+    }
+}
+
+/// Serialize a row into a JSON object
+fn row_object<R: Serialize>(row: &R) -> crate::Result<Map<String, Value>> {
+    match serde_json::to_value(row) {
+        Ok(Value::Object(object)) => Ok(object),
+        Ok(_) => Err(crate::GraphQLError::PaginationError(
+            "Row must serialize to an object".to_string(),
+        )),
+        Err(e) => Err(crate::GraphQLError::PaginationError(e.to_string())),
+    }
+}
+
+/// Order JSON values: null < bool < number < string, arrays/objects equal
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) | Value::Object(_) => 4,
+        }
+    }
+
+    match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => x
+                .as_f64()
+                .partial_cmp(&y.as_f64())
+                .unwrap_or(Ordering::Equal),
+        },
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Builds a [`Connection`] from rows fetched by a paginated query
+///
+/// Cursors are structured JSON objects keyed by sort column name, the same
+/// shape [`KeysetPaginator`] and the database adapters read from
+/// `after`/`before`.
+#[derive(Debug, Clone)]
+pub struct PageDecoder {
+    columns: Vec<SortColumn>,
+    input: PaginationInput,
+    config: PaginationConfig,
+}
+
+impl PageDecoder {
+    /// Create decoder for a page fetched with `limit_with(config) + 1`
+    pub fn new(columns: Vec<SortColumn>, input: PaginationInput, config: PaginationConfig) -> Self {
+        Self {
+            columns,
+            input,
+            config,
+        }
+    }
+
+    /// Build connection from fetched rows (in query order)
+    pub fn into_connection<T: Serialize>(self, rows: Vec<T>) -> crate::Result<Connection<T>> {
+        let rows = rows
+            .into_iter()
+            .map(|row| Ok((self.cursor_for(&row)?, row)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let connection = Connection::from_overfetched_with(rows, &self.input, &self.config, |(cursor, _)| {
+            cursor.clone()
+        });
+        Ok(connection.map(|(_, row)| row))
+    }
+
+    /// Encode the cursor for a row
+    pub fn cursor_for<T: Serialize>(&self, row: &T) -> crate::Result<String> {
+        let row = row_object(row)?;
+        let cursor: Map<String, Value> = self
+            .columns
+            .iter()
+            .map(|c| {
+                row.get(&c.name)
+                    .cloned()
+                    .map(|v| (c.name.clone(), v))
+                    .ok_or_else(|| {
+                        crate::GraphQLError::PaginationError(format!("Row is missing field '{}'", c.name))
+                    })
+            })
+            .collect::<crate::Result<_>>()?;
+
+        CursorCodec::encode_structured(&cursor)
+    }
+
+    /// Sort columns
+    pub fn columns(&self) -> &[SortColumn] {
+        &self.columns
+    }
+
+    /// Number of rows to fetch (`limit + 1`)
+    pub fn fetch_limit(&self) -> i64 {
+        self.input.limit_with(&self.config) as i64 + 1
+    }
+
+    /// Whether the page is fetched backward (`last`/`before`)
+    pub fn is_backward(&self) -> bool {
+        self.input.paginates_backward()
+    }
+
+    /// Effective sort directions for the query (reversed when backward)
+    pub fn directions(&self) -> Vec<SortDirection> {
+        let backward = self.is_backward();
+        self.columns
+            .iter()
+            .map(|c| if backward { c.direction.reverse() } else { c.direction })
+            .collect()
+    }
+
+    /// Decode the request cursor into values in column order
+    ///
+    /// Reads `before` when paginating backward and `after` otherwise.
+    pub fn cursor_values(&self) -> crate::Result<Option<Vec<Value>>> {
+        let cursor: Option<Map<String, Value>> = if self.is_backward() {
+            self.input.decode_before()?
+        } else {
+            self.input.decode_after()?
+        };
+
+        cursor
+            .map(|cursor| {
+                self.columns
+                    .iter()
+                    .map(|c| {
+                        cursor.get(&c.name).cloned().ok_or_else(|| {
+                            crate::GraphQLError::InvalidCursor(format!("Cursor is missing field '{}'", c.name))
+                        })
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_keyset_forward_uniform_direction() {
+        let paginator = KeysetPaginator::new(vec![
+            SortColumn::desc("created_at"),
+            SortColumn::desc("id"),
+        ]);
+        let cursor = json!({ "created_at": "2024-01-01T00:00:00Z", "id": "abc" });
+        let query = paginator.build(Some(&cursor), false).unwrap();
+
+        assert_eq!(query.predicate.as_deref(), Some("(created_at, id) < ($1, $2)"));
+        assert_eq!(query.params, vec![json!("2024-01-01T00:00:00Z"), json!("abc")]);
+        assert_eq!(query.order_by, "created_at DESC, id DESC");
+        assert!(!query.reverse_results);
+    }
+
+    #[test]
+    fn test_keyset_backward_mixed_direction() {
+        let paginator = KeysetPaginator::new(vec![
+            SortColumn::asc("priority"),
+            SortColumn::desc("id"),
+        ])
+        .with_param_offset(1);
+        let cursor = json!({ "priority": 3, "id": "abc" });
+        let query = paginator.build(Some(&cursor), true).unwrap();
+
+        assert_eq!(
+            query.predicate.as_deref(),
+            Some("((priority < $2) OR (priority = $2 AND (id > $3 OR id IS NULL)))")
+        );
+        assert_eq!(query.order_by, "priority DESC, id ASC");
+        assert!(query.reverse_results);
+    }
+
+    #[test]
+    fn test_keyset_typed_placeholders() {
+        let paginator = KeysetPaginator::new(vec![
+            SortColumn::desc("created_at").with_sql_type("timestamptz"),
+            SortColumn::desc("id").with_sql_type("uuid"),
+        ]);
+        let cursor = json!({ "created_at": "2024-01-01T00:00:00Z", "id": "abc" });
+        let query = paginator.build(Some(&cursor), false).unwrap();
+        assert_eq!(
+            query.predicate.as_deref(),
+            Some("(created_at, id) < ($1::timestamptz, $2::uuid)")
+        );
+    }
+
+    #[test]
+    fn test_keyset_null_cursor_values() {
+        let paginator = KeysetPaginator::new(vec![
+            SortColumn::asc("priority"),
+            SortColumn::desc("due_at").with_sql_type("timestamptz"),
+            SortColumn::asc("id"),
+        ]);
+        let cursor = json!({ "priority": 2, "due_at": null, "id": 5 });
+        let query = paginator.build(Some(&cursor), false).unwrap();
+        assert_eq!(
+            query.predicate.as_deref(),
+            Some(
+                "(((priority > $1 OR priority IS NULL)) OR \
+                 (priority = $1 AND due_at IS NOT NULL) OR \
+                 (priority = $1 AND due_at IS NULL AND (id > $2 OR id IS NULL)))"
+            )
+        );
+        assert_eq!(query.params, vec![json!(2), json!(5)]);
+
+        let paginator = KeysetPaginator::new(vec![SortColumn::desc("due_at"), SortColumn::desc("id")]);
+        let query = paginator.build(Some(&json!({ "due_at": null, "id": 5 })), false).unwrap();
+        assert_eq!(
+            query.predicate.as_deref(),
+            Some("((due_at IS NOT NULL) OR (due_at IS NULL AND id < $1))")
+        );
+
+        let query = paginator.build(Some(&json!({ "due_at": null, "id": 5 })), true).unwrap();
+        assert_eq!(
+            query.predicate.as_deref(),
+            Some("((FALSE) OR (due_at IS NULL AND (id > $1 OR id IS NULL)))")
+        );
+    }
+
+    #[test]
+    fn test_keyset_first_page_and_missing_field() {
+        let paginator = KeysetPaginator::new(vec![SortColumn::asc("id")]);
+        let query = paginator.build::<Value>(None, false).unwrap();
+        assert!(query.predicate.is_none());
+        assert!(query.params.is_empty());
+
+        let cursor = json!({ "created_at": "2024-01-01T00:00:00Z" });
+        assert!(paginator.build(Some(&cursor), false).is_err());
+    }
+
+    #[test]
+    fn test_cursor_payload_from_row_and_compare() {
+        let columns = vec![
+            SortColumn::asc("status"),
+            SortColumn::desc("priority"),
+            SortColumn::asc("id"),
+        ];
+        let payload = CursorPayload::from_row(&json!({ "status": "open", "priority": 2, "id": 10 }), &columns).unwrap();
+        assert_eq!(payload.columns(&columns).unwrap(), columns);
+        assert_eq!(
+            payload.order_by(&columns, true).unwrap(),
+            "status DESC, priority ASC, id DESC"
+        );
+
+        let later = json!({ "status": "open", "priority": 1, "id": 1 });
+        let earlier = json!({ "status": "open", "priority": 2, "id": 5 });
+        assert_eq!(payload.compare_row(&later).unwrap(), Ordering::Greater);
+        assert_eq!(payload.compare_row(&earlier).unwrap(), Ordering::Less);
+    }
+
+    #[test]
+    fn test_cursor_payload_checked_against_sort() {
+        let sort = vec![
+            SortColumn::desc("created_at").with_sql_type("timestamptz"),
+            SortColumn::desc("id").with_sql_type("uuid"),
+        ];
+        let payload = CursorPayload::new(SortKey::new("id", SortDirection::Desc, "abc"))
+            .with_key(SortKey::new("created_at", SortDirection::Desc, "2024-01-01T00:00:00Z"));
+        assert_eq!(payload.columns(&sort).unwrap(), sort);
+
+        let injected = CursorPayload::new(SortKey::new("id; DROP TABLE posts", SortDirection::Desc, "abc"))
+            .with_key(SortKey::new("created_at", SortDirection::Desc, "2024-01-01T00:00:00Z"));
+        assert!(injected.columns(&sort).is_err());
+        assert!(injected.order_by(&sort, false).is_err());
+
+        let flipped = CursorPayload::new(SortKey::new("id", SortDirection::Asc, "abc"))
+            .with_key(SortKey::new("created_at", SortDirection::Desc, "2024-01-01T00:00:00Z"));
+        assert!(flipped.columns(&sort).is_err());
+        assert!(CursorPayload::new(SortKey::new("id", SortDirection::Desc, "abc")).columns(&sort).is_err());
+    }
+
+    #[test]
+    fn test_cursor_payload_structured_roundtrip() {
+        let payload = CursorPayload::new(SortKey::new("id", SortDirection::Asc, "abc"))
+            .with_key(SortKey::new("created_at", SortDirection::Desc, "2024-01-01T00:00:00Z"));
+        let cursor = crate::pagination::CursorCodec::encode_structured(&payload).unwrap();
+        let decoded: CursorPayload = crate::pagination::CursorCodec::decode_structured(&cursor).unwrap();
+        assert_eq!(decoded, payload);
+    }
+}
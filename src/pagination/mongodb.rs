@@ -0,0 +1,268 @@
+//! MongoDB cursor pagination adapter
+//!
+//! Translates [`PaginationInput`] and structured cursors into Mongo filter
+//! documents (`{_id: {$gt: ...}}`) and sort specs, replacing skip/limit.
+//!
+//! Cursor values are stored as relaxed extended JSON, so `ObjectId` and
+//! `DateTime` fields round-trip through cursors with their BSON types.
+//!
+//! Mongo sorts null and missing fields before every other value, so null
+//! cursor values seek with `$ne: null` ascending and descending seeks also
+//! match the trailing nulls.
+
+use bson::{doc, Bson, Document};
+use serde_json::Value;
+
+use super::{PageDecoder, PaginationConfig, PaginationInput, SortColumn, SortDirection};
+
+/// Filter, sort and limit for a paginated `find`
+#[derive(Debug, Clone)]
+pub struct MongoPage {
+    /// Keyset filter, empty on the first page
+    ///
+    /// Combine with the service's own filter via `$and`.
+    pub filter: Document,
+
+    /// Sort specification (`{field: 1 | -1}`)
+    pub sort: Document,
+
+    /// Number of documents to fetch (`limit + 1`)
+    pub limit: i64,
+
+    /// Builds the connection from returned documents
+    pub decoder: PageDecoder,
+}
+
+/// Build Mongo filter and sort for a page
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::pagination::{mongodb::paginate_find, SortColumn};
+///
+/// let page = paginate_find(&input, &[SortColumn::desc("created_at"), SortColumn::asc("_id")])?;
+/// let options = FindOptions::builder().sort(page.sort.clone()).limit(page.limit).build();
+/// let filter = doc! { "$and": [{ "company_id": company_id }, page.filter.clone()] };
+/// let products: Vec<Product> = collection.find(filter).with_options(options).await?.try_collect().await?;
+/// let connection = page.decoder.into_connection(products)?;
+/// ```
+pub fn paginate_find(input: &PaginationInput, sort: &[SortColumn]) -> crate::Result<MongoPage> {
+    paginate_find_with(input, sort, &PaginationConfig::default())
+}
+
+/// Build Mongo filter and sort using custom page size limits
+pub fn paginate_find_with(
+    input: &PaginationInput,
+    sort: &[SortColumn],
+    config: &PaginationConfig,
+) -> crate::Result<MongoPage> {
+    input.validate_with(config)?;
+
+    if sort.is_empty() {
+        return Err(crate::GraphQLError::PaginationError(
+            "Keyset pagination requires at least one sort column".to_string(),
+        ));
+    }
+
+    let decoder = PageDecoder::new(sort.to_vec(), input.clone(), *config);
+    let directions = decoder.directions();
+
+    let filter = match decoder.cursor_values()? {
+        Some(values) => {
+            let values = values
+                .into_iter()
+                .map(to_bson)
+                .collect::<crate::Result<Vec<_>>>()?;
+            seek_filter(sort, &directions, &values)
+        }
+        None => Document::new(),
+    };
+
+    let mut sort_spec = Document::new();
+    for (column, direction) in sort.iter().zip(&directions) {
+        let order = match direction {
+            SortDirection::Asc => 1,
+            SortDirection::Desc => -1,
+        };
+        sort_spec.insert(column.name.clone(), order);
+    }
+
+    Ok(MongoPage {
+        filter,
+        sort: sort_spec,
+        limit: decoder.fetch_limit(),
+        decoder,
+    })
+}
+
+/// `{$or: [{a: {$gt: x}}, {a: x, b: {$lt: y}}, ...]}`
+fn seek_filter(sort: &[SortColumn], directions: &[SortDirection], values: &[Bson]) -> Document {
+    let branches: Vec<Bson> = sort
+        .iter()
+        .zip(directions)
+        .zip(values)
+        .enumerate()
+        .filter_map(|(i, ((column, direction), value))| {
+            let mut branch = Document::new();
+            for (prev, prev_value) in sort[..i].iter().zip(values) {
+                branch.insert(prev.name.clone(), prev_value.clone());
+            }
+            let name = column.name.clone();
+            match (direction, value) {
+                (SortDirection::Asc, Bson::Null) => {
+                    branch.insert(name, doc! { "$ne": null });
+                }
+                (SortDirection::Asc, value) => {
+                    branch.insert(name, doc! { "$gt": value.clone() });
+                }
+                // Nothing sorts before the leading nulls
+                (SortDirection::Desc, Bson::Null) => return None,
+                (SortDirection::Desc, value) => {
+                    let below = doc! { &name: { "$lt": value.clone() } };
+                    branch.insert("$or", vec![Bson::Document(below), doc! { name: null }.into()]);
+                }
+            }
+            Some(Bson::Document(branch))
+        })
+        .collect();
+
+    match branches.len() {
+        // Matches no document
+        0 => doc! { "$expr": false },
+        1 => match branches.into_iter().next() {
+            Some(Bson::Document(branch)) => branch,
+            _ => Document::new(),
+        },
+        _ => doc! { "$or": branches },
+    }
+}
+
+/// Convert an extended-JSON cursor value into BSON
+///
+/// Only scalars are accepted: a client-forged document or array would be
+/// spliced into the filter as query operators (e.g. `{$ne: null}`).
+fn to_bson(value: Value) -> crate::Result<Bson> {
+    let value =
+        Bson::try_from(value).map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))?;
+    match value {
+        Bson::Null
+        | Bson::Boolean(_)
+        | Bson::Int32(_)
+        | Bson::Int64(_)
+        | Bson::Double(_)
+        | Bson::Decimal128(_)
+        | Bson::String(_)
+        | Bson::ObjectId(_)
+        | Bson::DateTime(_) => Ok(value),
+        _ => Err(crate::GraphQLError::InvalidCursor(
+            "Cursor values must be scalars".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pagination::CursorCodec;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn test_paginate_find_object_id_cursor() {
+        let id = ObjectId::new();
+        let after = CursorCodec::encode_structured(&serde_json::json!({ "_id": { "$oid": id.to_hex() } })).unwrap();
+        let input = PaginationInput {
+            first: Some(10),
+            after: Some(after),
+            last: None,
+            before: None,
+        };
+
+        let page = paginate_find(&input, &[SortColumn::asc("_id")]).unwrap();
+        assert_eq!(page.filter, doc! { "_id": { "$gt": id } });
+        assert_eq!(page.sort, doc! { "_id": 1 });
+        assert_eq!(page.limit, 11);
+    }
+
+    #[test]
+    fn test_paginate_find_backward_multi_column() {
+        let before = CursorCodec::encode_structured(&serde_json::json!({ "name": "m", "sku": "a" })).unwrap();
+        let input = PaginationInput {
+            first: None,
+            after: None,
+            last: Some(5),
+            before: Some(before),
+        };
+
+        let page = paginate_find(&input, &[SortColumn::desc("name"), SortColumn::asc("sku")]).unwrap();
+        assert_eq!(
+            page.filter,
+            doc! {
+                "$or": [
+                    { "name": { "$gt": "m" } },
+                    { "name": "m", "$or": [{ "sku": { "$lt": "a" } }, { "sku": null }] },
+                ]
+            }
+        );
+        assert_eq!(page.sort, doc! { "name": 1, "sku": -1 });
+    }
+
+    #[test]
+    fn test_paginate_find_null_cursor_values() {
+        let page_after = |sort: &[SortColumn]| {
+            let after = CursorCodec::encode_structured(&serde_json::json!({ "due": null, "_id": 7 })).unwrap();
+            let input = PaginationInput {
+                first: Some(10),
+                after: Some(after),
+                last: None,
+                before: None,
+            };
+            paginate_find(&input, sort).unwrap().filter
+        };
+
+        // Ascending: past the leading nulls onto the non-null values
+        assert_eq!(
+            page_after(&[SortColumn::asc("due"), SortColumn::asc("_id")]),
+            doc! { "$or": [{ "due": { "$ne": null } }, { "due": null, "_id": { "$gt": 7 } }] }
+        );
+
+        // Descending: nulls come last, so only the tie on null remains
+        assert_eq!(
+            page_after(&[SortColumn::desc("due"), SortColumn::asc("_id")]),
+            doc! { "due": null, "_id": { "$gt": 7 } }
+        );
+        assert_eq!(page_after(&[SortColumn::desc("due")]), doc! { "$expr": false });
+
+        // Descending past a value reaches the null block
+        let after = CursorCodec::encode_structured(&serde_json::json!({ "due": 3 })).unwrap();
+        let input = PaginationInput {
+            first: Some(10),
+            after: Some(after),
+            last: None,
+            before: None,
+        };
+        assert_eq!(
+            paginate_find(&input, &[SortColumn::desc("due")]).unwrap().filter,
+            doc! { "$or": [{ "due": { "$lt": 3 } }, { "due": null }] }
+        );
+    }
+
+    #[test]
+    fn test_paginate_find_rejects_operator_cursor() {
+        for forged in [
+            serde_json::json!({ "name": { "$ne": null } }),
+            serde_json::json!({ "name": ["a", "b"] }),
+            serde_json::json!({ "name": { "$regularExpression": { "pattern": ".*", "options": "" } } }),
+        ] {
+            let after = CursorCodec::encode_structured(&forged).unwrap();
+            let input = PaginationInput {
+                first: Some(10),
+                after: Some(after),
+                last: None,
+                before: None,
+            };
+
+            let result = paginate_find(&input, &[SortColumn::asc("name")]);
+            assert!(matches!(result, Err(crate::GraphQLError::InvalidCursor(_))));
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Page-number (offset) pagination
+//!
+//! For admin dashboards that need classic numbered pages and total page
+//! counts rather than Relay cursors.
+
+use async_graphql::{InputObject, Object};
+
+use super::PaginationConfig;
+
+/// Page-number pagination input
+#[derive(InputObject, Debug, Clone, Default)]
+pub struct OffsetPaginationInput {
+    /// Page number, starting at 1
+    pub page: Option<i32>,
+
+    /// Number of items per page
+    pub per_page: Option<i32>,
+}
+
+impl OffsetPaginationInput {
+    /// Validate pagination input
+    pub fn validate(&self) -> crate::Result<()> {
+        self.validate_with(&PaginationConfig::default())
+    }
+
+    /// Validate pagination input against custom page size limits
+    pub fn validate_with(&self, config: &PaginationConfig) -> crate::Result<()> {
+        if let Some(page) = self.page {
+            if page < 1 {
+                return Err(crate::GraphQLError::PaginationError(
+                    "'page' must be at least 1".to_string(),
+                ));
+            }
+        }
+
+        if let Some(per_page) = self.per_page {
+            if per_page < 1 {
+                return Err(crate::GraphQLError::PaginationError(
+                    "'perPage' must be at least 1".to_string(),
+                ));
+            }
+            if per_page > config.max_page_size {
+                return Err(crate::GraphQLError::PaginationError(format!(
+                    "'perPage' cannot exceed {}",
+                    config.max_page_size
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Current page number (1-based)
+    pub fn page(&self) -> i32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    /// Get `LIMIT` for database query
+    pub fn limit(&self) -> i32 {
+        self.limit_with(&PaginationConfig::default())
+    }
+
+    /// Get `LIMIT` for database query using custom page size limits
+    pub fn limit_with(&self, config: &PaginationConfig) -> i32 {
+        self.per_page
+            .unwrap_or(config.default_page_size)
+            .clamp(1, config.max_page_size.max(1))
+    }
+
+    /// Get `OFFSET` for database query
+    pub fn offset(&self) -> i64 {
+        self.offset_with(&PaginationConfig::default())
+    }
+
+    /// Get `OFFSET` for database query using custom page size limits
+    pub fn offset_with(&self, config: &PaginationConfig) -> i64 {
+        (self.page() as i64 - 1) * self.limit_with(config) as i64
+    }
+}
+
+/// Page of results with page-number metadata
+#[derive(Debug, Clone)]
+pub struct OffsetPage<T> {
+    pub items: Vec<T>,
+    pub page: i32,
+    pub per_page: i32,
+    pub total_count: i64,
+}
+
+#[Object]
+impl<T: async_graphql::OutputType> OffsetPage<T> {
+    async fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    async fn page(&self) -> i32 {
+        self.page
+    }
+
+    async fn per_page(&self) -> i32 {
+        self.per_page
+    }
+
+    async fn total_count(&self) -> i64 {
+        self.total_count
+    }
+
+    async fn total_pages(&self) -> i64 {
+        self.page_count()
+    }
+
+    async fn has_next_page(&self) -> bool {
+        self.has_next()
+    }
+
+    async fn has_previous_page(&self) -> bool {
+        self.has_previous()
+    }
+}
+
+impl<T> OffsetPage<T> {
+    /// Create page from fetched items and total row count
+    pub fn new(items: Vec<T>, input: &OffsetPaginationInput, total_count: i64) -> Self {
+        Self::with_config(items, input, &PaginationConfig::default(), total_count)
+    }
+
+    /// Create page using custom page size limits
+    pub fn with_config(
+        items: Vec<T>,
+        input: &OffsetPaginationInput,
+        config: &PaginationConfig,
+        total_count: i64,
+    ) -> Self {
+        Self {
+            items,
+            page: input.page(),
+            per_page: input.limit_with(config),
+            total_count,
+        }
+    }
+
+    /// Total number of pages
+    pub fn page_count(&self) -> i64 {
+        if self.per_page <= 0 {
+            return 0;
+        }
+        let per_page = self.per_page as i64;
+        (self.total_count + per_page - 1) / per_page
+    }
+
+    /// Whether a later page exists
+    pub fn has_next(&self) -> bool {
+        (self.page as i64) < self.page_count()
+    }
+
+    /// Whether an earlier page exists
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_input_limit_offset() {
+        let input = OffsetPaginationInput {
+            page: Some(3),
+            per_page: Some(25),
+        };
+        assert!(input.validate().is_ok());
+        assert_eq!(input.limit(), 25);
+        assert_eq!(input.offset(), 50);
+
+        let invalid = OffsetPaginationInput {
+            page: Some(0),
+            per_page: Some(500),
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_offset_page_metadata() {
+        let input = OffsetPaginationInput {
+            page: Some(2),
+            per_page: Some(10),
+        };
+        let page = OffsetPage::new(vec![1; 10], &input, 25);
+        assert_eq!(page.page_count(), 3);
+        assert!(page.has_next());
+        assert!(page.has_previous());
+
+        let last = OffsetPage::new(vec![1; 5], &OffsetPaginationInput { page: Some(3), ..input }, 25);
+        assert!(!last.has_next());
+    }
+}
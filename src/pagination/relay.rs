@@ -0,0 +1,120 @@
+//! Relay cursor algorithms over in-memory edges
+//!
+//! Implements `ApplyCursorsToEdges` and `EdgesToReturn` from the Relay Cursor
+//! Connections Specification:
+//! https://relay.dev/graphql/connections.htm#sec-Pagination-algorithm
+
+use super::{Connection, Edge, PaginationInput};
+
+/// Paginate an in-memory slice of edges
+///
+/// Applies `after`/`before` by cursor equality, then trims to `first`/`last`.
+/// Cursors that don't match any edge are ignored, as the spec allows.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{relay, Edge, PaginationInput};
+///
+/// let edges: Vec<Edge<i32>> = (1..=5)
+///     .map(|n| Edge { cursor: n.to_string(), node: n })
+///     .collect();
+/// let input = PaginationInput { first: Some(2), after: Some("2".into()), ..Default::default() };
+///
+/// let conn = relay::paginate_slice(edges, &input).unwrap();
+/// assert_eq!(conn.edges.len(), 2);
+/// assert!(conn.page_info.has_next_page);
+/// assert!(conn.page_info.has_previous_page);
+/// ```
+pub fn paginate_slice<T>(all_edges: Vec<Edge<T>>, input: &PaginationInput) -> crate::Result<Connection<T>> {
+    input.validate()?;
+
+    let total = all_edges.len();
+    let (start, end) = apply_cursors(&all_edges, input.after.as_deref(), input.before.as_deref());
+
+    let mut edges: Vec<Edge<T>> = all_edges.into_iter().skip(start).take(end - start).collect();
+    let cursor_count = edges.len();
+
+    if let Some(first) = input.first {
+        edges.truncate(first as usize);
+    }
+
+    if let Some(last) = input.last {
+        let last = last as usize;
+        if edges.len() > last {
+            edges.drain(..edges.len() - last);
+        }
+    }
+
+    let has_previous = match input.last {
+        Some(last) => cursor_count > last as usize,
+        None => input.after.is_some() && start > 0,
+    };
+
+    let has_next = match input.first {
+        Some(first) => cursor_count > first as usize,
+        None => input.before.is_some() && end < total,
+    };
+
+    Ok(Connection::from_edges(edges, has_next, has_previous))
+}
+
+/// `ApplyCursorsToEdges`: returns the `[start, end)` window between cursors
+fn apply_cursors<T>(edges: &[Edge<T>], after: Option<&str>, before: Option<&str>) -> (usize, usize) {
+    let position = |cursor: &str| edges.iter().position(|e| e.cursor == cursor);
+
+    let start = after.and_then(position).map(|idx| idx + 1).unwrap_or(0);
+    let end = before
+        .and_then(position)
+        .unwrap_or(edges.len())
+        .max(start);
+
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(n: i32) -> Vec<Edge<i32>> {
+        (1..=n)
+            .map(|i| Edge {
+                cursor: i.to_string(),
+                node: i,
+            })
+            .collect()
+    }
+
+    fn nodes(conn: &Connection<i32>) -> Vec<i32> {
+        conn.edges.iter().map(|e| e.node).collect()
+    }
+
+    #[test]
+    fn test_paginate_slice_last_before() {
+        let input = PaginationInput {
+            first: None,
+            after: None,
+            last: Some(2),
+            before: Some("4".to_string()),
+        };
+        let conn = paginate_slice(edges(5), &input).unwrap();
+        assert_eq!(nodes(&conn), vec![2, 3]);
+        assert!(conn.page_info.has_previous_page);
+        assert!(conn.page_info.has_next_page);
+        assert_eq!(conn.page_info.start_cursor.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_paginate_slice_unknown_cursor_and_tail() {
+        let input = PaginationInput {
+            first: Some(10),
+            after: Some("missing".to_string()),
+            last: None,
+            before: None,
+        };
+        let conn = paginate_slice(edges(3), &input).unwrap();
+        assert_eq!(nodes(&conn), vec![1, 2, 3]);
+        assert!(!conn.page_info.has_next_page);
+        assert!(!conn.page_info.has_previous_page);
+    }
+}
@@ -0,0 +1,211 @@
+//! SeaORM pagination adapter
+//!
+//! Applies keyset conditions, ordering and `limit + 1` to a `Select<E>` and
+//! builds a [`Connection`] of models from the result. The condition is
+//! [`KeysetPaginator`](super::KeysetPaginator)'s Postgres predicate.
+
+use ::sea_orm::sea_query::{Alias, Expr, SimpleExpr};
+use ::sea_orm::{
+    ConnectionTrait, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, Select,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::keyset::SeekPart;
+use super::{
+    Connection, KeysetPaginator, PageDecoder, PaginationConfig, PaginationInput, SortColumn,
+    SortDirection,
+};
+
+/// Fetch one page of entities with keyset pagination
+///
+/// Sort column names must match both the database columns and the model's
+/// serialized field names, since cursors are read back from the models.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::pagination::{sea_orm::paginate_select, SortColumn};
+///
+/// let select = post::Entity::find().filter(post::Column::CompanyId.eq(company_id));
+/// let connection = paginate_select(
+///     &db,
+///     select,
+///     &input,
+///     &[SortColumn::desc("created_at"), SortColumn::desc("id").with_sql_type("uuid")],
+/// )
+/// .await?;
+/// ```
+pub async fn paginate_select<E, C>(
+    db: &C,
+    select: Select<E>,
+    input: &PaginationInput,
+    sort: &[SortColumn],
+) -> crate::Result<Connection<E::Model>>
+where
+    E: EntityTrait,
+    E::Model: Serialize,
+    C: ConnectionTrait,
+{
+    paginate_select_with(db, select, input, sort, &PaginationConfig::default()).await
+}
+
+/// Fetch one page of entities using custom page size limits
+pub async fn paginate_select_with<E, C>(
+    db: &C,
+    select: Select<E>,
+    input: &PaginationInput,
+    sort: &[SortColumn],
+    config: &PaginationConfig,
+) -> crate::Result<Connection<E::Model>>
+where
+    E: EntityTrait,
+    E::Model: Serialize,
+    C: ConnectionTrait,
+{
+    let (select, decoder) = apply_keyset(select, input, sort, config)?;
+    let models = select
+        .all(db)
+        .await
+        .map_err(|e| crate::GraphQLError::DatabaseError(e.to_string()))?;
+
+    decoder.into_connection(models)
+}
+
+/// Apply keyset conditions, ordering and limit without executing
+pub fn apply_keyset<E: EntityTrait>(
+    mut select: Select<E>,
+    input: &PaginationInput,
+    sort: &[SortColumn],
+    config: &PaginationConfig,
+) -> crate::Result<(Select<E>, PageDecoder)> {
+    input.validate_with(config)?;
+
+    if sort.is_empty() {
+        return Err(crate::GraphQLError::PaginationError(
+            "Keyset pagination requires at least one sort column".to_string(),
+        ));
+    }
+
+    let decoder = PageDecoder::new(sort.to_vec(), input.clone(), *config);
+    let directions = decoder.directions();
+
+    if let Some(values) = decoder.cursor_values()? {
+        select = select.filter(keyset_condition(sort, &directions, &values)?);
+    }
+
+    for (column, direction) in sort.iter().zip(&directions) {
+        let order = match direction {
+            SortDirection::Asc => Order::Asc,
+            SortDirection::Desc => Order::Desc,
+        };
+        select = select.order_by(column_expr(column), order);
+    }
+
+    let select = select.limit(decoder.fetch_limit() as u64);
+    Ok((select, decoder))
+}
+
+/// [`KeysetPaginator`]'s seek predicate, binding cursor values with the
+/// columns' casts
+fn keyset_condition(
+    sort: &[SortColumn],
+    directions: &[SortDirection],
+    values: &[Value],
+) -> crate::Result<SimpleExpr> {
+    let paginator = KeysetPaginator::new(sort.to_vec());
+    let mut sql = String::new();
+    let mut exprs = Vec::new();
+    // Numbered placeholder per bound column
+    let mut placeholders = vec![None; sort.len()];
+    for part in paginator.seek_parts(directions, values) {
+        match part {
+            SeekPart::Sql(text) => sql.push_str(&text),
+            SeekPart::Bind(i) => {
+                let number = match placeholders[i] {
+                    Some(number) => number,
+                    None => {
+                        exprs.push(value_expr(&sort[i], &values[i])?);
+                        placeholders[i] = Some(exprs.len());
+                        exprs.len()
+                    }
+                };
+                sql.push_str(&format!("${number}"));
+            }
+        }
+    }
+    Ok(Expr::cust_with_exprs(sql, exprs))
+}
+
+fn column_expr(column: &SortColumn) -> SimpleExpr {
+    Expr::col(Alias::new(column.name.as_str())).into()
+}
+
+/// Convert a cursor value into a bound expression with the column's cast
+fn value_expr(column: &SortColumn, value: &Value) -> crate::Result<SimpleExpr> {
+    let expr = match value {
+        Value::String(s) => Expr::val(s.clone()),
+        Value::Bool(b) => Expr::val(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Expr::val(i),
+            None => Expr::val(n.as_f64().unwrap_or_default()),
+        },
+        Value::Null | Value::Array(_) | Value::Object(_) => {
+            return Err(crate::GraphQLError::InvalidCursor(format!(
+                "Unsupported cursor value for '{}'",
+                column.name
+            )));
+        }
+    };
+
+    Ok(match &column.sql_type {
+        Some(sql_type) => expr.cast_as(Alias::new(sql_type.as_str())),
+        None => expr.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::sea_orm::sea_query::{Asterisk, ConditionalStatement, PostgresQueryBuilder, Query};
+    use serde_json::json;
+
+    #[test]
+    fn test_keyset_condition_sql() {
+        let sort = [SortColumn::desc("created_at").with_sql_type("timestamptz"), SortColumn::asc("id")];
+        let directions = [SortDirection::Desc, SortDirection::Asc];
+        let values = [json!("2024-01-01T00:00:00Z"), json!(5)];
+
+        let condition = keyset_condition(&sort, &directions, &values).unwrap();
+        let sql = Query::select()
+            .column(Asterisk)
+            .from(Alias::new("posts"))
+            .cond_where(condition)
+            .to_string(PostgresQueryBuilder);
+
+        assert!(sql.contains("created_at < CAST('2024-01-01T00:00:00Z' AS timestamptz)"));
+        assert!(sql.contains("created_at = CAST('2024-01-01T00:00:00Z' AS timestamptz) AND"));
+        assert!(sql.contains("id > 5 OR id IS NULL"));
+        assert!(sql.contains(" OR "));
+    }
+
+    #[test]
+    fn test_keyset_condition_null_values() {
+        let sort = [SortColumn::asc("priority"), SortColumn::desc("due_at"), SortColumn::asc("id")];
+        let directions = [SortDirection::Asc, SortDirection::Desc, SortDirection::Asc];
+        let values = [json!(2), Value::Null, json!(5)];
+
+        let condition = keyset_condition(&sort, &directions, &values).unwrap();
+        let sql = Query::select()
+            .column(Asterisk)
+            .from(Alias::new("tasks"))
+            .cond_where(condition)
+            .to_string(PostgresQueryBuilder);
+
+        assert!(sql.contains("priority > 2 OR priority IS NULL"));
+        assert!(sql.contains("priority = 2 AND due_at IS NOT NULL"));
+        assert!(sql.contains("due_at IS NULL AND"));
+        assert!(sql.contains("id > 5 OR id IS NULL"));
+        assert!(!sql.contains("= NULL"));
+    }
+}
@@ -0,0 +1,224 @@
+//! sqlx `QueryBuilder` integration
+//!
+//! Appends keyset `WHERE`/`ORDER BY`/`LIMIT` clauses to a Postgres query and
+//! builds the resulting [`Connection`](super::Connection) from the fetched rows.
+
+use ::sqlx::{Postgres, QueryBuilder};
+use serde_json::Value;
+
+use super::keyset::SeekPart;
+use super::{KeysetPaginator, PageDecoder, PaginationConfig, PaginationInput, SortColumn};
+
+/// Append keyset pagination clauses to a query
+///
+/// The builder must end inside a `WHERE` clause (use `WHERE TRUE` when there
+/// is no filter); the keyset predicate is appended with `AND`, followed by
+/// `ORDER BY` and `LIMIT limit + 1`. The predicate is
+/// [`KeysetPaginator`]'s, null cursor values included.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use pleme_graphql_helpers::pagination::{sqlx::paginate_query, PaginationInput, SortColumn};
+/// use sqlx::{FromRow, PgPool, QueryBuilder};
+///
+/// #[derive(FromRow, serde::Serialize)]
+/// struct Post { id: uuid::Uuid, created_at: chrono::DateTime<chrono::Utc> }
+///
+/// # async fn example(pool: PgPool, input: PaginationInput, company_id: uuid::Uuid) -> pleme_graphql_helpers::Result<()> {
+/// let sort = [
+///     SortColumn::desc("created_at").with_sql_type("timestamptz"),
+///     SortColumn::desc("id").with_sql_type("uuid"),
+/// ];
+/// let mut builder = QueryBuilder::new("SELECT id, created_at FROM posts WHERE company_id = ");
+/// builder.push_bind(company_id);
+///
+/// let decoder = paginate_query(&mut builder, &input, &sort)?;
+/// let rows: Vec<Post> = builder.build_query_as().fetch_all(&pool).await.unwrap();
+/// let connection = decoder.into_connection(rows)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn paginate_query(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    input: &PaginationInput,
+    sort: &[SortColumn],
+) -> crate::Result<PageDecoder> {
+    paginate_query_with(builder, input, sort, &PaginationConfig::default())
+}
+
+/// Append keyset pagination clauses using custom page size limits
+pub fn paginate_query_with(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    input: &PaginationInput,
+    sort: &[SortColumn],
+    config: &PaginationConfig,
+) -> crate::Result<PageDecoder> {
+    input.validate_with(config)?;
+
+    if sort.is_empty() {
+        return Err(crate::GraphQLError::PaginationError(
+            "Keyset pagination requires at least one sort column".to_string(),
+        ));
+    }
+
+    let decoder = PageDecoder::new(sort.to_vec(), input.clone(), *config);
+    let directions = decoder.directions();
+
+    if let Some(values) = decoder.cursor_values()? {
+        builder.push(" AND ");
+        let paginator = KeysetPaginator::new(sort.to_vec());
+        for part in paginator.seek_parts(&directions, &values) {
+            match part {
+                SeekPart::Sql(sql) => {
+                    builder.push(sql);
+                }
+                SeekPart::Bind(i) => push_value(builder, &sort[i], &values[i])?,
+            }
+        }
+    }
+
+    builder.push(" ORDER BY ");
+    for (idx, (column, direction)) in sort.iter().zip(&directions).enumerate() {
+        if idx > 0 {
+            builder.push(", ");
+        }
+        builder.push(&column.name).push(" ").push(direction.as_sql());
+    }
+
+    builder.push(" LIMIT ");
+    builder.push_bind(decoder.fetch_limit());
+
+    Ok(decoder)
+}
+
+/// Bind a cursor value with the column's cast
+fn push_value(builder: &mut QueryBuilder<'_, Postgres>, column: &SortColumn, value: &Value) -> crate::Result<()> {
+    match value {
+        Value::String(s) => {
+            builder.push_bind(s.clone());
+        }
+        Value::Bool(b) => {
+            builder.push_bind(*b);
+        }
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => {
+                builder.push_bind(i);
+            }
+            None => {
+                builder.push_bind(n.as_f64().unwrap_or_default());
+            }
+        },
+        Value::Null | Value::Array(_) | Value::Object(_) => {
+            return Err(crate::GraphQLError::InvalidCursor(format!(
+                "Unsupported cursor value for '{}'",
+                column.name
+            )));
+        }
+    }
+    builder.push(column.cast_suffix());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pagination::CursorCodec;
+    use serde::Serialize;
+
+    #[test]
+    fn test_paginate_query_sql() {
+        let sort = [
+            SortColumn::desc("created_at").with_sql_type("timestamptz"),
+            SortColumn::desc("id"),
+        ];
+        let input = PaginationInput {
+            first: Some(10),
+            after: Some(
+                CursorCodec::encode_structured(&serde_json::json!({
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "id": 5,
+                }))
+                .unwrap(),
+            ),
+            last: None,
+            before: None,
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM posts WHERE TRUE");
+        paginate_query(&mut builder, &input, &sort).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM posts WHERE TRUE AND (created_at, id) < ($1::timestamptz, $2) \
+             ORDER BY created_at DESC, id DESC LIMIT $3"
+        );
+    }
+
+    #[test]
+    fn test_paginate_query_null_cursor_values() {
+        let sort = [SortColumn::asc("priority"), SortColumn::desc("due_at"), SortColumn::asc("id")];
+        let input = PaginationInput {
+            first: Some(10),
+            after: Some(
+                CursorCodec::encode_structured(&serde_json::json!({
+                    "priority": 2,
+                    "due_at": null,
+                    "id": 5,
+                }))
+                .unwrap(),
+            ),
+            last: None,
+            before: None,
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM tasks WHERE TRUE");
+        paginate_query(&mut builder, &input, &sort).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM tasks WHERE TRUE AND (((priority > $1 OR priority IS NULL)) OR \
+             (priority = $2 AND due_at IS NOT NULL) OR \
+             (priority = $3 AND due_at IS NULL AND (id > $4 OR id IS NULL))) \
+             ORDER BY priority ASC, due_at DESC, id ASC LIMIT $5"
+        );
+
+        let input = PaginationInput {
+            first: Some(10),
+            after: Some(CursorCodec::encode_structured(&serde_json::json!({ "priority": null })).unwrap()),
+            last: None,
+            before: None,
+        };
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM tasks WHERE TRUE");
+        paginate_query(&mut builder, &input, &[SortColumn::asc("priority")]).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM tasks WHERE TRUE AND ((FALSE)) ORDER BY priority ASC LIMIT $1"
+        );
+    }
+
+    #[test]
+    fn test_page_decoder_cursors() {
+        #[derive(Serialize)]
+        struct Row {
+            id: i64,
+            title: String,
+        }
+
+        let input = PaginationInput {
+            first: Some(1),
+            ..Default::default()
+        };
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM posts WHERE TRUE");
+        let decoder = paginate_query(&mut builder, &input, &[SortColumn::asc("id")]).unwrap();
+
+        let rows = vec![
+            Row { id: 1, title: "a".to_string() },
+            Row { id: 2, title: "b".to_string() },
+        ];
+        let conn = decoder.into_connection(rows).unwrap();
+        assert_eq!(conn.edges.len(), 1);
+        assert!(conn.page_info.has_next_page);
+
+        let cursor: Value = CursorCodec::decode_structured(&conn.edges[0].cursor).unwrap();
+        assert_eq!(cursor, serde_json::json!({ "id": 1 }));
+    }
+}
@@ -0,0 +1,129 @@
+//! Versioned cursors and schema migration
+//!
+//! Cursors are wrapped in a `{v, payload}` envelope so services can change
+//! their cursor shape (e.g. add a sort field) and upgrade cursors still held
+//! by clients instead of failing with opaque decode errors.
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::CursorCodec;
+
+/// Versioned cursor envelope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CursorEnvelope<T> {
+    /// Cursor schema version
+    pub v: u8,
+    pub payload: T,
+}
+
+/// Upgrades cursors written by older cursor schema versions
+///
+/// Cursors without an envelope (written before versioning was adopted) are
+/// reported as version `0`.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::pagination::{CursorCodec, CursorMigrator};
+/// use serde::{Deserialize, Serialize};
+/// use serde_json::Value;
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct PostCursor { created_at: String, id: u64 }
+///
+/// struct PostCursorMigrator;
+///
+/// impl CursorMigrator<PostCursor> for PostCursorMigrator {
+///     fn current_version(&self) -> u8 { 2 }
+///
+///     fn migrate(&self, _version: u8, payload: Value) -> pleme_graphql_helpers::Result<PostCursor> {
+///         // v1 cursors only carried the id
+///         let id = payload.as_u64().unwrap_or_default();
+///         Ok(PostCursor { created_at: "1970-01-01T00:00:00Z".into(), id })
+///     }
+/// }
+///
+/// let old = CursorCodec::encode_versioned(1, &7u64).unwrap();
+/// let cursor: PostCursor = CursorCodec::decode_versioned(&old, &PostCursorMigrator).unwrap();
+/// assert_eq!(cursor.id, 7);
+/// ```
+pub trait CursorMigrator<T> {
+    /// Version written by [`CursorCodec::encode_versioned`] today
+    fn current_version(&self) -> u8;
+
+    /// Convert a payload from an older `version` into the current shape
+    fn migrate(&self, version: u8, payload: Value) -> crate::Result<T>;
+}
+
+impl CursorCodec {
+    /// Encode structured cursor in a versioned envelope
+    pub fn encode_versioned<T: Serialize>(version: u8, value: &T) -> crate::Result<String> {
+        Self::encode_structured(&CursorEnvelope { v: version, payload: value })
+    }
+
+    /// Decode versioned cursor, migrating older versions
+    ///
+    /// Cursors newer than `migrator.current_version()` are rejected.
+    pub fn decode_versioned<T, M>(cursor: &str, migrator: &M) -> crate::Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+        M: CursorMigrator<T>,
+    {
+        let value: Value = Self::decode_structured(cursor)?;
+        let (version, payload) = match serde_json::from_value::<CursorEnvelope<Value>>(value.clone()) {
+            Ok(envelope) => (envelope.v, envelope.payload),
+            Err(_) => (0, value),
+        };
+
+        let current = migrator.current_version();
+        if version == current {
+            serde_json::from_value(payload)
+                .map_err(|e| crate::GraphQLError::InvalidCursor(e.to_string()))
+        } else if version < current {
+            migrator.migrate(version, payload)
+        } else {
+            Err(crate::GraphQLError::InvalidCursor(format!(
+                "Unsupported cursor version {}",
+                version
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct IdMigrator;
+
+    impl CursorMigrator<(String, u64)> for IdMigrator {
+        fn current_version(&self) -> u8 {
+            1
+        }
+
+        fn migrate(&self, version: u8, payload: Value) -> crate::Result<(String, u64)> {
+            match (version, payload.as_u64()) {
+                (0, Some(id)) => Ok((String::new(), id)),
+                _ => Err(crate::GraphQLError::InvalidCursor("Cannot migrate cursor".to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_versioned_cursor_current_and_legacy() {
+        let cursor = CursorCodec::encode_versioned(1, &("2024-01-01".to_string(), 3u64)).unwrap();
+        let decoded = CursorCodec::decode_versioned(&cursor, &IdMigrator).unwrap();
+        assert_eq!(decoded, ("2024-01-01".to_string(), 3));
+
+        let legacy = CursorCodec::encode_structured(&9u64).unwrap();
+        let decoded = CursorCodec::decode_versioned(&legacy, &IdMigrator).unwrap();
+        assert_eq!(decoded, (String::new(), 9));
+    }
+
+    #[test]
+    fn test_versioned_cursor_rejects_future_version() {
+        let cursor = CursorCodec::encode_versioned(5, &("x".to_string(), 1u64)).unwrap();
+        assert!(CursorCodec::decode_versioned(&cursor, &IdMigrator).is_err());
+    }
+}
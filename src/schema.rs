@@ -0,0 +1,293 @@
+//! Schema construction with the crate's recommended defaults
+//!
+//! One call gives new services a consistent setup: federation, depth and
+//! cost limits, error masking and localization, `@auth` directive
+//! enforcement, introspection and alias hardening, timeouts, slow query
+//! logging, tracing (with the `tracing` feature), service name tagging of
+//! errors, and shared [`PaginationConfig`].
+
+use async_graphql::{ObjectType, Schema, SchemaBuilder, SubscriptionType};
+use std::time::Duration;
+
+use crate::auth::AuthDirectives;
+use crate::error::MessageCatalog;
+use crate::extensions::{
+    CostAnalysis, DepthLimit, LocalizeErrors, MaskErrors, SecurityConfig, SlowQueryLog, TagErrors,
+    Timeout,
+};
+use crate::pagination::PaginationConfig;
+
+/// Default maximum query depth
+pub const DEFAULT_MAX_DEPTH: usize = 15;
+
+/// Default query cost budget
+pub const DEFAULT_MAX_COMPLEXITY: usize = 1000;
+
+/// Schema defaults
+#[derive(Debug, Clone)]
+pub struct SchemaConfig {
+    /// Enable Apollo Federation (`_service`, `_entities`)
+    pub federation: bool,
+    /// Maximum query depth, enforced by [`DepthLimit`]
+    pub max_depth: Option<usize>,
+    /// Query cost budget, enforced by [`CostAnalysis`]
+    pub max_complexity: Option<usize>,
+    /// Mask resolver errors without a `code` extension
+    pub mask_errors: bool,
+    /// Localize coded error messages per the request's `Accept-Language`
+    pub messages: Option<MessageCatalog>,
+    /// Enforce `@auth` / `@hasRole` field directives
+    pub auth_directives: bool,
+    /// Introspection, suggestions, and alias / root field limits
+    pub security: SecurityConfig,
+    /// Execution time limit per operation, enforced by [`Timeout`]
+    pub timeout: Option<Duration>,
+    /// Report operations slower than this, via [`SlowQueryLog`]
+    pub slow_query_threshold: Option<Duration>,
+    /// Add the async-graphql tracing extension (`tracing` feature only)
+    pub tracing: bool,
+    /// Pagination config registered as schema data
+    pub pagination: PaginationConfig,
+    /// Subgraph name stamped into error extensions, via [`TagErrors`]
+    pub service_name: Option<String>,
+}
+
+impl Default for SchemaConfig {
+    fn default() -> Self {
+        Self {
+            federation: true,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_complexity: Some(DEFAULT_MAX_COMPLEXITY),
+            mask_errors: true,
+            messages: None,
+            auth_directives: true,
+            security: SecurityConfig::default(),
+            timeout: None,
+            slow_query_threshold: None,
+            tracing: true,
+            pagination: PaginationConfig::default(),
+            service_name: None,
+        }
+    }
+}
+
+impl SchemaConfig {
+    /// Recommended defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable federation
+    pub fn with_federation(mut self, federation: bool) -> Self {
+        self.federation = federation;
+        self
+    }
+
+    /// Set the maximum query depth
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Set the query cost budget
+    pub fn with_max_complexity(mut self, complexity: usize) -> Self {
+        self.max_complexity = Some(complexity);
+        self
+    }
+
+    /// Enable or disable error masking
+    pub fn with_mask_errors(mut self, mask_errors: bool) -> Self {
+        self.mask_errors = mask_errors;
+        self
+    }
+
+    /// Localize coded error messages with `catalog`
+    pub fn with_messages(mut self, catalog: MessageCatalog) -> Self {
+        self.messages = Some(catalog);
+        self
+    }
+
+    /// Enable or disable `@auth` / `@hasRole` enforcement
+    pub fn with_auth_directives(mut self, auth_directives: bool) -> Self {
+        self.auth_directives = auth_directives;
+        self
+    }
+
+    /// Set introspection, suggestion, and operation shape hardening
+    pub fn with_security(mut self, security: SecurityConfig) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Cancel operations running longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Report operations slower than `threshold`
+    pub fn with_slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query_threshold = Some(threshold);
+        self
+    }
+
+    /// Enable or disable the tracing extension
+    pub fn with_tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    /// Set the pagination config registered as schema data
+    pub fn with_pagination(mut self, pagination: PaginationConfig) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// Tag errors with `service`, `code`, and `requestId` for gateways
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = Some(service_name.into());
+        self
+    }
+}
+
+/// Apply [`SchemaConfig`] defaults to a schema builder
+pub trait SchemaBuilderExt: Sized {
+    /// Apply the defaults in `config`
+    fn with_defaults(self, config: &SchemaConfig) -> Self;
+}
+
+impl<Query, Mutation, Subscription> SchemaBuilderExt
+    for SchemaBuilder<Query, Mutation, Subscription>
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    fn with_defaults(self, config: &SchemaConfig) -> Self {
+        let mut builder = self.data(config.pagination);
+
+        if config.federation {
+            builder = builder.enable_federation();
+        }
+        if !config.security.introspection {
+            builder = builder.disable_introspection();
+        }
+        if config.security != SecurityConfig::default() {
+            builder = builder.extension(config.security);
+        }
+        if let Some(depth) = config.max_depth {
+            builder = builder.extension(DepthLimit::new(depth));
+        }
+        if let Some(complexity) = config.max_complexity {
+            builder = builder.extension(CostAnalysis::new(complexity));
+        }
+        // Registered first so it tags errors after masking and localization
+        if let Some(service_name) = &config.service_name {
+            builder = builder.extension(TagErrors::new(service_name.clone()));
+        }
+        if let Some(catalog) = &config.messages {
+            builder = builder.extension(LocalizeErrors::new(catalog.clone()));
+        }
+        if config.mask_errors {
+            builder = builder.extension(MaskErrors::new());
+        }
+        if config.auth_directives {
+            builder = builder.extension(AuthDirectives);
+        }
+        // Registered before the timeout so timed-out operations are reported
+        if let Some(threshold) = config.slow_query_threshold {
+            builder = builder.extension(SlowQueryLog::new(threshold));
+        }
+        if let Some(timeout) = config.timeout {
+            builder = builder.extension(Timeout::new(timeout));
+        }
+        #[cfg(feature = "tracing")]
+        if config.tracing {
+            builder = builder.extension(async_graphql::extensions::Tracing);
+        }
+
+        builder
+    }
+}
+
+/// Schema builder with the crate's defaults applied
+///
+/// Returns the builder so services can register their own data before
+/// calling `finish()`.
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object};
+/// use pleme_graphql_helpers::schema::{build_schema, SchemaConfig};
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     async fn ping(&self) -> &str {
+///         "pong"
+///     }
+/// }
+///
+/// let schema = build_schema(Query, EmptyMutation, EmptySubscription, &SchemaConfig::new())
+///     .finish();
+/// ```
+pub fn build_schema<Query, Mutation, Subscription>(
+    query: Query,
+    mutation: Mutation,
+    subscription: Subscription,
+    config: &SchemaConfig,
+) -> SchemaBuilder<Query, Mutation, Subscription>
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    Schema::build(query, mutation, subscription).with_defaults(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, SimpleObject};
+
+    #[derive(SimpleObject)]
+    struct Node {
+        depth: i32,
+        child: Option<Box<Node>>,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn node(&self) -> Node {
+            Node {
+                depth: 0,
+                child: None,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_schema_applies_defaults() {
+        let schema = build_schema(
+            Query,
+            EmptyMutation,
+            EmptySubscription,
+            &SchemaConfig::new().with_max_depth(2),
+        )
+        .finish();
+
+        assert!(schema.execute("{ node { depth } }").await.is_ok());
+        let response = schema
+            .execute("{ node { child { child { depth } } } }")
+            .await;
+        assert_eq!(
+            response.errors[0].extensions.as_ref().unwrap().get("code"),
+            Some(&async_graphql::Value::from("QUERY_TOO_DEEP"))
+        );
+        assert!(schema.execute("{ _service { sdl } }").await.is_ok());
+    }
+}
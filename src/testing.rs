@@ -0,0 +1,9 @@
+//! Test helpers
+//!
+//! Fakes for unit-testing resolvers built on this crate.
+
+pub mod loaders;
+pub mod request;
+
+pub use loaders::MockBatchLoader;
+pub use request::{execute_with_auth, TestRequest};
@@ -0,0 +1,253 @@
+//! Mock batch loaders
+//!
+//! [`MockBatchLoader`] serves fixture data and records every batch it
+//! receives, so tests can assert that resolvers batch instead of issuing
+//! N+1 loads.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use crate::dataloaders::{successes, BatchLoader, LoadError};
+
+/// Fixture-backed [`BatchLoader`] that records its calls
+///
+/// Clones share fixtures and call history: pass one clone to the
+/// [`DataLoader`](crate::dataloaders::DataLoader) and assert on another.
+///
+/// # Example
+///
+/// ```rust
+/// use pleme_graphql_helpers::dataloaders::DataLoader;
+/// use pleme_graphql_helpers::testing::MockBatchLoader;
+///
+/// # tokio_test::block_on(async {
+/// let mock = MockBatchLoader::<u32, String>::new()
+///     .with(1, "alice")
+///     .with(2, "bob");
+/// let loader = DataLoader::new(mock.clone());
+///
+/// loader.load_many(vec![1, 2, 3]).await;
+/// mock.assert_batched_once();
+/// assert_eq!(mock.calls(), vec![vec![1, 2, 3]]);
+/// # });
+/// ```
+pub struct MockBatchLoader<K, V> {
+    state: Arc<Mutex<MockState<K, V>>>,
+}
+
+struct MockState<K, V> {
+    fixtures: HashMap<K, Result<V, LoadError>>,
+    batch_error: Option<LoadError>,
+    calls: Vec<Vec<K>>,
+}
+
+impl<K, V> MockBatchLoader<K, V>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Clone,
+{
+    /// Create a loader with no fixtures
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                fixtures: HashMap::new(),
+                batch_error: None,
+                calls: Vec::new(),
+            })),
+        }
+    }
+
+    /// Add a fixture value (builder style)
+    pub fn with(self, key: K, value: impl Into<V>) -> Self {
+        self.insert(key, value.into());
+        self
+    }
+
+    /// Fail loads of `key` with `error` (builder style)
+    pub fn with_key_error(self, key: K, error: LoadError) -> Self {
+        self.state().fixtures.insert(key, Err(error));
+        self
+    }
+
+    /// Fail every batch with `error` (builder style)
+    pub fn with_batch_error(self, error: LoadError) -> Self {
+        self.state().batch_error = Some(error);
+        self
+    }
+
+    /// Add or replace a fixture value
+    pub fn insert(&self, key: K, value: V) {
+        self.state().fixtures.insert(key, Ok(value));
+    }
+
+    /// Keys of every `load_batch` call, in call order
+    pub fn calls(&self) -> Vec<Vec<K>> {
+        self.state().calls.clone()
+    }
+
+    /// Number of `load_batch` calls
+    pub fn call_count(&self) -> usize {
+        self.state().calls.len()
+    }
+
+    /// Forget recorded calls
+    pub fn reset_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    /// Assert exactly one `load_batch` call was made
+    #[track_caller]
+    pub fn assert_batched_once(&self) {
+        self.assert_call_count(1);
+    }
+
+    /// Assert exactly `expected` `load_batch` calls were made
+    #[track_caller]
+    pub fn assert_call_count(&self, expected: usize) {
+        let calls = self.calls();
+        assert_eq!(
+            calls.len(),
+            expected,
+            "expected {} load_batch call(s), got {}: {:?}",
+            expected,
+            calls.len(),
+            calls
+        );
+    }
+
+    /// Assert `key` was requested in some batch
+    #[track_caller]
+    pub fn assert_loaded(&self, key: &K) {
+        let calls = self.calls();
+        assert!(
+            calls.iter().flatten().any(|k| k == key),
+            "expected {:?} to be loaded, calls: {:?}",
+            key,
+            calls
+        );
+    }
+
+    /// Assert `key` was never requested
+    #[track_caller]
+    pub fn assert_not_loaded(&self, key: &K) {
+        let calls = self.calls();
+        assert!(
+            !calls.iter().flatten().any(|k| k == key),
+            "expected {:?} not to be loaded, calls: {:?}",
+            key,
+            calls
+        );
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState<K, V>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K, V> Default for MockBatchLoader<K, V>
+where
+    K: Clone + Eq + Hash + Debug,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for MockBatchLoader<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<K, V> BatchLoader<K, V> for MockBatchLoader<K, V>
+where
+    K: Send + Sync + Clone + Eq + Hash + Debug + 'static,
+    V: Send + Sync + Clone + 'static,
+{
+    async fn load_batch(&self, keys: &[K]) -> HashMap<K, V> {
+        successes(self.try_load_batch(keys).await)
+    }
+
+    async fn try_load_batch(
+        &self,
+        keys: &[K],
+    ) -> Result<HashMap<K, Result<V, LoadError>>, LoadError> {
+        let mut state = self.state();
+        state.calls.push(keys.to_vec());
+
+        if let Some(error) = &state.batch_error {
+            return Err(error.clone());
+        }
+
+        Ok(keys
+            .iter()
+            .filter_map(|key| {
+                state
+                    .fixtures
+                    .get(key)
+                    .map(|value| (key.clone(), value.clone()))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataloaders::DataLoader;
+
+    #[tokio::test]
+    async fn test_mock_records_batches() {
+        let mock = MockBatchLoader::<u32, String>::new()
+            .with(1, "one")
+            .with(2, "two");
+        let loader = DataLoader::new(mock.clone());
+
+        let (a, b) = tokio::join!(loader.load(1), loader.load(1));
+        assert_eq!(a, Some("one".to_string()));
+        assert_eq!(b, a);
+        mock.assert_batched_once();
+
+        loader.load_many(vec![1, 2, 3]).await;
+        mock.assert_call_count(2);
+        assert_eq!(mock.calls()[1], vec![2, 3]);
+        mock.assert_loaded(&3);
+        mock.assert_not_loaded(&4);
+    }
+
+    #[tokio::test]
+    async fn test_mock_errors() {
+        let mock = MockBatchLoader::<u32, String>::new()
+            .with(1, "one")
+            .with_key_error(2, LoadError::Key("boom".to_string()));
+        let loader = DataLoader::new(mock.clone());
+
+        assert_eq!(loader.try_load(1).await, Ok(Some("one".to_string())));
+        assert_eq!(
+            loader.try_load(2).await,
+            Err(LoadError::Key("boom".to_string()))
+        );
+
+        let failing = DataLoader::new(
+            MockBatchLoader::<u32, String>::new()
+                .with_batch_error(LoadError::Batch("down".to_string())),
+        );
+        assert_eq!(
+            failing.try_load(1).await,
+            Err(LoadError::Batch("down".to_string()))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 load_batch call(s), got 0")]
+    fn test_assert_batched_once_fails_without_calls() {
+        MockBatchLoader::<u32, String>::new().assert_batched_once();
+    }
+}
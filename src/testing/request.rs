@@ -0,0 +1,216 @@
+//! Authenticated test requests
+//!
+//! [`TestRequest`] injects the same request data as the GraphQL handlers, so
+//! resolver and guard tests don't hand-build `Uuid` and `AuthzContext` data.
+
+use async_graphql::{ObjectType, Request, Response, Schema, SubscriptionType, Variables};
+use pleme_rbac::AuthzContext;
+use uuid::Uuid;
+
+use crate::auth::{insert_auth_data, AuthContext};
+
+/// Builder for a request executed as a given user
+///
+/// Roles and permissions go into the [`AuthzContext`], which is what
+/// [`AuthContext::has_role`] and [`AuthContext::has_permission`] read.
+///
+/// # Example
+///
+/// ```rust
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+/// use pleme_graphql_helpers::auth::PermissionRequired;
+/// use pleme_graphql_helpers::testing::{execute_with_auth, TestRequest};
+/// use uuid::Uuid;
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///     #[graphql(guard = "PermissionRequired(\"orders:read\")")]
+///     async fn orders(&self) -> i32 {
+///         3
+///     }
+/// }
+///
+/// # tokio_test::block_on(async {
+/// let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+/// let request = TestRequest::new("{ orders }")
+///     .as_user(Uuid::new_v4())
+///     .in_company(Uuid::new_v4())
+///     .with_permissions(["orders:read"]);
+///
+/// assert!(execute_with_auth(&schema, request).await.is_ok());
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestRequest {
+    query: String,
+    operation_name: Option<String>,
+    variables: Variables,
+    auth: AuthContext,
+    roles: Vec<String>,
+    permissions: Vec<String>,
+}
+
+impl TestRequest {
+    /// Anonymous request for `query`
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            operation_name: None,
+            variables: Variables::default(),
+            auth: AuthContext::anonymous(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    /// Execute as `user_id`
+    pub fn as_user(mut self, user_id: Uuid) -> Self {
+        self.auth.user_id = Some(user_id);
+        self
+    }
+
+    /// Execute within `company_id`
+    pub fn in_company(mut self, company_id: Uuid) -> Self {
+        self.auth.company_id = Some(company_id);
+        self
+    }
+
+    /// Execute as the service principal `name`
+    pub fn as_service(mut self, name: impl Into<String>) -> Self {
+        self.auth.service = Some(name.into());
+        self
+    }
+
+    /// Grant roles
+    pub fn with_roles<I, S>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles.extend(roles.into_iter().map(Into::into));
+        self
+    }
+
+    /// Grant permissions
+    pub fn with_permissions<I, S>(mut self, permissions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.permissions
+            .extend(permissions.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the query variables
+    pub fn with_variables(mut self, variables: serde_json::Value) -> Self {
+        self.variables = Variables::from_json(variables);
+        self
+    }
+
+    /// Select the operation to execute
+    pub fn with_operation_name(mut self, name: impl Into<String>) -> Self {
+        self.operation_name = Some(name.into());
+        self
+    }
+
+    /// The auth context the request executes with
+    pub fn auth_context(&self) -> AuthContext {
+        let mut auth = self.auth.clone();
+        if !self.roles.is_empty() || !self.permissions.is_empty() {
+            auth.authz = AuthzContext::from_claims(
+                auth.user_id.unwrap_or_default(),
+                String::new(),
+                String::new(),
+                self.roles.clone(),
+                self.permissions.clone(),
+                Default::default(),
+            );
+        }
+        auth
+    }
+
+    /// Build the request with auth data injected
+    pub fn into_request(self) -> Request {
+        let auth = self.auth_context();
+        let mut request = Request::new(self.query).variables(self.variables);
+        if let Some(name) = self.operation_name {
+            request = request.operation_name(name);
+        }
+        insert_auth_data(&mut request.data, auth);
+        request
+    }
+}
+
+impl From<&str> for TestRequest {
+    fn from(query: &str) -> Self {
+        Self::new(query)
+    }
+}
+
+impl From<String> for TestRequest {
+    fn from(query: String) -> Self {
+        Self::new(query)
+    }
+}
+
+/// Execute a [`TestRequest`] (or an anonymous query string) against `schema`
+pub async fn execute_with_auth<Query, Mutation, Subscription>(
+    schema: &Schema<Query, Mutation, Subscription>,
+    request: impl Into<TestRequest>,
+) -> Response
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    schema.execute(request.into().into_request()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{get_company_id, get_user_id, RoleRequired};
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object};
+    use serde_json::json;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn ids(&self, ctx: &Context<'_>) -> Vec<Option<String>> {
+            vec![
+                get_user_id(ctx).map(|id| id.to_string()),
+                get_company_id(ctx).map(|id| id.to_string()),
+            ]
+        }
+
+        #[graphql(guard = "RoleRequired(\"admin\")")]
+        async fn admin(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_auth() {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let (user, company) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let request = TestRequest::new("{ ids }")
+            .as_user(user)
+            .in_company(company);
+        let response = execute_with_auth(&schema, request).await;
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            json!({ "ids": [user.to_string(), company.to_string()] })
+        );
+
+        assert!(execute_with_auth(&schema, "{ admin }").await.is_err());
+        let admin = TestRequest::new("{ admin }")
+            .as_user(user)
+            .with_roles(["admin"]);
+        assert!(execute_with_auth(&schema, admin).await.is_ok());
+    }
+}
@@ -1,7 +1,20 @@
 //! Common GraphQL types
+//!
+//! `DateTime`, `JSON`, file uploads, and `Decimal` (with the `decimal`
+//! feature).
 
-use async_graphql::{Scalar, ScalarType, Value};
+use async_graphql::{
+    Context, CustomValidator, InputValueError, InputValueResult, Scalar, ScalarType, Value,
+};
 use chrono::{DateTime as ChronoDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+#[cfg(feature = "decimal")]
+pub mod decimal;
+
+#[cfg(feature = "decimal")]
+pub use decimal::{Decimal, DecimalLimits};
 
 /// DateTime scalar
 #[derive(Debug, Clone)]
@@ -26,6 +39,100 @@ impl ScalarType for DateTime {
     }
 }
 
+/// Schemaless `JSON` scalar for free-form data such as metadata or webhook
+/// payloads
+///
+/// Accepts any value. Bound untrusted input with [`JsonLimits`]:
+///
+/// ```rust,ignore
+/// async fn set_metadata(
+///     &self,
+///     #[graphql(validator(custom = "JsonLimits::new(5, 16 * 1024)"))] metadata: Json,
+/// ) -> Result<bool> {
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Json(pub serde_json::Value);
+
+#[Scalar(name = "JSON")]
+impl ScalarType for Json {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        value
+            .into_json()
+            .map(Json)
+            .map_err(|e| InputValueError::custom(format!("Invalid JSON: {}", e)))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::from_json(self.0.clone()).unwrap_or(Value::Null)
+    }
+}
+
+impl Json {
+    /// Nesting depth; scalars are 0, `[]` and `{}` are 1
+    pub fn depth(&self) -> usize {
+        fn depth(value: &serde_json::Value) -> usize {
+            match value {
+                serde_json::Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+                serde_json::Value::Object(fields) => {
+                    1 + fields.values().map(depth).max().unwrap_or(0)
+                }
+                _ => 0,
+            }
+        }
+        depth(&self.0)
+    }
+
+    /// Length of the serialized value in bytes
+    pub fn size(&self) -> usize {
+        self.0.to_string().len()
+    }
+}
+
+impl From<serde_json::Value> for Json {
+    fn from(value: serde_json::Value) -> Self {
+        Json(value)
+    }
+}
+
+/// Depth and size limits for [`Json`] arguments, as a custom validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLimits {
+    /// Maximum nesting depth
+    pub max_depth: usize,
+    /// Maximum serialized size in bytes
+    pub max_size: usize,
+}
+
+impl JsonLimits {
+    pub fn new(max_depth: usize, max_size: usize) -> Self {
+        Self {
+            max_depth,
+            max_size,
+        }
+    }
+}
+
+impl CustomValidator<Json> for JsonLimits {
+    fn check(&self, value: &Json) -> Result<(), InputValueError<Json>> {
+        if value.depth() > self.max_depth {
+            return Err(InputValueError::custom(format!(
+                "JSON is nested deeper than {}",
+                self.max_depth
+            )));
+        }
+        if value.size() > self.max_size {
+            return Err(InputValueError::custom(format!(
+                "JSON is larger than {} bytes",
+                self.max_size
+            )));
+        }
+        Ok(())
+    }
+}
+
 /// File upload scalar
 #[derive(Debug, Clone)]
 pub struct Upload {
@@ -34,6 +141,35 @@ pub struct Upload {
     pub data: Vec<u8>,
 }
 
+impl Upload {
+    /// Read an uploaded file from a multipart request into memory
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// async fn upload_avatar(&self, ctx: &Context<'_>, file: async_graphql::Upload) -> Result<bool> {
+    ///     let upload = Upload::from_graphql(ctx, &file)?;
+    ///     storage.put(&upload.filename, upload.data).await?;
+    ///     Ok(true)
+    /// }
+    /// ```
+    pub fn from_graphql(
+        ctx: &Context<'_>,
+        upload: &async_graphql::Upload,
+    ) -> std::io::Result<Self> {
+        let mut value = upload.value(ctx)?;
+        let mut data = Vec::new();
+        value.content.read_to_end(&mut data)?;
+        Ok(Self {
+            filename: value.filename,
+            content_type: value
+                .content_type
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+            data,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,4 +180,44 @@ mod tests {
         let value = dt.to_value();
         assert!(matches!(value, Value::String(_)));
     }
+
+    #[test]
+    fn test_json_roundtrip_and_limits() {
+        let json = Json(serde_json::json!({ "tags": ["a", { "b": 1 }], "count": 2 }));
+        assert_eq!(Json::parse(json.to_value()).unwrap(), json);
+        assert_eq!(json.depth(), 3);
+
+        assert!(JsonLimits::new(3, 1024).check(&json).is_ok());
+        assert!(JsonLimits::new(2, 1024).check(&json).is_err());
+        assert!(JsonLimits::new(3, 8).check(&json).is_err());
+    }
+
+    #[test]
+    fn test_json_alongside_connection_aggregates() {
+        use crate::pagination::Connection;
+        use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+
+        #[derive(SimpleObject, Clone)]
+        struct Item {
+            id: i32,
+        }
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn metadata(&self) -> Json {
+                Json::default()
+            }
+
+            async fn items(&self) -> Connection<Item> {
+                Connection::empty()
+            }
+        }
+
+        // Aggregate values share the `JSON` scalar instead of registering a
+        // second type under the same name
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+        assert!(schema.sdl().contains("scalar JSON"));
+    }
 }
@@ -0,0 +1,217 @@
+//! `Decimal` scalar for money and other exact quantities
+//!
+//! Floats can't represent most decimal fractions, so amounts drift as they
+//! are added up. [`Decimal`] wraps [`rust_decimal::Decimal`]: it parses
+//! from a string or a number and is always sent as a string, so clients
+//! never round-trip it through a float. Bound input precision and scale
+//! with [`DecimalLimits`], and convert to and from integer minor units
+//! (cents) with [`Decimal::from_minor_units`] / [`Decimal::to_minor_units`].
+
+use async_graphql::{
+    CustomValidator, InputValueError, InputValueResult, Scalar, ScalarType, Value,
+};
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// Exact decimal scalar, serialized as a string
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Decimal(pub rust_decimal::Decimal);
+
+#[Scalar(name = "Decimal")]
+impl ScalarType for Decimal {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        let text = match &value {
+            Value::String(s) => s.clone(),
+            // Display gives the shortest form that reads back as the number
+            Value::Number(n) => n.to_string(),
+            _ => return Err(InputValueError::expected_type(value)),
+        };
+        text.parse()
+            .map_err(|e| InputValueError::custom(format!("Invalid Decimal: {}", e)))
+    }
+
+    fn is_valid(value: &Value) -> bool {
+        matches!(value, Value::String(_) | Value::Number(_))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(self.0.to_string())
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = rust_decimal::Error;
+
+    /// Plain (`19.99`) or scientific (`1.5e3`) notation
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        rust_decimal::Decimal::from_str_exact(s)
+            .or_else(|_| rust_decimal::Decimal::from_scientific(s))
+            .map(Decimal)
+    }
+}
+
+impl Decimal {
+    /// `units` of `10^-scale`, e.g. `from_minor_units(1999, 2)` is `19.99`
+    ///
+    /// `None` if `scale` exceeds 28.
+    pub fn from_minor_units(units: i64, scale: u32) -> Option<Self> {
+        rust_decimal::Decimal::try_new(units, scale)
+            .ok()
+            .map(Decimal)
+    }
+
+    /// The value in units of `10^-scale`, e.g. cents for a scale of 2
+    ///
+    /// `None` if the value has more decimal places than `scale` or doesn't
+    /// fit in an `i64`, rather than rounding silently.
+    pub fn to_minor_units(&self, scale: u32) -> Option<i64> {
+        if self.0.scale() > scale {
+            return None;
+        }
+        let factor =
+            rust_decimal::Decimal::try_from_i128_with_scale(10i128.checked_pow(scale)?, 0).ok()?;
+        i64::try_from(self.0.checked_mul(factor)?.trunc()).ok()
+    }
+
+    /// Round to `scale` decimal places, halves away from zero
+    pub fn round_to(&self, scale: u32) -> Self {
+        Decimal(
+            self.0
+                .round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero),
+        )
+    }
+
+    /// Significant digits, ignoring the sign
+    pub fn precision(&self) -> u32 {
+        let mantissa = self.0.mantissa().unsigned_abs();
+        mantissa.checked_ilog10().map_or(1, |digits| digits + 1)
+    }
+
+    /// Digits after the decimal point
+    pub fn scale(&self) -> u32 {
+        self.0.scale()
+    }
+}
+
+impl Deref for Decimal {
+    type Target = rust_decimal::Decimal;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<rust_decimal::Decimal> for Decimal {
+    fn from(value: rust_decimal::Decimal) -> Self {
+        Decimal(value)
+    }
+}
+
+impl From<Decimal> for rust_decimal::Decimal {
+    fn from(value: Decimal) -> Self {
+        value.0
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal(value.into())
+    }
+}
+
+/// Precision and scale limits for [`Decimal`] arguments, as a custom
+/// validator
+///
+/// ```rust,ignore
+/// async fn charge(
+///     &self,
+///     #[graphql(validator(custom = "DecimalLimits::new(12, 2)"))] amount: Decimal,
+/// ) -> Result<Invoice> {
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalLimits {
+    /// Maximum significant digits
+    pub max_precision: u32,
+    /// Maximum digits after the decimal point
+    pub max_scale: u32,
+}
+
+impl DecimalLimits {
+    pub fn new(max_precision: u32, max_scale: u32) -> Self {
+        Self {
+            max_precision,
+            max_scale,
+        }
+    }
+}
+
+impl CustomValidator<Decimal> for DecimalLimits {
+    fn check(&self, value: &Decimal) -> Result<(), InputValueError<Decimal>> {
+        // Trailing zeros (`1.50`) don't count against the scale
+        let value = Decimal(value.0.normalize());
+        if value.scale() > self.max_scale {
+            return Err(InputValueError::custom(format!(
+                "Decimal has more than {} decimal places",
+                self.max_scale
+            )));
+        }
+        if value.precision() > self.max_precision {
+            return Err(InputValueError::custom(format!(
+                "Decimal has more than {} digits",
+                self.max_precision
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_serialize() {
+        let from_string = Decimal::parse(Value::String("19.99".to_string())).unwrap();
+        assert_eq!(from_string.to_value(), Value::String("19.99".to_string()));
+
+        let from_number =
+            Decimal::parse(Value::Number(async_graphql::Number::from_f64(0.1).unwrap())).unwrap();
+        assert_eq!(from_number.to_string(), "0.1");
+        assert_eq!(
+            Decimal::parse(Value::String("1.5e3".to_string())).unwrap(),
+            Decimal::from(1500)
+        );
+        assert!(Decimal::parse(Value::Boolean(true)).is_err());
+        assert!(Decimal::parse(Value::String("abc".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_minor_units_and_limits() {
+        let amount = Decimal::from_minor_units(1999, 2).unwrap();
+        assert_eq!(amount.to_string(), "19.99");
+        assert_eq!(amount.to_minor_units(2), Some(1999));
+        assert_eq!(amount.to_minor_units(3), Some(19990));
+        assert_eq!(amount.to_minor_units(1), None);
+        assert_eq!(amount.round_to(1).to_string(), "20.0");
+
+        assert!(DecimalLimits::new(4, 2).check(&amount).is_ok());
+        assert!(DecimalLimits::new(3, 2).check(&amount).is_err());
+        assert!(DecimalLimits::new(4, 1).check(&amount).is_err());
+        let padded: Decimal = "19.9900".parse().unwrap();
+        assert!(DecimalLimits::new(4, 2).check(&padded).is_ok());
+    }
+}